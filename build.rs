@@ -1,17 +1,41 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Compile protobuf files from the proto/ directory.
-    prost_build::compile_protos(
-        &[
-            "proto/common.proto",
-            "proto/king.proto",
-            "proto/queen.proto",
-            "proto/knight.proto",
-            "proto/bishop.proto",
-            "proto/pawn.proto",
-            "proto/pieces.proto",
-            "proto/board.proto",
-        ],
-        &["proto"],
-    )?;
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
+
+    // Compile protobuf files from the proto/ directory. Listed explicitly, since
+    // the ffi block below narrows what cargo watches once it names any file.
+    println!("cargo:rerun-if-changed=proto");
+    // The descriptor set is kept so the API can be discovered at runtime (gRPC reflection).
+    prost_build::Config::new()
+        .file_descriptor_set_path(out_dir.join("rchess_descriptor.bin"))
+        .compile_protos(
+            &[
+                "proto/common.proto",
+                "proto/king.proto",
+                "proto/queen.proto",
+                "proto/rook.proto",
+                "proto/knight.proto",
+                "proto/bishop.proto",
+                "proto/pawn.proto",
+                "proto/pieces.proto",
+                "proto/clock.proto",
+                "proto/board.proto",
+                "proto/service.proto",
+                "proto/record.proto",
+            ],
+            &["proto"],
+        )?;
+
+    // C header for the FFI layer, written to OUT_DIR so builds never touch the
+    // source tree.
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_config(cbindgen::Config::from_file("cbindgen.toml")?)
+            .with_src("src/ffi.rs")
+            .generate()?
+            .write_to_file(out_dir.join("rchess.h"));
+    }
     Ok(())
 }
@@ -0,0 +1,15 @@
+// Any FEN that parses must serialize, reparse to the same FEN, and generate moves.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rchess::board::Board;
+
+fuzz_target!(|data: &str| {
+    let Ok(board) = Board::from_fen(data) else { return };
+    let fen = board.to_fen();
+    let again = Board::from_fen(&fen).expect("serialized FEN parses");
+    assert_eq!(again.to_fen(), fen);
+    for mv in board.legal_moves(board.current_player()) {
+        let _ = board.apply(mv);
+    }
+});
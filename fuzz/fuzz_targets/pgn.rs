@@ -0,0 +1,14 @@
+// PGN import must reject malformed text with an error, never a panic, and
+// whatever it accepts must export.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rchess::pgn::parse_games;
+
+fuzz_target!(|data: &str| {
+    if let Ok(games) = parse_games(data) {
+        for game in games {
+            let _ = game.to_pgn();
+        }
+    }
+});
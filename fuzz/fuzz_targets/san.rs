@@ -0,0 +1,17 @@
+// SAN and UCI move parsing. The input is a FEN line followed by the move text;
+// without a newline the move text is read against the starting position.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rchess::board::Board;
+use rchess::fen::STARTING_FEN;
+use rchess::moves::Move;
+
+fuzz_target!(|data: &str| {
+    let (fen, text) = data.split_once('\n').unwrap_or((STARTING_FEN, data));
+    let Ok(board) = Board::from_fen(fen) else { return };
+    if let Ok(mv) = Move::from_san(text, &board) {
+        assert_eq!(Move::from_san(&mv.to_san(&board), &board), Ok(mv));
+    }
+    let _ = Move::from_uci(text, &board);
+});
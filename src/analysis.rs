@@ -0,0 +1,289 @@
+// Tactical motifs in a position: pins, skewers, forks, and hanging pieces. Each
+// finder reports the squares taking part, for tactics trainers and annotators.
+// Pins and skewers come from the x-rays in attacks.rs; forks and hanging pieces
+// from the attack and defender queries. Checkmates are also matched against
+// the named mating patterns, for puzzle tags and game summaries.
+
+use crate::board::Board;
+use crate::pieces::{Color, PieceType, Square};
+use std::fmt;
+
+/// A piece that cannot move off a line without exposing a more valuable one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pin {
+    pub pinner: Square,
+    pub pinned: Square,
+    /// The piece the pinned one shields.
+    pub target: Square,
+    /// Whether the target is the king, so the pinned piece may not move at all
+    /// off the line.
+    pub absolute: bool,
+}
+
+/// A valuable piece attacked along a line with a lesser one behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Skewer {
+    pub attacker: Square,
+    /// The piece attacked first, which must move.
+    pub front: Square,
+    /// The piece left en prise once the front one moves.
+    pub behind: Square,
+}
+
+/// One piece attacking two or more worthwhile targets at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fork {
+    pub forker: Square,
+    pub forker_type: PieceType,
+    /// Targets in rank-then-file order.
+    pub targets: Vec<Square>,
+}
+
+/// A named checkmate pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatePattern {
+    /// A knight mates a king hemmed in on every side by its own pieces.
+    Smothered,
+    /// A rook or queen mates along the king's back rank, with the king's own
+    /// pieces blocking the rank in front of it.
+    BackRank,
+    /// A rook next to the cornered king mates, guarded by a knight.
+    Arabian,
+    /// A rook or queen mates along the edge file, the king's own piece beside
+    /// it and a knight covering its escape.
+    Anastasia,
+}
+
+impl fmt::Display for MatePattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            MatePattern::Smothered => "smothered mate",
+            MatePattern::BackRank => "back-rank mate",
+            MatePattern::Arabian => "Arabian mate",
+            MatePattern::Anastasia => "Anastasia's mate",
+        })
+    }
+}
+
+/// Ranking of pieces for motif detection: material value, with the king above
+/// everything.
+fn worth(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::King => 10_000,
+        _ => piece_type.value(),
+    }
+}
+
+/// Pins `color`'s sliders hold on the opponent's pieces: the pinned piece and
+/// the piece behind it both belong to the opponent, and the one behind is worth
+/// more.
+pub fn find_pins(board: &Board, color: Color) -> Vec<Pin> {
+    let opponent = color.opposite();
+    board
+        .xray_attacks(color)
+        .into_iter()
+        .filter_map(|xray| {
+            let (pinned, _) = board.piece_at_typed(xray.blocker).filter(|&(_, c)| c == opponent)?;
+            let (target, _) = board.piece_at_typed(xray.target).filter(|&(_, c)| c == opponent)?;
+            (worth(target) > worth(pinned)).then_some(Pin {
+                pinner: xray.attacker,
+                pinned: xray.blocker,
+                target: xray.target,
+                absolute: target == PieceType::King,
+            })
+        })
+        .collect()
+}
+
+/// Skewers by `color`'s sliders: the front piece is the opponent's and worth
+/// more than the opponent's piece behind it, or is the king.
+pub fn find_skewers(board: &Board, color: Color) -> Vec<Skewer> {
+    let opponent = color.opposite();
+    board
+        .xray_attacks(color)
+        .into_iter()
+        .filter_map(|xray| {
+            let (front, _) = board.piece_at_typed(xray.blocker).filter(|&(_, c)| c == opponent)?;
+            let (behind, _) = board.piece_at_typed(xray.target).filter(|&(_, c)| c == opponent)?;
+            (worth(front) > worth(behind)).then_some(Skewer {
+                attacker: xray.attacker,
+                front: xray.blocker,
+                behind: xray.target,
+            })
+        })
+        .collect()
+}
+
+/// Forks by `color`'s pieces: each piece attacking two or more of the
+/// opponent's pieces that are the king, worth more than the forker, or
+/// undefended.
+pub fn find_forks(board: &Board, color: Color) -> Vec<Fork> {
+    let opponent = color.opposite();
+    let mut forks = Vec::new();
+    for forker in board.bitboards().color(color).squares() {
+        let Some((forker_type, _)) = board.piece_at_typed(forker) else {
+            continue;
+        };
+        let targets: Vec<Square> = (board.attacks_from(forker) & board.bitboards().color(opponent))
+            .squares()
+            .filter(|&target| {
+                board.piece_at_typed(target).is_some_and(|(piece_type, _)| {
+                    piece_type == PieceType::King
+                        || worth(piece_type) > worth(forker_type)
+                        || board.defenders_of(target).is_empty()
+                })
+            })
+            .collect();
+        if targets.len() >= 2 {
+            forks.push(Fork { forker, forker_type, targets });
+        }
+    }
+    forks
+}
+
+/// `color`'s pieces, other than the king, that the opponent can win: attacked
+/// and undefended, or attacked by a less valuable piece. Rank-then-file order.
+pub fn hanging_pieces(board: &Board, color: Color) -> Vec<Square> {
+    board
+        .bitboards()
+        .color(color)
+        .squares()
+        .filter(|&square| {
+            let Some((piece_type, _)) = board.piece_at_typed(square).filter(|&(pt, _)| pt != PieceType::King) else {
+                return false;
+            };
+            let attackers = board.attackers_of(square, color.opposite());
+            let cheapest = attackers
+                .iter()
+                .filter_map(|&from| board.piece_at_typed(from))
+                .map(|(attacker, _)| worth(attacker))
+                .min();
+            match cheapest {
+                None => false,
+                Some(cheapest) => cheapest < worth(piece_type) || board.defenders_of(square).is_empty(),
+            }
+        })
+        .collect()
+}
+
+/// The named patterns the side to move is mated by; empty when it is not
+/// checkmated or the mate fits none of them.
+pub fn mate_patterns(board: &Board) -> Vec<MatePattern> {
+    let mated = board.current_player();
+    let Some(king) = board.king_square(mated) else {
+        return Vec::new();
+    };
+    let checkers = board.checkers();
+    let &[checker] = checkers.as_slice() else {
+        return Vec::new();
+    };
+    if !board.legal_moves(mated).is_empty() {
+        return Vec::new();
+    }
+    let Some((checker_type, _)) = board.piece_at_typed(checker) else {
+        return Vec::new();
+    };
+    let own = |square: Square| board.piece_at_typed(square).is_some_and(|(_, color)| color == mated);
+    let neighbours: Vec<Square> = (-1..=1)
+        .flat_map(|df| (-1..=1).map(move |dr| (df, dr)))
+        .filter(|&step| step != (0, 0))
+        .filter_map(|(df, dr)| king.offset(df, dr))
+        .collect();
+    let knight_covers = |square: Square| {
+        board.bitboards().color(mated.opposite()).squares().any(|from| {
+            board.piece_at_typed(from).is_some_and(|(piece_type, _)| piece_type == PieceType::Knight)
+                && board.attacks_from(from).contains(square)
+        })
+    };
+    let line_piece = matches!(checker_type, PieceType::Rook | PieceType::Queen);
+    let back_rank = match mated {
+        Color::White => 0,
+        Color::Black => 7,
+    };
+    let forward = if back_rank == 0 { 1 } else { -1 };
+    let edge_file = king.file == 0 || king.file == 7;
+    let corner = edge_file && (king.rank == 0 || king.rank == 7);
+
+    let mut patterns = Vec::new();
+    if checker_type == PieceType::Knight && neighbours.iter().all(|&square| own(square)) {
+        patterns.push(MatePattern::Smothered);
+    }
+    if line_piece
+        && king.rank == back_rank
+        && checker.rank == back_rank
+        && (-1..=1).filter_map(|df| king.offset(df, forward)).all(own)
+    {
+        patterns.push(MatePattern::BackRank);
+    }
+    if checker_type == PieceType::Rook && corner && neighbours.contains(&checker) && knight_covers(checker) {
+        patterns.push(MatePattern::Arabian);
+    }
+    let beside = king.offset(if king.file == 0 { 1 } else { -1 }, 0);
+    if line_piece
+        && edge_file
+        && !corner
+        && checker.file == king.file
+        && beside.is_some_and(own)
+        && neighbours.iter().any(|&square| knight_covers(square))
+    {
+        patterns.push(MatePattern::Anastasia);
+    }
+    patterns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_pins_and_skewers() {
+        // The b5 bishop pins the c6 knight to the king.
+        let board = Board::from_fen("q3k3/8/2n5/1B6/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            find_pins(&board, Color::White),
+            vec![Pin { pinner: sq!("b5"), pinned: sq!("c6"), target: sq!("e8"), absolute: true }]
+        );
+        // The a1 rook skewers the king on a4 to the queen on a8.
+        let skewered = Board::from_fen("q7/8/8/8/k7/8/8/R3K3 b - - 0 1").unwrap();
+        assert_eq!(
+            find_skewers(&skewered, Color::White),
+            vec![Skewer { attacker: sq!("a1"), front: sq!("a4"), behind: sq!("a8") }]
+        );
+        assert!(find_pins(&skewered, Color::White).is_empty());
+    }
+
+    #[test]
+    fn test_forks_and_hanging_pieces() {
+        // The knight on c7 forks king and rook, and the undefended b5 pawn too.
+        let board = Board::from_fen("r3k3/2N5/8/1p6/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(
+            find_forks(&board, Color::White),
+            vec![Fork {
+                forker: sq!("c7"),
+                forker_type: PieceType::Knight,
+                targets: vec![sq!("b5"), sq!("a8"), sq!("e8")],
+            }]
+        );
+        assert!(find_forks(&board, Color::Black).is_empty());
+
+        // A pawn attacks the queen; the knight is attacked only by a rook and
+        // defended by the queen; the c4 pawn is attacked and undefended.
+        let board = Board::from_fen("4k3/8/8/3q4/2P1n3/8/4R3/4K3 w - - 0 1").unwrap();
+        assert_eq!(hanging_pieces(&board, Color::Black), vec![sq!("d5")]);
+        assert_eq!(hanging_pieces(&board, Color::White), vec![sq!("c4")]);
+    }
+
+    #[test]
+    fn test_mate_patterns() {
+        let patterns = |fen| mate_patterns(&Board::from_fen(fen).unwrap());
+        assert_eq!(patterns("6rk/5Npp/8/8/8/8/8/6K1 b - - 0 1"), vec![MatePattern::Smothered]);
+        assert_eq!(patterns("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1"), vec![MatePattern::BackRank]);
+        assert_eq!(patterns("7k/7R/5N2/8/8/8/8/6K1 b - - 0 1"), vec![MatePattern::Arabian]);
+        assert_eq!(patterns("8/4Nppk/8/7R/8/8/8/6K1 b - - 0 1"), vec![MatePattern::Anastasia]);
+        // Check that is not mate, and a mate that fits no pattern.
+        assert!(patterns("3R2k1/5pp1/8/8/8/8/8/6K1 b - - 0 1").is_empty());
+        assert!(patterns("k7/1Q6/2K5/8/8/8/8/8 b - - 0 1").is_empty());
+        assert_eq!(MatePattern::Anastasia.to_string(), "Anastasia's mate");
+    }
+}
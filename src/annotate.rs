@@ -0,0 +1,212 @@
+// Game review: search every position of a game's main line, measure how much
+// each move gave away against the engine's choice, and mark the costly ones
+// with the usual NAGs and a comment naming the better move. Scores are capped
+// before taking differences, so missing a mate or walking into one costs about
+// as much as dropping a rook rather than tens of thousands of centipawns.
+//
+// Use an engine without an opening book: book moves come back unsearched with a
+// score of 0.
+
+use crate::engine::{Engine, SearchLimits};
+use crate::game::Game;
+use crate::moves::Move;
+use crate::pieces::Color;
+use std::fmt;
+
+/// Largest score, in centipawns either way, that counts toward a move's loss.
+const SCORE_CAP: i32 = 1000;
+
+/// How bad a move was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// The PGN NAG: $6 "?!", $2 "?", or $4 "??".
+    pub fn nag(self) -> u8 {
+        match self {
+            Judgement::Inaccuracy => 6,
+            Judgement::Mistake => 2,
+            Judgement::Blunder => 4,
+        }
+    }
+}
+
+impl fmt::Display for Judgement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Judgement::Inaccuracy => "Inaccuracy",
+            Judgement::Mistake => "Mistake",
+            Judgement::Blunder => "Blunder",
+        })
+    }
+}
+
+/// Centipawn losses at which a move becomes an inaccuracy, a mistake, and a
+/// blunder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    pub inaccuracy: i32,
+    pub mistake: i32,
+    pub blunder: i32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            inaccuracy: 50,
+            mistake: 100,
+            blunder: 300,
+        }
+    }
+}
+
+impl Thresholds {
+    /// The judgement for a move losing `loss` centipawns, if it deserves one.
+    pub fn judge(&self, loss: i32) -> Option<Judgement> {
+        if loss >= self.blunder {
+            Some(Judgement::Blunder)
+        } else if loss >= self.mistake {
+            Some(Judgement::Mistake)
+        } else if loss >= self.inaccuracy {
+            Some(Judgement::Inaccuracy)
+        } else {
+            None
+        }
+    }
+}
+
+/// The review of one main-line move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveAnnotation {
+    /// Index of the move in [`Game::moves`].
+    pub index: usize,
+    pub mv: Move,
+    pub mover: Color,
+    /// The engine's choice in the position before the move.
+    pub best_move: Option<Move>,
+    /// Score of the position after the move, in centipawns from White's point
+    /// of view; mates as in [`mate_distance`](crate::engine::mate_distance).
+    pub eval: i32,
+    /// Centipawns the move gave away against `best_move`; 0 when it is the
+    /// engine's choice or scores as well.
+    pub loss: i32,
+    pub judgement: Option<Judgement>,
+}
+
+/// Search every position of the game's main line with `limits` and review each
+/// move.
+pub fn analyze(game: &Game, engine: &mut Engine, limits: SearchLimits, thresholds: &Thresholds) -> Vec<MoveAnnotation> {
+    let positions = game.positions();
+    let results: Vec<_> = positions.iter().map(|board| engine.best_move(board, limits)).collect();
+    game.moves()
+        .iter()
+        .enumerate()
+        .map(|(index, &mv)| {
+            let mover = positions[index].current_player();
+            let (before, after) = (&results[index], &results[index + 1]);
+            let loss = if before.best_move == Some(mv) {
+                0
+            } else {
+                let cap = |score: i32| score.clamp(-SCORE_CAP, SCORE_CAP);
+                (cap(before.score) - cap(-after.score)).max(0)
+            };
+            // `after.score` is from the opponent's side.
+            let eval = match mover {
+                Color::White => -after.score,
+                Color::Black => after.score,
+            };
+            MoveAnnotation {
+                index,
+                mv,
+                mover,
+                best_move: before.best_move,
+                eval,
+                loss,
+                judgement: thresholds.judge(loss),
+            }
+        })
+        .collect()
+}
+
+/// Mean loss over `color`'s moves, or `None` if it made none.
+pub fn average_loss(annotations: &[MoveAnnotation], color: Color) -> Option<f64> {
+    let losses: Vec<i32> = annotations.iter().filter(|a| a.mover == color).map(|a| a.loss).collect();
+    if losses.is_empty() {
+        return None;
+    }
+    Some(losses.iter().map(|&loss| f64::from(loss)).sum::<f64>() / losses.len() as f64)
+}
+
+impl Game {
+    /// Review the main line as [`analyze`] does, store each move's eval in the
+    /// tree, and mark each judged move with its NAG and a comment such as
+    /// "Blunder. Nf3 was best.", after any comment already there, for PGN export.
+    pub fn annotate(&mut self, engine: &mut Engine, limits: SearchLimits, thresholds: &Thresholds) -> Vec<MoveAnnotation> {
+        let annotations = analyze(self, engine, limits, thresholds);
+        let positions = self.positions();
+        let mainline = self.tree().mainline();
+        for annotation in &annotations {
+            self.tree_mut().set_eval(mainline[annotation.index], Some(annotation.eval));
+            let Some(judgement) = annotation.judgement else {
+                continue;
+            };
+            let node = mainline[annotation.index];
+            let mut comment = format!("{}.", judgement);
+            if let Some(best) = annotation.best_move {
+                comment += &format!(" {} was best.", best.to_san(&positions[annotation.index]));
+            }
+            if let Some(existing) = self.tree().node(node).comment() {
+                comment = format!("{} {}", existing, comment);
+            }
+            let tree = self.tree_mut();
+            tree.add_nag(node, judgement.nag());
+            tree.set_comment(node, Some(comment));
+        }
+        annotations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MATE_SCORE;
+
+    #[test]
+    fn test_thresholds_judge_losses() {
+        let thresholds = Thresholds::default();
+        assert_eq!(thresholds.judge(49), None);
+        assert_eq!(thresholds.judge(50), Some(Judgement::Inaccuracy));
+        assert_eq!(thresholds.judge(150), Some(Judgement::Mistake));
+        assert_eq!(thresholds.judge(300), Some(Judgement::Blunder));
+        let strict = Thresholds { inaccuracy: 10, mistake: 20, blunder: 30 };
+        assert_eq!(strict.judge(30).map(Judgement::nag), Some(4));
+    }
+
+    #[test]
+    fn test_annotate_marks_the_blunder_allowing_mate() {
+        let mut game = Game::new();
+        for san in ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"] {
+            let mv = Move::from_san(san, game.board()).unwrap();
+            game.play(mv).unwrap();
+        }
+        let annotations = game.annotate(&mut Engine::new(), SearchLimits::depth(3), &Thresholds::default());
+        assert_eq!(annotations.len(), 7);
+
+        let nf6 = &annotations[5];
+        assert_eq!((nf6.mover, nf6.judgement), (Color::Black, Some(Judgement::Blunder)));
+        assert_eq!(nf6.eval, MATE_SCORE - 1);
+        let mate = &annotations[6];
+        assert_eq!((mate.loss, mate.judgement, mate.eval), (0, None, MATE_SCORE));
+
+        let node = game.tree().mainline()[5];
+        assert_eq!(game.tree().node(node).nags(), &[4]);
+        assert_eq!(game.tree().node(node).eval(), Some(MATE_SCORE - 1));
+        assert!(game.tree().node(node).comment().unwrap().starts_with("Blunder. "));
+        assert!(game.to_pgn().contains("Nf6 $4"));
+        assert!(average_loss(&annotations, Color::Black).unwrap() > average_loss(&annotations, Color::White).unwrap());
+    }
+}
@@ -0,0 +1,113 @@
+// Spoken-style announcements of moves and game events for voice clients and
+// screen readers: "Black knight captures on f6, check" rather than "Nxf6+".
+// Pieces and actions are spelled out, and every sentence names the side, so a
+// listener who missed a move still knows whose turn it is. English only.
+
+use crate::board::Board;
+use crate::game::{GameEvent, GameResult};
+use crate::moves::{CastlingSide, Move};
+use crate::pieces::PieceType;
+use crate::status::GameStatus;
+
+/// Describe `mv`, a legal move on `board`, as it would be spoken, with
+/// ", check", ", checkmate" or ", stalemate" when the move brings one about.
+/// The starting square is named only when another piece of the same kind
+/// could also have gone to the same square.
+pub fn announce_move(board: &Board, mv: Move) -> String {
+    let mv = board.annotate_move(mv);
+    let mover = board.current_player();
+    let mut text = match (mv.castling, mv.drop) {
+        (Some(CastlingSide::Kingside), _) => format!("{} castles kingside", mover),
+        (Some(CastlingSide::Queenside), _) => format!("{} castles queenside", mover),
+        (None, Some(piece_type)) => format!("{} drops a {} on {}", mover, spoken(piece_type), mv.to),
+        (None, None) => {
+            let piece_type = mv.piece.unwrap_or(PieceType::Pawn);
+            let ambiguous = board.legal_moves(mover).iter().any(|other| {
+                other.to == mv.to && other.from != mv.from && board.annotate_move(*other).piece == mv.piece
+            });
+            let from = if ambiguous { format!(" from {}", mv.from) } else { String::new() };
+            let action = match (mv.capture, mv.en_passant) {
+                (_, true) => format!("captures en passant on {}", mv.to),
+                (Some(_), false) => format!("captures on {}", mv.to),
+                (None, false) => format!("to {}", mv.to),
+            };
+            format!("{} {}{} {}", mover, spoken(piece_type), from, action)
+        }
+    };
+    if let Some(promotion) = mv.promotion {
+        text.push_str(&format!(" and promotes to a {}", spoken(promotion)));
+    }
+    let mut after = board.clone();
+    if after.apply_move(mv).is_ok() {
+        match after.game_status() {
+            GameStatus::Checkmate { .. } => text.push_str(", checkmate"),
+            GameStatus::Stalemate => text.push_str(", stalemate"),
+            _ if after.is_in_check(after.current_player()) => text.push_str(", check"),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Describe a draw offer, resignation, or adjudication as it would be spoken.
+pub fn announce_event(event: &GameEvent) -> String {
+    match event {
+        GameEvent::Adjudicated { result, reason } => {
+            let outcome = match result.winner() {
+                Some(winner) => format!("{} wins", winner),
+                None if *result == GameResult::Draw => "draw".to_string(),
+                None => "no result".to_string(),
+            };
+            if reason.is_empty() {
+                format!("The game is adjudicated, {}", outcome)
+            } else {
+                format!("The game is adjudicated, {}: {}", outcome, reason)
+            }
+        }
+        event => event.to_string(),
+    }
+}
+
+/// Lowercase piece name for the middle of a sentence.
+fn spoken(piece_type: PieceType) -> String {
+    piece_type.to_string().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pieces::Color;
+
+    fn announce(fen: &str, uci: &str) -> String {
+        let board = Board::from_fen(fen).unwrap();
+        announce_move(&board, Move::from_uci(uci, &board).unwrap())
+    }
+
+    #[test]
+    fn test_announce_move() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(announce(start, "g1f3"), "White knight to f3");
+        assert_eq!(announce(start, "e2e4"), "White pawn to e4");
+        assert_eq!(announce("8/3K4/5B2/8/4n3/8/8/7k b - - 0 1", "e4f6"), "Black knight captures on f6, check");
+        assert_eq!(announce("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "b1d2"), "White knight from b1 to d2");
+        assert_eq!(announce("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"), "White castles kingside");
+        assert_eq!(announce("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "White rook to a8, checkmate");
+        assert_eq!(announce("k7/8/1Q6/8/8/8/8/K7 w - - 0 1", "b6c7"), "White queen to c7, stalemate");
+        assert_eq!(
+            announce("8/P6k/8/8/8/8/8/K7 w - - 0 1", "a7a8q"),
+            "White pawn to a8 and promotes to a queen"
+        );
+        assert_eq!(
+            announce("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"),
+            "White pawn captures en passant on d6"
+        );
+    }
+
+    #[test]
+    fn test_announce_event() {
+        assert_eq!(announce_event(&GameEvent::Resigned(Color::Black)), "Black resigns");
+        let reason = "Black abandoned the game".to_string();
+        let event = GameEvent::Adjudicated { result: GameResult::WhiteWins, reason };
+        assert_eq!(announce_event(&event), "The game is adjudicated, White wins: Black abandoned the game");
+    }
+}
@@ -0,0 +1,96 @@
+// proptest strategies for the core types, behind the "proptest" feature.
+// Squares, colors, and moves are uniform over their values; moves need not be
+// legal anywhere. Boards come from random playouts of legal moves from the
+// starting position, and shrink towards shorter games.
+
+use crate::board::Board;
+use crate::moves::Move;
+use crate::pieces::{Color, PieceType, Square};
+use proptest::prelude::*;
+
+impl Arbitrary for Square {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Square>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (0u8..64).prop_map(|index| Square::from_index(index).expect("index is below 64")).boxed()
+    }
+}
+
+impl Arbitrary for Color {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Color>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![Just(Color::White), Just(Color::Black)].boxed()
+    }
+}
+
+impl Arbitrary for Move {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Move>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let promotion = prop_oneof![
+            4 => Just(None),
+            1 => Just(Some(PieceType::Queen)),
+            1 => Just(Some(PieceType::Rook)),
+            1 => Just(Some(PieceType::Bishop)),
+            1 => Just(Some(PieceType::Knight)),
+        ];
+        (any::<Square>(), any::<Square>(), promotion)
+            .prop_map(|(from, to, promotion)| Move { promotion, ..Move::new(from, to) })
+            .boxed()
+    }
+}
+
+/// Positions reached by up to `max_plies` random legal moves from the start.
+/// A playout stops early when the side to move has no legal moves.
+pub fn playout_boards(max_plies: usize) -> impl Strategy<Value = Board> {
+    proptest::collection::vec(any::<prop::sample::Index>(), 0..=max_plies).prop_map(|choices| {
+        let mut board = Board::starting_position();
+        for choice in choices {
+            let moves = board.legal_moves(board.current_player());
+            if moves.is_empty() {
+                break;
+            }
+            board.make_move(*choice.get(&moves));
+        }
+        board
+    })
+}
+
+/// A position from [`playout_boards`] together with one of its legal moves.
+pub fn boards_with_move(max_plies: usize) -> impl Strategy<Value = (Board, Move)> {
+    (playout_boards(max_plies), any::<prop::sample::Index>()).prop_filter_map("no legal moves", |(board, choice)| {
+        let moves = board.legal_moves(board.current_player());
+        let mv = (!moves.is_empty()).then(|| *choice.get(&moves))?;
+        Some((board, mv))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_fen_round_trips(board in playout_boards(40)) {
+            let fen = board.to_fen();
+            prop_assert_eq!(Board::from_fen(&fen).unwrap().to_fen(), fen);
+        }
+
+        #[test]
+        fn test_undo_restores_position((mut board, mv) in boards_with_move(40)) {
+            let fen = board.to_fen();
+            board.apply_move(mv).unwrap();
+            board.undo_move();
+            prop_assert_eq!(board.to_fen(), fen);
+        }
+
+        #[test]
+        fn test_squares_round_trip(square in any::<Square>()) {
+            prop_assert_eq!(Square::from_algebraic(&square.to_algebraic()), Some(square));
+        }
+    }
+}
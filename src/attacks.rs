@@ -0,0 +1,296 @@
+// Attack queries over the board: square attacks, check and how the last move
+// gave it, x-ray attacks, and attack maps of the squares each side controls.
+// Direct attacks are answered from the board's bitboards.
+// Used by move legality, exchange evaluation, pin detection, and tactics classification.
+
+use crate::bitboard::{self, Bitboard};
+use crate::board::Board;
+use crate::pieces::{Color, PieceType, Square};
+
+/// Orthogonal ray directions (file delta, rank delta).
+pub const ORTHOGONAL: [(i32, i32); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+/// Diagonal ray directions (file delta, rank delta).
+pub const DIAGONAL: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+/// Knight jump offsets (file delta, rank delta).
+pub const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (2, 1), (2, -1), (-2, 1), (-2, -1),
+    (1, 2), (1, -2), (-1, 2), (-1, -2),
+];
+/// King step offsets (file delta, rank delta).
+pub const KING_OFFSETS: [(i32, i32); 8] = [
+    (0, 1), (0, -1), (1, 0), (-1, 0),
+    (1, 1), (1, -1), (-1, 1), (-1, -1),
+];
+
+/// A sliding piece attacking `target` through exactly one intervening `blocker`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XRay {
+    pub attacker: Square,
+    pub attacker_type: PieceType,
+    pub blocker: Square,
+    pub target: Square,
+}
+
+/// How a move gave check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckKind {
+    /// The moved piece gives check, and nothing else does.
+    Direct,
+    /// A piece the move uncovered gives check, and the moved piece does not.
+    Discovered,
+    /// Two pieces give check, so only a king move gets out of it.
+    Double,
+}
+
+/// Ray directions a piece type slides along, if it is a slider.
+pub fn slider_directions(piece_type: PieceType) -> &'static [(i32, i32)] {
+    const ALL: [(i32, i32); 8] = [
+        (0, 1), (0, -1), (1, 0), (-1, 0),
+        (1, 1), (1, -1), (-1, 1), (-1, -1),
+    ];
+    match piece_type {
+        PieceType::Queen => &ALL,
+        PieceType::Rook => &ORTHOGONAL,
+        PieceType::Bishop => &DIAGONAL,
+        _ => &[],
+    }
+}
+
+impl Board {
+    /// Whether any piece of color `by` attacks `square`.
+    pub(crate) fn is_square_attacked(&self, square: Square, by: Color) -> bool {
+        !self.attackers(square, by).is_empty()
+    }
+
+    /// Squares of all pieces of color `by` that attack `square`, in rank-then-file order.
+    /// Sliders count only when nothing stands between them and the square.
+    pub fn attackers_of(&self, square: Square, by: Color) -> Vec<Square> {
+        self.attackers(square, by).squares().collect()
+    }
+
+    /// Attackers as a set: look outward from `square` with each piece's own pattern.
+    pub(crate) fn attackers(&self, square: Square, by: Color) -> Bitboard {
+        let bb = self.bitboards();
+        let occupied = bb.occupied();
+        let queens = bb.pieces(PieceType::Queen, by);
+        // A pawn attacks `square` exactly when an opposing pawn there would attack it.
+        (bitboard::pawn_attacks(square, by.opposite()) & bb.pieces(PieceType::Pawn, by))
+            | (bitboard::knight_attacks(square) & bb.pieces(PieceType::Knight, by))
+            | (bitboard::king_attacks(square) & bb.pieces(PieceType::King, by))
+            | (bitboard::rook_attacks(square, occupied) & (bb.pieces(PieceType::Rook, by) | queens))
+            | (bitboard::bishop_attacks(square, occupied) & (bb.pieces(PieceType::Bishop, by) | queens))
+    }
+
+    /// Pieces that protect the piece on `square`: those of its own color that
+    /// attack the square. Empty if the square is empty.
+    pub fn defenders_of(&self, square: Square) -> Vec<Square> {
+        match self.piece_at_typed(square) {
+            Some((_, color)) => self.attackers_of(square, color),
+            None => Vec::new(),
+        }
+    }
+
+    /// Squares the piece on `square` attacks, blocked by whatever stands in a
+    /// slider's way. Occupied squares of either color count; empty if the
+    /// square is empty.
+    pub fn attacks_from(&self, square: Square) -> Bitboard {
+        let Some((piece_type, color)) = self.piece_at_typed(square) else {
+            return Bitboard::EMPTY;
+        };
+        let occupied = self.bitboards().occupied();
+        match piece_type {
+            PieceType::Pawn => bitboard::pawn_attacks(square, color),
+            PieceType::Knight => bitboard::knight_attacks(square),
+            PieceType::King => bitboard::king_attacks(square),
+            PieceType::Rook => bitboard::rook_attacks(square, occupied),
+            PieceType::Bishop => bitboard::bishop_attacks(square, occupied),
+            PieceType::Queen => bitboard::rook_attacks(square, occupied) | bitboard::bishop_attacks(square, occupied),
+        }
+    }
+
+    /// Every square at least one of `color`'s pieces attacks.
+    pub fn attacked_squares(&self, color: Color) -> Bitboard {
+        self.bitboards()
+            .color(color)
+            .squares()
+            .fold(Bitboard::EMPTY, |attacked, from| attacked | self.attacks_from(from))
+    }
+
+    /// How many of `color`'s pieces attack each square, indexed like
+    /// [`Bitboard::index`]: a1 is 0, h1 is 7, h8 is 63.
+    pub fn attack_map(&self, color: Color) -> [u8; 64] {
+        let mut map = [0; 64];
+        for from in self.bitboards().color(color).squares() {
+            for target in self.attacks_from(from).squares() {
+                map[Bitboard::index(target)] += 1;
+            }
+        }
+        map
+    }
+
+    /// Square of `color`'s king, if it has one on the board.
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        self.pieces_of_color(color)
+            .iter()
+            .find(|p| self.piece_type(p) == Some(PieceType::King))
+            .and_then(|p| self.piece_square(p))
+    }
+
+    /// Whether `color`'s king is currently attacked.
+    pub fn is_in_check(&self, color: Color) -> bool {
+        self.king_square(color)
+            .is_some_and(|king| self.is_square_attacked(king, color.opposite()))
+    }
+
+    /// Squares of the pieces giving check to the side to move, in rank-then-file order.
+    pub fn checkers(&self) -> Vec<Square> {
+        let color = self.current_player();
+        self.king_square(color).map_or(Vec::new(), |king| self.attackers_of(king, color.opposite()))
+    }
+
+    /// How the last move made on this board gave check, if it did; None as well
+    /// when no move has been made on it. The moved piece checks from the square
+    /// it landed on, and when castling so does the rook; any other checker was
+    /// uncovered by the move, en passant captures included.
+    pub fn last_move_check(&self) -> Option<CheckKind> {
+        let mv = self.last_move()?;
+        let mut landed = vec![mv.to];
+        let castled = mv.drop.is_none()
+            && mv.from.rank == mv.to.rank
+            && mv.from.file.abs_diff(mv.to.file) == 2
+            && self.piece_at_typed(mv.to).is_some_and(|(piece_type, _)| piece_type == PieceType::King);
+        if castled {
+            // The rook lands on the square the king crossed.
+            landed.push(Square { file: (mv.from.file + mv.to.file) / 2, rank: mv.to.rank });
+        }
+        match self.checkers().as_slice() {
+            [] => None,
+            [checker] if landed.contains(checker) => Some(CheckKind::Direct),
+            [_] => Some(CheckKind::Discovered),
+            _ => Some(CheckKind::Double),
+        }
+    }
+
+    /// All x-rays by `color`'s sliding pieces: the first piece on a ray is the
+    /// blocker and the next piece behind it is the target. Blockers and targets
+    /// may be of either color; callers filter for the motif they need.
+    pub fn xray_attacks(&self, color: Color) -> Vec<XRay> {
+        let mut xrays = Vec::new();
+        for piece in self.pieces_of_color(color) {
+            let (Some(from), Some(piece_type)) = (self.piece_square(piece), self.piece_type(piece))
+            else {
+                continue;
+            };
+            for &(df, dr) in slider_directions(piece_type) {
+                let mut hits = self.ray(from, df, dr).filter(|sq| self.piece_at(*sq).is_some());
+                if let (Some(blocker), Some(target)) = (hits.next(), hits.next()) {
+                    xrays.push(XRay {
+                        attacker: from,
+                        attacker_type: piece_type,
+                        blocker,
+                        target,
+                    });
+                }
+            }
+        }
+        xrays.sort_by_key(|x| (x.attacker.rank, x.attacker.file, x.target.rank, x.target.file));
+        xrays
+    }
+
+    /// Squares from `from` (exclusive) to the board edge in one direction.
+    pub(crate) fn ray(&self, from: Square, df: i32, dr: i32) -> impl Iterator<Item = Square> {
+        (1..8).map_while(move |step| from.offset(df * step, dr * step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+    use crate::sq;
+
+    #[test]
+    fn test_attackers_of_and_check() {
+        let board = Board::from_fen("8/4q3/8/8/7b/3n4/5N2/4K3 w - - 0 1").unwrap();
+        // The knight on d3 and queen on e7 hit e1; the bishop on h4 is blocked by f2.
+        assert_eq!(board.attackers_of(sq!("e1"), Color::Black), vec![sq!("d3"), sq!("e7")]);
+        assert!(board.is_in_check(Color::White));
+        assert!(!board.is_in_check(Color::Black));
+        assert_eq!(board.attackers_of(sq!("d3"), Color::White), vec![sq!("f2")]);
+    }
+
+    #[test]
+    fn test_attack_maps_and_defenders() {
+        let board = Board::from_fen("4k3/8/8/3p4/4P3/2N5/8/4K3 w - - 0 1").unwrap();
+        let e4 = sq!("e4");
+        let d5 = sq!("d5");
+        // The c3 knight protects e4, which the d5 pawn attacks.
+        assert_eq!(board.defenders_of(e4), vec![sq!("c3")]);
+        assert_eq!(board.attackers_of(e4, Color::Black), vec![d5]);
+        assert!(board.defenders_of(d5).is_empty());
+        assert!(board.defenders_of(sq!("a1")).is_empty());
+
+        let map = board.attack_map(Color::White);
+        // Pawn and knight both hit d5, knight and king both hit e2.
+        assert_eq!(map[Bitboard::index(d5)], 2);
+        assert_eq!(map[Bitboard::index(sq!("e2"))], 2);
+        assert_eq!(map[Bitboard::index(sq!("f5"))], 1);
+        assert_eq!(map[Bitboard::index(sq!("a8"))], 0);
+        let attacked = board.attacked_squares(Color::White);
+        assert_eq!(attacked.count() as usize, map.iter().filter(|&&n| n > 0).count());
+        assert!(attacked.contains(e4) && !attacked.contains(sq!("e5")));
+    }
+
+    #[test]
+    fn test_bishop_xrays_queen_onto_king() {
+        let board = Board::from_fen("8/6k1/8/8/3q4/8/1B6/8 w - - 0 1").unwrap();
+        assert_eq!(
+            board.xray_attacks(Color::White),
+            vec![XRay {
+                attacker: sq!("b2"),
+                attacker_type: PieceType::Bishop,
+                blocker: sq!("d4"),
+                target: sq!("g7"),
+            }]
+        );
+        // Black's queen meets at most one piece along each of its lines.
+        assert!(board.xray_attacks(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn test_non_sliders_have_no_xrays() {
+        let board = Board::from_fen("8/8/8/8/8/k7/n7/N7 w - - 0 1").unwrap();
+        assert!(board.xray_attacks(Color::White).is_empty());
+    }
+
+    #[test]
+    fn test_discovered_and_double_checks() {
+        let board = Board::from_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+        assert_eq!(board.last_move_check(), None);
+
+        let mut discovered = board.clone();
+        discovered.apply_move(Move::new(sq!("e4"), sq!("c3"))).unwrap();
+        assert_eq!(discovered.checkers(), vec![sq!("e1")]);
+        assert_eq!(discovered.last_move_check(), Some(CheckKind::Discovered));
+
+        let mut double = board.clone();
+        double.apply_move(Move::new(sq!("e4"), sq!("f6"))).unwrap();
+        assert_eq!(double.checkers(), vec![sq!("e1"), sq!("f6")]);
+        assert_eq!(double.last_move_check(), Some(CheckKind::Double));
+
+        let mut quiet = board.clone();
+        quiet.apply_move(Move::new(sq!("g1"), sq!("h1"))).unwrap();
+        assert_eq!(quiet.last_move_check(), None);
+
+        let mut direct = Board::from_fen("4k3/8/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        direct.apply_move(Move::new(sq!("a1"), sq!("a8"))).unwrap();
+        assert_eq!(direct.last_move_check(), Some(CheckKind::Direct));
+
+        // Castling checks with the rook.
+        let mut castled = Board::from_fen("5k2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        castled.apply_move(Move::new(sq!("e1"), sq!("g1"))).unwrap();
+        assert_eq!(castled.checkers(), vec![sq!("f1")]);
+        assert_eq!(castled.last_move_check(), Some(CheckKind::Direct));
+    }
+}
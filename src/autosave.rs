@@ -0,0 +1,132 @@
+// Crash recovery: games in progress kept in a directory, one save file per game
+// named by its id, and removed once the game ends. Whatever files are left after
+// a crash are the interrupted games, which `rchess recover` lists and restores.
+//
+// Each save is written to a temporary file and renamed over the old one, so a
+// crash mid-write leaves the previous save intact. A directory belongs to one
+// CLI session, GameManager, or GameStore at a time.
+
+use crate::game::{Game, GameResult};
+use crate::save::SaveError;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Extension of the save files in an autosave directory.
+pub const EXTENSION: &str = "rchs";
+
+/// An autosave directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Autosave {
+    dir: PathBuf,
+}
+
+impl Autosave {
+    /// Use `dir` for autosaves, creating it if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Autosave> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Autosave { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Save `game` under `id`, or remove its save if the game is over. Ids may
+    /// use ASCII letters, digits, '-', and '_'.
+    pub fn save(&self, id: &str, game: &Game) -> Result<(), SaveError> {
+        if game.result() != GameResult::Ongoing {
+            return Ok(self.discard(id)?);
+        }
+        let path = self.path(id)?;
+        let temporary = path.with_extension(format!("{}.tmp", EXTENSION));
+        game.save(&temporary)?;
+        fs::rename(&temporary, &path)?;
+        Ok(())
+    }
+
+    /// Remove the save of `id`, if there is one.
+    pub fn discard(&self, id: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(id)?) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Ids of the saved games, sorted.
+    pub fn interrupted(&self) -> io::Result<Vec<String>> {
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == EXTENSION)
+                && let Some(id) = path.file_stem().and_then(|stem| stem.to_str())
+                && valid_id(id)
+            {
+                ids.push(id.to_string());
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Load the game saved under `id`. Its save stays until the game is saved
+    /// again or discarded.
+    pub fn recover(&self, id: &str) -> Result<Game, SaveError> {
+        Game::load(self.path(id)?)
+    }
+
+    /// Make the directory hold exactly `games`: save those in progress under
+    /// their ids, and remove every other save, finished and vanished games alike.
+    pub fn sync(&self, games: impl IntoIterator<Item = (String, Game)>) -> Result<(), SaveError> {
+        let mut stale = self.interrupted()?;
+        for (id, game) in games {
+            self.save(&id, &game)?;
+            stale.retain(|saved| *saved != id);
+        }
+        for id in stale {
+            self.discard(&id)?;
+        }
+        Ok(())
+    }
+
+    fn path(&self, id: &str) -> io::Result<PathBuf> {
+        if !valid_id(id) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid autosave id {:?}", id)));
+        }
+        Ok(self.dir.join(format!("{}.{}", id, EXTENSION)))
+    }
+}
+
+/// Whether `id` is safe to use as a file name.
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+    use crate::pieces::Color;
+    use crate::sq;
+
+    #[test]
+    fn test_save_recover_and_sync() {
+        let dir = std::env::temp_dir().join(format!("rchess-autosave-test-{}", std::process::id()));
+        let autosave = Autosave::new(&dir).unwrap();
+        let mut game = Game::new();
+        game.play(Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        autosave.save("1", &game).unwrap();
+        autosave.save("2", &Game::new()).unwrap();
+        assert_eq!(autosave.interrupted().unwrap(), ["1", "2"]);
+        assert_eq!(autosave.recover("1").unwrap().board().to_fen(), game.board().to_fen());
+        assert!(autosave.save("../escape", &game).is_err());
+
+        let mut resigned = game.clone();
+        resigned.resign(Color::Black).unwrap();
+        autosave.sync([("1".to_string(), resigned), ("3".to_string(), game)]).unwrap();
+        let interrupted = autosave.interrupted();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(interrupted.unwrap(), ["3"]);
+    }
+}
@@ -0,0 +1,69 @@
+// Benchmark: perft and fixed-depth searches over the PERFT_SUITE positions.
+// The node total is a signature of move generation and search behaviour, so a
+// change that alters it changes the engine; nodes per second tracks speed.
+
+use crate::board::Board;
+use crate::engine::{Engine, SearchLimits};
+use crate::perft::PERFT_SUITE;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Perft depth run on every position.
+pub const PERFT_DEPTH: u32 = 3;
+/// Search depth run on every position.
+pub const SEARCH_DEPTH: u32 = 6;
+
+/// Totals over all benchmark positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BenchReport {
+    pub perft_nodes: u64,
+    pub search_nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl BenchReport {
+    /// Nodes searched and counted per second of wall time.
+    pub fn nodes_per_second(&self) -> u64 {
+        let nodes = self.perft_nodes + self.search_nodes;
+        (nodes as u128 * 1000 / self.elapsed.as_millis().max(1)) as u64
+    }
+}
+
+/// Run the benchmark at the given depths. Each search starts from an empty
+/// transposition table on one thread, so the node counts are reproducible.
+pub fn bench(perft_depth: u32, search_depth: u32) -> BenchReport {
+    let mut report = BenchReport::default();
+    let start = Instant::now();
+    let mut engine = Engine::new();
+    for case in PERFT_SUITE {
+        let board = Board::from_fen(case.fen).expect("suite FENs are valid");
+        report.perft_nodes += board.perft(perft_depth);
+        engine.clear_hash();
+        report.search_nodes += engine.best_move(&board, SearchLimits::depth(search_depth)).nodes;
+    }
+    report.elapsed = start.elapsed();
+    report
+}
+
+/// Run the standard benchmark and print its summary to `output`.
+pub fn run<W: Write>(mut output: W) -> io::Result<()> {
+    let report = bench(PERFT_DEPTH, SEARCH_DEPTH);
+    writeln!(output, "Perft nodes: {}", report.perft_nodes)?;
+    writeln!(output, "Nodes searched: {}", report.search_nodes)?;
+    writeln!(output, "Total time (ms): {}", report.elapsed.as_millis())?;
+    writeln!(output, "Nodes/second: {}", report.nodes_per_second())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_signature_is_reproducible() {
+        let first = bench(1, 2);
+        let expected: u64 = PERFT_SUITE.iter().map(|case| case.nodes[0]).sum();
+        assert_eq!(first.perft_nodes, expected);
+        assert!(first.search_nodes > 0);
+        assert_eq!(bench(1, 2).search_nodes, first.search_nodes);
+    }
+}
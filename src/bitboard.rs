@@ -0,0 +1,274 @@
+// Bitboards: 64-bit square sets, one bit per square (bit = rank * 8 + file, a1 = bit 0).
+// Board keeps a set per color and piece type in sync with its proto state for fast
+// occupancy and attack queries.
+
+use crate::pieces::{Color, PieceType, Square};
+use std::ops::{BitAnd, BitOr, Not};
+use std::sync::OnceLock;
+
+/// A set of squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+
+    /// Bit index of a square.
+    pub fn index(square: Square) -> usize {
+        (square.rank * 8 + square.file) as usize
+    }
+
+    pub fn from_square(square: Square) -> Self {
+        Bitboard(1 << Bitboard::index(square))
+    }
+
+    pub fn contains(&self, square: Square) -> bool {
+        self.0 & (1 << Bitboard::index(square)) != 0
+    }
+
+    pub fn insert(&mut self, square: Square) {
+        self.0 |= 1 << Bitboard::index(square);
+    }
+
+    pub fn remove(&mut self, square: Square) {
+        self.0 &= !(1 << Bitboard::index(square));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn count(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Squares in the set, from a1 towards h8.
+    pub fn squares(self) -> impl Iterator<Item = Square> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let index = bits.trailing_zeros() as u8;
+            bits &= bits - 1;
+            Some(Square { file: index % 8, rank: index / 8 })
+        })
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Bitboard;
+    fn bitor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Bitboard;
+    fn bitand(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+/// Occupancy sets per color and piece type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PieceBitboards {
+    pieces: [[Bitboard; 6]; 2],
+    colors: [Bitboard; 2],
+}
+
+impl PieceBitboards {
+    pub fn insert(&mut self, square: Square, piece_type: PieceType, color: Color) {
+        self.pieces[color_index(color)][type_index(piece_type)].insert(square);
+        self.colors[color_index(color)].insert(square);
+    }
+
+    pub fn remove(&mut self, square: Square, piece_type: PieceType, color: Color) {
+        self.pieces[color_index(color)][type_index(piece_type)].remove(square);
+        self.colors[color_index(color)].remove(square);
+    }
+
+    /// Squares holding `color`'s pieces of `piece_type`.
+    pub fn pieces(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.pieces[color_index(color)][type_index(piece_type)]
+    }
+
+    /// Squares holding any of `color`'s pieces.
+    pub fn color(&self, color: Color) -> Bitboard {
+        self.colors[color_index(color)]
+    }
+
+    /// Squares holding any piece.
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn type_index(piece_type: PieceType) -> usize {
+    match piece_type {
+        PieceType::King => 0,
+        PieceType::Queen => 1,
+        PieceType::Rook => 2,
+        PieceType::Bishop => 3,
+        PieceType::Knight => 4,
+        PieceType::Pawn => 5,
+    }
+}
+
+/// The eight ray directions as (file delta, rank delta).
+const DIRECTIONS: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+/// Precomputed leaper attacks and empty-board rays.
+struct Tables {
+    knight: [Bitboard; 64],
+    king: [Bitboard; 64],
+    /// Squares a pawn of each color on a square attacks.
+    pawn: [[Bitboard; 64]; 2],
+    /// Squares from a square to the edge, per direction in `DIRECTIONS` order.
+    rays: [[Bitboard; 64]; 8],
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let offsets = |square: Square, deltas: &[(i32, i32)]| {
+            let mut set = Bitboard::EMPTY;
+            for &(df, dr) in deltas {
+                if let Some(target) = square.offset(df, dr) {
+                    set.insert(target);
+                }
+            }
+            set
+        };
+        let mut tables = Tables {
+            knight: [Bitboard::EMPTY; 64],
+            king: [Bitboard::EMPTY; 64],
+            pawn: [[Bitboard::EMPTY; 64]; 2],
+            rays: [[Bitboard::EMPTY; 64]; 8],
+        };
+        for index in 0..64u8 {
+            let square = Square { file: index % 8, rank: index / 8 };
+            let i = index as usize;
+            tables.knight[i] = offsets(square, &crate::attacks::KNIGHT_OFFSETS);
+            tables.king[i] = offsets(square, &crate::attacks::KING_OFFSETS);
+            tables.pawn[0][i] = offsets(square, &[(-1, 1), (1, 1)]);
+            tables.pawn[1][i] = offsets(square, &[(-1, -1), (1, -1)]);
+            for (d, &(df, dr)) in DIRECTIONS.iter().enumerate() {
+                let mut step = 1;
+                while let Some(target) = square.offset(df * step, dr * step) {
+                    tables.rays[d][i].insert(target);
+                    step += 1;
+                }
+            }
+        }
+        tables
+    })
+}
+
+pub fn knight_attacks(square: Square) -> Bitboard {
+    tables().knight[Bitboard::index(square)]
+}
+
+pub fn king_attacks(square: Square) -> Bitboard {
+    tables().king[Bitboard::index(square)]
+}
+
+/// Squares a pawn of `color` on `square` attacks.
+pub fn pawn_attacks(square: Square, color: Color) -> Bitboard {
+    tables().pawn[color_index(color)][Bitboard::index(square)]
+}
+
+/// Squares a slider on `square` reaches in direction (df, dr), up to and including
+/// the first occupied square.
+pub fn ray_attacks(square: Square, df: i32, dr: i32, occupied: Bitboard) -> Bitboard {
+    let Some(d) = DIRECTIONS.iter().position(|&dir| dir == (df, dr)) else {
+        return Bitboard::EMPTY;
+    };
+    let rays = &tables().rays[d];
+    let ray = rays[Bitboard::index(square)];
+    let blockers = ray.0 & occupied.0;
+    if blockers == 0 {
+        return ray;
+    }
+    // Rays towards higher bit indices meet their nearest blocker at the lowest set bit.
+    let towards_h8 = dr > 0 || (dr == 0 && df > 0);
+    let nearest = if towards_h8 {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    Bitboard(ray.0 & !rays[nearest as usize].0)
+}
+
+/// Rook attacks from `square` given the occupied squares.
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    crate::attacks::ORTHOGONAL
+        .iter()
+        .fold(Bitboard::EMPTY, |set, &(df, dr)| set | ray_attacks(square, df, dr, occupied))
+}
+
+/// Bishop attacks from `square` given the occupied squares.
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    crate::attacks::DIAGONAL
+        .iter()
+        .fold(Bitboard::EMPTY, |set, &(df, dr)| set | ray_attacks(square, df, dr, occupied))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    fn names(set: Bitboard) -> Vec<String> {
+        set.squares().map(|s| s.to_algebraic()).collect()
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let mut set = Bitboard::EMPTY;
+        set.insert(sq("a1"));
+        set.insert(sq("h8"));
+        assert!(set.contains(sq("h8")));
+        assert_eq!(set.count(), 2);
+        set.remove(sq("a1"));
+        assert_eq!(names(set), vec!["h8"]);
+        assert_eq!((set | Bitboard::from_square(sq("e4"))).count(), 2);
+        assert!((set & !set).is_empty());
+    }
+
+    #[test]
+    fn test_leaper_tables() {
+        assert_eq!(names(knight_attacks(sq("a1"))), vec!["c2", "b3"]);
+        assert_eq!(king_attacks(sq("e4")).count(), 8);
+        assert_eq!(names(pawn_attacks(sq("e4"), Color::Black)), vec!["d3", "f3"]);
+        assert_eq!(names(pawn_attacks(sq("a2"), Color::White)), vec!["b3"]);
+    }
+
+    #[test]
+    fn test_slider_attacks_stop_at_blockers() {
+        let occupied = Bitboard::from_square(sq("d6")) | Bitboard::from_square(sq("b4"));
+        let rook = rook_attacks(sq("d4"), occupied);
+        assert_eq!(rook.count(), 4 + 2 + 3 + 2);
+        assert!(rook.contains(sq("d6")) && !rook.contains(sq("d7")));
+        assert!(rook.contains(sq("b4")) && !rook.contains(sq("a4")));
+        assert_eq!(bishop_attacks(sq("a1"), Bitboard::EMPTY).count(), 7);
+        assert_eq!(names(ray_attacks(sq("h8"), -1, -1, Bitboard::from_square(sq("f6")))), vec!["f6", "g7"]);
+    }
+}
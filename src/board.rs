@@ -1,115 +1,400 @@
 // Board state and move validation logic.
 // Board struct wraps proto GameState and provides efficient indices for piece lookups.
 
-use crate::pieces::{Color, Square};
+use crate::bitboard::{self, Bitboard, PieceBitboards};
+use crate::error::Error;
+use crate::moves::{CastlingSide, Move, MoveError, MoveOutcome};
+use crate::pieces::{self, Color, PieceType, Square};
+use crate::pocket::Pocket;
 use crate::rchess::v1::{self as proto};
-use std::collections::HashMap;
+use crate::variant::{self, Standard, Variant};
+use crate::zobrist;
+use std::sync::Arc;
+
+/// Snapshot of the four castling rights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+/// Identity of a position: piece placement, side to move, castling rights, and
+/// en-passant target. Clocks and move history are ignored, so two keys compare
+/// equal exactly when the positions repeat.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PositionKey {
+    placement: Vec<(Square, PieceType, Color)>,
+    side_to_move: Color,
+    castling_rights: CastlingRights,
+    en_passant_target: Option<Square>,
+    pockets: (Pocket, Pocket),
+}
+
+/// A piece that has left the board, with the metadata recorded when it was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapturedPiece {
+    pub piece_type: PieceType,
+    pub color: Color,
+    /// Capture details; `None` for states that only set the `captured` flag.
+    pub capture: Option<CaptureInfo>,
+}
+
+/// Who captured a piece, where, and on which move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureInfo {
+    pub captured_by: Color,
+    pub square: Option<Square>,
+    pub move_number: i32,
+    pub ply: i32,
+}
+
+/// What a move changed, so it can be taken back: the prior contents of every
+/// piece slot it touched plus the scalar state it overwrote.
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    mv: Move,
+    pieces: Vec<(usize, proto::Piece)>,
+    current_player: i32,
+    castling_rights: CastlingRights,
+    en_passant_target: Option<proto::Position>,
+    halfmove_clock: i32,
+    fullmove_number: i32,
+    checks_given: (i32, i32),
+    pockets: (Option<proto::Pocket>, Option<proto::Pocket>),
+    // Whether the move added a slot to the piece list, as drops do
+    added_slot: bool,
+}
 
 /// Board wraps proto GameState and provides efficient piece lookup and move validation.
 #[derive(Debug, Clone)]
 pub struct Board {
     inner: proto::GameState,
-    // Efficient index: Square → Piece (cached from inner.board.pieces)
-    square_to_piece: HashMap<Square, proto::Piece>,
-    // Cached lists of pieces by color for quick filtering
-    white_pieces: Vec<proto::Piece>,
-    black_pieces: Vec<proto::Piece>,
+    // Efficient index: square (rank * 8 + file) → slot in inner.board.pieces
+    squares: [Option<usize>; 64],
+    // Slots of each color's live pieces, in proto order
+    white_pieces: Vec<usize>,
+    black_pieces: Vec<usize>,
+    // Occupancy sets per color and piece type, for attack and occupancy queries
+    bitboards: PieceBitboards,
+    // Zobrist hash of the piece placement alone; see zobrist_key
+    placement_key: u64,
+    // Moves made on this board that can be undone, and undone moves that can be redone
+    history: Vec<HistoryEntry>,
+    redo_stack: Vec<Move>,
+    // Rules in force; see the variant module
+    variant: Arc<dyn Variant>,
 }
 
 impl Board {
     /// Create a new board from a GameState proto, building indices.
+    /// An unknown variant name is played as standard chess.
+    #[deprecated(note = "accepts invalid colors, squares, and variants; use `Board::try_from_proto`")]
     pub fn from_proto(proto: proto::GameState) -> Self {
+        Board::from_proto_unchecked(proto)
+    }
+
+    /// Create a new board from a GameState proto, checking the side to move, the
+    /// variant name, the en-passant target, and every piece on the board.
+    pub fn try_from_proto(proto: proto::GameState) -> Result<Self, Error> {
+        Color::try_from_proto(proto.current_player)?;
+        if variant::from_name(&proto.variant).is_none() {
+            return Err(Error::UnknownVariant(proto.variant));
+        }
+        if let Some(target) = &proto.en_passant_target {
+            Square::try_from_proto(target)?;
+        }
+        for piece in proto.board.iter().flat_map(|board| &board.pieces) {
+            if !piece.captured {
+                pieces::try_from_proto_piece(piece)?;
+            }
+        }
+        Ok(Board::from_proto_unchecked(proto))
+    }
+
+    /// Create from a GameState proto built or checked by this crate.
+    pub(crate) fn from_proto_unchecked(proto: proto::GameState) -> Self {
+        let variant = variant::from_name(&proto.variant).unwrap_or_else(|| Arc::new(Standard));
         let mut board = Board {
             inner: proto,
-            square_to_piece: HashMap::new(),
+            squares: [None; 64],
             white_pieces: Vec::new(),
             black_pieces: Vec::new(),
+            bitboards: PieceBitboards::default(),
+            placement_key: 0,
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            variant,
         };
         board.rebuild_indices();
         board
     }
 
+    /// Create a board in the standard starting position with White to move.
+    pub fn starting_position() -> Self {
+        let back_rank = [
+            PieceType::Rook, PieceType::Knight, PieceType::Bishop, PieceType::Queen,
+            PieceType::King, PieceType::Bishop, PieceType::Knight, PieceType::Rook,
+        ];
+        let mut board_pieces = Vec::new();
+        for (color, prefix, home, pawn_rank) in [(Color::White, 'w', 0, 1), (Color::Black, 'b', 7, 6)] {
+            for (file, &piece_type) in back_rank.iter().enumerate() {
+                let mut piece = pieces::new_proto_piece(piece_type, color, Square { file: file as u8, rank: home });
+                // Ids follow the "wR1", "bK" convention from pieces.proto.
+                piece.id = match piece_type {
+                    PieceType::King | PieceType::Queen => format!("{}{}", prefix, piece_type.letter()),
+                    _ => format!("{}{}{}", prefix, piece_type.letter(), if file < 4 { 1 } else { 2 }),
+                };
+                board_pieces.push(piece);
+            }
+            for file in 0..8u8 {
+                let mut pawn = pieces::new_proto_piece(PieceType::Pawn, color, Square { file, rank: pawn_rank });
+                pawn.id = format!("{}P{}", prefix, file + 1);
+                board_pieces.push(pawn);
+            }
+        }
+
+        Board::from_proto_unchecked(proto::GameState {
+            board: Some(proto::Board { pieces: board_pieces }),
+            current_player: Color::White.to_proto(),
+            white_kingside_castling: true,
+            white_queenside_castling: true,
+            black_kingside_castling: true,
+            black_queenside_castling: true,
+            fullmove_number: 1,
+            ..Default::default()
+        })
+    }
+
     /// Convert back to proto GameState.
     pub fn to_proto(&self) -> proto::GameState {
         self.inner.clone()
     }
 
+    /// Rules this board is played under.
+    pub fn variant(&self) -> &dyn Variant {
+        self.variant.as_ref()
+    }
+
+    /// Switch to another variant's rules. Standard chess is stored as an empty name.
+    pub fn set_variant(&mut self, variant: Arc<dyn Variant>) {
+        self.inner.variant = match variant.name() {
+            "standard" => String::new(),
+            name => name.to_string(),
+        };
+        self.variant = variant;
+    }
+
+    /// Checks `color` has given, in variants that count them.
+    pub fn checks_given(&self, color: Color) -> u32 {
+        let checks = match color {
+            Color::White => self.inner.white_checks_given,
+            Color::Black => self.inner.black_checks_given,
+        };
+        checks.max(0) as u32
+    }
+
+    /// Pieces `color` holds in hand, in variants with drops.
+    pub fn pocket(&self, color: Color) -> Pocket {
+        match color {
+            Color::White => Pocket::from_proto(self.inner.white_pocket.as_ref()),
+            Color::Black => Pocket::from_proto(self.inner.black_pocket.as_ref()),
+        }
+    }
+
+    /// Replace the pieces `color` holds in hand.
+    pub fn set_pocket(&mut self, color: Color, pocket: Pocket) {
+        let pocket = Some(pocket.to_proto());
+        match color {
+            Color::White => self.inner.white_pocket = pocket,
+            Color::Black => self.inner.black_pocket = pocket,
+        }
+    }
+
     /// Rebuild internal indices from the proto pieces list.
-    /// Call this after modifying the pieces.
+    /// Only needed when the whole list is replaced; moves update indices incrementally.
     fn rebuild_indices(&mut self) {
-        self.square_to_piece.clear();
+        self.squares = [None; 64];
         self.white_pieces.clear();
         self.black_pieces.clear();
+        self.bitboards = PieceBitboards::default();
+        self.placement_key = 0;
 
-        if let Some(board) = &self.inner.board {
-            for piece in &board.pieces {
-                if piece.captured {
-                    continue;
-                }
+        let slots = self.inner.board.as_ref().map_or(0, |b| b.pieces.len());
+        for slot in 0..slots {
+            self.index_slot(slot);
+        }
+    }
 
-                // Add to square-to-piece map
-                if let Some(square) = self.piece_square(piece) {
-                    self.square_to_piece.insert(square, piece.clone());
-                }
+    /// Add the piece in proto slot `slot` to the indices, unless it is captured.
+    fn index_slot(&mut self, slot: usize) {
+        let Some(piece) = self.inner.board.as_ref().map(|b| &b.pieces[slot]) else {
+            return;
+        };
+        if piece.captured {
+            return;
+        }
+        let square = self.piece_square(piece);
+        let piece_type = self.piece_type(piece);
+        let Some(color) = self.piece_color(piece) else {
+            return;
+        };
 
-                // Add to color-filtered lists
-                if let Some(color) = self.piece_color(piece) {
-                    match color {
-                        Color::White => self.white_pieces.push(piece.clone()),
-                        Color::Black => self.black_pieces.push(piece.clone()),
-                    }
-                }
+        if let Some(square) = square {
+            self.squares[Bitboard::index(square)] = Some(slot);
+            if let Some(piece_type) = piece_type {
+                self.bitboards.insert(square, piece_type, color);
+                self.placement_key ^= zobrist::piece_key(piece_type, color, square);
             }
         }
+        let list = match color {
+            Color::White => &mut self.white_pieces,
+            Color::Black => &mut self.black_pieces,
+        };
+        if let Err(pos) = list.binary_search(&slot) {
+            list.insert(pos, slot);
+        }
+    }
+
+    /// Remove the piece in proto slot `slot` from the indices; the inverse of `index_slot`.
+    fn unindex_slot(&mut self, slot: usize) {
+        let Some(piece) = self.inner.board.as_ref().map(|b| &b.pieces[slot]) else {
+            return;
+        };
+        if piece.captured {
+            return;
+        }
+        let square = self.piece_square(piece);
+        let piece_type = self.piece_type(piece);
+        let Some(color) = self.piece_color(piece) else {
+            return;
+        };
+
+        if let Some(square) = square {
+            let index = Bitboard::index(square);
+            if self.squares[index] == Some(slot) {
+                self.squares[index] = None;
+            }
+            if let Some(piece_type) = piece_type {
+                self.bitboards.remove(square, piece_type, color);
+                self.placement_key ^= zobrist::piece_key(piece_type, color, square);
+            }
+        }
+        let list = match color {
+            Color::White => &mut self.white_pieces,
+            Color::Black => &mut self.black_pieces,
+        };
+        if let Ok(pos) = list.binary_search(&slot) {
+            list.remove(pos);
+        }
     }
 
     /// Get the piece at a given square, if any.
     pub fn piece_at(&self, square: Square) -> Option<&proto::Piece> {
-        self.square_to_piece.get(&square)
+        let slot = self.squares[Bitboard::index(square)]?;
+        self.inner.board.as_ref().map(|b| &b.pieces[slot])
+    }
+
+    /// Type and color of the piece at a given square, if any.
+    pub fn piece_at_typed(&self, square: Square) -> Option<(PieceType, Color)> {
+        let piece = self.piece_at(square)?;
+        Some((self.piece_type(piece)?, self.piece_color(piece)?))
     }
 
     /// Check if a square is empty or contains an opponent's piece.
     pub fn is_empty_or_capturable(&self, square: Square, color: Color) -> bool {
-        if let Some(piece) = self.piece_at(square) {
-            // Square has a piece; check if it's an opponent
-            let piece_color = self.piece_color(piece);
-            piece_color != Some(color)
-        } else {
-            // Square is empty
-            true
-        }
+        !self.bitboards.color(color).contains(square)
+    }
+
+    /// Zobrist hash of the piece placement, without side to move, castling, or en passant.
+    pub(crate) fn placement_key(&self) -> u64 {
+        self.placement_key
+    }
+
+    /// Occupancy sets for the pieces on the board.
+    pub fn bitboards(&self) -> &PieceBitboards {
+        &self.bitboards
     }
 
     /// Get all pieces of a given color.
-    pub fn pieces_of_color(&self, color: Color) -> &[proto::Piece] {
-        match color {
+    pub fn pieces_of_color(&self, color: Color) -> Vec<&proto::Piece> {
+        let slots = match color {
             Color::White => &self.white_pieces,
             Color::Black => &self.black_pieces,
-        }
+        };
+        let Some(board) = &self.inner.board else {
+            return Vec::new();
+        };
+        slots.iter().map(|&slot| &board.pieces[slot]).collect()
     }
 
-    /// Get all non-captured pieces.
+    /// Get all non-captured pieces, in square order from a1.
     pub fn all_pieces(&self) -> impl Iterator<Item = &proto::Piece> {
-        self.square_to_piece.values()
+        self.squares.iter().filter_map(|slot| {
+            let slot = (*slot)?;
+            self.inner.board.as_ref().map(|b| &b.pieces[slot])
+        })
+    }
+
+    /// Get all captured pieces in capture order; pieces lacking metadata come last.
+    pub fn captured_pieces(&self) -> Vec<CapturedPiece> {
+        let Some(board) = &self.inner.board else {
+            return Vec::new();
+        };
+        let mut captured: Vec<CapturedPiece> = board
+            .pieces
+            .iter()
+            .filter(|piece| piece.captured)
+            .filter_map(|piece| {
+                Some(CapturedPiece {
+                    piece_type: self.piece_type(piece)?,
+                    color: self.piece_color(piece)?,
+                    capture: piece.capture.as_ref().map(|c| CaptureInfo {
+                        captured_by: Color::from_checked_proto(c.captured_by),
+                        square: c.square.as_ref().and_then(Square::from_proto),
+                        move_number: c.move_number,
+                        ply: c.ply,
+                    }),
+                })
+            })
+            .collect();
+        captured.sort_by_key(|c| c.capture.map_or(i32::MAX, |info| info.ply));
+        captured
     }
 
     /// Get the color of a piece from its proto representation.
-    fn piece_color(&self, piece: &proto::Piece) -> Option<Color> {
+    pub(crate) fn piece_color(&self, piece: &proto::Piece) -> Option<Color> {
         if let Some(kind) = &piece.kind {
             match kind {
-                proto::piece::Kind::King(k) => Some(Color::from_proto(k.color)),
-                proto::piece::Kind::Queen(q) => Some(Color::from_proto(q.color)),
-                proto::piece::Kind::Knight(n) => Some(Color::from_proto(n.color)),
-                proto::piece::Kind::Bishop(b) => Some(Color::from_proto(b.color)),
-                proto::piece::Kind::Pawn(p) => Some(Color::from_proto(p.color)),
+                proto::piece::Kind::King(k) => Some(Color::from_checked_proto(k.color)),
+                proto::piece::Kind::Queen(q) => Some(Color::from_checked_proto(q.color)),
+                proto::piece::Kind::Knight(n) => Some(Color::from_checked_proto(n.color)),
+                proto::piece::Kind::Bishop(b) => Some(Color::from_checked_proto(b.color)),
+                proto::piece::Kind::Pawn(p) => Some(Color::from_checked_proto(p.color)),
+                proto::piece::Kind::Rook(r) => Some(Color::from_checked_proto(r.color)),
             }
         } else {
             None
         }
     }
 
+    /// Get the type of a piece from its proto representation; promoted pawns report their new type.
+    pub(crate) fn piece_type(&self, piece: &proto::Piece) -> Option<PieceType> {
+        piece.kind.as_ref().map(|kind| match kind {
+            proto::piece::Kind::King(_) => PieceType::King,
+            proto::piece::Kind::Queen(_) => PieceType::Queen,
+            proto::piece::Kind::Knight(_) => PieceType::Knight,
+            proto::piece::Kind::Bishop(_) => PieceType::Bishop,
+            proto::piece::Kind::Pawn(p) => PieceType::from_proto(p.promoted_to)
+                .filter(PieceType::is_promotion_target)
+                .unwrap_or(PieceType::Pawn),
+            proto::piece::Kind::Rook(_) => PieceType::Rook,
+        })
+    }
+
     /// Get the square of a piece from its proto representation.
-    fn piece_square(&self, piece: &proto::Piece) -> Option<Square> {
+    pub(crate) fn piece_square(&self, piece: &proto::Piece) -> Option<Square> {
         if let Some(kind) = &piece.kind {
             match kind {
                 proto::piece::Kind::King(k) => k.position.as_ref().and_then(Square::from_proto),
@@ -117,6 +402,7 @@ impl Board {
                 proto::piece::Kind::Knight(n) => n.position.as_ref().and_then(Square::from_proto),
                 proto::piece::Kind::Bishop(b) => b.position.as_ref().and_then(Square::from_proto),
                 proto::piece::Kind::Pawn(p) => p.position.as_ref().and_then(Square::from_proto),
+                proto::piece::Kind::Rook(r) => r.position.as_ref().and_then(Square::from_proto),
             }
         } else {
             None
@@ -130,42 +416,16 @@ impl Board {
         color: Color,
         directions: &[(i32, i32)],
     ) -> Vec<Square> {
-        let mut moves = Vec::new();
-
-        for &(df, dr) in directions {
-            let mut file = from.file as i32;
-            let mut rank = from.rank as i32;
-
-            loop {
-                file += df;
-                rank += dr;
-
-                if file < 0 || file > 7 || rank < 0 || rank > 7 {
-                    break;
-                }
-
-                if let Some(target) = Square::new(file as u8, rank as u8) {
-                    if self.is_empty_or_capturable(target, color) {
-                        moves.push(target);
-                        // If there's an opponent piece, stop sliding in this direction
-                        if let Some(piece) = self.piece_at(target) {
-                            if self.piece_color(piece) != Some(color) {
-                                break;
-                            }
-                        }
-                    } else {
-                        // Square occupied by own piece, stop sliding
-                        break;
-                    }
-                }
-            }
-        }
-
-        moves
+        let occupied = self.bitboards.occupied();
+        let own = self.bitboards.color(color);
+        directions
+            .iter()
+            .flat_map(|&(df, dr)| (bitboard::ray_attacks(from, df, dr, occupied) & !own).squares())
+            .collect()
     }
 
     /// Get all valid pawn moves from a given square.
-    pub fn pawn_moves(&self, from: Square, color: Color, has_moved: bool) -> Vec<Square> {
+    pub fn pawn_moves(&self, from: Square, color: Color) -> Vec<Square> {
         let mut moves = Vec::new();
         let direction = match color {
             Color::White => 1i32,
@@ -176,45 +436,466 @@ impl Board {
         if let Some(target) = Square::new(
             from.file,
             (from.rank as i32 + direction) as u8,
-        ) {
-            if self.piece_at(target).is_none() {
-                moves.push(target);
-
-                // Two-square move from starting position
-                if !has_moved {
-                    if let Some(two_sq) = Square::new(
-                        from.file,
-                        (from.rank as i32 + 2 * direction) as u8,
-                    ) {
-                        if self.piece_at(two_sq).is_none() {
-                            moves.push(two_sq);
-                        }
-                    }
-                }
+        ) && self.piece_at(target).is_none()
+        {
+            moves.push(target);
+
+            // Two-square move from starting position
+            if from.rank == color.pawn_start_rank()
+                && let Some(two_sq) = Square::new(
+                    from.file,
+                    (from.rank as i32 + 2 * direction) as u8,
+                )
+                && self.piece_at(two_sq).is_none()
+            {
+                moves.push(two_sq);
             }
         }
 
-        // Capture moves
+        // Capture moves, including en passant onto the square a pawn just skipped
+        let en_passant_rank = match color {
+            Color::White => 5,
+            Color::Black => 2,
+        };
         for &df in &[-1i32, 1i32] {
             if let Some(target) = Square::new(
                 (from.file as i32 + df) as u8,
                 (from.rank as i32 + direction) as u8,
             ) {
-                if let Some(piece) = self.piece_at(target) {
-                    if self.piece_color(piece) == Some(color.opposite()) {
-                        moves.push(target);
-                    }
+                let capture = self
+                    .piece_at_typed(target)
+                    .is_some_and(|(_, owner)| owner == color.opposite());
+                let en_passant =
+                    target.rank == en_passant_rank && self.en_passant_target() == Some(target);
+                if capture || en_passant {
+                    moves.push(target);
                 }
-                // TODO: En-passant capture
             }
         }
 
         moves
     }
 
+    /// King destinations for the castling moves `color` may make: the right must
+    /// still be held, the king and rook must be on their home squares, the squares
+    /// between them empty, and the king not in, passing through, or landing in check.
+    pub fn castling_moves(&self, color: Color) -> Vec<Square> {
+        let rights = self.castling_rights();
+        let (rank, kingside, queenside) = match color {
+            Color::White => (0, rights.white_kingside, rights.white_queenside),
+            Color::Black => (7, rights.black_kingside, rights.black_queenside),
+        };
+        let king_from = Square { file: 4, rank };
+        let is_own = |file: u8, piece_type: PieceType| {
+            self.piece_at_typed(Square { file, rank }) == Some((piece_type, color))
+        };
+        if !is_own(4, PieceType::King) || self.is_square_attacked(king_from, color.opposite()) {
+            return Vec::new();
+        }
+
+        let mut moves = Vec::new();
+        // (allowed, rook file, files that must be empty, files the king crosses)
+        let sides: [(bool, u8, &[u8], &[u8]); 2] = [
+            (kingside, 7, &[5, 6], &[5, 6]),
+            (queenside, 0, &[1, 2, 3], &[3, 2]),
+        ];
+        for (allowed, rook_file, empty, crossed) in sides {
+            if allowed
+                && is_own(rook_file, PieceType::Rook)
+                && empty.iter().all(|&file| self.piece_at(Square { file, rank }).is_none())
+                && !crossed
+                    .iter()
+                    .any(|&file| self.is_square_attacked(Square { file, rank }, color.opposite()))
+            {
+                moves.push(Square { file: crossed[1], rank });
+            }
+        }
+        moves
+    }
+
+    /// King destinations for castling, as a set; see `castling_moves`.
+    pub(crate) fn castling_targets(&self, color: Color) -> Bitboard {
+        let mut targets = Bitboard::EMPTY;
+        for square in self.castling_moves(color) {
+            targets.insert(square);
+        }
+        targets
+    }
+
+    /// Validate a move for the side to move and apply it.
+    /// Updates the piece list, side to move, clocks, and castling rights, and
+    /// appends the move to the proto move history.
+    pub fn apply_move(&mut self, mv: Move) -> Result<MoveOutcome, MoveError> {
+        let color = match mv.drop {
+            Some(piece_type) => self.validate_drop(piece_type, mv)?,
+            None => self.validate_piece_move(mv)?,
+        };
+
+        let annotated = self.annotate_move(mv);
+        let captured = self.make_move(mv);
+        if let Err(err) = self.variant.check_legal(annotated, color, self) {
+            self.unmake_move();
+            return Err(err);
+        }
+        let check = self.is_in_check(color.opposite());
+        // A new move starts a new line; moves undone before it can no longer be redone.
+        self.redo_stack.clear();
+        Ok(MoveOutcome { captured, check })
+    }
+
+    /// The position after `mv`, leaving this board untouched. Sibling positions
+    /// can be held side by side this way without make/unmake bookkeeping; each
+    /// is a full copy, so search code should prefer `make_move`.
+    pub fn apply(&self, mv: Move) -> Result<Board, MoveError> {
+        let mut next = self.clone();
+        next.apply_move(mv)?;
+        Ok(next)
+    }
+
+    /// Check that the piece on `mv.from` belongs to the side to move and can make
+    /// the move, promotion included; returns its color.
+    fn validate_piece_move(&self, mv: Move) -> Result<Color, MoveError> {
+        let (piece_type, color) = self.piece_at_typed(mv.from).ok_or(MoveError::NoPiece(mv.from))?;
+        if color != self.current_player() {
+            return Err(MoveError::NotYourTurn { color });
+        }
+        if !self.piece_targets(mv.from, color).contains(mv.to) {
+            return Err(MoveError::IllegalPieceMove {
+                piece: piece_type,
+                from: mv.from,
+                to: mv.to,
+            });
+        }
+        let promotes = piece_type == PieceType::Pawn && (mv.to.rank == 0 || mv.to.rank == 7);
+        match mv.promotion {
+            None if promotes => return Err(MoveError::PromotionRequired),
+            Some(piece) if !promotes || !piece.is_promotion_target() => {
+                return Err(MoveError::InvalidPromotion(piece));
+            }
+            _ => {}
+        }
+        Ok(color)
+    }
+
+    /// Check that the side to move may drop `piece_type` onto `mv.to`; returns its color.
+    fn validate_drop(&self, piece_type: PieceType, mv: Move) -> Result<Color, MoveError> {
+        let color = self.current_player();
+        if !self.variant.has_drops() {
+            return Err(MoveError::VariantRule("this variant has no drops"));
+        }
+        if let Some(piece) = mv.promotion {
+            return Err(MoveError::InvalidPromotion(piece));
+        }
+        if self.piece_at(mv.to).is_some() {
+            return Err(MoveError::SquareOccupied(mv.to));
+        }
+        if self.pocket(color).count(piece_type) == 0 {
+            return Err(MoveError::NotInPocket(piece_type));
+        }
+        self.variant.check_drop(piece_type, mv.to, color)?;
+        Ok(color)
+    }
+
+    /// The last move made on this board, the one `undo_move` would take back.
+    pub fn last_move(&self) -> Option<Move> {
+        self.history.last().map(|entry| entry.mv)
+    }
+
+    /// Take back the last move made on this board and return it.
+    /// Moves that only exist in the proto history of a loaded state cannot be undone.
+    pub fn undo_move(&mut self) -> Option<Move> {
+        let mv = self.unmake_move()?;
+        self.redo_stack.push(mv);
+        Some(mv)
+    }
+
+    /// Take back the last move without making it available to redo.
+    pub(crate) fn unmake_move(&mut self) -> Option<Move> {
+        let entry = self.history.pop()?;
+        if entry.added_slot
+            && let Some(slot) = self.inner.board.as_ref().and_then(|b| b.pieces.len().checked_sub(1))
+        {
+            self.unindex_slot(slot);
+            if let Some(board) = self.inner.board.as_mut() {
+                board.pieces.truncate(slot);
+            }
+        }
+        for (slot, piece) in entry.pieces {
+            self.unindex_slot(slot);
+            if let Some(board) = self.inner.board.as_mut() {
+                board.pieces[slot] = piece;
+            }
+            self.index_slot(slot);
+        }
+        self.inner.current_player = entry.current_player;
+        self.inner.white_kingside_castling = entry.castling_rights.white_kingside;
+        self.inner.white_queenside_castling = entry.castling_rights.white_queenside;
+        self.inner.black_kingside_castling = entry.castling_rights.black_kingside;
+        self.inner.black_queenside_castling = entry.castling_rights.black_queenside;
+        self.inner.en_passant_target = entry.en_passant_target;
+        self.inner.halfmove_clock = entry.halfmove_clock;
+        self.inner.fullmove_number = entry.fullmove_number;
+        (self.inner.white_checks_given, self.inner.black_checks_given) = entry.checks_given;
+        (self.inner.white_pocket, self.inner.black_pocket) = entry.pockets;
+        self.inner.moves.pop();
+        Some(entry.mv)
+    }
+
+    /// Replay the most recently undone move and return it.
+    pub fn redo_move(&mut self) -> Option<Move> {
+        let mv = self.redo_stack.pop()?;
+        self.make_move(mv);
+        Some(mv)
+    }
+
+    /// All legal moves for `color`: pseudo-legal piece moves that do not leave
+    /// its king in check. This covers pins, and while in check leaves only
+    /// blocks, captures of the checker, and king escapes. The variant has the
+    /// final say on which moves are legal. In variants with drops, drops from
+    /// the pocket follow the piece moves.
+    /// For hot loops, see `legal_moves_iter` and `generate_into`.
+    pub fn legal_moves(&self, color: Color) -> Vec<Move> {
+        let mut moves: Vec<Move> = self.legal_moves_iter(color).collect();
+        moves.sort_by_key(|mv| (mv.is_drop(), mv.from.rank, mv.from.file, mv.to.rank, mv.to.file));
+        moves
+    }
+
+    /// Fill in `mv`'s metadata (moving piece, capture, castling, en passant) from
+    /// this position. The move is assumed to be at least pseudo-legal.
+    pub fn annotate_move(&self, mv: Move) -> Move {
+        if let Some(piece_type) = mv.drop {
+            return Move::new_drop(piece_type, mv.to);
+        }
+        let piece = self.piece_at_typed(mv.from).map(|(piece_type, _)| piece_type);
+        // A pawn moving diagonally onto an empty square captures en passant.
+        let en_passant = piece == Some(PieceType::Pawn)
+            && mv.from.file != mv.to.file
+            && self.piece_at(mv.to).is_none();
+        let capture = if en_passant {
+            Some(PieceType::Pawn)
+        } else {
+            self.piece_at_typed(mv.to).map(|(piece_type, _)| piece_type)
+        };
+        // Castling is a two-file king move.
+        let side = if mv.to.file > mv.from.file { CastlingSide::Kingside } else { CastlingSide::Queenside };
+        let castling = (piece == Some(PieceType::King) && mv.from.file.abs_diff(mv.to.file) == 2).then_some(side);
+        Move {
+            piece,
+            capture,
+            castling,
+            en_passant,
+            ..mv
+        }
+    }
+
+    /// Apply a move without validating it and return the captured piece type, if any.
+    /// The caller must ensure the move is at least pseudo-legal for the moving piece.
+    pub(crate) fn make_move(&mut self, mv: Move) -> Option<PieceType> {
+        if let Some(piece_type) = mv.drop {
+            self.make_drop(piece_type, mv.to);
+            return None;
+        }
+        let (moved_type, color) = self.piece_at_typed(mv.from)?;
+        let mv = self.annotate_move(mv);
+        let mover = self.piece_index(mv.from)?;
+        // The pawn captured en passant stands beside the mover, not on the destination.
+        let capture_square = if mv.en_passant {
+            Square { file: mv.to.file, rank: mv.from.rank }
+        } else {
+            mv.to
+        };
+        let victim = self.piece_index(capture_square);
+        let board_pieces = &self.inner.board.as_ref()?.pieces;
+        let captured = mv.capture;
+
+        // The castling rook lands on the square the king crossed.
+        let castling_rook = mv.castling.and_then(|side| {
+            let (rook_file, rook_to) = match side {
+                CastlingSide::Kingside => (7, 5),
+                CastlingSide::Queenside => (0, 3),
+            };
+            self.piece_index(Square { file: rook_file, rank: mv.from.rank })
+                .map(|i| (i, Square { file: rook_to, rank: mv.from.rank }))
+        });
+
+        // Pieces the variant removes along with the captured one, such as an atomic explosion.
+        let exploded_squares = self.variant.capture_side_effects(self, mv);
+        let exploded: Vec<usize> = exploded_squares
+            .iter()
+            .filter_map(|&square| self.piece_index(square))
+            .filter(|&i| Some(i) != victim)
+            .collect();
+        let mut touched: Vec<usize> = [Some(mover), victim, castling_rook.map(|(i, _)| i)]
+            .into_iter()
+            .flatten()
+            .collect();
+        touched.extend(exploded.iter().filter(|&&i| i != mover));
+        // In drop variants the capturer takes the piece in hand; a promoted pawn
+        // goes back to being a pawn.
+        let pocketed = victim.filter(|_| self.variant.has_drops()).and_then(|i| match board_pieces[i].kind {
+            Some(proto::piece::Kind::Pawn(_)) => Some(PieceType::Pawn),
+            _ => self.piece_type(&board_pieces[i]),
+        });
+        self.history.push(HistoryEntry {
+            mv,
+            pieces: touched.iter().map(|&i| (i, board_pieces[i].clone())).collect(),
+            current_player: self.inner.current_player,
+            castling_rights: self.castling_rights(),
+            en_passant_target: self.inner.en_passant_target.clone(),
+            halfmove_clock: self.inner.halfmove_clock,
+            fullmove_number: self.inner.fullmove_number,
+            checks_given: (self.inner.white_checks_given, self.inner.black_checks_given),
+            pockets: (self.inner.white_pocket.clone(), self.inner.black_pocket.clone()),
+            added_slot: false,
+        });
+
+        if let Some(piece_type) = pocketed {
+            let mut pocket = self.pocket(color);
+            pocket.add(piece_type);
+            self.set_pocket(color, pocket);
+        }
+
+        for &slot in &touched {
+            self.unindex_slot(slot);
+        }
+        let ply = self.inner.moves.len() as i32;
+        let move_number = self.inner.fullmove_number;
+        let board_pieces = &mut self.inner.board.as_mut()?.pieces;
+        if let Some(i) = victim {
+            board_pieces[i].captured = true;
+            board_pieces[i].capture = Some(proto::Capture {
+                captured_by: color.to_proto(),
+                square: Some(capture_square.to_proto()),
+                move_number,
+                ply,
+            });
+        }
+        set_piece_square(&mut board_pieces[mover], mv.to);
+        mark_piece_moved(&mut board_pieces[mover]);
+        if let Some(promoted) = mv.promotion
+            && let Some(proto::piece::Kind::Pawn(p)) = &mut board_pieces[mover].kind
+        {
+            p.promoted_to = promoted.to_proto();
+        }
+        if let Some((rook, rook_to)) = castling_rook {
+            set_piece_square(&mut board_pieces[rook], rook_to);
+            mark_piece_moved(&mut board_pieces[rook]);
+        }
+        for &i in &exploded {
+            board_pieces[i].captured = true;
+            board_pieces[i].capture = Some(proto::Capture {
+                captured_by: color.to_proto(),
+                square: Some(mv.to.to_proto()),
+                move_number,
+                ply,
+            });
+        }
+        for &slot in &touched {
+            self.index_slot(slot);
+        }
+
+        // Castling rights are lost when the king moves, or when a rook leaves or
+        // is captured on its home corner.
+        if moved_type == PieceType::King {
+            match color {
+                Color::White => {
+                    self.inner.white_kingside_castling = false;
+                    self.inner.white_queenside_castling = false;
+                }
+                Color::Black => {
+                    self.inner.black_kingside_castling = false;
+                    self.inner.black_queenside_castling = false;
+                }
+            }
+        }
+        for square in [mv.from, mv.to].into_iter().chain(exploded_squares) {
+            match (square.file, square.rank) {
+                (0, 0) => self.inner.white_queenside_castling = false,
+                (7, 0) => self.inner.white_kingside_castling = false,
+                (0, 7) => self.inner.black_queenside_castling = false,
+                (7, 7) => self.inner.black_kingside_castling = false,
+                _ => {}
+            }
+        }
+
+        // After a double push the skipped square becomes the en-passant target.
+        self.inner.en_passant_target = if moved_type == PieceType::Pawn
+            && mv.from.rank.abs_diff(mv.to.rank) == 2
+        {
+            Some(Square { file: mv.from.file, rank: (mv.from.rank + mv.to.rank) / 2 }.to_proto())
+        } else {
+            None
+        };
+        self.end_turn(mv, color, moved_type == PieceType::Pawn || captured.is_some());
+        captured
+    }
+
+    /// Drop a `piece_type` from the side to move's pocket onto `to`, adding a new
+    /// slot to the piece list. The caller must ensure the drop is allowed.
+    fn make_drop(&mut self, piece_type: PieceType, to: Square) {
+        let color = self.current_player();
+        let mv = Move::new_drop(piece_type, to);
+        self.history.push(HistoryEntry {
+            mv,
+            pieces: Vec::new(),
+            current_player: self.inner.current_player,
+            castling_rights: self.castling_rights(),
+            en_passant_target: self.inner.en_passant_target.clone(),
+            halfmove_clock: self.inner.halfmove_clock,
+            fullmove_number: self.inner.fullmove_number,
+            checks_given: (self.inner.white_checks_given, self.inner.black_checks_given),
+            pockets: (self.inner.white_pocket.clone(), self.inner.black_pocket.clone()),
+            added_slot: true,
+        });
+
+        let mut pocket = self.pocket(color);
+        pocket.remove(piece_type);
+        self.set_pocket(color, pocket);
+
+        let mut piece = pieces::new_proto_piece(piece_type, color, to);
+        // Set has_moved as FEN loading infers it, for every piece but a pawn on
+        // its start rank. Double pushes go by the rank, not by this flag.
+        if piece_type != PieceType::Pawn || to.rank != color.pawn_start_rank() {
+            mark_piece_moved(&mut piece);
+        }
+        let board = self.inner.board.get_or_insert_with(Default::default);
+        board.pieces.push(piece);
+        let slot = board.pieces.len() - 1;
+        self.index_slot(slot);
+
+        self.inner.en_passant_target = None;
+        self.end_turn(mv, color, piece_type == PieceType::Pawn);
+    }
+
+    /// Clocks, side to move, checks given, and move history after `color` plays `mv`.
+    fn end_turn(&mut self, mv: Move, color: Color, resets_clock: bool) {
+        if resets_clock {
+            self.inner.halfmove_clock = 0;
+        } else {
+            // Saturate: FEN accepts clocks up to i32::MAX.
+            self.inner.halfmove_clock = self.inner.halfmove_clock.saturating_add(1);
+        }
+        if color == Color::Black {
+            self.inner.fullmove_number = self.inner.fullmove_number.saturating_add(1);
+        }
+        self.inner.current_player = color.opposite().to_proto();
+        if self.variant.counts_checks() && self.is_in_check(color.opposite()) {
+            match color {
+                Color::White => self.inner.white_checks_given += 1,
+                Color::Black => self.inner.black_checks_given += 1,
+            }
+        }
+        self.inner.moves.push(mv.to_proto());
+    }
+
+    /// Index in the proto piece list of the live piece on `square`.
+    fn piece_index(&self, square: Square) -> Option<usize> {
+        self.squares[Bitboard::index(square)]
+    }
+
     /// Get current player color.
     pub fn current_player(&self) -> Color {
-        Color::from_proto(self.inner.current_player)
+        Color::from_checked_proto(self.inner.current_player)
     }
 
     /// Get castling rights.
@@ -234,6 +915,16 @@ impl Board {
         self.inner.black_queenside_castling
     }
 
+    /// Get all four castling rights at once.
+    pub fn castling_rights(&self) -> CastlingRights {
+        CastlingRights {
+            white_kingside: self.inner.white_kingside_castling,
+            white_queenside: self.inner.white_queenside_castling,
+            black_kingside: self.inner.black_kingside_castling,
+            black_queenside: self.inner.black_queenside_castling,
+        }
+    }
+
     /// Get en-passant target square, if any.
     pub fn en_passant_target(&self) -> Option<Square> {
         self.inner
@@ -242,6 +933,29 @@ impl Board {
             .and_then(Square::from_proto)
     }
 
+    /// Build the position-identity key used for repetition detection and deduplication.
+    pub fn position_key(&self) -> PositionKey {
+        let mut placement: Vec<(Square, PieceType, Color)> = self
+            .all_pieces()
+            .filter_map(|piece| {
+                Some((
+                    self.piece_square(piece)?,
+                    self.piece_type(piece)?,
+                    self.piece_color(piece)?,
+                ))
+            })
+            .collect();
+        placement.sort_by_key(|(square, _, _)| (square.rank, square.file));
+
+        PositionKey {
+            placement,
+            side_to_move: self.current_player(),
+            castling_rights: self.castling_rights(),
+            en_passant_target: self.en_passant_target(),
+            pockets: (self.pocket(Color::White), self.pocket(Color::Black)),
+        }
+    }
+
     /// Get halfmove clock (for fifty-move rule).
     pub fn halfmove_clock(&self) -> i32 {
         self.inner.halfmove_clock
@@ -253,9 +967,34 @@ impl Board {
     }
 }
 
+/// Move a proto piece to a new square.
+pub(crate) fn set_piece_square(piece: &mut proto::Piece, square: Square) {
+    let position = Some(square.to_proto());
+    match &mut piece.kind {
+        Some(proto::piece::Kind::King(k)) => k.position = position,
+        Some(proto::piece::Kind::Queen(q)) => q.position = position,
+        Some(proto::piece::Kind::Rook(r)) => r.position = position,
+        Some(proto::piece::Kind::Knight(n)) => n.position = position,
+        Some(proto::piece::Kind::Bishop(b)) => b.position = position,
+        Some(proto::piece::Kind::Pawn(p)) => p.position = position,
+        None => {}
+    }
+}
+
+/// Set the has-moved flag on pieces that track it.
+pub(crate) fn mark_piece_moved(piece: &mut proto::Piece) {
+    match &mut piece.kind {
+        Some(proto::piece::Kind::King(k)) => k.has_moved = true,
+        Some(proto::piece::Kind::Rook(r)) => r.has_moved = true,
+        Some(proto::piece::Kind::Pawn(p)) => p.has_moved = true,
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sq;
 
     #[test]
     fn test_board_creation_empty() {
@@ -264,19 +1003,36 @@ mod tests {
             current_player: 1, // White
             ..Default::default()
         };
-        let board = Board::from_proto(game_state);
+        let board = Board::try_from_proto(game_state).unwrap();
         assert_eq!(board.all_pieces().count(), 0);
         assert_eq!(board.pieces_of_color(Color::White).len(), 0);
         assert_eq!(board.pieces_of_color(Color::Black).len(), 0);
     }
 
+    #[test]
+    fn test_try_from_proto_rejects_invalid_states() {
+        let valid = Board::starting_position().to_proto();
+        assert!(Board::try_from_proto(valid.clone()).is_ok());
+
+        let no_side = proto::GameState { current_player: 0, ..valid.clone() };
+        assert_eq!(Board::try_from_proto(no_side).unwrap_err(), Error::InvalidColor(0));
+        let variant = proto::GameState { variant: "shogi".to_string(), ..valid.clone() };
+        assert_eq!(Board::try_from_proto(variant).unwrap_err(), Error::UnknownVariant("shogi".to_string()));
+        let mut bad_color = valid;
+        if let Some(proto::piece::Kind::Pawn(pawn)) = &mut bad_color.board.as_mut().unwrap().pieces[8].kind {
+            pawn.color = 9;
+        }
+        assert_eq!(Board::try_from_proto(bad_color).unwrap_err(), Error::InvalidColor(9));
+    }
+
     #[test]
     fn test_piece_at_empty_square() {
         let game_state = proto::GameState {
             board: Some(proto::Board::default()),
+            current_player: 1, // White
             ..Default::default()
         };
-        let board = Board::from_proto(game_state);
+        let board = Board::try_from_proto(game_state).unwrap();
         let sq = Square::new(4, 4).unwrap();
         assert!(board.piece_at(sq).is_none());
     }
@@ -285,14 +1041,435 @@ mod tests {
     fn test_empty_or_capturable() {
         let game_state = proto::GameState {
             board: Some(proto::Board::default()),
+            current_player: 1, // White
             ..Default::default()
         };
-        let board = Board::from_proto(game_state);
+        let board = Board::try_from_proto(game_state).unwrap();
         let sq = Square::new(4, 4).unwrap();
         assert!(board.is_empty_or_capturable(sq, Color::White));
         assert!(board.is_empty_or_capturable(sq, Color::Black));
     }
 
+    #[test]
+    fn test_position_key_ignores_clocks() {
+        let king = proto::Piece {
+            kind: Some(proto::piece::Kind::King(
+                crate::pieces::King::new(Color::White, Square::new(4, 0).unwrap()).to_proto(),
+            )),
+            ..Default::default()
+        };
+        let state = |halfmove_clock, current_player| proto::GameState {
+            board: Some(proto::Board { pieces: vec![king.clone()] }),
+            current_player,
+            halfmove_clock,
+            ..Default::default()
+        };
+        let a = Board::try_from_proto(state(0, 1)).unwrap();
+        let b = Board::try_from_proto(state(12, 1)).unwrap();
+        let c = Board::try_from_proto(state(0, 2)).unwrap();
+        assert_eq!(a.position_key(), b.position_key());
+        assert_ne!(a.position_key(), c.position_key());
+
+        let seen: std::collections::HashSet<PositionKey> =
+            [a.position_key(), b.position_key()].into_iter().collect();
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[test]
+    fn test_captured_pieces_in_capture_order() {
+        let knight = crate::pieces::Knight::new(Color::Black, Square::new(5, 5).unwrap());
+        let pawn = crate::pieces::Pawn::new(Color::White, Square::new(4, 3).unwrap());
+        let capture = |captured_by: Color, ply| proto::Capture {
+            captured_by: captured_by.to_proto(),
+            square: None,
+            move_number: ply / 2 + 1,
+            ply,
+        };
+        let game_state = proto::GameState {
+            board: Some(proto::Board {
+                pieces: vec![
+                    proto::Piece {
+                        kind: Some(proto::piece::Kind::Knight(knight.to_proto())),
+                        captured: true,
+                        capture: Some(capture(Color::White, 6)),
+                        ..Default::default()
+                    },
+                    proto::Piece {
+                        kind: Some(proto::piece::Kind::Pawn(pawn.to_proto())),
+                        captured: true,
+                        capture: Some(capture(Color::Black, 3)),
+                        ..Default::default()
+                    },
+                ],
+            }),
+            current_player: 1, // White
+            ..Default::default()
+        };
+        let board = Board::try_from_proto(game_state).unwrap();
+        assert_eq!(board.all_pieces().count(), 0);
+
+        let captured = board.captured_pieces();
+        assert_eq!(captured.len(), 2);
+        assert_eq!(captured[0].piece_type, PieceType::Pawn);
+        assert_eq!(captured[0].capture.unwrap().captured_by, Color::Black);
+        assert_eq!(captured[1].piece_type, PieceType::Knight);
+        assert_eq!(captured[1].capture.unwrap().move_number, 4);
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let board = Board::starting_position();
+        assert_eq!(board.all_pieces().count(), 32);
+        assert_eq!(board.pieces_of_color(Color::White).len(), 16);
+        assert_eq!(board.piece_at(Square::new(4, 0).unwrap()).unwrap().id, "wK");
+        assert_eq!(board.piece_at(Square::new(7, 7).unwrap()).unwrap().id, "bR2");
+        assert_eq!(board.fullmove_number(), 1);
+    }
+
+    #[test]
+    fn test_apply_move_updates_state() {
+        let mut board = Board::starting_position();
+        let outcome = board.apply_move(Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        assert_eq!(outcome, MoveOutcome { captured: None, check: false });
+        assert!(board.piece_at(Square::new(4, 1).unwrap()).is_none());
+        assert_eq!(board.piece_at(Square::new(4, 3).unwrap()).unwrap().id, "wP5");
+        assert_eq!(board.current_player(), Color::Black);
+        assert_eq!(board.fullmove_number(), 1);
+        assert_eq!(board.to_proto().moves.len(), 1);
+
+        board.apply_move(Move::new(sq!("g8"), sq!("f6"))).unwrap();
+        assert_eq!(board.fullmove_number(), 2);
+        assert_eq!(board.halfmove_clock(), 1);
+    }
+
+    #[test]
+    fn test_apply_leaves_board_untouched() {
+        let board = Board::starting_position();
+        let e4 = board.apply(Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        let d4 = board.apply(Move::new(sq!("d2"), sq!("d4"))).unwrap();
+        assert_eq!(board.to_fen(), crate::fen::STARTING_FEN);
+        assert!(e4.piece_at(sq!("e4")).is_some());
+        assert!(d4.piece_at(sq!("e4")).is_none());
+        assert!(board.apply(Move::new(sq!("e2"), sq!("e5"))).is_err());
+    }
+
+    #[test]
+    fn test_clocks_saturate() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 2147483647 2147483647").unwrap();
+        board.apply_move(Move::new(sq!("e8"), sq!("d8"))).unwrap();
+        assert_eq!(board.halfmove_clock(), i32::MAX);
+        assert_eq!(board.fullmove_number(), i32::MAX);
+    }
+
+    #[test]
+    fn test_apply_move_rejections() {
+        let mut board = Board::starting_position();
+        assert_eq!(
+            board.apply_move(Move::new(sq!("e4"), sq!("e5"))),
+            Err(MoveError::NoPiece(Square::new(4, 3).unwrap()))
+        );
+        assert_eq!(
+            board.apply_move(Move::new(sq!("e7"), sq!("e5"))),
+            Err(MoveError::NotYourTurn { color: Color::Black })
+        );
+        assert!(matches!(
+            board.apply_move(Move::new(sq!("e2"), sq!("e5"))),
+            Err(MoveError::IllegalPieceMove { piece: PieceType::Pawn, .. })
+        ));
+        assert!(matches!(
+            board.apply_move(Move::new(sq!("a1"), sq!("a3"))),
+            Err(MoveError::IllegalPieceMove { piece: PieceType::Rook, .. })
+        ));
+        // Nothing changed after the rejections.
+        assert_eq!(board.current_player(), Color::White);
+        assert!(board.to_proto().moves.is_empty());
+    }
+
+    #[test]
+    fn test_apply_move_rejects_pinned_piece() {
+        let pieces = vec![
+            pieces::new_proto_piece(PieceType::King, Color::White, Square::new(4, 0).unwrap()),
+            pieces::new_proto_piece(PieceType::Knight, Color::White, Square::new(4, 1).unwrap()),
+            pieces::new_proto_piece(PieceType::Queen, Color::Black, Square::new(4, 7).unwrap()),
+            pieces::new_proto_piece(PieceType::King, Color::Black, Square::new(0, 7).unwrap()),
+        ];
+        let mut board = Board::try_from_proto(proto::GameState {
+            board: Some(proto::Board { pieces }),
+            current_player: 1,
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(board.apply_move(Move::new(sq!("e2"), sq!("c3"))), Err(MoveError::LeavesKingInCheck));
+        assert!(board.apply_move(Move::new(sq!("e1"), sq!("d1"))).is_ok());
+    }
+
+    #[test]
+    fn test_capture_and_castling_rights() {
+        let mut board = Board::starting_position();
+        let moves = [
+            (sq!("e2"), sq!("e4")),
+            (sq!("d7"), sq!("d5")),
+            (sq!("e4"), sq!("d5")),
+            (sq!("d8"), sq!("d5")),
+            (sq!("e1"), sq!("e2")),
+        ];
+        for (from, to) in moves {
+            board.apply_move(Move::new(from, to)).unwrap();
+        }
+        assert!(!board.white_kingside_castling());
+        assert!(!board.white_queenside_castling());
+        assert!(board.black_kingside_castling());
+
+        let captured = board.captured_pieces();
+        assert_eq!(captured.len(), 2);
+        assert_eq!(captured[0].color, Color::Black);
+        assert_eq!(captured[0].capture.unwrap().ply, 2);
+        assert_eq!(captured[1].piece_type, PieceType::Pawn);
+        assert_eq!(captured[1].capture.unwrap().captured_by, Color::Black);
+    }
+
+    fn legal_targets(board: &Board, from: &str) -> Vec<String> {
+        board
+            .legal_moves(board.current_player())
+            .into_iter()
+            .filter(|m| m.from.to_algebraic() == from)
+            .map(|m| m.to.to_algebraic())
+            .collect()
+    }
+
+    #[test]
+    fn test_starting_position_has_twenty_moves() {
+        let board = Board::starting_position();
+        assert_eq!(board.legal_moves(Color::White).len(), 20);
+        assert_eq!(board.legal_moves(Color::Black).len(), 20);
+    }
+
+    #[test]
+    fn test_legal_moves_respect_pins_and_check() {
+        // The e2 knight is pinned by the e8 queen; the king may not step onto the d-file
+        // covered by the d8 rook.
+        let board = Board::from_fen("3rq2k/8/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+        assert!(legal_targets(&board, "e2").is_empty());
+        assert_eq!(legal_targets(&board, "e1"), vec!["f1", "f2"]);
+
+        // In check from the e8 queen: block on e2 with the knight, or step aside.
+        let board = Board::from_fen("4q2k/8/8/8/8/8/8/2N1K3 w - - 0 1").unwrap();
+        assert_eq!(legal_targets(&board, "c1"), vec!["e2"]);
+        assert_eq!(legal_targets(&board, "e1"), vec!["d1", "f1", "d2", "f2"]);
+    }
+
+    #[test]
+    fn test_castling_both_sides() {
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(board.castling_moves(Color::White), vec![Square::new(6, 0).unwrap(), Square::new(2, 0).unwrap()]);
+
+        board.apply_move(Move::new(sq!("e1"), sq!("g1"))).unwrap();
+        assert_eq!(board.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+        board.apply_move(Move::new(sq!("e8"), sq!("c8"))).unwrap();
+        assert_eq!(board.to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+    }
+
+    #[test]
+    fn test_castling_restrictions() {
+        // The f2 bishop gives check, so neither side may castle.
+        let board = Board::from_fen("4k3/8/8/8/8/8/5b2/R3K2R w KQ - 0 1").unwrap();
+        assert!(board.castling_moves(Color::White).is_empty());
+
+        // Queenside is blocked by the b1 knight; kingside is through an attacked f1.
+        let board = Board::from_fen("4kr2/8/8/8/8/8/8/RN2K2R w KQ - 0 1").unwrap();
+        assert!(board.castling_moves(Color::White).is_empty());
+
+        // Only b1 is attacked, which the king does not cross, so queenside is fine.
+        let board = Board::from_fen("1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        assert_eq!(board.castling_moves(Color::White), vec![Square::new(2, 0).unwrap()]);
+
+        // Moving a rook revokes only its side's right.
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        board.apply_move(Move::new(sq!("h1"), sq!("h2"))).unwrap();
+        assert!(!board.white_kingside_castling());
+        assert!(board.white_queenside_castling());
+    }
+
+    #[test]
+    fn test_en_passant_capture() {
+        let mut board = Board::starting_position();
+        let moves = [
+            (sq!("e2"), sq!("e4")),
+            (sq!("a7"), sq!("a6")),
+            (sq!("e4"), sq!("e5")),
+            (sq!("d7"), sq!("d5")),
+        ];
+        for (from, to) in moves {
+            board.apply_move(Move::new(from, to)).unwrap();
+        }
+        assert_eq!(board.en_passant_target(), Square::from_algebraic("d6"));
+        assert!(legal_targets(&board, "e5").contains(&"d6".to_string()));
+
+        let outcome = board.apply_move(Move::new(sq!("e5"), sq!("d6"))).unwrap();
+        assert_eq!(outcome.captured, Some(PieceType::Pawn));
+        assert!(board.piece_at(sq!("d5")).is_none());
+        assert_eq!(board.en_passant_target(), None);
+        let captured = board.captured_pieces();
+        assert_eq!(captured[0].capture.unwrap().square, Square::from_algebraic("d5"));
+    }
+
+    #[test]
+    fn test_en_passant_only_right_after_double_push() {
+        let mut board = Board::from_fen("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1").unwrap();
+        board.apply_move(Move::new(sq!("d7"), sq!("d5"))).unwrap();
+        board.apply_move(Move::new(sq!("e1"), sq!("e2"))).unwrap();
+        board.apply_move(Move::new(sq!("e8"), sq!("e7"))).unwrap();
+        assert!(!legal_targets(&board, "e5").contains(&"d6".to_string()));
+    }
+
+    #[test]
+    fn test_promotion() {
+        let mut board = Board::from_fen("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let a8 = sq!("a8");
+        let a7 = sq!("a7");
+        let b8 = sq!("b8");
+        assert_eq!(board.apply_move(Move::new(a7, a8)), Err(MoveError::PromotionRequired));
+        assert_eq!(
+            board.apply_move(Move::with_promotion(a7, a8, PieceType::King)),
+            Err(MoveError::InvalidPromotion(PieceType::King))
+        );
+        assert_eq!(
+            board.apply_move(Move::with_promotion(a7, a8, PieceType::Pawn)),
+            Err(MoveError::InvalidPromotion(PieceType::Pawn))
+        );
+        // The push and the b8 capture, each with four promotion choices.
+        assert_eq!(legal_targets(&board, "a7").len(), 8);
+
+        let outcome = board.apply_move(Move::with_promotion(a7, b8, PieceType::Queen)).unwrap();
+        assert_eq!(outcome.captured, Some(PieceType::Knight));
+        assert!(outcome.check);
+        assert_eq!(board.to_fen(), "1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        assert_eq!(board.to_proto().moves[0].promotion_piece_type, PieceType::Queen.to_proto());
+    }
+
+    #[test]
+    fn test_promoted_piece_moves_as_new_type() {
+        let mut board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let a7 = sq!("a7");
+        let a8 = sq!("a8");
+        board.apply_move(Move::with_promotion(a7, a8, PieceType::Knight)).unwrap();
+        board.apply_move(Move::new(sq!("e8"), sq!("d8"))).unwrap();
+        assert_eq!(legal_targets(&board, "a8"), vec!["b6", "c7"]);
+        assert!(matches!(
+            board.apply_move(Move::new(sq!("a8"), sq!("a1"))),
+            Err(MoveError::IllegalPieceMove { piece: PieceType::Knight, .. })
+        ));
+    }
+
+    #[test]
+    fn test_undo_restores_every_kind_of_move() {
+        let fen = "r3k2r/6P1/8/3pP3/8/8/8/R3K2R w KQkq d6 4 20";
+        let moves = [
+            Move::new(sq!("e5"), sq!("d6")),
+            Move::new(sq!("e8"), sq!("c8")),
+            Move::with_promotion(sq!("g7"), sq!("h8"), PieceType::Rook),
+            Move::new(sq!("c8"), sq!("b8")),
+            Move::new(sq!("e1"), sq!("g1")),
+        ];
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut fens = vec![board.to_fen()];
+        for m in moves {
+            board.apply_move(m).unwrap();
+            fens.push(board.to_fen());
+        }
+        for expected in fens.iter().rev().skip(1) {
+            assert!(board.undo_move().is_some());
+            assert_eq!(&board.to_fen(), expected);
+        }
+        assert_eq!(board.undo_move(), None);
+        assert!(board.captured_pieces().is_empty());
+        assert!(board.to_proto().moves.is_empty());
+
+        for expected in fens.iter().skip(1) {
+            assert!(board.redo_move().is_some());
+            assert_eq!(&board.to_fen(), expected);
+        }
+        assert_eq!(board.redo_move(), None);
+    }
+
+    #[test]
+    fn test_incremental_indices_match_rebuild() {
+        let mut board = Board::from_fen("r3k2r/6P1/8/3pP3/8/8/8/R3K2R w KQkq d6 4 20").unwrap();
+        let assert_consistent = |board: &Board| {
+            let rebuilt = Board::try_from_proto(board.to_proto()).unwrap();
+            assert_eq!(board.squares, rebuilt.squares);
+            assert_eq!(board.white_pieces, rebuilt.white_pieces);
+            assert_eq!(board.black_pieces, rebuilt.black_pieces);
+            assert_eq!(board.bitboards(), rebuilt.bitboards());
+        };
+        for m in [
+            Move::new(sq!("e5"), sq!("d6")),
+            Move::new(sq!("e8"), sq!("c8")),
+            Move::with_promotion(sq!("g7"), sq!("h8"), PieceType::Rook),
+            Move::new(sq!("d8"), sq!("h8")),
+        ] {
+            board.apply_move(m).unwrap();
+            assert_consistent(&board);
+        }
+        while board.undo_move().is_some() {
+            assert_consistent(&board);
+        }
+    }
+
+    #[test]
+    fn test_legal_moves_carry_metadata() {
+        let board = Board::from_fen("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let moves = board.legal_moves(Color::White);
+        let find = |from: Square, to: Square| *moves.iter().find(|m| **m == Move::new(from, to)).unwrap();
+
+        let en_passant = find(sq!("e5"), sq!("d6"));
+        assert_eq!(en_passant.piece, Some(PieceType::Pawn));
+        assert_eq!(en_passant.capture, Some(PieceType::Pawn));
+        assert!(en_passant.en_passant);
+        assert_eq!(find(sq!("e1"), sq!("c1")).castling, Some(CastlingSide::Queenside));
+        assert_eq!(find(sq!("a1"), sq!("a8")).capture, Some(PieceType::Rook));
+        let quiet = find(sq!("e5"), sq!("e6"));
+        assert!(!quiet.is_capture() && !quiet.en_passant && quiet.castling.is_none());
+
+        let mut played = board.clone();
+        played.apply_move(Move::new(sq!("e1"), sq!("g1"))).unwrap();
+        let recorded = Move::from_proto(played.to_proto().moves.last().unwrap()).unwrap();
+        assert_eq!(recorded.castling, Some(CastlingSide::Kingside));
+    }
+
+    #[test]
+    fn test_piece_at_typed() {
+        let mut board = Board::from_fen("4k3/6P1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let sq = |name: &str| Square::from_algebraic(name).unwrap();
+        assert_eq!(board.piece_at_typed(sq("e8")), Some((PieceType::King, Color::Black)));
+        assert_eq!(board.piece_at_typed(sq("g7")), Some((PieceType::Pawn, Color::White)));
+        assert_eq!(board.piece_at_typed(sq("d4")), None);
+        board.apply_move(Move::with_promotion(sq("g7"), sq("g8"), PieceType::Knight)).unwrap();
+        assert_eq!(board.piece_at_typed(sq("g8")), Some((PieceType::Knight, Color::White)));
+    }
+
+    #[test]
+    fn test_new_move_clears_redo() {
+        let mut board = Board::starting_position();
+        board.apply_move(Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        assert_eq!(board.undo_move(), Some(Move::new(sq!("e2"), sq!("e4"))));
+        board.apply_move(Move::new(sq!("d2"), sq!("d4"))).unwrap();
+        assert_eq!(board.redo_move(), None);
+        assert_eq!(board.undo_move(), Some(Move::new(sq!("d2"), sq!("d4"))));
+        assert_eq!(board.to_fen(), crate::fen::STARTING_FEN);
+    }
+
+    #[test]
+    fn test_bitboards_follow_moves() {
+        let mut board = Board::starting_position();
+        assert_eq!(board.bitboards().occupied().count(), 32);
+        board.apply_move(Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        let pawns = board.bitboards().pieces(PieceType::Pawn, Color::White);
+        assert!(pawns.contains(sq!("e4")));
+        assert!(!pawns.contains(sq!("e2")));
+        board.undo_move();
+        assert_eq!(board.bitboards(), Board::starting_position().bitboards());
+    }
+
     #[test]
     fn test_current_player() {
         let game_state = proto::GameState {
@@ -300,7 +1477,7 @@ mod tests {
             current_player: 1, // White
             ..Default::default()
         };
-        let board = Board::from_proto(game_state);
+        let board = Board::try_from_proto(game_state).unwrap();
         assert_eq!(board.current_player(), Color::White);
     }
 }
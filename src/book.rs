@@ -0,0 +1,261 @@
+// Polyglot opening books: the .bin format read by most engines and GUIs.
+//
+// A book is a list of 16-byte entries sorted by position key: key (u64), move
+// (u16), weight (u16), learn (u32), all big-endian. Keys are the Polyglot hash of
+// the position, which uses its own random numbers rather than the zobrist module's.
+
+mod keys;
+
+use crate::board::Board;
+use crate::moves::Move;
+use crate::pieces::{Color, PieceType, Square};
+use keys::RANDOM64;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// Size of one book entry in bytes.
+pub const ENTRY_SIZE: usize = 16;
+
+/// Why a book could not be read.
+#[derive(Debug)]
+pub enum BookError {
+    Io(io::Error),
+    /// The data is not a whole number of entries long.
+    Truncated(usize),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookError::Io(e) => write!(f, "{}", e),
+            BookError::Truncated(len) => {
+                write!(f, "book length {} is not a multiple of {} bytes", len, ENTRY_SIZE)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BookError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BookError::Io(e) => Some(e),
+            BookError::Truncated(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for BookError {
+    fn from(e: io::Error) -> Self {
+        BookError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    key: u64,
+    mv: u16,
+    weight: u16,
+}
+
+/// An opening book loaded into memory.
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    // Sorted by key, so the entries for a position are one contiguous run
+    entries: Vec<Entry>,
+}
+
+impl Book {
+    /// Read a book file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Book, BookError> {
+        Book::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Read a book from any reader.
+    pub fn read<R: Read>(mut input: R) -> Result<Book, BookError> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        if !data.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::Truncated(data.len()));
+        }
+        let mut entries: Vec<Entry> = data
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| Entry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                mv: u16::from_be_bytes([chunk[8], chunk[9]]),
+                weight: u16::from_be_bytes([chunk[10], chunk[11]]),
+            })
+            .collect();
+        // Books are written sorted; a stable sort keeps each position's move order.
+        entries.sort_by_key(|entry| entry.key);
+        Ok(Book { entries })
+    }
+
+    /// Number of entries in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Book moves for the position with their weights, heaviest first. Entries
+    /// whose move is not legal on `board` are skipped.
+    pub fn probe(&self, board: &Board) -> Vec<(Move, u16)> {
+        let key = board.polyglot_key();
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let legal = board.legal_moves(board.current_player());
+        let mut moves: Vec<(Move, u16)> = self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                let mv = legal.iter().find(|mv| encode_move(mv) == entry.mv & MOVE_MASK)?;
+                Some((*mv, entry.weight))
+            })
+            .collect();
+        moves.sort_by_key(|&(_, weight)| std::cmp::Reverse(weight));
+        moves
+    }
+
+    /// The heaviest book move for the position, if the book has one.
+    pub fn best_move(&self, board: &Board) -> Option<Move> {
+        self.probe(board).first().map(|&(mv, _)| mv)
+    }
+}
+
+/// Bits of an entry's move field that name the move; the rest are unused.
+const MOVE_MASK: u16 = 0x7FFF;
+
+/// Polyglot encoding of a move: to file, to row, from file, from row, and
+/// promotion piece in three bits each. Castling is written as the king taking
+/// its own rook.
+fn encode_move(mv: &Move) -> u16 {
+    let to = match mv.castling {
+        Some(_) => Square { file: if mv.to.file > mv.from.file { 7 } else { 0 }, rank: mv.to.rank },
+        None => mv.to,
+    };
+    let promotion = match mv.promotion {
+        Some(PieceType::Knight) => 1,
+        Some(PieceType::Bishop) => 2,
+        Some(PieceType::Rook) => 3,
+        Some(PieceType::Queen) => 4,
+        _ => 0,
+    };
+    to.file as u16 | (to.rank as u16) << 3 | (mv.from.file as u16) << 6 | (mv.from.rank as u16) << 9 | promotion << 12
+}
+
+/// Offset of a piece's 64 keys in `RANDOM64`.
+fn piece_offset(piece_type: PieceType, color: Color) -> usize {
+    let kind = match piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+    64 * (2 * kind + usize::from(color == Color::White))
+}
+
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const WHITE_TO_MOVE: usize = 780;
+
+impl Board {
+    /// Polyglot hash of the position, the key opening books are indexed by. The
+    /// en-passant file only counts when a pawn of the side to move stands ready
+    /// to capture there.
+    pub fn polyglot_key(&self) -> u64 {
+        let mut key = 0;
+        for rank in 0..8 {
+            for file in 0..8 {
+                if let Some((piece_type, color)) = self.piece_at_typed(Square { file, rank }) {
+                    key ^= RANDOM64[piece_offset(piece_type, color) + 8 * rank as usize + file as usize];
+                }
+            }
+        }
+        let rights = self.castling_rights();
+        for (i, held) in [rights.white_kingside, rights.white_queenside, rights.black_kingside, rights.black_queenside]
+            .into_iter()
+            .enumerate()
+        {
+            if held {
+                key ^= RANDOM64[CASTLING_OFFSET + i];
+            }
+        }
+        let side = self.current_player();
+        if let Some(target) = self.en_passant_target() {
+            // The capturing pawn stands on the rank the double push ended on.
+            let rank = if side == Color::White { target.rank - 1 } else { target.rank + 1 };
+            let capturer = [-1i8, 1]
+                .into_iter()
+                .filter_map(|df| Square::new((target.file as i8 + df) as u8, rank))
+                .any(|square| self.piece_at_typed(square) == Some((PieceType::Pawn, side)));
+            if capturer {
+                key ^= RANDOM64[EN_PASSANT_OFFSET + target.file as usize];
+            }
+        }
+        if side == Color::White {
+            key ^= RANDOM64[WHITE_TO_MOVE];
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(moves: &[&str]) -> Board {
+        let mut board = Board::starting_position();
+        for text in moves {
+            let mv = Move::from_uci(text, &board).unwrap();
+            board.apply_move(mv).unwrap();
+        }
+        board
+    }
+
+    fn entry(key: u64, mv: u16, weight: u16) -> Vec<u8> {
+        let mut bytes = key.to_be_bytes().to_vec();
+        bytes.extend(mv.to_be_bytes());
+        bytes.extend(weight.to_be_bytes());
+        bytes.extend(0u32.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_keys_match_the_format_reference() {
+        // Test positions from the Polyglot format description.
+        assert_eq!(play(&[]).polyglot_key(), 0x463B96181691FC9C);
+        assert_eq!(play(&["e2e4"]).polyglot_key(), 0x823C9B50FD114196);
+        assert_eq!(play(&["e2e4", "d7d5", "e4e5", "f7f5"]).polyglot_key(), 0x22A48B5A8E47FF78);
+        assert_eq!(play(&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"]).polyglot_key(), 0x00FDD303C946BDD9);
+        assert_eq!(play(&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"]).polyglot_key(), 0x3C8123EA7B067637);
+        assert_eq!(
+            play(&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"]).polyglot_key(),
+            0x5C3F9B829B279560
+        );
+    }
+
+    #[test]
+    fn test_probe_orders_by_weight_and_decodes_castling() {
+        let start = Board::starting_position();
+        let castle = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        // e2e4 and d2d4 from the start; e1h1, i.e. O-O, in the second position;
+        // and an entry whose move is illegal there.
+        let mut data = entry(start.polyglot_key(), 0x031C, 5);
+        data.extend(entry(start.polyglot_key(), 0x02DB, 20));
+        data.extend(entry(start.polyglot_key(), 0x0000, 50));
+        data.extend(entry(castle.polyglot_key(), 0x0107, 1));
+        let book = Book::read(data.as_slice()).unwrap();
+        assert_eq!(book.len(), 4);
+
+        let moves: Vec<(String, u16)> = book.probe(&start).iter().map(|(mv, w)| (mv.to_uci(), *w)).collect();
+        assert_eq!(moves, vec![("d2d4".to_string(), 20), ("e2e4".to_string(), 5)]);
+        assert_eq!(book.best_move(&castle).map(|mv| mv.to_uci()), Some("e1g1".to_string()));
+        assert!(book.probe(&play(&["g1f3"])).is_empty());
+        assert!(matches!(Book::read(&data[..20]), Err(BookError::Truncated(20))));
+    }
+}
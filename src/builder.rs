@@ -0,0 +1,218 @@
+// Position setup: build a board piece by piece, as a position editor or puzzle
+// loader does, instead of assembling proto messages by hand. Squares are given in
+// algebraic notation, and the finished position is checked with Board::validate.
+
+use crate::board::{self, Board, CastlingRights};
+use crate::fen;
+use crate::pieces::{self, Color, PieceType, Square};
+use crate::rchess::v1::{self as proto};
+use crate::validate::Violation;
+use std::fmt;
+
+/// Why a builder could not produce a board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// A square name that is not on the board, e.g. "i9".
+    InvalidSquare(String),
+    /// The position could not arise in a game; every rule it breaks.
+    Illegal(Vec<Violation>),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::InvalidSquare(name) => write!(f, "invalid square {:?}", name),
+            BuildError::Illegal(violations) => {
+                let reasons: Vec<String> = violations.iter().map(Violation::to_string).collect();
+                write!(f, "impossible position: {}", reasons.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// A position under construction. Starts empty, with White to move, no castling
+/// rights, and the clocks at "0 1".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardBuilder {
+    pieces: Vec<(Square, PieceType, Color)>,
+    side_to_move: Color,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    /// First bad square name, reported by `build`.
+    error: Option<BuildError>,
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        BoardBuilder::new()
+    }
+}
+
+impl BoardBuilder {
+    pub fn new() -> Self {
+        BoardBuilder {
+            pieces: Vec::new(),
+            side_to_move: Color::White,
+            castling: CastlingRights::default(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            error: None,
+        }
+    }
+
+    /// A builder holding `board`'s position, to edit. The move history is not kept.
+    pub fn from_board(board: &Board) -> Self {
+        let pieces = (0..64)
+            .filter_map(Square::from_index)
+            .filter_map(|square| board.piece_at_typed(square).map(|(piece_type, color)| (square, piece_type, color)))
+            .collect();
+        BoardBuilder {
+            pieces,
+            side_to_move: board.current_player(),
+            castling: board.castling_rights(),
+            en_passant: board.en_passant_target(),
+            halfmove_clock: board.halfmove_clock().max(0) as u32,
+            fullmove_number: board.fullmove_number().max(1) as u32,
+            error: None,
+        }
+    }
+
+    /// Put a piece on `square`, replacing any piece already there.
+    pub fn piece(mut self, color: Color, piece_type: PieceType, square: &str) -> Self {
+        if let Some(square) = self.square(square) {
+            self.pieces.retain(|&(s, _, _)| s != square);
+            self.pieces.push((square, piece_type, color));
+        }
+        self
+    }
+
+    /// Empty `square`.
+    pub fn remove(mut self, square: &str) -> Self {
+        if let Some(square) = self.square(square) {
+            self.pieces.retain(|&(s, _, _)| s != square);
+        }
+        self
+    }
+
+    pub fn castling(mut self, rights: CastlingRights) -> Self {
+        self.castling = rights;
+        self
+    }
+
+    pub fn side_to_move(mut self, color: Color) -> Self {
+        self.side_to_move = color;
+        self
+    }
+
+    /// The square a pawn passed over on a double step just played, e.g. "e3".
+    pub fn en_passant(mut self, square: &str) -> Self {
+        self.en_passant = self.square(square);
+        self
+    }
+
+    pub fn halfmove_clock(mut self, plies: u32) -> Self {
+        self.halfmove_clock = plies;
+        self
+    }
+
+    /// Number of the move about to be played; at least 1.
+    pub fn fullmove_number(mut self, number: u32) -> Self {
+        self.fullmove_number = number.max(1);
+        self
+    }
+
+    /// The board, if every square name was valid and the position could arise
+    /// in a game.
+    pub fn build(self) -> Result<Board, BuildError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let mut state = proto::GameState {
+            current_player: self.side_to_move.to_proto(),
+            white_kingside_castling: self.castling.white_kingside,
+            white_queenside_castling: self.castling.white_queenside,
+            black_kingside_castling: self.castling.black_kingside,
+            black_queenside_castling: self.castling.black_queenside,
+            en_passant_target: self.en_passant.map(|square| square.to_proto()),
+            halfmove_clock: self.halfmove_clock.min(i32::MAX as u32) as i32,
+            fullmove_number: self.fullmove_number.min(i32::MAX as u32) as i32,
+            ..Default::default()
+        };
+        let pieces = self
+            .pieces
+            .iter()
+            .map(|&(square, piece_type, color)| {
+                let mut piece = pieces::new_proto_piece(piece_type, color, square);
+                if fen::has_moved(&state, square, piece_type, color) {
+                    board::mark_piece_moved(&mut piece);
+                }
+                piece
+            })
+            .collect();
+        state.board = Some(proto::Board { pieces });
+
+        let board = Board::from_proto_unchecked(state);
+        let violations = board.validate();
+        if !violations.is_empty() {
+            return Err(BuildError::Illegal(violations));
+        }
+        Ok(board)
+    }
+
+    /// Parse a square name, keeping the first failure for `build`.
+    fn square(&mut self, name: &str) -> Option<Square> {
+        let square = Square::from_algebraic(name);
+        if square.is_none() && self.error.is_none() {
+            self.error = Some(BuildError::InvalidSquare(name.to_string()));
+        }
+        square
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::STARTING_FEN;
+
+    #[test]
+    fn test_build_a_position() {
+        let board = BoardBuilder::new()
+            .piece(Color::White, PieceType::King, "e1")
+            .piece(Color::White, PieceType::Rook, "h1")
+            .piece(Color::Black, PieceType::King, "e8")
+            .piece(Color::Black, PieceType::Pawn, "d4")
+            .piece(Color::White, PieceType::Pawn, "e4")
+            .castling(CastlingRights { white_kingside: true, ..Default::default() })
+            .side_to_move(Color::Black)
+            .en_passant("e3")
+            .fullmove_number(12)
+            .build()
+            .unwrap();
+        assert_eq!(board.to_fen(), "4k3/8/8/8/3pP3/8/8/4K2R b K e3 0 12");
+        assert!(board.legal_moves(Color::Black).iter().any(|mv| mv.en_passant));
+
+        let start = Board::starting_position();
+        let edited = BoardBuilder::from_board(&start).remove("d1").build().unwrap();
+        assert_eq!(edited.to_fen(), STARTING_FEN.replace("RNBQKBNR", "RNB1KBNR"));
+    }
+
+    #[test]
+    fn test_build_rejects_bad_squares_and_impossible_positions() {
+        let bad = BoardBuilder::new().piece(Color::White, PieceType::King, "e9").build();
+        assert_eq!(bad.unwrap_err(), BuildError::InvalidSquare("e9".to_string()));
+
+        let no_black_king = BoardBuilder::new().piece(Color::White, PieceType::King, "e1").build();
+        assert_eq!(no_black_king.unwrap_err(), BuildError::Illegal(vec![Violation::MissingKing(Color::Black)]));
+        let replaced = BoardBuilder::new()
+            .piece(Color::White, PieceType::King, "e1")
+            .piece(Color::Black, PieceType::King, "e1")
+            .build()
+            .unwrap_err();
+        assert_eq!(replaced.to_string(), "impossible position: White has no king");
+    }
+}
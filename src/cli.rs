@@ -0,0 +1,353 @@
+// Interactive terminal play: a command loop that shows the board after every move,
+// takes moves in SAN or UCI notation, and can hand either side to the engine.
+// With an autosave directory the game is saved after every command, and
+// `recover` lists the games a crash interrupted and resumes them.
+
+use crate::autosave::Autosave;
+use crate::board::Board;
+use crate::engine::{Engine, SearchLimits};
+use crate::game::{Game, GameResult};
+use crate::moves::{Move, MoveError};
+use crate::pieces::Color;
+use crate::render::text;
+use std::io::{self, BufRead, Write};
+
+/// Engine thinking time per move unless changed with "time".
+pub const DEFAULT_MOVE_TIME_MS: u64 = 1000;
+
+const HELP: &str = "\
+Enter moves in SAN (Nf3, exd5, O-O) or UCI (g1f3) notation.
+Commands:
+  new                  start a new game
+  play white|black     play that side against the engine
+  play both            play both sides yourself
+  go                   let the engine move for the side to move
+  undo                 take back your last move
+  moves                list the legal moves
+  fen [FEN]            show the position as FEN, or set it
+  time MS              engine thinking time per move
+  resign               give up the game
+  help                 show this text
+  quit                 leave";
+
+/// Read commands from `input` until "quit" or end of input, writing to `output`.
+/// The human plays White against the engine until told otherwise.
+pub fn run<R: BufRead, W: Write>(input: R, output: W) -> io::Result<()> {
+    let mut session = Session::new(output);
+    session.send("Type 'help' for commands.")?;
+    session.show()?;
+    session.run(input)
+}
+
+/// Like `run`, but saving the game to `autosave` under `id` after every
+/// command. If a game is already saved under `id`, play resumes from it with
+/// the human on move.
+pub fn run_autosaved<R: BufRead, W: Write>(input: R, output: W, autosave: Autosave, id: &str) -> io::Result<()> {
+    let mut session = Session::new(output);
+    session.send("Type 'help' for commands.")?;
+    if autosave.interrupted()?.iter().any(|saved| saved == id) {
+        session.game = autosave.recover(id).map_err(io::Error::other)?;
+        let human = session.board().current_player();
+        session.engine_color = Some(human.opposite());
+        session.send(&format!("Resuming game {}. You play {}.", id, human))?;
+    }
+    session.autosave = Some((autosave, id.to_string()));
+    session.show()?;
+    session.run(input)
+}
+
+/// The "recover" command: `recover DIR` lists the games saved in the autosave
+/// directory DIR, and `recover DIR ID` resumes game ID there, reading commands
+/// from `input`.
+pub fn recover<R: BufRead, W: Write>(args: &[String], input: R, mut output: W) -> io::Result<()> {
+    let (dir, id) = match args {
+        [dir] => (dir, None),
+        [dir, id] => (dir, Some(id)),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "usage: rchess recover DIR [ID]")),
+    };
+    let autosave = Autosave::new(dir)?;
+    if let Some(id) = id {
+        return run_autosaved(input, output, autosave, id);
+    }
+    let ids = autosave.interrupted()?;
+    if ids.is_empty() {
+        writeln!(output, "No interrupted games in {}.", dir)?;
+    }
+    for id in ids {
+        match autosave.recover(&id) {
+            Ok(game) => {
+                let board = game.board();
+                writeln!(output, "{}: {} to move, {}", id, board.current_player(), board.to_fen())?
+            }
+            Err(err) => writeln!(output, "{}: unreadable ({})", id, err)?,
+        }
+    }
+    Ok(())
+}
+
+struct Session<W> {
+    out: W,
+    engine: Engine,
+    /// The game in progress, which keeps its result once it has ended.
+    game: Game,
+    /// Side the engine plays; `None` when the human plays both.
+    engine_color: Option<Color>,
+    move_time_ms: u64,
+    /// Where the game is saved after every command, and under which id.
+    autosave: Option<(Autosave, String)>,
+}
+
+impl<W: Write> Session<W> {
+    fn new(out: W) -> Self {
+        Session {
+            out,
+            engine: Engine::new(),
+            game: Game::new(),
+            engine_color: Some(Color::Black),
+            move_time_ms: DEFAULT_MOVE_TIME_MS,
+            autosave: None,
+        }
+    }
+
+    /// Handle lines from `input` until "quit" or end of input.
+    fn run<R: BufRead>(&mut self, input: R) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?)? {
+                break;
+            }
+            self.autosave()?;
+        }
+        Ok(())
+    }
+
+    /// Save the game if autosaving; the save is removed once the game is over.
+    /// A failed save is reported and play goes on.
+    fn autosave(&mut self) -> io::Result<()> {
+        let Some((autosave, id)) = &self.autosave else {
+            return Ok(());
+        };
+        match autosave.save(id, &self.game) {
+            Ok(()) => Ok(()),
+            Err(err) => self.send(&format!("Autosave failed: {}", err)),
+        }
+    }
+
+    fn board(&self) -> &Board {
+        self.game.board()
+    }
+
+    fn is_over(&self) -> bool {
+        self.game.result() != GameResult::Ongoing
+    }
+
+    /// Handle one input line; returns false on "quit".
+    fn handle(&mut self, line: &str) -> io::Result<bool> {
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').map_or((line, ""), |(c, a)| (c, a.trim()));
+        match command {
+            "" => {}
+            "help" => self.send(HELP)?,
+            "quit" | "exit" => return Ok(false),
+            "new" => {
+                self.game = Game::new();
+                self.engine.clear_hash();
+                self.show()?;
+                self.engine_turn()?;
+            }
+            "play" => match args {
+                "white" | "black" => {
+                    let human = if args == "white" { Color::White } else { Color::Black };
+                    self.engine_color = Some(human.opposite());
+                    self.send(&format!("You play {}.", human))?;
+                    self.engine_turn()?;
+                }
+                "both" => {
+                    self.engine_color = None;
+                    self.send("You play both sides.")?;
+                }
+                _ => self.send("Usage: play white|black|both")?,
+            },
+            "go" => {
+                if !self.is_over() {
+                    self.engine_move()?;
+                }
+            }
+            "undo" => self.undo()?,
+            "moves" => {
+                let board = self.board();
+                let moves: Vec<String> =
+                    board.legal_moves(board.current_player()).iter().map(|mv| mv.to_san(board)).collect();
+                self.send(&moves.join(" "))?;
+            }
+            "fen" if args.is_empty() => self.send(&self.board().to_fen())?,
+            "fen" => match Board::from_fen(args) {
+                Ok(board) => {
+                    self.game = Game::from_position(board);
+                    self.show()?;
+                    self.engine_turn()?;
+                }
+                Err(err) => self.send(&format!("Invalid FEN: {}", err))?,
+            },
+            "time" => match args.parse() {
+                Ok(ms) => self.move_time_ms = ms,
+                Err(_) => self.send("Usage: time MS")?,
+            },
+            "resign" => self.resign()?,
+            _ => self.user_move(line)?,
+        }
+        Ok(true)
+    }
+
+    fn user_move(&mut self, text: &str) -> io::Result<()> {
+        let board = self.board();
+        let mv = Move::from_uci(text, board).or_else(|_| Move::from_san(text, board));
+        match mv {
+            Ok(mv) => {
+                // Both parsers only return legal moves.
+                self.play(mv)?;
+                self.engine_turn()
+            }
+            Err(err) => {
+                // Say why a move given in coordinates is illegal, not just that it is.
+                let reason = Move::parse_uci(text).and_then(|mv| self.board().clone().try_move(mv).err());
+                let reason = reason.map_or_else(|| err.to_string(), |reason| reason.to_string());
+                self.send(&format!("Illegal move {}: {}", text, reason))
+            }
+        }
+    }
+
+    /// Move for the engine if it is the engine's turn in a running game.
+    fn engine_turn(&mut self) -> io::Result<()> {
+        if !self.is_over() && self.engine_color == Some(self.board().current_player()) {
+            self.engine_move()?;
+        }
+        Ok(())
+    }
+
+    fn engine_move(&mut self) -> io::Result<()> {
+        let result = self.engine.best_move(self.board(), SearchLimits::move_time(self.move_time_ms));
+        let Some(mv) = result.best_move else {
+            return Ok(());
+        };
+        self.send(&format!("rchess plays {}", mv.to_san(self.board())))?;
+        self.play(mv)
+    }
+
+    /// Play a legal move, show the new position, and report check or the end of
+    /// the game. The game refuses moves once it is over.
+    fn play(&mut self, mv: Move) -> io::Result<()> {
+        match self.game.play(mv) {
+            Ok(outcome) => {
+                self.show()?;
+                let status = self.game.game_status();
+                if status.is_over() {
+                    self.send(&format!("{}.", status))
+                } else if outcome.check {
+                    self.send("Check.")
+                } else {
+                    Ok(())
+                }
+            }
+            Err(MoveError::GameOver) => {
+                let status = self.game.game_status();
+                self.send(&format!("The game is over ({}). Type 'new' to play again.", status))
+            }
+            Err(err) => self.send(&format!("Illegal move: {}", err)),
+        }
+    }
+
+    /// Take back the last move, and one more if that leaves the engine on move,
+    /// so the human is to move again. A resignation goes with the move.
+    fn undo(&mut self) -> io::Result<()> {
+        if self.game.undo().is_none() {
+            return self.send("Nothing to undo.");
+        }
+        if self.engine_color == Some(self.board().current_player()) {
+            self.game.undo();
+        }
+        self.show()
+    }
+
+    fn resign(&mut self) -> io::Result<()> {
+        let loser = match self.engine_color {
+            Some(engine) => engine.opposite(),
+            None => self.board().current_player(),
+        };
+        match self.game.resign(loser) {
+            Ok(_) => self.send(&format!("{}.", self.game.game_status())),
+            Err(_) => self.send("The game is already over."),
+        }
+    }
+
+    fn show(&mut self) -> io::Result<()> {
+        let flipped = self.engine_color == Some(Color::White);
+        let diagram = text::render(self.board(), flipped);
+        write!(self.out, "\n{}", diagram)?;
+        let side = self.board().current_player();
+        self.send(&format!("{} to move.", side))
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.out, "{}", line)?;
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(input: &str) -> String {
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_engine_answers_san_and_uci_moves() {
+        let output = session("time 20\ne4\ng1f3\n");
+        assert_eq!(output.matches("rchess plays ").count(), 2);
+        assert!(output.contains("4  . . . . P . . ."));
+    }
+
+    #[test]
+    fn test_commands_in_two_player_mode() {
+        let output = session("play both\ne9\ne1e2\nundo\ne4\nfen\nundo\nmoves\nresign\nNf3\n");
+        assert!(output.contains("Illegal move e9"));
+        assert!(output.contains("Illegal move e1e2: e2 holds a piece of your own"));
+        assert!(output.contains("Nothing to undo."));
+        assert!(output.contains("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq"));
+        assert!(output.contains("Na3 Nc3 Nf3 Nh3 a3 a4 b3 b4"));
+        assert!(output.contains("White resigns, Black wins."));
+        assert!(output.contains("The game is over (White resigns, Black wins)."));
+        assert!(!output.contains("rchess plays"));
+    }
+
+    #[test]
+    fn test_autosaved_game_is_recovered() {
+        let dir = std::env::temp_dir().join(format!("rchess-cli-autosave-test-{}", std::process::id()));
+        let autosave = Autosave::new(&dir).unwrap();
+        run_autosaved("play both\ne4\n".as_bytes(), Vec::new(), autosave.clone(), "casual").unwrap();
+        let dir_arg = [dir.display().to_string()];
+        let mut listing = Vec::new();
+        recover(&dir_arg, "".as_bytes(), &mut listing).unwrap();
+        let mut resumed = Vec::new();
+        let args = [dir_arg[0].clone(), "casual".to_string()];
+        recover(&args, "play both\ne5\nresign\n".as_bytes(), &mut resumed).unwrap();
+        let saves = autosave.interrupted();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let listing = String::from_utf8(listing).unwrap();
+        assert_eq!(listing, "casual: Black to move, rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1\n");
+        let resumed = String::from_utf8(resumed).unwrap();
+        assert!(resumed.contains("Resuming game casual. You play Black."));
+        assert!(resumed.contains("5  . . . . p . . ."));
+        assert_eq!(saves.unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_checkmate_is_reported() {
+        let output = session("play both\nf3\ne5\ng4\nQh4\n");
+        assert!(output.trim_end().ends_with("Checkmate, Black wins."));
+    }
+}
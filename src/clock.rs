@@ -0,0 +1,256 @@
+// Chess clocks: each side's remaining time under a classical, Fischer increment,
+// or Bronstein delay time control. Game presses the clock after every move; the
+// clock charges the mover, starts the opponent's time, and records a flag fall.
+
+use crate::pieces::Color;
+use crate::rchess::v1::{self as proto};
+use crate::time_manager::TimeInfo;
+use std::time::Instant;
+
+/// How much time each side gets and how it is replenished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    /// `base_ms` for every `moves` moves, e.g. 40 moves in 90 minutes; with
+    /// `moves` zero, for the whole game.
+    Classical { base_ms: u64, moves: u32 },
+    /// Fischer: `increment_ms` is added after every move.
+    Increment { base_ms: u64, increment_ms: u64 },
+    /// Bronstein: time used on a move is given back, up to `delay_ms`.
+    Delay { base_ms: u64, delay_ms: u64 },
+}
+
+impl TimeControl {
+    /// Starting time per side.
+    pub fn base_ms(&self) -> u64 {
+        match *self {
+            TimeControl::Classical { base_ms, .. }
+            | TimeControl::Increment { base_ms, .. }
+            | TimeControl::Delay { base_ms, .. } => base_ms,
+        }
+    }
+
+    /// Create from a proto TimeControl. An increment takes precedence over a
+    /// delay; negative times read as zero.
+    pub fn from_proto(control: &proto::TimeControl) -> Self {
+        let ms = |value: i64| value.max(0) as u64;
+        let base_ms = ms(control.base_ms);
+        if control.increment_ms > 0 {
+            TimeControl::Increment { base_ms, increment_ms: ms(control.increment_ms) }
+        } else if control.delay_ms > 0 {
+            TimeControl::Delay { base_ms, delay_ms: ms(control.delay_ms) }
+        } else {
+            TimeControl::Classical { base_ms, moves: control.moves_per_period.max(0) as u32 }
+        }
+    }
+
+    /// Convert to proto TimeControl.
+    pub fn to_proto(&self) -> proto::TimeControl {
+        let mut control = proto::TimeControl { base_ms: self.base_ms() as i64, ..Default::default() };
+        match *self {
+            TimeControl::Classical { moves, .. } => control.moves_per_period = moves as i32,
+            TimeControl::Increment { increment_ms, .. } => control.increment_ms = increment_ms as i64,
+            TimeControl::Delay { delay_ms, .. } => control.delay_ms = delay_ms as i64,
+        }
+        control
+    }
+}
+
+/// A two-sided game clock. Times are kept in milliseconds between moves; the
+/// side to move's time also runs on the wall clock once started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clock {
+    control: TimeControl,
+    remaining_ms: [u64; 2],
+    moves: [u32; 2],
+    flagged: Option<Color>,
+    /// Side whose time is running and since when.
+    running: Option<(Color, Instant)>,
+}
+
+fn index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+impl Clock {
+    /// A stopped clock with the starting time on both sides.
+    pub fn new(control: TimeControl) -> Self {
+        Clock {
+            control,
+            remaining_ms: [control.base_ms(); 2],
+            moves: [0; 2],
+            flagged: None,
+            running: None,
+        }
+    }
+
+    pub fn time_control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Time `color` had left after their last move, not counting the time
+    /// running now.
+    pub fn remaining_ms(&self, color: Color) -> u64 {
+        self.remaining_ms[index(color)]
+    }
+
+    /// Time `color` has left right now.
+    pub fn time_left_ms(&self, color: Color) -> u64 {
+        self.remaining_ms(color).saturating_sub(self.running_ms(color))
+    }
+
+    /// Moves `color` has completed on the clock.
+    pub fn moves(&self, color: Color) -> u32 {
+        self.moves[index(color)]
+    }
+
+    /// Side whose time is running, if the clock is started.
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// Start `color`'s time on the wall clock.
+    pub fn start(&mut self, color: Color) {
+        if self.flagged.is_none() {
+            self.running = Some((color, Instant::now()));
+        }
+    }
+
+    /// Stop the wall clock, charging nobody.
+    pub fn stop(&mut self) {
+        self.running = None;
+    }
+
+    /// Side whose time has run out, counting the time running now.
+    pub fn flagged(&self) -> Option<Color> {
+        self.flagged.or_else(|| {
+            let (color, _) = self.running?;
+            (self.time_left_ms(color) == 0).then_some(color)
+        })
+    }
+
+    /// End `color`'s move after `elapsed_ms`: charge the time, add any increment
+    /// or delay, and start the opponent's time. Returns false, and records the
+    /// flag fall, if the time ran out before the move was made. A clock whose
+    /// flag has fallen no longer runs.
+    pub fn press(&mut self, color: Color, elapsed_ms: u64) -> bool {
+        if self.flagged.is_some() {
+            return false;
+        }
+        let i = index(color);
+        if elapsed_ms >= self.remaining_ms[i] {
+            self.remaining_ms[i] = 0;
+            self.flagged = Some(color);
+            self.running = None;
+            return false;
+        }
+        self.remaining_ms[i] -= elapsed_ms;
+        self.moves[i] += 1;
+        self.remaining_ms[i] += match self.control {
+            TimeControl::Increment { increment_ms, .. } => increment_ms,
+            TimeControl::Delay { delay_ms, .. } => elapsed_ms.min(delay_ms),
+            TimeControl::Classical { base_ms, moves } if moves > 0 && self.moves[i].is_multiple_of(moves) => base_ms,
+            TimeControl::Classical { .. } => 0,
+        };
+        self.running = Some((color.opposite(), Instant::now()));
+        true
+    }
+
+    /// Like [`press`](Clock::press), charging the wall-clock time since
+    /// `color`'s time was started; nothing if it was not running.
+    pub fn press_now(&mut self, color: Color) -> bool {
+        let elapsed = self.running_ms(color);
+        self.press(color, elapsed)
+    }
+
+    /// Clock state in the form the engine's time managers take.
+    pub fn time_info(&self, color: Color) -> TimeInfo {
+        let (increment_ms, moves_to_go) = match self.control {
+            TimeControl::Increment { increment_ms, .. } => (increment_ms, None),
+            // The delay is given back on every move that uses it up, so it
+            // budgets like an increment.
+            TimeControl::Delay { delay_ms, .. } => (delay_ms, None),
+            TimeControl::Classical { moves, .. } => (0, (moves > 0).then(|| moves - self.moves(color) % moves)),
+        };
+        TimeInfo { remaining_ms: self.time_left_ms(color), increment_ms, moves_to_go }
+    }
+
+    /// Create from a proto Clock; the clock starts stopped.
+    pub fn from_proto(clock: &proto::Clock) -> Self {
+        let control = clock.time_control.as_ref().map_or(
+            TimeControl::Classical { base_ms: 0, moves: 0 },
+            TimeControl::from_proto,
+        );
+        let ms = |value: i64| value.max(0) as u64;
+        let moves = |value: i32| value.max(0) as u32;
+        Clock {
+            control,
+            remaining_ms: [ms(clock.white_remaining_ms), ms(clock.black_remaining_ms)],
+            moves: [moves(clock.white_moves), moves(clock.black_moves)],
+            flagged: Color::try_from_proto(clock.flagged).ok(),
+            running: None,
+        }
+    }
+
+    /// Convert to proto Clock, with the time running now charged.
+    pub fn to_proto(&self) -> proto::Clock {
+        proto::Clock {
+            time_control: Some(self.control.to_proto()),
+            white_remaining_ms: self.time_left_ms(Color::White) as i64,
+            black_remaining_ms: self.time_left_ms(Color::Black) as i64,
+            white_moves: self.moves[0] as i32,
+            black_moves: self.moves[1] as i32,
+            flagged: self.flagged().map_or(proto::Color::Unspecified as i32, |color| color.to_proto()),
+        }
+    }
+
+    fn running_ms(&self, color: Color) -> u64 {
+        match self.running {
+            Some((running, since)) if running == color => since.elapsed().as_millis() as u64,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_increment_delay_and_classical_periods() {
+        let mut fischer = Clock::new(TimeControl::Increment { base_ms: 60_000, increment_ms: 2_000 });
+        assert!(fischer.press(Color::White, 5_000));
+        assert_eq!(fischer.remaining_ms(Color::White), 57_000);
+        assert_eq!(fischer.running(), Some(Color::Black));
+
+        let mut bronstein = Clock::new(TimeControl::Delay { base_ms: 60_000, delay_ms: 3_000 });
+        bronstein.press(Color::White, 2_000);
+        bronstein.press(Color::Black, 5_000);
+        assert_eq!((bronstein.remaining_ms(Color::White), bronstein.remaining_ms(Color::Black)), (60_000, 58_000));
+
+        let mut classical = Clock::new(TimeControl::Classical { base_ms: 10_000, moves: 2 });
+        classical.press(Color::White, 4_000);
+        assert_eq!(classical.time_info(Color::White).moves_to_go, Some(1));
+        classical.press(Color::White, 4_000);
+        assert_eq!(classical.remaining_ms(Color::White), 12_000);
+        assert_eq!(classical.moves(Color::White), 2);
+    }
+
+    #[test]
+    fn test_flag_fall_and_proto_round_trip() {
+        let mut clock = Clock::new(TimeControl::Increment { base_ms: 1_000, increment_ms: 500 });
+        assert!(clock.press(Color::White, 200));
+        assert_eq!(clock.flagged(), None);
+        assert!(!clock.press(Color::Black, 1_000));
+        assert_eq!(clock.flagged(), Some(Color::Black));
+        assert_eq!(clock.remaining_ms(Color::Black), 0);
+        assert!(!clock.press(Color::White, 0));
+
+        let restored = Clock::from_proto(&clock.to_proto());
+        assert_eq!(restored, clock);
+        let control = TimeControl::Delay { base_ms: 300_000, delay_ms: 5_000 };
+        assert_eq!(TimeControl::from_proto(&control.to_proto()), control);
+    }
+}
@@ -0,0 +1,100 @@
+// Cloud evaluations from the lichess cloud-eval API: deep searches of positions
+// other users have analysed, returned at once when the server has one.
+
+use crate::board::Board;
+use crate::engine::{MATE_SCORE, SearchResult};
+use crate::moves::Move;
+use crate::pieces::Color;
+use serde_json::Value;
+use std::time::Duration;
+
+/// The lichess cloud-eval endpoint.
+pub const LICHESS_CLOUD_EVAL: &str = "https://lichess.org/api/cloud-eval";
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A client for a lichess-compatible cloud-eval server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudEval {
+    endpoint: String,
+}
+
+impl Default for CloudEval {
+    fn default() -> Self {
+        CloudEval::new(LICHESS_CLOUD_EVAL)
+    }
+}
+
+impl CloudEval {
+    /// A client for the server at `endpoint`, which takes the position as its
+    /// "fen" query parameter.
+    pub fn new(endpoint: &str) -> Self {
+        CloudEval { endpoint: endpoint.to_string() }
+    }
+
+    /// The server's evaluation of `board`, as a search result with no nodes
+    /// or time. `None` if the server has none or cannot be reached.
+    pub fn lookup(&self, board: &Board) -> Option<SearchResult> {
+        let body = ureq::get(&self.endpoint)
+            .query("fen", &board.to_fen())
+            .query("multiPv", "1")
+            .timeout(TIMEOUT)
+            .call()
+            .ok()?
+            .into_string()
+            .ok()?;
+        parse_response(&body, board)
+    }
+}
+
+/// The first line of a cloud-eval response, with its score turned from White's
+/// point of view to the side to move's and its moves checked against `board`.
+fn parse_response(body: &str, board: &Board) -> Option<SearchResult> {
+    let response: Value = serde_json::from_str(body).ok()?;
+    let line = &response["pvs"][0];
+    let sign = if board.current_player() == Color::White { 1 } else { -1 };
+    // Mates are in moves: an odd number of plies for the mating side to move.
+    let score = match (line["cp"].as_i64(), line["mate"].as_i64()) {
+        (Some(cp), _) => sign * i32::try_from(cp).ok()?,
+        (None, Some(moves)) => match sign * i32::try_from(moves).ok()? {
+            moves if moves > 0 => MATE_SCORE - (2 * moves - 1),
+            moves => -MATE_SCORE - 2 * moves,
+        },
+        (None, None) => return None,
+    };
+    let mut position = board.clone();
+    let mut pv = Vec::new();
+    for uci in line["moves"].as_str()?.split_whitespace() {
+        let mv = Move::from_uci(uci, &position).ok()?;
+        position.apply_move(mv).ok()?;
+        pv.push(mv);
+    }
+    Some(SearchResult {
+        best_move: pv.first().copied(),
+        score,
+        depth: u32::try_from(response["depth"].as_u64()?).ok()?,
+        nodes: 0,
+        time_ms: 0,
+        pv,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_parse_response() {
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        let body = r#"{"fen":"","knodes":100,"depth":40,"pvs":[{"moves":"c7c5 g1f3","cp":32}]}"#;
+        let result = parse_response(body, &board).unwrap();
+        assert_eq!((result.score, result.depth), (-32, 40));
+        assert_eq!(result.pv, vec![Move::new(sq!("c7"), sq!("c5")), Move::new(sq!("g1"), sq!("f3"))]);
+
+        let mated = r#"{"depth":20,"pvs":[{"moves":"c7c5","mate":1}]}"#;
+        assert_eq!(parse_response(mated, &board).unwrap().score, -(MATE_SCORE - 2));
+        assert_eq!(parse_response(r#"{"depth":20,"pvs":[{"moves":"e2e4","cp":0}]}"#, &board), None);
+        assert_eq!(parse_response(r#"{"error":"Not found"}"#, &board), None);
+    }
+}
@@ -0,0 +1,420 @@
+// Differences between two board states.
+// StateDiff lets the server send incremental updates and clients animate changes.
+// GameStateDelta is the same change as a proto message, with piece ids and the
+// variant counters, so that a client holding the earlier state can bring it up
+// to date with `apply_delta` instead of receiving the whole piece list.
+
+use crate::board::{self, Board, CastlingRights};
+use crate::error::Error;
+use crate::fen;
+use crate::pieces::{self, Color, PieceType, Square};
+use crate::rchess::v1::{self as proto};
+
+/// A piece that appears in one state but not the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceChange {
+    pub piece_type: PieceType,
+    pub color: Color,
+    pub square: Square,
+}
+
+/// A piece that is present in both states but on a different square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceMove {
+    pub piece_type: PieceType,
+    pub color: Color,
+    pub from: Square,
+    pub to: Square,
+}
+
+/// Everything that changed between two board states.
+/// Scalar fields are `None` when the value is unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StateDiff {
+    pub moved: Vec<PieceMove>,
+    pub added: Vec<PieceChange>,
+    pub removed: Vec<PieceChange>,
+    pub current_player: Option<Color>,
+    pub castling_rights: Option<CastlingRights>,
+    pub en_passant_target: Option<Option<Square>>,
+    pub halfmove_clock: Option<i32>,
+    pub fullmove_number: Option<i32>,
+}
+
+impl StateDiff {
+    /// Return true if the two states were identical.
+    pub fn is_empty(&self) -> bool {
+        *self == StateDiff::default()
+    }
+}
+
+/// A piece as seen by the diff: identity (if any) plus type, color, and square.
+struct Placed<'a> {
+    id: &'a str,
+    change: PieceChange,
+}
+
+impl Board {
+    /// Describe how to get from this state to `other`.
+    pub fn diff(&self, other: &Board) -> StateDiff {
+        let before = placed_pieces(self);
+        let after = placed_pieces(other);
+
+        // Pieces standing on the same square with the same type and color are unchanged.
+        let mut removed: Vec<&Placed> = before
+            .iter()
+            .filter(|p| !after.iter().any(|q| q.change == p.change))
+            .collect();
+        let mut added: Vec<&Placed> = after
+            .iter()
+            .filter(|q| !before.iter().any(|p| p.change == q.change))
+            .collect();
+
+        // Pair removed and added pieces into moves: first by id, then by type and color.
+        let mut moved = Vec::new();
+        for by_id in [true, false] {
+            let mut i = 0;
+            while i < removed.len() {
+                let from = removed[i];
+                let partner = added.iter().position(|to| {
+                    let same_kind = to.change.piece_type == from.change.piece_type
+                        && to.change.color == from.change.color;
+                    if by_id {
+                        same_kind && !from.id.is_empty() && to.id == from.id
+                    } else {
+                        same_kind
+                    }
+                });
+                if let Some(j) = partner {
+                    let to = added.remove(j);
+                    removed.remove(i);
+                    moved.push(PieceMove {
+                        piece_type: from.change.piece_type,
+                        color: from.change.color,
+                        from: from.change.square,
+                        to: to.change.square,
+                    });
+                } else {
+                    i += 1;
+                }
+            }
+        }
+
+        StateDiff {
+            moved,
+            added: added.iter().map(|p| p.change).collect(),
+            removed: removed.iter().map(|p| p.change).collect(),
+            current_player: changed(self.current_player(), other.current_player()),
+            castling_rights: changed(self.castling_rights(), other.castling_rights()),
+            en_passant_target: changed(self.en_passant_target(), other.en_passant_target()),
+            halfmove_clock: changed(self.halfmove_clock(), other.halfmove_clock()),
+            fullmove_number: changed(self.fullmove_number(), other.fullmove_number()),
+        }
+    }
+}
+
+impl Board {
+    /// The change from this state to `other`, as [`diff`](Board::diff) finds it,
+    /// for sending to a client that holds this state.
+    pub fn delta(&self, other: &Board) -> proto::GameStateDelta {
+        let diff = self.diff(other);
+        let id = |board: &Board, square: Square| board.piece_at(square).map_or_else(String::new, |piece| piece.id.clone());
+        let placed = |board: &Board, change: &PieceChange| proto::PlacedPiece {
+            id: id(board, change.square),
+            piece_type: change.piece_type.to_proto(),
+            color: change.color.to_proto(),
+            square: Some(change.square.to_proto()),
+        };
+        let (before, after) = (self.to_proto(), other.to_proto());
+        let castling = other.castling_rights();
+        proto::GameStateDelta {
+            moved: diff
+                .moved
+                .iter()
+                .map(|m| proto::PieceMove {
+                    id: id(self, m.from),
+                    piece_type: m.piece_type.to_proto(),
+                    color: m.color.to_proto(),
+                    from: Some(m.from.to_proto()),
+                    to: Some(m.to.to_proto()),
+                })
+                .collect(),
+            added: diff.added.iter().map(|change| placed(other, change)).collect(),
+            removed: diff.removed.iter().map(|change| placed(self, change)).collect(),
+            current_player_changed: diff.current_player.is_some(),
+            current_player: other.current_player().to_proto(),
+            castling_changed: diff.castling_rights.is_some(),
+            white_kingside_castling: castling.white_kingside,
+            white_queenside_castling: castling.white_queenside,
+            black_kingside_castling: castling.black_kingside,
+            black_queenside_castling: castling.black_queenside,
+            en_passant_changed: diff.en_passant_target.is_some(),
+            en_passant_target: other.en_passant_target().map(|square| square.to_proto()),
+            halfmove_clock_changed: diff.halfmove_clock.is_some(),
+            halfmove_clock: other.halfmove_clock(),
+            fullmove_number_changed: diff.fullmove_number.is_some(),
+            fullmove_number: other.fullmove_number(),
+            checks_given_changed: (before.white_checks_given, before.black_checks_given)
+                != (after.white_checks_given, after.black_checks_given),
+            white_checks_given: after.white_checks_given,
+            black_checks_given: after.black_checks_given,
+            pockets_changed: (self.pocket(Color::White), self.pocket(Color::Black))
+                != (other.pocket(Color::White), other.pocket(Color::Black)),
+            white_pocket: Some(other.pocket(Color::White).to_proto()),
+            black_pocket: Some(other.pocket(Color::Black).to_proto()),
+            moves: match after.moves.strip_prefix(before.moves.as_slice()) {
+                Some(played) => played.to_vec(),
+                None => Vec::new(),
+            },
+        }
+    }
+
+    /// Bring this state up to date with a delta made by [`delta`](Board::delta)
+    /// from an equal state. Pieces the side not to move loses are kept as
+    /// captured, and a pawn replaced on the last rank by a piece of its color
+    /// promotes. Moves the delta lists join the proto history but cannot be
+    /// undone. On error the board is left unchanged.
+    pub fn apply_delta(&mut self, delta: &proto::GameStateDelta) -> Result<(), Error> {
+        let square = |position: &Option<proto::Position>, field: &'static str| {
+            Square::try_from_proto(position.as_ref().ok_or(Error::MissingField(field))?)
+        };
+        let mut state = self.to_proto();
+        let mut pieces = state.board.take().unwrap_or_default().pieces;
+        // Find every piece the delta names before moving any, so that pieces
+        // trading squares, as in castling, are told apart.
+        let find = |square: Square, piece_type: i32, color: i32| -> Result<usize, Error> {
+            let (piece_type, color) = (PieceType::try_from_proto(piece_type)?, Color::try_from_proto(color)?);
+            if self.piece_at_typed(square) != Some((piece_type, color)) {
+                return Err(Error::DeltaMismatch(square));
+            }
+            live_slot(self, &pieces, square).ok_or(Error::DeltaMismatch(square))
+        };
+        let mut moved = Vec::new();
+        for m in &delta.moved {
+            let from = square(&m.from, "moved.from")?;
+            moved.push((find(from, m.piece_type, m.color)?, square(&m.to, "moved.to")?));
+        }
+        let mut removed = Vec::new();
+        for r in &delta.removed {
+            let at = square(&r.square, "removed.square")?;
+            removed.push((find(at, r.piece_type, r.color)?, at));
+        }
+        let mut added = Vec::new();
+        for a in &delta.added {
+            let (piece_type, color) = (PieceType::try_from_proto(a.piece_type)?, Color::try_from_proto(a.color)?);
+            added.push((a, piece_type, color, square(&a.square, "added.square")?));
+        }
+
+        let mover = self.current_player();
+        let last_rank = if mover == Color::White { 7 } else { 0 };
+        let ply = state.moves.len() as i32;
+        let move_number = state.fullmove_number;
+        for &(slot, to) in &moved {
+            board::set_piece_square(&mut pieces[slot], to);
+            board::mark_piece_moved(&mut pieces[slot]);
+        }
+        let mut deleted = Vec::new();
+        for &(slot, at) in &removed {
+            let own = self.piece_color(&pieces[slot]) == Some(mover);
+            let pawn = self.piece_type(&pieces[slot]) == Some(PieceType::Pawn);
+            let promotion = added.iter().position(|&(_, piece_type, color, to)| {
+                own && pawn
+                    && color == mover
+                    && piece_type.is_promotion_target()
+                    && to.rank == last_rank
+                    && to.file.abs_diff(at.file) <= 1
+            });
+            if let Some(index) = promotion {
+                let (_, piece_type, _, to) = added.remove(index);
+                board::set_piece_square(&mut pieces[slot], to);
+                if let Some(proto::piece::Kind::Pawn(p)) = &mut pieces[slot].kind {
+                    p.promoted_to = piece_type.to_proto();
+                    p.has_moved = true;
+                }
+            } else if own {
+                deleted.push(slot);
+            } else {
+                pieces[slot].captured = true;
+                pieces[slot].capture = Some(proto::Capture {
+                    captured_by: mover.to_proto(),
+                    square: Some(at.to_proto()),
+                    move_number,
+                    ply,
+                });
+            }
+        }
+        deleted.sort_unstable();
+        for slot in deleted.into_iter().rev() {
+            pieces.remove(slot);
+        }
+        for &(_, to) in &moved {
+            if pieces.iter().filter(|piece| stands_on(self, piece, to)).count() > 1 {
+                return Err(Error::DeltaMismatch(to));
+            }
+        }
+
+        if delta.current_player_changed {
+            state.current_player = Color::try_from_proto(delta.current_player)?.to_proto();
+        }
+        if delta.castling_changed {
+            state.white_kingside_castling = delta.white_kingside_castling;
+            state.white_queenside_castling = delta.white_queenside_castling;
+            state.black_kingside_castling = delta.black_kingside_castling;
+            state.black_queenside_castling = delta.black_queenside_castling;
+        }
+        if delta.en_passant_changed {
+            state.en_passant_target = match &delta.en_passant_target {
+                Some(target) => Some(Square::try_from_proto(target)?.to_proto()),
+                None => None,
+            };
+        }
+        if delta.halfmove_clock_changed {
+            state.halfmove_clock = delta.halfmove_clock;
+        }
+        if delta.fullmove_number_changed {
+            state.fullmove_number = delta.fullmove_number;
+        }
+        if delta.checks_given_changed {
+            state.white_checks_given = delta.white_checks_given;
+            state.black_checks_given = delta.black_checks_given;
+        }
+        if delta.pockets_changed {
+            state.white_pocket = delta.white_pocket.clone();
+            state.black_pocket = delta.black_pocket.clone();
+        }
+
+        for (a, piece_type, color, at) in added {
+            if live_slot(self, &pieces, at).is_some() {
+                return Err(Error::DeltaMismatch(at));
+            }
+            let mut piece = pieces::new_proto_piece(piece_type, color, at);
+            piece.id = a.id.clone();
+            if fen::has_moved(&state, at, piece_type, color) {
+                board::mark_piece_moved(&mut piece);
+            }
+            pieces.push(piece);
+        }
+        state.board = Some(proto::Board { pieces });
+        state.moves.extend(delta.moves.iter().cloned());
+        *self = Board::from_proto_unchecked(state);
+        Ok(())
+    }
+}
+
+/// Slot of the piece standing on `square` in `pieces`, ignoring captured ones.
+fn live_slot(board: &Board, pieces: &[proto::Piece], square: Square) -> Option<usize> {
+    pieces.iter().position(|piece| stands_on(board, piece, square))
+}
+
+fn stands_on(board: &Board, piece: &proto::Piece, square: Square) -> bool {
+    !piece.captured && board.piece_square(piece) == Some(square)
+}
+
+/// Collect on-board pieces in square order so diffs are deterministic.
+fn placed_pieces(board: &Board) -> Vec<Placed<'_>> {
+    let mut pieces: Vec<Placed> = board
+        .all_pieces()
+        .filter_map(|piece| {
+            Some(Placed {
+                id: &piece.id,
+                change: PieceChange {
+                    piece_type: board.piece_type(piece)?,
+                    color: board.piece_color(piece)?,
+                    square: board.piece_square(piece)?,
+                },
+            })
+        })
+        .collect();
+    pieces.sort_by_key(|p| (p.change.square.rank, p.change.square.file));
+    pieces
+}
+
+/// Return the new value if it differs from the old one.
+fn changed<T: PartialEq>(old: T, new: T) -> Option<T> {
+    if old != new { Some(new) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rchess::v1::{self as proto};
+    use crate::sq;
+
+    #[test]
+    fn test_deltas_keep_a_client_in_sync() {
+        use crate::moves::Move;
+        use prost::Message;
+
+        let mut server = Board::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let mut client = server.clone();
+        for uci in ["e5d6", "e8g8", "b7a8q", "g8g7", "e1c1"] {
+            let before = server.clone();
+            server.apply_move(Move::from_uci(uci, &server).unwrap()).unwrap();
+            let delta = before.delta(&server);
+            assert!(delta.encoded_len() < server.to_proto().encoded_len() / 2);
+            client.apply_delta(&delta).unwrap();
+            assert_eq!(client.to_fen(), server.to_fen(), "after {}", uci);
+            assert!(client.diff(&server).is_empty());
+        }
+        let captured: Vec<_> = client.captured_pieces().iter().map(|c| (c.piece_type, c.color)).collect();
+        assert_eq!(captured, [(PieceType::Pawn, Color::Black), (PieceType::Rook, Color::Black)]);
+        assert_eq!(client.to_proto().moves, server.to_proto().moves);
+        let queen = client.piece_at(sq!("a8")).unwrap();
+        assert!(matches!(&queen.kind, Some(proto::piece::Kind::Pawn(p)) if p.promoted_to == PieceType::Queen.to_proto()));
+    }
+
+    #[test]
+    fn test_delta_from_another_state_is_rejected() {
+        let start = Board::starting_position();
+        let mut after = start.clone();
+        after.apply_move(crate::moves::Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        let delta = start.delta(&after);
+        let mut stale = after.clone();
+        assert_eq!(stale.apply_delta(&delta), Err(Error::DeltaMismatch(sq!("e2"))));
+        assert_eq!(stale.to_fen(), after.to_fen());
+    }
+
+    #[test]
+    fn test_diff_identical_is_empty() {
+        let a = Board::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let b = Board::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(a.diff(&b).is_empty());
+    }
+
+    #[test]
+    fn test_diff_pawn_push() {
+        let before = Board::from_fen("8/8/8/8/8/8/4P3/8 w - - 0 1").unwrap();
+        let after = Board::from_fen("8/8/8/8/4P3/8/8/8 b - - 0 1").unwrap();
+        let diff = before.diff(&after);
+        assert_eq!(
+            diff.moved,
+            vec![PieceMove {
+                piece_type: PieceType::Pawn,
+                color: Color::White,
+                from: sq!("e2"),
+                to: sq!("e4"),
+            }]
+        );
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.current_player, Some(Color::Black));
+        assert_eq!(diff.halfmove_clock, None);
+    }
+
+    #[test]
+    fn test_diff_capture_reports_removed_piece() {
+        let before = Board::from_fen("8/8/8/5p2/8/6N1/8/8 w - - 0 1").unwrap();
+        let after = Board::from_fen("8/8/8/5N2/8/8/8/8 b - - 0 1").unwrap();
+        let diff = before.diff(&after);
+        assert_eq!(diff.moved.len(), 1);
+        assert_eq!(diff.moved[0].to, sq!("f5"));
+        assert_eq!(
+            diff.removed,
+            vec![PieceChange {
+                piece_type: PieceType::Pawn,
+                color: Color::Black,
+                square: sq!("f5"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+// Endgame classification and drawishness scaling.
+// Positions are classified from material alone; evaluators scale their score by the class.
+
+use crate::board::Board;
+use crate::material::MaterialCount;
+use crate::pieces::{Color, PieceType};
+
+/// Scale factor meaning "evaluate normally".
+pub const SCALE_NORMAL: i32 = 64;
+
+/// Known endgame families.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndgameClass {
+    /// Neither side can possibly mate (KvK, KNvK, KBvK).
+    InsufficientMaterial,
+    /// King and pawn against a bare king; `strong` owns the pawn.
+    Kpk { strong: Color },
+    /// King and rook against king and pawn; `strong` owns the rook.
+    Krkp { strong: Color },
+    /// Kings and pawns only.
+    PawnEnding,
+    /// One rook each plus pawns.
+    RookEnding,
+    /// One bishop each on opposite-colored squares plus pawns.
+    OppositeBishops,
+}
+
+impl EndgameClass {
+    /// Drawishness scale out of [`SCALE_NORMAL`]; lower values pull the score toward zero.
+    pub fn scale_factor(&self) -> i32 {
+        match self {
+            EndgameClass::InsufficientMaterial => 0,
+            EndgameClass::OppositeBishops => 32,
+            EndgameClass::RookEnding => 48,
+            EndgameClass::Kpk { .. } | EndgameClass::Krkp { .. } | EndgameClass::PawnEnding => {
+                SCALE_NORMAL
+            }
+        }
+    }
+
+    /// Apply this class's drawishness scaling to a centipawn score.
+    pub fn scale_eval(&self, score: i32) -> i32 {
+        score * self.scale_factor() / SCALE_NORMAL
+    }
+}
+
+impl Board {
+    /// Classify the position into a known endgame family, if it is one.
+    pub fn endgame_class(&self) -> Option<EndgameClass> {
+        let white = self.material(Color::White);
+        let black = self.material(Color::Black);
+        let minors = |m: &MaterialCount| m.bishops + m.knights;
+        let is_bare = |m: &MaterialCount| m.pieces() == 0 && m.pawns == 0;
+
+        if white.pawns == 0
+            && black.pawns == 0
+            && white.queens + white.rooks + black.queens + black.rooks == 0
+            && minors(&white) + minors(&black) <= 1
+        {
+            return Some(EndgameClass::InsufficientMaterial);
+        }
+
+        for (strong, s, w) in [(Color::White, &white, &black), (Color::Black, &black, &white)] {
+            if s.pieces() == 0 && s.pawns == 1 && is_bare(w) {
+                return Some(EndgameClass::Kpk { strong });
+            }
+            if s.pieces() == 1 && s.rooks == 1 && s.pawns == 0 && w.pieces() == 0 && w.pawns == 1 {
+                return Some(EndgameClass::Krkp { strong });
+            }
+        }
+
+        if white.pieces() == 0 && black.pieces() == 0 {
+            return Some(EndgameClass::PawnEnding);
+        }
+        if white.pieces() == 1 && black.pieces() == 1 {
+            if white.rooks == 1 && black.rooks == 1 {
+                return Some(EndgameClass::RookEnding);
+            }
+            if white.bishops == 1
+                && black.bishops == 1
+                && self.light_bishops(Color::White) != self.light_bishops(Color::Black)
+            {
+                return Some(EndgameClass::OppositeBishops);
+            }
+        }
+        None
+    }
+
+    /// Number of `color`'s bishops standing on light squares.
+    fn light_bishops(&self, color: Color) -> usize {
+        self.pieces_of_color(color)
+            .iter()
+            .filter(|p| self.piece_type(p) == Some(PieceType::Bishop))
+            .filter_map(|p| self.piece_square(p))
+            // a1 is dark, so light squares have odd file + rank.
+            .filter(|sq| (sq.file + sq.rank) % 2 == 1)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insufficient_material() {
+        let bare = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(bare.endgame_class(), Some(EndgameClass::InsufficientMaterial));
+        let knight = Board::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(knight.endgame_class(), Some(EndgameClass::InsufficientMaterial));
+    }
+
+    #[test]
+    fn test_kpk_strong_side() {
+        let board = Board::from_fen("4k3/8/3p4/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.endgame_class(), Some(EndgameClass::Kpk { strong: Color::Black }));
+    }
+
+    #[test]
+    fn test_opposite_bishops_scaled() {
+        // c1 is dark, c8 is light.
+        let board = Board::from_fen("2b1k3/8/8/8/P7/8/8/2B1K3 w - - 0 1").unwrap();
+        let class = board.endgame_class().unwrap();
+        assert_eq!(class, EndgameClass::OppositeBishops);
+        assert_eq!(class.scale_eval(200), 100);
+    }
+
+    #[test]
+    fn test_unclassified_position() {
+        let board = Board::from_fen("2b1k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(board.endgame_class(), None);
+    }
+}
@@ -0,0 +1,1170 @@
+// Alpha-beta search: iterative deepening over a negamax search with quiescence.
+// Limits come from depth, node, or clock settings; clock budgets use the time_manager strategies.
+// Leaves are scored by eval::evaluate; results are cached in a transposition table.
+// An optional opening book is consulted before searching, and with the tablebase
+// feature, Syzygy tables both at the root and after captures and pawn moves.
+// With the online feature, the lichess tablebase answers at the root when the
+// local tables cannot.
+// With the parallel feature, helper threads search the same root alongside the
+// main one (Lazy SMP), sharing only the transposition table. Leaves are scored
+// by an NNUE network instead once one is loaded with `set_eval`, or with the
+// onnx feature by an ONNX value network.
+
+use crate::board::Board;
+use crate::book::Book;
+use crate::eval::evaluate;
+use crate::moves::Move;
+use crate::nnue::{Network, NnueError, NnueStack};
+#[cfg(feature = "onnx")]
+use crate::onnx::{OnnxError, OnnxModel};
+#[cfg(feature = "online")]
+use crate::online::OnlineTablebase;
+use crate::pieces::Color;
+#[cfg(feature = "tablebase")]
+use crate::tablebase::{Tablebase, Wdl};
+use crate::time_manager::{
+    BudgetUnit, FixedTimeManager, StandardTimeManager, TimeBudget, TimeInfo, TimeManager, TimeOptions,
+};
+use crate::tt::{Bound, TranspositionTable};
+#[cfg(feature = "parallel")]
+use rayon::{ThreadPool, ThreadPoolBuilder};
+#[cfg(feature = "parallel")]
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Score of a mate delivered at the root; mates further away score less.
+pub const MATE_SCORE: i32 = 30_000;
+
+/// Deepest iteration the search will start.
+pub const MAX_DEPTH: u32 = 64;
+
+/// Scores beyond this magnitude are mates.
+const MATE_THRESHOLD: i32 = MATE_SCORE - MAX_DEPTH as i32 - 1;
+
+/// Score of a tablebase win at the root, below every mate score; wins found
+/// deeper in the tree score less.
+#[cfg(feature = "tablebase")]
+pub const TABLEBASE_WIN: i32 = MATE_THRESHOLD - MAX_DEPTH as i32 - 1;
+
+/// How often, in nodes, the search checks its time and node limits. The stop
+/// flag is checked at every node.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// When to stop searching. Unset limits do not constrain the search; with
+/// none set it runs to [`MAX_DEPTH`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchLimits {
+    /// Maximum depth in plies.
+    pub depth: Option<u32>,
+    /// Maximum number of nodes to visit.
+    pub nodes: Option<u64>,
+    /// Fixed time to spend on the move (UCI `go movetime`).
+    pub move_time_ms: Option<u64>,
+    /// Clock of the side to move; the engine's time manager turns it into a budget.
+    pub clock: Option<TimeInfo>,
+}
+
+impl SearchLimits {
+    /// Search to a fixed depth.
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    /// Search for a fixed amount of time.
+    pub fn move_time(ms: u64) -> Self {
+        SearchLimits {
+            move_time_ms: Some(ms),
+            ..Default::default()
+        }
+    }
+}
+
+/// How the engine scores the positions at the leaves of its search.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum EvalBackend {
+    /// The hand-crafted evaluation in eval.rs.
+    #[default]
+    Classical,
+    /// The NNUE network in the file at this path, for standard chess; other
+    /// variants fall back to the classical evaluation.
+    Nnue(PathBuf),
+    /// The ONNX value network in the file at this path, for standard chess;
+    /// other variants fall back to the classical evaluation.
+    #[cfg(feature = "onnx")]
+    Onnx(PathBuf),
+}
+
+/// Why [`Engine::set_eval`] could not load an evaluation.
+#[derive(Debug)]
+pub enum EvalError {
+    Nnue(NnueError),
+    #[cfg(feature = "onnx")]
+    Onnx(OnnxError),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::Nnue(e) => write!(f, "{}", e),
+            #[cfg(feature = "onnx")]
+            EvalError::Onnx(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+impl From<NnueError> for EvalError {
+    fn from(e: NnueError) -> Self {
+        EvalError::Nnue(e)
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl From<OnnxError> for EvalError {
+    fn from(e: OnnxError) -> Self {
+        EvalError::Onnx(e)
+    }
+}
+
+/// What [`Engine::analyse`] searches for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnalysisOptions {
+    /// Number of lines to report, each with a different first move.
+    pub multipv: usize,
+    pub limits: SearchLimits,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        AnalysisOptions {
+            multipv: 1,
+            limits: SearchLimits::default(),
+        }
+    }
+}
+
+/// Outcome of a search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// Best move found; `None` when the side to move has no legal moves.
+    pub best_move: Option<Move>,
+    /// Score in centipawns from the side to move's point of view; see [`mate_distance`].
+    pub score: i32,
+    /// Depth of the last completed iteration.
+    pub depth: u32,
+    /// Nodes visited, including quiescence nodes.
+    pub nodes: u64,
+    /// Time spent searching, in milliseconds.
+    pub time_ms: u64,
+    /// Principal variation, starting with `best_move`.
+    pub pv: Vec<Move>,
+}
+
+/// Plies to mate for a mate score: positive when the side to move mates,
+/// negative when it is mated. `None` for ordinary scores.
+pub fn mate_distance(score: i32) -> Option<i32> {
+    if score > MATE_THRESHOLD {
+        Some(MATE_SCORE - score)
+    } else if score < -MATE_THRESHOLD {
+        Some(-(MATE_SCORE + score))
+    } else {
+        None
+    }
+}
+
+/// Mate distance in moves, as UCI reports it: a mate in 1 ply is 1, being
+/// mated in 2 plies is -1. `None` for ordinary scores.
+pub fn mate_moves(score: i32) -> Option<i32> {
+    mate_distance(score).map(|plies| if plies > 0 { (plies + 1) / 2 } else { plies / 2 })
+}
+
+/// A search engine with a pluggable time management strategy and a
+/// transposition table that persists between searches.
+#[derive(Debug)]
+pub struct Engine {
+    time_manager: Box<dyn TimeManager>,
+    time_options: TimeOptions,
+    tt: TranspositionTable,
+    stop: Arc<AtomicBool>,
+    book: Option<Book>,
+    #[cfg(feature = "tablebase")]
+    tablebase: Option<Tablebase>,
+    #[cfg(feature = "online")]
+    online: Option<OnlineTablebase>,
+    /// Threads for the Lazy SMP helpers; None searches on the caller's thread alone.
+    #[cfg(feature = "parallel")]
+    helpers: Option<ThreadPool>,
+    /// None for the classical evaluation.
+    network: Option<Arc<Network>>,
+    /// Set only for the ONNX backend, when `network` is None.
+    #[cfg(feature = "onnx")]
+    onnx: Option<OnnxModel>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+impl Engine {
+    /// Create an engine using the standard time manager and a default-sized hash table.
+    pub fn new() -> Self {
+        Engine {
+            time_manager: Box::new(StandardTimeManager),
+            time_options: TimeOptions::default(),
+            tt: TranspositionTable::default(),
+            stop: Arc::new(AtomicBool::new(false)),
+            book: None,
+            #[cfg(feature = "tablebase")]
+            tablebase: None,
+            #[cfg(feature = "online")]
+            online: None,
+            #[cfg(feature = "parallel")]
+            helpers: None,
+            network: None,
+            #[cfg(feature = "onnx")]
+            onnx: None,
+        }
+    }
+
+    /// Flag that makes a running search return its best line so far once set,
+    /// e.g. from another thread. The engine never clears it; callers reset it
+    /// before the next search.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Resize the transposition table to about `size_mb` megabytes, clearing it.
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.tt = TranspositionTable::new(size_mb);
+    }
+
+    /// Forget all cached search results, e.g. before a new game.
+    pub fn clear_hash(&mut self) {
+        self.tt.clear();
+    }
+
+    pub fn transposition_table(&self) -> &TranspositionTable {
+        &self.tt
+    }
+
+    /// Replace the strategy used to budget clock time.
+    pub fn set_time_manager(&mut self, time_manager: Box<dyn TimeManager>) {
+        self.time_manager = time_manager;
+    }
+
+    pub fn time_options(&self) -> &TimeOptions {
+        &self.time_options
+    }
+
+    pub fn set_time_options(&mut self, options: TimeOptions) {
+        self.time_options = options;
+    }
+
+    /// Opening book to play from while it knows the position; `None` turns it off.
+    pub fn set_book(&mut self, book: Option<Book>) {
+        self.book = book;
+    }
+
+    pub fn book(&self) -> Option<&Book> {
+        self.book.as_ref()
+    }
+
+    /// Endgame tablebases to play perfectly from once they cover the position;
+    /// `None` turns them off.
+    #[cfg(feature = "tablebase")]
+    pub fn set_tablebase(&mut self, tablebase: Option<Tablebase>) {
+        self.tablebase = tablebase;
+    }
+
+    #[cfg(feature = "tablebase")]
+    pub fn tablebase(&self) -> Option<&Tablebase> {
+        self.tablebase.as_ref()
+    }
+
+    /// Online tablebase to ask at the root when the local tables cannot answer.
+    #[cfg(feature = "online")]
+    pub fn set_online_tablebase(&mut self, online: Option<OnlineTablebase>) {
+        self.online = online;
+    }
+
+    /// The move the tablebases give at the root: the local ones', or with the
+    /// online feature the online tablebase's when they have none.
+    #[cfg(feature = "tablebase")]
+    fn root_tablebase_move(&self, board: &Board) -> Option<(Move, Wdl)> {
+        if let Some(found) = self.tablebase.as_ref().and_then(|tablebase| tablebase.best_move(board)) {
+            return Some(found);
+        }
+        #[cfg(feature = "online")]
+        if let Some(online) = &self.online {
+            return online.best_move(board);
+        }
+        None
+    }
+
+    /// Choose the evaluation. Loading a network can fail, in which case the
+    /// current evaluation stays.
+    pub fn set_eval(&mut self, backend: EvalBackend) -> Result<(), EvalError> {
+        let network = match backend {
+            EvalBackend::Classical => None,
+            EvalBackend::Nnue(path) => Some(Arc::new(Network::open(path)?)),
+            #[cfg(feature = "onnx")]
+            EvalBackend::Onnx(path) => {
+                self.onnx = Some(OnnxModel::open(path)?);
+                self.network = None;
+                return Ok(());
+            }
+        };
+        self.network = network;
+        #[cfg(feature = "onnx")]
+        {
+            self.onnx = None;
+        }
+        Ok(())
+    }
+
+    /// The loaded NNUE network, if the engine evaluates with one.
+    pub fn network(&self) -> Option<&Network> {
+        self.network.as_deref()
+    }
+
+    /// Search with `threads` threads in all: the caller's, plus helpers that
+    /// fill the shared hash table. At least one; falls back to one if the
+    /// helper threads cannot be started.
+    #[cfg(feature = "parallel")]
+    pub fn set_threads(&mut self, threads: usize) {
+        self.helpers = (threads > 1)
+            .then(|| {
+                ThreadPoolBuilder::new()
+                    .num_threads(threads - 1)
+                    .thread_name(|i| format!("search-helper-{}", i))
+                    .build()
+                    .ok()
+            })
+            .flatten();
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn threads(&self) -> usize {
+        self.helpers.as_ref().map_or(1, |pool| pool.current_num_threads() + 1)
+    }
+
+    /// Search `board` for the side to move and return the best line found.
+    /// A book move, if there is one, is returned at depth 0 without searching,
+    /// as is the tablebase move in positions the tablebases cover.
+    pub fn best_move(&mut self, board: &Board, limits: SearchLimits) -> SearchResult {
+        self.search_with_info(board, limits, |_| {})
+    }
+
+    /// Search on a thread of its own, for pondering and live analysis. The
+    /// engine moves into the search and comes back from
+    /// [`SearchHandle::stop`] or [`SearchHandle::wait`]. Clears the stop flag
+    /// first.
+    pub fn start(self, board: &Board, limits: SearchLimits) -> SearchHandle {
+        let options = AnalysisOptions { limits, ..Default::default() };
+        self.start_with_info(board, options, |_| {}, |_| {})
+    }
+
+    /// Like [`start`](Engine::start), searching as [`analyse`](Engine::analyse)
+    /// does when `options.multipv` is above 1 and as
+    /// [`best_move`](Engine::best_move) otherwise. On the search thread,
+    /// `on_iteration` gets the lines of each completed iteration and
+    /// `on_finish` the final ones; the handle's `wait` and `stop` return once
+    /// `on_finish` does.
+    pub fn start_with_info(
+        self,
+        board: &Board,
+        options: AnalysisOptions,
+        mut on_iteration: impl FnMut(&[SearchResult]) + Send + 'static,
+        on_finish: impl FnOnce(&[SearchResult]) + Send + 'static,
+    ) -> SearchHandle {
+        let mut engine = self;
+        engine.stop.store(false, Ordering::Relaxed);
+        let stop = engine.stop_flag();
+        let latest = Arc::new(Mutex::new(Vec::new()));
+        let board = board.clone();
+        let shared = Arc::clone(&latest);
+        let thread = thread::spawn(move || {
+            let mut report = |lines: &[SearchResult]| {
+                *shared.lock().unwrap_or_else(PoisonError::into_inner) = lines.to_vec();
+                on_iteration(lines);
+            };
+            let lines = if options.multipv > 1 {
+                engine.analyse_with_info(&board, options, report)
+            } else {
+                vec![engine.search_with_info(&board, options.limits, |r| report(std::slice::from_ref(r)))]
+            };
+            on_finish(&lines);
+            (engine, lines)
+        });
+        SearchHandle { stop, latest, thread }
+    }
+
+    /// Like [`best_move`](Engine::best_move), also calling `on_iteration` with
+    /// the result of each completed iteration, for progress reports.
+    pub fn search_with_info(
+        &mut self,
+        board: &Board,
+        limits: SearchLimits,
+        mut on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let budget = self.budget(board, limits);
+        self.tt.new_search();
+        let mut search = Search::new(board, &self.tt, &self.stop, limits.nodes);
+        search.budget = budget;
+        search.use_network(self.network.as_ref());
+        #[cfg(feature = "onnx")]
+        search.use_onnx(self.onnx.as_ref());
+        #[cfg(feature = "tablebase")]
+        {
+            search.tablebase = self.tablebase.as_ref();
+        }
+        let root_moves = board.legal_moves(board.current_player());
+        let mut result = SearchResult {
+            best_move: root_moves.first().copied(),
+            score: if root_moves.is_empty() { search.terminal_score(0) } else { 0 },
+            depth: 0,
+            nodes: 0,
+            time_ms: 0,
+            pv: root_moves.first().copied().into_iter().collect(),
+        };
+        if root_moves.is_empty() {
+            result.pv.clear();
+            return result;
+        }
+        // Book keys describe standard chess positions only.
+        if board.variant().name() == "standard"
+            && let Some(mv) = self.book.as_ref().and_then(|book| book.best_move(board))
+        {
+            result.best_move = Some(mv);
+            result.pv = vec![mv];
+            return result;
+        }
+        #[cfg(feature = "tablebase")]
+        if let Some((mv, wdl)) = self.root_tablebase_move(board) {
+            result.best_move = Some(mv);
+            result.score = tablebase_score(wdl, 0);
+            result.pv = vec![mv];
+            return result;
+        }
+
+        let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        #[cfg(feature = "parallel")]
+        let helper_nodes = self.deepen_with_helpers(&mut search, &root_moves, max_depth, &mut result, &mut on_iteration);
+        #[cfg(not(feature = "parallel"))]
+        let helper_nodes = {
+            self.deepen(&mut search, &root_moves, max_depth, &mut result, &mut on_iteration);
+            0
+        };
+        result.nodes = search.nodes + helper_nodes;
+        result.time_ms = search.start.elapsed().as_millis() as u64;
+        result
+    }
+
+    /// [`deepen`](Engine::deepen) with the helper threads searching the same
+    /// root until it is done, however it ends. Returns the helpers' node count.
+    #[cfg(feature = "parallel")]
+    fn deepen_with_helpers(
+        &self,
+        search: &mut Search,
+        root_moves: &[Move],
+        max_depth: u32,
+        result: &mut SearchResult,
+        on_iteration: &mut impl FnMut(&SearchResult),
+    ) -> u64 {
+        let Some(helpers) = &self.helpers else {
+            self.deepen(search, root_moves, max_depth, result, on_iteration);
+            return 0;
+        };
+        let done = AtomicBool::new(false);
+        let nodes = AtomicU64::new(0);
+        helpers.in_place_scope(|scope| {
+            for i in 0..helpers.current_num_threads() {
+                let board = search.board.clone();
+                let (tt, done, nodes, node_limit) = (&self.tt, &done, &nodes, search.node_limit);
+                let network = self.network.as_ref();
+                #[cfg(feature = "onnx")]
+                let onnx = self.onnx.as_ref();
+                #[cfg(feature = "tablebase")]
+                let tablebase = self.tablebase.as_ref();
+                scope.spawn(move |_| {
+                    let mut helper = Search::new(&board, tt, done, node_limit);
+                    helper.use_network(network);
+                    #[cfg(feature = "onnx")]
+                    helper.use_onnx(onnx);
+                    #[cfg(feature = "tablebase")]
+                    {
+                        helper.tablebase = tablebase;
+                    }
+                    helper.help(root_moves, max_depth, u32::from(i % 2 == 0));
+                    nodes.fetch_add(helper.nodes, Ordering::Relaxed);
+                });
+            }
+            self.deepen(search, root_moves, max_depth, result, on_iteration);
+            done.store(true, Ordering::Relaxed);
+        });
+        nodes.into_inner()
+    }
+
+    /// The best `options.multipv` lines for the side to move, best first, all
+    /// searched to the same depth; fewer when there are fewer legal moves. With
+    /// no legal moves, one line without a move. Unlike
+    /// [`best_move`](Engine::best_move) this always searches, ignoring the book
+    /// and root tablebase moves, and runs on the calling thread alone.
+    pub fn analyse(&mut self, board: &Board, options: AnalysisOptions) -> Vec<SearchResult> {
+        self.analyse_with_info(board, options, |_| {})
+    }
+
+    /// Like [`analyse`](Engine::analyse), also calling `on_iteration` with the
+    /// lines of each completed iteration.
+    pub fn analyse_with_info(
+        &mut self,
+        board: &Board,
+        options: AnalysisOptions,
+        mut on_iteration: impl FnMut(&[SearchResult]),
+    ) -> Vec<SearchResult> {
+        let budget = self.budget(board, options.limits);
+        self.tt.new_search();
+        let mut search = Search::new(board, &self.tt, &self.stop, options.limits.nodes);
+        search.budget = budget;
+        search.use_network(self.network.as_ref());
+        #[cfg(feature = "onnx")]
+        search.use_onnx(self.onnx.as_ref());
+        #[cfg(feature = "tablebase")]
+        {
+            search.tablebase = self.tablebase.as_ref();
+        }
+        let root_moves = board.legal_moves(board.current_player());
+        let mut lines = vec![SearchResult {
+            best_move: root_moves.first().copied(),
+            score: if root_moves.is_empty() { search.terminal_score(0) } else { 0 },
+            depth: 0,
+            nodes: 0,
+            time_ms: 0,
+            pv: root_moves.first().copied().into_iter().collect(),
+        }];
+        if root_moves.is_empty() {
+            return lines;
+        }
+
+        let multipv = options.multipv.clamp(1, root_moves.len());
+        let max_depth = options.limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        for depth in 1..=max_depth {
+            // Each line searches the root moves the lines above it did not take.
+            let mut remaining = root_moves.clone();
+            let mut iteration: Vec<SearchResult> = Vec::with_capacity(multipv);
+            while iteration.len() < multipv && !search.aborted {
+                let previous_pv = lines.get(iteration.len()).map_or(&[][..], |line| &line.pv);
+                let mut pv = Vec::new();
+                let score = search.root(&remaining, depth, previous_pv, &mut pv);
+                remaining.retain(|&mv| pv.first() != Some(&mv));
+                iteration.push(SearchResult {
+                    best_move: pv.first().copied(),
+                    score,
+                    depth,
+                    nodes: 0,
+                    time_ms: 0,
+                    pv,
+                });
+            }
+            if search.aborted {
+                break;
+            }
+            // A later line can outscore an earlier one that was searched with
+            // less of the table filled in.
+            iteration.sort_by_key(|line| -line.score);
+            let time_ms = search.start.elapsed().as_millis() as u64;
+            for line in &mut iteration {
+                (line.nodes, line.time_ms) = (search.nodes, time_ms);
+            }
+            if depth > 1 && let Some(current) = search.budget {
+                search.budget = Some(self.time_manager.on_fail_low(current, lines[0].score - iteration[0].score));
+            }
+            lines = iteration;
+            on_iteration(&lines);
+            if search.budget.is_some_and(|b| search.used(b) >= b.optimum) {
+                break;
+            }
+        }
+        lines
+    }
+
+    /// Time or node budget for a search under `limits`, if it has one.
+    fn budget(&self, board: &Board, limits: SearchLimits) -> Option<TimeBudget> {
+        let ply = (board.fullmove_number().max(1) as u32 - 1) * 2
+            + u32::from(board.current_player() == Color::Black);
+        match (limits.move_time_ms, limits.clock) {
+            (Some(ms), _) => Some(FixedTimeManager { move_time_ms: ms }.allocate(
+                &TimeInfo::default(),
+                &self.time_options,
+                ply,
+            )),
+            (None, Some(clock)) => Some(self.time_manager.allocate(&clock, &self.time_options, ply)),
+            (None, None) => None,
+        }
+    }
+
+    /// Iterative deepening on the calling thread, recording each completed
+    /// iteration in `result`.
+    fn deepen(
+        &self,
+        search: &mut Search,
+        root_moves: &[Move],
+        max_depth: u32,
+        result: &mut SearchResult,
+        on_iteration: &mut impl FnMut(&SearchResult),
+    ) {
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = search.root(root_moves, depth, &result.pv, &mut pv);
+            if search.aborted {
+                break;
+            }
+            if depth > 1 && let Some(current) = search.budget {
+                search.budget = Some(self.time_manager.on_fail_low(current, result.score - score));
+            }
+            result.best_move = pv.first().copied();
+            result.score = score;
+            result.depth = depth;
+            result.pv = pv;
+            result.nodes = search.nodes;
+            result.time_ms = search.start.elapsed().as_millis() as u64;
+            on_iteration(result);
+            if mate_distance(score).is_some() || search.budget.is_some_and(|b| search.used(b) >= b.optimum) {
+                break;
+            }
+        }
+    }
+}
+
+/// A search running on its own thread, from [`Engine::start`].
+#[derive(Debug)]
+pub struct SearchHandle {
+    stop: Arc<AtomicBool>,
+    latest: Arc<Mutex<Vec<SearchResult>>>,
+    thread: JoinHandle<(Engine, Vec<SearchResult>)>,
+}
+
+impl SearchHandle {
+    /// The lines of the last completed iteration, best first; empty until the
+    /// first one is done.
+    pub fn latest(&self) -> Vec<SearchResult> {
+        self.latest.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Whether the search has ended, so that `wait` will not block.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Stop the search and return the engine with the best lines found so
+    /// far, at least one.
+    pub fn stop(self) -> (Engine, Vec<SearchResult>) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.wait()
+    }
+
+    /// Let the search run to its limits and return the engine with its lines,
+    /// at least one. A search without limits runs until stopped from another
+    /// thread through the engine's stop flag.
+    pub fn wait(self) -> (Engine, Vec<SearchResult>) {
+        self.thread.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
+
+/// State of one search: a scratch board, the shared table, counters, and limits.
+struct Search<'a> {
+    board: Board,
+    tt: &'a TranspositionTable,
+    nodes: u64,
+    start: Instant,
+    budget: Option<TimeBudget>,
+    node_limit: Option<u64>,
+    stop: &'a AtomicBool,
+    aborted: bool,
+    #[cfg(feature = "tablebase")]
+    tablebase: Option<&'a Tablebase>,
+    /// Accumulators for the NNUE evaluation; None for the classical one.
+    nnue: Option<NnueStack>,
+    #[cfg(feature = "onnx")]
+    onnx: Option<&'a OnnxModel>,
+}
+
+impl<'a> Search<'a> {
+    /// A search of `board` with no time budget, no tablebases, and the
+    /// classical evaluation.
+    fn new(board: &Board, tt: &'a TranspositionTable, stop: &'a AtomicBool, node_limit: Option<u64>) -> Self {
+        Search {
+            board: board.clone(),
+            tt,
+            nodes: 0,
+            start: Instant::now(),
+            budget: None,
+            node_limit,
+            stop,
+            aborted: false,
+            #[cfg(feature = "tablebase")]
+            tablebase: None,
+            nnue: None,
+            #[cfg(feature = "onnx")]
+            onnx: None,
+        }
+    }
+
+    /// Evaluate with `network` where it applies: it is trained on standard chess.
+    fn use_network(&mut self, network: Option<&Arc<Network>>) {
+        self.nnue = network
+            .filter(|_| self.board.variant().name() == "standard")
+            .map(|network| NnueStack::new(Arc::clone(network), &self.board));
+    }
+
+    /// Evaluate with the ONNX `model` where it applies, as for NNUE networks.
+    #[cfg(feature = "onnx")]
+    fn use_onnx(&mut self, model: Option<&'a OnnxModel>) {
+        self.onnx = model.filter(|_| self.board.variant().name() == "standard");
+    }
+
+    fn make(&mut self, mv: Move) {
+        self.board.make_move(mv);
+        if let Some(nnue) = &mut self.nnue {
+            nnue.push(&self.board);
+        }
+    }
+
+    fn unmake(&mut self) {
+        self.board.unmake_move();
+        if let Some(nnue) = &mut self.nnue {
+            nnue.pop();
+        }
+    }
+
+    /// Static score of the position from the side to move's point of view.
+    fn evaluate(&self) -> i32 {
+        #[cfg(feature = "onnx")]
+        if let Some(model) = self.onnx {
+            return model.evaluate(&self.board);
+        }
+        match &self.nnue {
+            Some(nnue) => nnue.evaluate(self.board.current_player()),
+            None => evaluate(&self.board),
+        }
+    }
+
+    /// Lazy SMP helper: iterative deepening until the stop flag is set or
+    /// `max_depth` is done. Starting `skip` plies deeper keeps helpers off the
+    /// iteration the main thread is on, so between them they cover more of the
+    /// tree.
+    #[cfg(feature = "parallel")]
+    fn help(&mut self, moves: &[Move], max_depth: u32, skip: u32) {
+        let mut previous_pv = Vec::new();
+        for depth in (1 + skip).min(max_depth)..=max_depth {
+            let mut pv = Vec::new();
+            self.root(moves, depth, &previous_pv, &mut pv);
+            if self.aborted {
+                return;
+            }
+            previous_pv = pv;
+        }
+    }
+
+    /// Search the root moves to `depth`, trying the previous iteration's best move first.
+    fn root(&mut self, moves: &[Move], depth: u32, previous_pv: &[Move], pv: &mut Vec<Move>) -> i32 {
+        let mut moves = moves.to_vec();
+        order_moves(&mut moves, previous_pv.first().copied());
+        let mut alpha = -MATE_SCORE;
+        let mut child_pv = Vec::new();
+        for mv in moves {
+            self.make(mv);
+            child_pv.clear();
+            let score = -self.negamax(depth - 1, 1, -MATE_SCORE, -alpha, &mut child_pv);
+            self.unmake();
+            if self.aborted {
+                break;
+            }
+            if score > alpha || pv.is_empty() {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+            }
+        }
+        alpha
+    }
+
+    fn negamax(&mut self, depth: u32, ply: u32, mut alpha: i32, beta: i32, pv: &mut Vec<Move>) -> i32 {
+        if self.tick() {
+            return 0;
+        }
+        if self.board.halfmove_clock() >= 100 {
+            return 0;
+        }
+        if let Some(score) = self.variant_score(ply) {
+            return score;
+        }
+        #[cfg(feature = "tablebase")]
+        if let Some(score) = self.tablebase_score(ply) {
+            return score;
+        }
+        if depth == 0 {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        let key = self.board.zobrist_key();
+        let mut hash_move = None;
+        if let Some(entry) = self.tt.probe(key) {
+            hash_move = entry.best_move;
+            let score = score_from_tt(entry.score, ply);
+            let usable = entry.depth >= depth
+                && match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => score >= beta,
+                    Bound::Upper => score <= alpha,
+                };
+            if usable {
+                pv.clear();
+                pv.extend(entry.best_move);
+                return score;
+            }
+        }
+
+        let mut moves = self.board.legal_moves(self.board.current_player());
+        if moves.is_empty() {
+            return self.terminal_score(ply);
+        }
+        order_moves(&mut moves, hash_move);
+        let original_alpha = alpha;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        for mv in moves {
+            self.make(mv);
+            child_pv.clear();
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            self.unmake();
+            if self.aborted {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = Some(mv);
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        let bound = if alpha >= beta {
+            Bound::Lower
+        } else if alpha > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt.store(key, best_move, score_to_tt(alpha, ply), depth, bound);
+        alpha
+    }
+
+    /// Search captures and promotions until the position is quiet.
+    fn quiescence(&mut self, ply: u32, mut alpha: i32, beta: i32) -> i32 {
+        if self.tick() {
+            return 0;
+        }
+        if let Some(score) = self.variant_score(ply) {
+            return score;
+        }
+        let moves = self.board.legal_moves(self.board.current_player());
+        if moves.is_empty() {
+            return self.terminal_score(ply);
+        }
+        let stand_pat = self.evaluate();
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut tactical: Vec<Move> =
+            moves.into_iter().filter(|mv| mv.is_capture() || mv.promotion.is_some()).collect();
+        order_moves(&mut tactical, None);
+        for mv in tactical {
+            self.make(mv);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.unmake();
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// Score of a position without legal moves: mated, or stalemate.
+    fn terminal_score(&self, ply: u32) -> i32 {
+        if self.board.is_in_check(self.board.current_player()) {
+            -MATE_SCORE + ply as i32
+        } else {
+            0
+        }
+    }
+
+    /// Score of a position the variant has already decided, such as an exploded king.
+    fn variant_score(&self, ply: u32) -> Option<i32> {
+        let status = self.board.variant().outcome(&self.board)?;
+        Some(match status.winner() {
+            Some(winner) if winner == self.board.current_player() => MATE_SCORE - ply as i32,
+            Some(_) => -MATE_SCORE + ply as i32,
+            None => 0,
+        })
+    }
+
+    /// Tablebase score right after a capture or pawn move, when the win/draw/loss
+    /// tables are exact with respect to the fifty-move rule.
+    #[cfg(feature = "tablebase")]
+    fn tablebase_score(&self, ply: u32) -> Option<i32> {
+        let tablebase = self.tablebase?;
+        if self.board.halfmove_clock() != 0 || !tablebase.covers(&self.board) {
+            return None;
+        }
+        tablebase.probe_wdl(&self.board).map(|wdl| tablebase_score(wdl, ply))
+    }
+
+    /// Count a node and report whether the search must stop.
+    fn tick(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let out_of_nodes = self.node_limit.is_some_and(|limit| self.nodes >= limit);
+            let out_of_time = self.budget.is_some_and(|b| self.used(b) >= b.maximum);
+            self.aborted |= out_of_nodes || out_of_time;
+        }
+        self.aborted |= self.stop.load(Ordering::Relaxed);
+        self.aborted
+    }
+
+    /// Amount of `budget` spent so far, in the budget's unit.
+    fn used(&self, budget: TimeBudget) -> u64 {
+        match budget.unit {
+            BudgetUnit::Millis => self.start.elapsed().as_millis() as u64,
+            BudgetUnit::Nodes => self.nodes,
+        }
+    }
+}
+
+/// Search score of a tablebase result `ply` plies from the root. Cursed wins
+/// and blessed losses are draws under the fifty-move rule.
+#[cfg(feature = "tablebase")]
+fn tablebase_score(wdl: Wdl, ply: u32) -> i32 {
+    match wdl {
+        Wdl::Win => TABLEBASE_WIN - ply as i32,
+        Wdl::Loss => -TABLEBASE_WIN + ply as i32,
+        _ => 0,
+    }
+}
+
+/// Mate scores are stored relative to the node rather than the root, so a
+/// mate found through a transposition keeps the right distance.
+fn score_to_tt(score: i32, ply: u32) -> i32 {
+    if score > MATE_THRESHOLD {
+        score + ply as i32
+    } else if score < -MATE_THRESHOLD {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: u32) -> i32 {
+    if score > MATE_THRESHOLD {
+        score - ply as i32
+    } else if score < -MATE_THRESHOLD {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// Put `first` at the front, then captures by most valuable victim and least
+/// valuable attacker, then promotions, then quiet moves.
+fn order_moves(moves: &mut [Move], first: Option<Move>) {
+    moves.sort_by_key(|mv| {
+        if Some(*mv) == first {
+            return i32::MIN;
+        }
+        let victim = mv.capture.map_or(0, |p| p.value());
+        let attacker = mv.piece.map_or(0, |p| p.value());
+        let promotion = mv.promotion.map_or(0, |p| p.value());
+        let capture_bonus = if mv.is_capture() { 10_000 - attacker / 10 } else { 0 };
+        -(victim * 10 + capture_bonus + promotion)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_finds_mate_in_one() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = Engine::new().best_move(&board, SearchLimits::depth(3));
+        assert_eq!(result.best_move, Some(Move::new(sq!("a1"), sq!("a8"))));
+        assert_eq!(mate_distance(result.score), Some(1));
+        assert_eq!(result.pv.first(), result.best_move.as_ref());
+    }
+
+    #[test]
+    fn test_wins_hanging_queen() {
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let result = Engine::new().best_move(&board, SearchLimits::depth(2));
+        assert_eq!(result.best_move, Some(Move::new(sq!("d1"), sq!("d5"))));
+        assert!(result.score > 400);
+        assert_eq!(result.depth, 2);
+    }
+
+    #[test]
+    fn test_no_legal_moves() {
+        let mated = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        let result = Engine::new().best_move(&mated, SearchLimits::depth(2));
+        assert_eq!(result.best_move, None);
+        assert_eq!(mate_distance(result.score), Some(0));
+
+        let stalemate = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        let result = Engine::new().best_move(&stalemate, SearchLimits::depth(2));
+        assert_eq!((result.best_move, result.score), (None, 0));
+    }
+
+    #[test]
+    fn test_hash_table_is_filled_and_reused() {
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let mut engine = Engine::new();
+        engine.set_hash_size(1);
+        let first = engine.best_move(&board, SearchLimits::depth(3));
+        assert!(engine.transposition_table().probe(first_child_key(&board, &first)).is_some());
+        let second = engine.best_move(&board, SearchLimits::depth(3));
+        assert_eq!(second.score, first.score);
+        assert!(second.nodes < first.nodes);
+
+        engine.clear_hash();
+        assert_eq!(engine.transposition_table().hashfull(), 0);
+    }
+
+    fn first_child_key(board: &Board, result: &SearchResult) -> u64 {
+        let mut child = board.clone();
+        child.apply_move(result.best_move.unwrap()).unwrap();
+        child.zobrist_key()
+    }
+
+    #[test]
+    fn test_stop_flag_and_iteration_reports() {
+        let board = Board::starting_position();
+        let mut engine = Engine::new();
+        let mut depths = Vec::new();
+        let result = engine.search_with_info(&board, SearchLimits::depth(3), |r| depths.push(r.depth));
+        assert_eq!(depths, vec![1, 2, 3]);
+        assert_eq!(result.depth, 3);
+
+        engine.stop_flag().store(true, Ordering::Relaxed);
+        let result = engine.best_move(&board, SearchLimits::default());
+        assert!(result.best_move.is_some());
+        assert_eq!(result.depth, 0);
+    }
+
+    #[test]
+    fn test_book_move_is_played_without_searching() {
+        let board = Board::starting_position();
+        // One entry: d2d4 for the starting position.
+        let mut data = board.polyglot_key().to_be_bytes().to_vec();
+        data.extend([0x02, 0xDB, 0, 1, 0, 0, 0, 0]);
+        let mut engine = Engine::new();
+        engine.set_book(Some(Book::read(data.as_slice()).unwrap()));
+        let result = engine.best_move(&board, SearchLimits::depth(4));
+        assert_eq!(result.best_move, Some(Move::new(sq!("d2"), sq!("d4"))));
+        assert_eq!((result.depth, result.nodes), (0, 0));
+        // Out of book the engine searches as usual.
+        let result = engine.best_move(&Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap(), SearchLimits::depth(2));
+        assert_eq!(result.depth, 2);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_helper_threads_share_the_table() {
+        let mut engine = Engine::new();
+        engine.set_threads(3);
+        assert_eq!(engine.threads(), 3);
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let result = engine.best_move(&board, SearchLimits::depth(4));
+        assert_eq!(result.best_move, Some(Move::new(sq!("d1"), sq!("d5"))));
+        assert_eq!(result.depth, 4);
+        engine.set_threads(1);
+        assert_eq!(engine.threads(), 1);
+    }
+
+    #[test]
+    fn test_analyse_reports_distinct_lines_best_first() {
+        // Only Rxd5 wins the queen; the king moves and the other rook moves do not.
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let mut engine = Engine::new();
+        let mut reports = 0;
+        let options = AnalysisOptions { multipv: 3, limits: SearchLimits::depth(3) };
+        let lines = engine.analyse_with_info(&board, options, |lines| {
+            reports += 1;
+            assert_eq!(lines.len(), 3);
+        });
+        assert_eq!(reports, 3);
+        assert_eq!(lines[0].best_move, Some(Move::new(sq!("d1"), sq!("d5"))));
+        assert!(lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert!(lines[0].score > lines[1].score + 400);
+        let firsts: Vec<_> = lines.iter().map(|line| line.pv[0]).collect();
+        assert!(firsts[1] != firsts[0] && firsts[2] != firsts[0] && firsts[2] != firsts[1]);
+
+        let options = AnalysisOptions { multipv: 50, limits: SearchLimits::depth(1) };
+        let legal = board.legal_moves(board.current_player()).len();
+        assert_eq!(engine.analyse(&board, options).len(), legal);
+    }
+
+    #[test]
+    fn test_started_search_reports_and_stops() {
+        let engine = Engine::new();
+        engine.stop_flag().store(true, Ordering::Relaxed);
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let handle = engine.start(&board, SearchLimits::default());
+        while handle.latest().first().is_none_or(|r| r.depth < 3) {
+            assert!(!handle.is_finished());
+            thread::yield_now();
+        }
+        let (engine, lines) = handle.stop();
+        assert!(lines[0].depth >= 3);
+        assert_eq!(lines[0].best_move, Some(Move::new(sq!("d1"), sq!("d5"))));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let options = AnalysisOptions { multipv: 2, limits: SearchLimits::depth(2) };
+        let handle = engine.start_with_info(&board, options, |_| {}, move |lines| sender.send(lines.len()).unwrap());
+        let (_, lines) = handle.wait();
+        assert_eq!((lines[1].depth, receiver.recv()), (2, Ok(2)));
+    }
+
+    #[test]
+    fn test_node_and_time_limits_still_return_a_move() {
+        let board = Board::starting_position();
+        let result = Engine::new().best_move(&board, SearchLimits { nodes: Some(2_000), ..Default::default() });
+        assert!(result.best_move.is_some());
+        assert!(result.nodes < 2_000 + CHECK_INTERVAL);
+
+        let result = Engine::new().best_move(&board, SearchLimits::move_time(50));
+        assert!(result.best_move.is_some());
+        assert!(result.depth >= 1);
+    }
+}
@@ -0,0 +1,118 @@
+// The crate-wide error type. Proto messages are untrusted input, so the
+// `try_from_proto` conversions report what is wrong with them here instead of
+// reading unknown values as defaults; the module errors convert into it so that
+// callers mixing FEN, notation, and moves can use one `?`.
+
+use crate::builder::BuildError;
+use crate::fen::FenError;
+use crate::legality::IllegalMoveReason;
+use crate::moves::MoveError;
+use crate::notation::NotationError;
+use crate::pgn::PgnError;
+use crate::pieces::{ParseError, Square};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A proto Color value other than WHITE or BLACK.
+    InvalidColor(i32),
+    /// A proto PieceType value that names no piece.
+    InvalidPieceType(i32),
+    /// A proto Position off the board; proto files and ranks run from 1 to 8.
+    InvalidSquare { file: i32, rank: i32 },
+    /// A proto field that must be set is not, e.g. "piece.kind".
+    MissingField(&'static str),
+    UnknownVariant(String),
+    /// A GameStateDelta names a piece the board does not have there, or adds
+    /// one to an occupied square: it was made from a different state.
+    DeltaMismatch(Square),
+    Parse(ParseError),
+    Fen(FenError),
+    Move(MoveError),
+    Illegal(IllegalMoveReason),
+    Notation(NotationError),
+    Pgn(PgnError),
+    Build(BuildError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidColor(value) => write!(f, "invalid color value {}", value),
+            Error::InvalidPieceType(value) => write!(f, "invalid piece type value {}", value),
+            Error::InvalidSquare { file, rank } => write!(f, "square off the board: file {}, rank {}", file, rank),
+            Error::MissingField(field) => write!(f, "missing field {}", field),
+            Error::UnknownVariant(name) => write!(f, "unknown variant {:?}", name),
+            Error::DeltaMismatch(square) => write!(f, "delta does not match the board on {}", square),
+            Error::Parse(e) => write!(f, "{}", e),
+            Error::Fen(e) => write!(f, "{}", e),
+            Error::Move(e) => write!(f, "{}", e),
+            Error::Illegal(e) => write!(f, "{}", e),
+            Error::Notation(e) => write!(f, "{}", e),
+            Error::Pgn(e) => write!(f, "{}", e),
+            Error::Build(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(e) => Some(e),
+            Error::Fen(e) => Some(e),
+            Error::Move(e) => Some(e),
+            Error::Illegal(e) => Some(e),
+            Error::Notation(e) => Some(e),
+            Error::Pgn(e) => Some(e),
+            Error::Build(e) => Some(e),
+            Error::InvalidColor(_)
+            | Error::InvalidPieceType(_)
+            | Error::InvalidSquare { .. }
+            | Error::MissingField(_)
+            | Error::UnknownVariant(_)
+            | Error::DeltaMismatch(_) => None,
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<FenError> for Error {
+    fn from(e: FenError) -> Self {
+        Error::Fen(e)
+    }
+}
+
+impl From<MoveError> for Error {
+    fn from(e: MoveError) -> Self {
+        Error::Move(e)
+    }
+}
+
+impl From<IllegalMoveReason> for Error {
+    fn from(e: IllegalMoveReason) -> Self {
+        Error::Illegal(e)
+    }
+}
+
+impl From<NotationError> for Error {
+    fn from(e: NotationError) -> Self {
+        Error::Notation(e)
+    }
+}
+
+impl From<PgnError> for Error {
+    fn from(e: PgnError) -> Self {
+        Error::Pgn(e)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Self {
+        Error::Build(e)
+    }
+}
@@ -0,0 +1,339 @@
+// Static evaluation: material, piece-square tables, pawn structure, and king safety.
+// Components are reported separately so GUIs can explain a score; the engine uses the total.
+
+use crate::bitboard::Bitboard;
+use crate::board::Board;
+use crate::endgame::{EndgameClass, SCALE_NORMAL};
+use crate::pieces::{Color, PieceType, Square};
+
+/// Base score of an endgame the bitbase proves won, before the usual terms.
+pub const KNOWN_WIN: i32 = 1_000;
+
+/// Game phase of the starting position: knights and bishops count 1, rooks 2, queens 4.
+const MAX_PHASE: i32 = 24;
+
+const DOUBLED_PAWN: i32 = -15;
+const ISOLATED_PAWN: i32 = -12;
+/// Bonus for a passed pawn by its rank counted from its own side.
+const PASSED_PAWN: [i32; 8] = [0, 5, 10, 20, 35, 60, 100, 0];
+const SHIELD_PAWN_NEAR: i32 = 10;
+const SHIELD_PAWN_FAR: i32 = 5;
+const SHIELD_MISSING: i32 = -15;
+const OPEN_FILE_NEAR_KING: i32 = -15;
+
+/// Evaluation split into its terms. Every term is in centipawns from White's
+/// point of view, before endgame scaling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalBreakdown {
+    pub material: i32,
+    pub piece_squares: i32,
+    pub pawn_structure: i32,
+    pub king_safety: i32,
+    /// Known endgame family, if any.
+    pub endgame: Option<EndgameClass>,
+    /// Drawishness scale applied to the sum, out of [`SCALE_NORMAL`].
+    pub scale: i32,
+    /// Final score from White's point of view.
+    pub total: i32,
+}
+
+impl EvalBreakdown {
+    /// The final score from `color`'s point of view.
+    pub fn relative_to(&self, color: Color) -> i32 {
+        match color {
+            Color::White => self.total,
+            Color::Black => -self.total,
+        }
+    }
+}
+
+/// Score of `board` in centipawns from the side to move's point of view.
+pub fn evaluate(board: &Board) -> i32 {
+    breakdown(board).relative_to(board.current_player())
+}
+
+/// Score of `board` with each term reported separately.
+pub fn breakdown(board: &Board) -> EvalBreakdown {
+    let phase = game_phase(board);
+    let term = |f: &dyn Fn(Color) -> i32| f(Color::White) - f(Color::Black);
+    // Pieces in hand, in drop variants, count as material too.
+    let material = term(&|color| board.material(color).value() + board.pocket(color).value());
+    let piece_squares = term(&|color| piece_square_score(board, color, phase));
+    let pawn_structure = term(&|color| pawn_structure_score(board, color));
+    let king_safety = term(&|color| king_safety_score(board, color) * phase / MAX_PHASE);
+    let sum = material + piece_squares + pawn_structure + king_safety;
+
+    let endgame = board.endgame_class();
+    let mut scale = endgame.map_or(SCALE_NORMAL, |class| class.scale_factor());
+    let mut total = sum * scale / SCALE_NORMAL;
+    // KPK is solved exactly: a won ending scores as a known win, anything else is a draw.
+    if let Some(EndgameClass::Kpk { strong }) = endgame {
+        if board.kpk_win() == Some(true) {
+            let sign = if strong == Color::White { 1 } else { -1 };
+            total = sign * KNOWN_WIN + sum;
+        } else {
+            scale = 0;
+            total = 0;
+        }
+    }
+
+    EvalBreakdown {
+        material,
+        piece_squares,
+        pawn_structure,
+        king_safety,
+        endgame,
+        scale,
+        total,
+    }
+}
+
+/// Remaining non-pawn material, from [`MAX_PHASE`] in the opening down to 0.
+fn game_phase(board: &Board) -> i32 {
+    let phase: u32 = [Color::White, Color::Black]
+        .into_iter()
+        .map(|color| {
+            let m = board.material(color);
+            m.knights + m.bishops + 2 * m.rooks + 4 * m.queens
+        })
+        .sum();
+    (phase as i32).min(MAX_PHASE)
+}
+
+fn piece_square_score(board: &Board, color: Color, phase: i32) -> i32 {
+    let mut score = 0;
+    for piece_type in [
+        PieceType::King, PieceType::Queen, PieceType::Rook,
+        PieceType::Bishop, PieceType::Knight, PieceType::Pawn,
+    ] {
+        for square in board.bitboards().pieces(piece_type, color).squares() {
+            let index = table_index(square, color);
+            score += match piece_type {
+                PieceType::King => {
+                    (KING_MIDDLEGAME[index] * phase + KING_ENDGAME[index] * (MAX_PHASE - phase)) / MAX_PHASE
+                }
+                PieceType::Queen => QUEEN[index],
+                PieceType::Rook => ROOK[index],
+                PieceType::Bishop => BISHOP[index],
+                PieceType::Knight => KNIGHT[index],
+                PieceType::Pawn => PAWN[index],
+            };
+        }
+    }
+    score
+}
+
+/// Doubled and isolated pawn penalties plus passed pawn bonuses.
+fn pawn_structure_score(board: &Board, color: Color) -> i32 {
+    let pawns = board.bitboards().pieces(PieceType::Pawn, color);
+    let mut per_file = [0i32; 8];
+    for square in pawns.squares() {
+        per_file[square.file as usize] += 1;
+    }
+
+    let mut score = 0;
+    for file in 0..8 {
+        let count = per_file[file];
+        if count == 0 {
+            continue;
+        }
+        score += DOUBLED_PAWN * (count - 1);
+        let left = file.checked_sub(1).map_or(0, |f| per_file[f]);
+        let right = per_file.get(file + 1).copied().unwrap_or(0);
+        if left == 0 && right == 0 {
+            score += ISOLATED_PAWN * count;
+        }
+    }
+    for pawn in board.passed_pawns(color) {
+        score += PASSED_PAWN[relative_rank(pawn, color) as usize];
+    }
+    score
+}
+
+/// Pawn shield in front of the king and open files beside it. The caller
+/// scales this by game phase, since king shelter matters less as pieces come off.
+fn king_safety_score(board: &Board, color: Color) -> i32 {
+    let Some(king) = board.king_square(color) else {
+        return 0;
+    };
+    let own = board.bitboards().pieces(PieceType::Pawn, color);
+    let all_pawns = own | board.bitboards().pieces(PieceType::Pawn, color.opposite());
+    let forward = match color {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+
+    let mut score = 0;
+    for df in -1..=1 {
+        let Some(file_square) = king.offset(df, 0) else {
+            continue;
+        };
+        let has_pawn = |dr: i32| file_square.offset(0, dr * forward).is_some_and(|sq| own.contains(sq));
+        score += if has_pawn(1) {
+            SHIELD_PAWN_NEAR
+        } else if has_pawn(2) {
+            SHIELD_PAWN_FAR
+        } else {
+            SHIELD_MISSING
+        };
+        if (all_pawns & file_mask(file_square.file)).is_empty() {
+            score += OPEN_FILE_NEAR_KING;
+        }
+    }
+    score
+}
+
+fn file_mask(file: u8) -> Bitboard {
+    Bitboard(0x0101_0101_0101_0101 << file)
+}
+
+fn relative_rank(square: Square, color: Color) -> u8 {
+    match color {
+        Color::White => square.rank,
+        Color::Black => 7 - square.rank,
+    }
+}
+
+/// Index into a table laid out from White's side, a8 first.
+fn table_index(square: Square, color: Color) -> usize {
+    (7 - relative_rank(square, color)) as usize * 8 + square.file as usize
+}
+
+#[rustfmt::skip]
+const PAWN: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_fen(fen: &str) -> EvalBreakdown {
+        breakdown(&Board::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_starting_position_is_balanced() {
+        let start = breakdown(&Board::starting_position());
+        assert_eq!(start.total, 0);
+        assert_eq!(start.material, 0);
+        assert_eq!(evaluate(&Board::starting_position()), 0);
+    }
+
+    #[test]
+    fn test_score_is_side_to_move_relative() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w Qkq - 0 1";
+        let white = evaluate(&Board::from_fen(fen).unwrap());
+        let black = evaluate(&Board::from_fen(&fen.replace(" w ", " b ")).unwrap());
+        assert!(white < -400);
+        assert_eq!(white, -black);
+    }
+
+    #[test]
+    fn test_pawn_structure_terms() {
+        // White has doubled, isolated pawns on the c-file; Black's d-pawn is passed.
+        let eval = eval_fen("4k3/8/8/3p4/8/2P5/2P5/4K3 w - - 0 1");
+        assert!(eval.pawn_structure < 0);
+        let healthy = eval_fen("4k3/8/8/8/8/8/3PP3/4K3 w - - 0 1");
+        assert!(healthy.pawn_structure > 0);
+    }
+
+    #[test]
+    fn test_king_safety_prefers_shelter() {
+        let sheltered = eval_fen("r5k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+        let exposed = eval_fen("r5k1/5ppp/8/8/8/8/PPP5/R5K1 w - - 0 1");
+        assert_eq!(sheltered.king_safety, 0);
+        assert!(exposed.king_safety < 0);
+    }
+
+    #[test]
+    fn test_endgame_scaling_and_kpk() {
+        let insufficient = eval_fen("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1");
+        assert_eq!((insufficient.scale, insufficient.total), (0, 0));
+
+        let won = eval_fen("7k/8/8/8/8/8/1P6/1K6 w - - 0 1");
+        assert!(won.total > KNOWN_WIN);
+        // Black's king stands in front of the pawn with the opposition: a draw.
+        let drawn = eval_fen("8/8/4k3/8/4P3/4K3/8/8 w - - 0 1");
+        assert_eq!(drawn.total, 0);
+    }
+}
@@ -0,0 +1,154 @@
+// Evaluations kept between runs, keyed by Zobrist hash: the depth, score, and
+// principal variation of the deepest search of each position. The analyze
+// command answers from it at once when it holds a search as deep as asked for.
+//
+// The file is text, one position per line: the key in hex, the depth, the
+// score from the side to move's point of view, then the PV in UCI notation.
+
+use crate::board::Board;
+use crate::engine::{SearchResult, mate_distance};
+use crate::moves::Move;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    depth: u32,
+    score: i32,
+    pv: Vec<Move>,
+}
+
+/// Stored evaluations by position.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvalCache {
+    entries: HashMap<u64, Entry>,
+}
+
+impl EvalCache {
+    pub fn new() -> Self {
+        EvalCache::default()
+    }
+
+    /// Read the cache at `path`; a missing file is an empty cache.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<EvalCache> {
+        match File::open(path) {
+            Ok(file) => EvalCache::read(BufReader::new(file)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(EvalCache::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Read a cache from `input`. Lines that do not parse are skipped.
+    pub fn read<R: BufRead>(input: R) -> io::Result<EvalCache> {
+        let mut cache = EvalCache::new();
+        for line in input.lines() {
+            let line = line?;
+            let mut fields = line.split_whitespace();
+            let (Some(key), Some(depth), Some(score)) = (fields.next(), fields.next(), fields.next()) else {
+                continue;
+            };
+            let (Ok(key), Ok(depth), Ok(score)) = (u64::from_str_radix(key, 16), depth.parse(), score.parse()) else {
+                continue;
+            };
+            let Some(pv) = fields.map(Move::parse_uci).collect::<Option<Vec<Move>>>() else {
+                continue;
+            };
+            cache.entries.insert(key, Entry { depth, score, pv });
+        }
+        Ok(cache)
+    }
+
+    /// Write the cache to `path`, replacing the file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut output = BufWriter::new(File::create(path)?);
+        self.write(&mut output)?;
+        output.flush()
+    }
+
+    /// Write the cache to `output`, ordered by key.
+    pub fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
+        let mut keys: Vec<&u64> = self.entries.keys().collect();
+        keys.sort();
+        for key in keys {
+            let entry = &self.entries[key];
+            write!(output, "{:016x} {} {}", key, entry.depth, entry.score)?;
+            for mv in &entry.pv {
+                write!(output, " {}", mv.to_uci())?;
+            }
+            writeln!(output)?;
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The stored search of `board` if it reached at least `min_depth` or found
+    /// a forced mate, as a result with no nodes or time. Entries whose PV is not legal on `board`
+    /// belong to another position with the same key and are ignored.
+    pub fn get(&self, board: &Board, min_depth: u32) -> Option<SearchResult> {
+        let entry = self.entries.get(&board.zobrist_key()).filter(|entry| entry.depth >= min_depth || mate_distance(entry.score).is_some())?;
+        let mut line = board.clone();
+        for &mv in &entry.pv {
+            line.apply_move(mv).ok()?;
+        }
+        Some(SearchResult {
+            best_move: entry.pv.first().copied(),
+            score: entry.score,
+            depth: entry.depth,
+            nodes: 0,
+            time_ms: 0,
+            pv: entry.pv.clone(),
+        })
+    }
+
+    /// Store a search of `board`, unless a deeper one is already stored.
+    pub fn insert(&mut self, board: &Board, result: &SearchResult) {
+        let entry = Entry { depth: result.depth, score: result.score, pv: result.pv.clone() };
+        self.entries
+            .entry(board.zobrist_key())
+            .and_modify(|stored| {
+                if entry.depth >= stored.depth {
+                    *stored = entry.clone();
+                }
+            })
+            .or_insert(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_cache_round_trip() {
+        let board = Board::starting_position();
+        let e4 = Move::new(sq!("e2"), sq!("e4"));
+        let e5 = Move::new(sq!("e7"), sq!("e5"));
+        let result = SearchResult { best_move: Some(e4), score: 25, depth: 10, nodes: 1000, time_ms: 50, pv: vec![e4, e5] };
+
+        let mut cache = EvalCache::new();
+        cache.insert(&board, &result);
+        cache.insert(&board, &SearchResult { depth: 4, ..result.clone() });
+        let mut text = Vec::new();
+        cache.write(&mut text).unwrap();
+        let reloaded = EvalCache::read(&text[..]).unwrap();
+        assert_eq!(reloaded, cache);
+
+        let hit = reloaded.get(&board, 8).unwrap();
+        assert_eq!((hit.best_move, hit.score, hit.depth, hit.pv), (Some(e4), 25, 10, vec![e4, e5]));
+        assert_eq!(reloaded.get(&board, 12), None);
+
+        let other = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(reloaded.get(&other, 1), None);
+        assert_eq!(EvalCache::read("zz 1 2\n0 x 3\n".as_bytes()).unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,344 @@
+// FEN (Forsyth-Edwards Notation) parsing and serialization.
+// Maps the six FEN fields onto the proto GameState fields. Crazyhouse pockets
+// follow the placement in brackets, as in "rnbqkbnr/.../RNBQKBNR[Qn]".
+
+use crate::board::{self, Board};
+use crate::pieces::{self, Color, PieceType, Square};
+use crate::pocket::Pocket;
+use crate::rchess::v1::{self as proto};
+use std::fmt;
+
+/// FEN of the standard starting position.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Why a FEN string could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// A FEN needs 6 fields, or 4 when the clocks are omitted.
+    WrongFieldCount(usize),
+    /// The piece placement field is malformed.
+    InvalidPlacement(String),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    /// The halfmove clock or fullmove number is not a valid number.
+    InvalidClock(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongFieldCount(n) => write!(f, "expected 6 FEN fields, found {}", n),
+            FenError::InvalidPlacement(s) => write!(f, "invalid piece placement: {}", s),
+            FenError::InvalidSideToMove(s) => write!(f, "invalid side to move: {}", s),
+            FenError::InvalidCastling(s) => write!(f, "invalid castling rights: {}", s),
+            FenError::InvalidEnPassant(s) => write!(f, "invalid en-passant target: {}", s),
+            FenError::InvalidClock(s) => write!(f, "invalid move counter: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl Board {
+    /// Parse a position from FEN. The clock fields may be omitted and default to "0 1".
+    /// A bracketed pocket after the placement selects crazyhouse rules.
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 && fields.len() != 4 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut state = proto::GameState {
+            current_player: match fields[1] {
+                "w" => Color::White.to_proto(),
+                "b" => Color::Black.to_proto(),
+                other => return Err(FenError::InvalidSideToMove(other.to_string())),
+            },
+            ..Default::default()
+        };
+
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                let right = match c {
+                    'K' => &mut state.white_kingside_castling,
+                    'Q' => &mut state.white_queenside_castling,
+                    'k' => &mut state.black_kingside_castling,
+                    'q' => &mut state.black_queenside_castling,
+                    _ => return Err(FenError::InvalidCastling(fields[2].to_string())),
+                };
+                if *right {
+                    return Err(FenError::InvalidCastling(fields[2].to_string()));
+                }
+                *right = true;
+            }
+        }
+
+        if fields[3] != "-" {
+            let square = Square::from_algebraic(fields[3])
+                .filter(|sq| sq.rank == 2 || sq.rank == 5)
+                .ok_or_else(|| FenError::InvalidEnPassant(fields[3].to_string()))?;
+            state.en_passant_target = Some(square.to_proto());
+        }
+
+        if fields.len() == 6 {
+            let clock = |s: &str| s.parse::<i32>().ok().filter(|n| *n >= 0);
+            state.halfmove_clock =
+                clock(fields[4]).ok_or_else(|| FenError::InvalidClock(fields[4].to_string()))?;
+            state.fullmove_number = clock(fields[5])
+                .filter(|n| *n >= 1)
+                .ok_or_else(|| FenError::InvalidClock(fields[5].to_string()))?;
+        } else {
+            state.fullmove_number = 1;
+        }
+
+        let placement = match fields[0].strip_suffix(']').and_then(|field| field.split_once('[')) {
+            Some((placement, pocket)) => {
+                let (white, black) = parse_pockets(pocket)
+                    .ok_or_else(|| FenError::InvalidPlacement(format!("invalid pocket in {:?}", fields[0])))?;
+                state.white_pocket = Some(white.to_proto());
+                state.black_pocket = Some(black.to_proto());
+                state.variant = "crazyhouse".to_string();
+                parse_placement(placement)?
+            }
+            None => parse_placement(fields[0])?,
+        };
+        let pieces = placement
+            .into_iter()
+            .map(|(square, piece_type, color)| {
+                let mut piece = pieces::new_proto_piece(piece_type, color, square);
+                if has_moved(&state, square, piece_type, color) {
+                    board::mark_piece_moved(&mut piece);
+                }
+                piece
+            })
+            .collect();
+        state.board = Some(proto::Board { pieces });
+
+        Ok(Board::from_proto_unchecked(state))
+    }
+
+    /// Serialize the position as FEN.
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for rank in (0..8u8).rev() {
+            let mut empty = 0;
+            for file in 0..8u8 {
+                match self.piece_at_typed(Square { file, rank }) {
+                    Some((piece_type, color)) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(fen_char(piece_type, color));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+        if self.variant().has_drops() {
+            placement.push('[');
+            for color in [Color::White, Color::Black] {
+                let pocket = self.pocket(color);
+                for piece_type in pocket.piece_types() {
+                    for _ in 0..pocket.count(piece_type) {
+                        placement.push(fen_char(piece_type, color));
+                    }
+                }
+            }
+            placement.push(']');
+        }
+
+        let side = match self.current_player() {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let rights = self.castling_rights();
+        let mut castling: String = [
+            (rights.white_kingside, 'K'),
+            (rights.white_queenside, 'Q'),
+            (rights.black_kingside, 'k'),
+            (rights.black_queenside, 'q'),
+        ]
+        .iter()
+        .filter(|(allowed, _)| *allowed)
+        .map(|(_, c)| *c)
+        .collect();
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant_target()
+            .map(|sq| sq.to_algebraic())
+            .unwrap_or_else(|| "-".to_string());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement,
+            side,
+            castling,
+            en_passant,
+            self.halfmove_clock(),
+            self.fullmove_number().max(1)
+        )
+    }
+}
+
+/// FEN letter for a piece: uppercase for White, lowercase for Black.
+fn fen_char(piece_type: PieceType, color: Color) -> char {
+    match color {
+        Color::White => piece_type.letter(),
+        Color::Black => piece_type.letter().to_ascii_lowercase(),
+    }
+}
+
+/// Parse the placement field into (square, type, color) triples.
+fn parse_placement(field: &str) -> Result<Vec<(Square, PieceType, Color)>, FenError> {
+    let invalid = |reason: &str| FenError::InvalidPlacement(format!("{} in {:?}", reason, field));
+    let ranks: Vec<&str> = field.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(invalid("expected 8 ranks"));
+    }
+
+    let mut placement = Vec::new();
+    // FEN lists rank 8 first.
+    for (row, text) in ranks.iter().enumerate() {
+        let rank = 7 - row as u8;
+        let mut file = 0u8;
+        for c in text.chars() {
+            if let Some(skip) = c.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                file += skip as u8;
+            } else {
+                let piece_type = PieceType::from_letter(c).ok_or_else(|| invalid("unknown piece"))?;
+                let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+                let square = Square::new(file, rank).ok_or_else(|| invalid("rank too long"))?;
+                placement.push((square, piece_type, color));
+                file += 1;
+            }
+            if file > 8 {
+                return Err(invalid("rank too long"));
+            }
+        }
+        if file != 8 {
+            return Err(invalid("rank too short"));
+        }
+    }
+    Ok(placement)
+}
+
+/// Parse the letters between the pocket brackets: White's pieces uppercase,
+/// Black's lowercase. Kings cannot be held.
+fn parse_pockets(letters: &str) -> Option<(Pocket, Pocket)> {
+    let (mut white, mut black) = (Pocket::default(), Pocket::default());
+    for c in letters.chars() {
+        let piece_type = PieceType::from_letter(c).filter(|&p| p != PieceType::King)?;
+        if c.is_ascii_uppercase() {
+            white.add(piece_type);
+        } else {
+            black.add(piece_type);
+        }
+    }
+    Some((white, black))
+}
+
+/// Infer the has-moved flag FEN does not record: pawns off their start rank have moved,
+/// and kings and rooks have moved unless a castling right still depends on them.
+pub(crate) fn has_moved(state: &proto::GameState, square: Square, piece_type: PieceType, color: Color) -> bool {
+    let home = match color {
+        Color::White => 0,
+        Color::Black => 7,
+    };
+    let (kingside, queenside) = match color {
+        Color::White => (state.white_kingside_castling, state.white_queenside_castling),
+        Color::Black => (state.black_kingside_castling, state.black_queenside_castling),
+    };
+    match piece_type {
+        PieceType::Pawn => square.rank != if color == Color::White { 1 } else { 6 },
+        PieceType::King => !(square == Square { file: 4, rank: home } && (kingside || queenside)),
+        PieceType::Rook => match (square.file, square.rank == home) {
+            (7, true) => !kingside,
+            (0, true) => !queenside,
+            _ => true,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_fen_round_trip() {
+        let board = Board::from_fen(STARTING_FEN).unwrap();
+        assert_eq!(board.to_fen(), STARTING_FEN);
+        assert_eq!(Board::starting_position().to_fen(), STARTING_FEN);
+        assert_eq!(board.position_key(), Board::starting_position().position_key());
+    }
+
+    #[test]
+    fn test_fen_fields_round_trip() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b Kq e3 12 34";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.current_player(), Color::Black);
+        assert!(board.white_kingside_castling());
+        assert!(!board.white_queenside_castling());
+        assert_eq!(board.en_passant_target(), Square::from_algebraic("e3"));
+        assert_eq!(board.halfmove_clock(), 12);
+        assert_eq!(board.fullmove_number(), 34);
+        assert_eq!(board.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_fen_without_clocks() {
+        let board = Board::from_fen("8/8/8/8/8/8/8/K6k w - -").unwrap();
+        assert_eq!(board.to_fen(), "8/8/8/8/8/8/8/K6k w - - 0 1");
+    }
+
+    #[test]
+    fn test_invalid_fen() {
+        assert_eq!(Board::from_fen("8/8/8 w - - 0").err(), Some(FenError::WrongFieldCount(5)));
+        assert!(matches!(
+            Board::from_fen("8/8/8/8/8/8/8/K6 w - - 0 1"),
+            Err(FenError::InvalidPlacement(_))
+        ));
+        assert!(matches!(
+            Board::from_fen("8/8/8/8/8/8/8/K6x w - - 0 1"),
+            Err(FenError::InvalidPlacement(_))
+        ));
+        assert!(matches!(
+            Board::from_fen("8/8/8/8/8/8/8/K6k x - - 0 1"),
+            Err(FenError::InvalidSideToMove(_))
+        ));
+        assert!(matches!(
+            Board::from_fen("8/8/8/8/8/8/8/K6k w KK - 0 1"),
+            Err(FenError::InvalidCastling(_))
+        ));
+        assert!(matches!(
+            Board::from_fen("8/8/8/8/8/8/8/K6k w - e4 0 1"),
+            Err(FenError::InvalidEnPassant(_))
+        ));
+        assert!(matches!(
+            Board::from_fen("8/8/8/8/8/8/8/K6k w - - x 1"),
+            Err(FenError::InvalidClock(_))
+        ));
+    }
+
+    #[test]
+    fn test_fen_has_moved_inference() {
+        // Pawn on e3 cannot double-push; the pawn on d2 can.
+        let board = Board::from_fen("4k3/8/8/8/8/4P3/3P4/4K3 w - - 0 1").unwrap();
+        let e3 = board.piece_at(Square::new(4, 2).unwrap()).unwrap().clone();
+        assert!(matches!(e3.kind, Some(proto::piece::Kind::Pawn(p)) if p.has_moved));
+        let d2 = board.piece_at(Square::new(3, 1).unwrap()).unwrap().clone();
+        assert!(matches!(d2.kind, Some(proto::piece::Kind::Pawn(p)) if !p.has_moved));
+    }
+}
@@ -0,0 +1,209 @@
+// C ABI for embedding the board in programs written in other languages. A board
+// is an opaque heap handle; moves cross the boundary as plain structs of square
+// indices and piece letters. The header rchess.h is generated from this file by
+// build.rs when the "ffi" feature is on, into the build's OUT_DIR
+// (target/<profile>/build/rchess-*/out/rchess.h).
+//
+// Build a library to link against with, e.g.,
+// `cargo rustc --release --features ffi --crate-type staticlib`.
+
+use crate::board::Board;
+use crate::movegen::MoveList;
+use crate::moves::Move;
+use crate::pieces::{PieceType, Square};
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+/// Opaque board handle. Create with `rchess_board_new` or
+/// `rchess_board_from_fen`, release with `rchess_board_free`.
+pub struct RchessBoard {
+    board: Board,
+}
+
+/// A move. Squares are indices 0..64, a1 = 0, b1 = 1, ..., h8 = 63.
+/// `promotion` is 0 or the lowercase letter of the piece promoted to, as in UCI.
+/// `drop` is 0, or for a drop in crazyhouse the uppercase letter of the piece
+/// dropped, with `from` equal to `to`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RchessMove {
+    pub from: u8,
+    pub to: u8,
+    pub promotion: u8,
+    pub drop: u8,
+}
+
+/// Outcome of `rchess_apply_move`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RchessResult {
+    Ok = 0,
+    /// The board pointer was null.
+    NullBoard = 1,
+    /// A square index, promotion, or drop letter is out of range.
+    InvalidMove = 2,
+    /// The move is well formed but not legal in the position.
+    IllegalMove = 3,
+}
+
+impl RchessMove {
+    fn from_move(mv: Move) -> Self {
+        let letter = |piece_type: Option<PieceType>| piece_type.map_or(0, |piece_type| piece_type.letter() as u8);
+        RchessMove {
+            from: mv.from.index(),
+            to: mv.to.index(),
+            promotion: letter(mv.promotion).to_ascii_lowercase(),
+            drop: letter(mv.drop),
+        }
+    }
+
+    fn to_move(self) -> Option<Move> {
+        let piece = |letter: u8| PieceType::from_letter(letter as char);
+        let to = Square::from_index(self.to)?;
+        if self.drop != 0 {
+            return Some(Move::new_drop(piece(self.drop)?, to));
+        }
+        let from = Square::from_index(self.from)?;
+        match self.promotion {
+            0 => Some(Move::new(from, to)),
+            letter => Some(Move::with_promotion(from, to, piece(letter)?)),
+        }
+    }
+}
+
+/// A board in the standard starting position.
+#[unsafe(no_mangle)]
+pub extern "C" fn rchess_board_new() -> *mut RchessBoard {
+    Box::into_raw(Box::new(RchessBoard { board: Board::starting_position() }))
+}
+
+/// A board from a NUL-terminated FEN string, or null if `fen` is null or not a
+/// valid FEN.
+///
+/// # Safety
+///
+/// `fen` must be null or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rchess_board_from_fen(fen: *const c_char) -> *mut RchessBoard {
+    if fen.is_null() {
+        return ptr::null_mut();
+    }
+    let fen = unsafe { CStr::from_ptr(fen) };
+    match fen.to_str().ok().and_then(|fen| Board::from_fen(fen).ok()) {
+        Some(board) => Box::into_raw(Box::new(RchessBoard { board })),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Release a board. Null is ignored.
+///
+/// # Safety
+///
+/// `board` must be null or a handle from this library not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rchess_board_free(board: *mut RchessBoard) {
+    if !board.is_null() {
+        drop(unsafe { Box::from_raw(board) });
+    }
+}
+
+/// The position as a FEN string, to be released with `rchess_string_free`;
+/// null if `board` is null.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rchess_board_fen(board: *const RchessBoard) -> *mut c_char {
+    let Some(board) = (unsafe { board.as_ref() }) else {
+        return ptr::null_mut();
+    };
+    // A FEN never contains a NUL byte.
+    CString::new(board.board.to_fen()).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Release a string returned by this library. Null is ignored.
+///
+/// # Safety
+///
+/// `text` must be null or a string from this library not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rchess_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
+
+/// Write up to `capacity` legal moves for the side to move into `out` and
+/// return how many there are in all, which may exceed `capacity`. `out` may be
+/// null when `capacity` is 0, to ask for the count alone.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle, and `out` must have room for
+/// `capacity` moves.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rchess_legal_moves(board: *const RchessBoard, out: *mut RchessMove, capacity: usize) -> usize {
+    let Some(board) = (unsafe { board.as_ref() }) else {
+        return 0;
+    };
+    let mut moves = MoveList::new();
+    board.board.generate_into(board.board.current_player(), &mut moves);
+    if !out.is_null() {
+        for (i, mv) in moves.iter().take(capacity).enumerate() {
+            unsafe { out.add(i).write(RchessMove::from_move(mv)) };
+        }
+    }
+    moves.len()
+}
+
+/// Play `mv` for the side to move.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rchess_apply_move(board: *mut RchessBoard, mv: RchessMove) -> RchessResult {
+    let Some(board) = (unsafe { board.as_mut() }) else {
+        return RchessResult::NullBoard;
+    };
+    let Some(mv) = mv.to_move() else {
+        return RchessResult::InvalidMove;
+    };
+    match board.board.apply_move(mv) {
+        Ok(_) => RchessResult::Ok,
+        Err(_) => RchessResult::IllegalMove,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_round_trip_through_the_c_api() {
+        let fen = CString::new("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        unsafe {
+            let board = rchess_board_from_fen(fen.as_ptr());
+            assert!(!board.is_null());
+            let count = rchess_legal_moves(board, ptr::null_mut(), 0);
+            let mut moves = vec![RchessMove { from: 0, to: 0, promotion: 0, drop: 0 }; count];
+            assert_eq!(rchess_legal_moves(board, moves.as_mut_ptr(), count), count);
+            let queen = RchessMove { from: sq!("a7").index(), to: sq!("a8").index(), promotion: b'q', drop: 0 };
+            assert!(moves.contains(&queen));
+
+            assert_eq!(rchess_apply_move(board, RchessMove { to: 64, ..queen }), RchessResult::InvalidMove);
+            assert_eq!(rchess_apply_move(board, RchessMove { promotion: 0, ..queen }), RchessResult::IllegalMove);
+            assert_eq!(rchess_apply_move(board, queen), RchessResult::Ok);
+            let text = rchess_board_fen(board);
+            assert_eq!(CStr::from_ptr(text).to_str(), Ok("Q3k3/8/8/8/8/8/8/4K3 b - - 0 1"));
+            rchess_string_free(text);
+            rchess_board_free(board);
+
+            let bad = CString::new("not a fen").unwrap();
+            assert!(rchess_board_from_fen(bad.as_ptr()).is_null());
+            assert_eq!(rchess_apply_move(ptr::null_mut(), queen), RchessResult::NullBoard);
+        }
+    }
+}
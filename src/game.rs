@@ -0,0 +1,768 @@
+// A game: tags, a starting position, the moves played from it, and the result.
+// Game owns the live Board and can replay any earlier position, and the clock
+// when the game is timed. Draw offers, resignations, and adjudications are kept
+// alongside the moves as events.
+
+use crate::board::{Board, CapturedPiece};
+use crate::clock::Clock;
+use crate::error::Error;
+use crate::fen::STARTING_FEN;
+use crate::game_tree::{GameTree, NodeId};
+use crate::moves::{Move, MoveError, MoveOutcome};
+use crate::notation;
+use crate::observer::{GameUpdate, Listeners, SubscriptionId};
+use crate::pieces::{Color, Square};
+use crate::rchess::v1::{self as proto};
+use crate::status::GameStatus;
+use std::fmt;
+
+/// Outcome of a game as recorded in PGN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    /// Still in progress or unknown ("*").
+    #[default]
+    Ongoing,
+}
+
+impl GameResult {
+    /// The winner, if the game was decisive.
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            GameResult::WhiteWins => Some(Color::White),
+            GameResult::BlackWins => Some(Color::Black),
+            GameResult::Draw | GameResult::Ongoing => None,
+        }
+    }
+
+    /// PGN result token: "1-0", "0-1", "1/2-1/2", or "*".
+    pub fn to_pgn(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Ongoing => "*",
+        }
+    }
+
+    /// Parse a PGN result token.
+    pub fn from_pgn(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Ongoing),
+            _ => None,
+        }
+    }
+
+    /// Convert to the proto GameResult enum value.
+    pub fn to_proto(&self) -> i32 {
+        let result = match self {
+            GameResult::WhiteWins => proto::GameResult::WhiteWins,
+            GameResult::BlackWins => proto::GameResult::BlackWins,
+            GameResult::Draw => proto::GameResult::Draw,
+            GameResult::Ongoing => proto::GameResult::Ongoing,
+        };
+        result as i32
+    }
+
+    /// Convert from a proto GameResult enum value; unspecified reads as ongoing.
+    pub fn from_proto(value: i32) -> Option<Self> {
+        match proto::GameResult::try_from(value).ok()? {
+            proto::GameResult::WhiteWins => Some(GameResult::WhiteWins),
+            proto::GameResult::BlackWins => Some(GameResult::BlackWins),
+            proto::GameResult::Draw => Some(GameResult::Draw),
+            proto::GameResult::Ongoing | proto::GameResult::Unspecified => Some(GameResult::Ongoing),
+        }
+    }
+}
+
+/// Something that happened in a game besides a move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    DrawOffered(Color),
+    DrawAccepted(Color),
+    DrawDeclined(Color),
+    Resigned(Color),
+    /// Settled without being played out, for the stated reason.
+    Adjudicated { result: GameResult, reason: String },
+}
+
+impl GameEvent {
+    /// How the event ended the game, if it did.
+    pub fn status(&self) -> Option<GameStatus> {
+        match self {
+            GameEvent::DrawAccepted(_) => Some(GameStatus::DrawAgreed),
+            GameEvent::Resigned(color) => Some(GameStatus::Resignation { winner: color.opposite() }),
+            GameEvent::Adjudicated { result, .. } => Some(GameStatus::Adjudicated { result: *result }),
+            GameEvent::DrawOffered(_) | GameEvent::DrawDeclined(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameEvent::DrawOffered(color) => write!(f, "{} offers a draw", color),
+            GameEvent::DrawAccepted(color) => write!(f, "{} accepts the draw", color),
+            GameEvent::DrawDeclined(color) => write!(f, "{} declines the draw", color),
+            GameEvent::Resigned(color) => write!(f, "{} resigns", color),
+            GameEvent::Adjudicated { result, reason } if reason.is_empty() => {
+                write!(f, "Adjudicated {}", result.to_pgn())
+            }
+            GameEvent::Adjudicated { result, reason } => write!(f, "Adjudicated {}: {}", result.to_pgn(), reason),
+        }
+    }
+}
+
+/// Why a draw offer, resignation, or adjudication was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionError {
+    /// The game already has a result.
+    GameOver,
+    /// There is no draw offer from the opponent to answer.
+    NoDrawOffer,
+    /// An adjudication must name a decisive result or a draw.
+    NoResult,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionError::GameOver => write!(f, "the game is over"),
+            ActionError::NoDrawOffer => write!(f, "the opponent has not offered a draw"),
+            ActionError::NoResult => write!(f, "an adjudication needs a result"),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// A game record with its live board. The mainline of the move tree is the game
+/// as played; sidelines and annotations ride along for PGN round trips.
+#[derive(Debug, Clone)]
+pub struct Game {
+    /// Tag pairs in the order they were set.
+    tags: Vec<(String, String)>,
+    tree: GameTree,
+    /// Last mainline node, where played moves are appended.
+    tip: NodeId,
+    board: Board,
+    moves: Vec<Move>,
+    result: GameResult,
+    /// None for an untimed game.
+    clock: Option<Clock>,
+    /// Each event with the number of moves played before it.
+    events: Vec<(usize, GameEvent)>,
+    /// Side whose draw offer is standing.
+    draw_offer: Option<Color>,
+    /// Remaining time below which subscribers are sent ClockLow.
+    low_time_ms: Option<u64>,
+    /// Callbacks told about moves and events; see the observer module.
+    listeners: Listeners,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
+}
+
+impl Game {
+    /// A new game from the standard starting position.
+    pub fn new() -> Self {
+        Game::from_position(Board::starting_position())
+    }
+
+    /// A new game starting from an arbitrary position.
+    pub fn from_position(board: Board) -> Self {
+        Game::from_tree(GameTree::new(board))
+    }
+
+    /// A game whose moves are the mainline of `tree`.
+    pub fn from_tree(tree: GameTree) -> Self {
+        let mainline = tree.mainline();
+        let tip = mainline.last().copied().unwrap_or(tree.root());
+        Game {
+            tags: Vec::new(),
+            board: tree.board_at(tip),
+            moves: tree.mainline_moves(),
+            tree,
+            tip,
+            result: GameResult::Ongoing,
+            clock: None,
+            events: Vec::new(),
+            draw_offer: None,
+            low_time_ms: None,
+            listeners: Listeners::default(),
+        }
+    }
+
+    /// Create from a proto GameState, clock included. The board's move history
+    /// becomes part of the starting position.
+    #[deprecated(note = "accepts invalid colors, squares, and variants; use `Game::try_from_proto`")]
+    pub fn from_proto(mut state: proto::GameState) -> Self {
+        let clock = state.clock.take().map(|clock| Clock::from_proto(&clock));
+        let mut game = Game::from_position(Board::from_proto_unchecked(state));
+        game.set_clock(clock);
+        game
+    }
+
+    /// Create from a proto GameState, clock included, checking the position as
+    /// `Board::try_from_proto` does.
+    pub fn try_from_proto(mut state: proto::GameState) -> Result<Self, Error> {
+        let clock = state.clock.take().map(|clock| Clock::from_proto(&clock));
+        let mut game = Game::from_position(Board::try_from_proto(state)?);
+        game.set_clock(clock);
+        Ok(game)
+    }
+
+    /// Convert to proto GameState: the current position with the clock.
+    pub fn to_proto(&self) -> proto::GameState {
+        let mut state = self.board.to_proto();
+        state.clock = self.clock.as_ref().map(Clock::to_proto);
+        state
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// Value of a tag, if set.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Set a tag, replacing any existing value but keeping its position.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Position the game started from.
+    pub fn start_position(&self) -> &Board {
+        self.tree.start_position()
+    }
+
+    /// The move tree, including sidelines and annotations.
+    pub fn tree(&self) -> &GameTree {
+        &self.tree
+    }
+
+    /// The move tree, for annotating. Callers must leave the mainline as played.
+    pub(crate) fn tree_mut(&mut self) -> &mut GameTree {
+        &mut self.tree
+    }
+
+    /// Current position.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    pub fn set_result(&mut self, result: GameResult) {
+        self.result = result;
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    /// Time the game, or stop timing it with None. The side to move's time
+    /// starts running at once.
+    pub fn set_clock(&mut self, clock: Option<Clock>) {
+        self.clock = clock;
+        if let Some(clock) = &mut self.clock {
+            clock.start(self.board.current_player());
+        }
+    }
+
+    /// Send subscribers ClockLow when a move takes the mover's remaining time
+    /// below `threshold_ms`, once per crossing; None turns the warning off.
+    pub fn set_low_time(&mut self, threshold_ms: Option<u64>) {
+        self.low_time_ms = threshold_ms;
+    }
+
+    /// Play a move on the current position. On a timed game the mover is
+    /// charged the wall-clock time since their clock started.
+    pub fn play(&mut self, mv: Move) -> Result<MoveOutcome, MoveError> {
+        self.apply(mv, None)
+    }
+
+    /// Play a move that took `elapsed_ms` to find, charging that to the mover's
+    /// clock instead of the wall-clock time.
+    pub fn play_timed(&mut self, mv: Move, elapsed_ms: u64) -> Result<MoveOutcome, MoveError> {
+        self.apply(mv, Some(elapsed_ms))
+    }
+
+    /// Play a move, charging the mover `elapsed_ms` or the wall-clock time;
+    /// replying declines the opponent's standing draw offer. A move that ends
+    /// the game records the result, and once it is over no move is accepted.
+    /// A move that takes the mover past their time does not stand: the game
+    /// ends on time instead and the move is refused as GameOver.
+    fn apply(&mut self, mv: Move, elapsed_ms: Option<u64>) -> Result<MoveOutcome, MoveError> {
+        self.check_ongoing().map_err(|_| MoveError::GameOver)?;
+        let mover = self.board.current_player();
+        // Subscribers are told the move in SAN, whose body needs the position before it.
+        let announced = (!self.listeners.is_empty()).then(|| {
+            let mv = self.board.annotate_move(mv);
+            (mv, notation::san_body(&mv, &self.board))
+        });
+        let outcome = self.board.apply_move(mv)?;
+        let before_ms = self.clock.as_ref().map(|clock| clock.remaining_ms(mover));
+        let in_time = match (&mut self.clock, elapsed_ms) {
+            (None, _) => true,
+            (Some(clock), Some(elapsed_ms)) => clock.press(mover, elapsed_ms),
+            (Some(clock), None) => clock.press_now(mover),
+        };
+        if !in_time {
+            self.board.unmake_move();
+            let status = self.game_status();
+            self.result = status.result();
+            self.draw_offer = None;
+            self.listeners.notify(&GameUpdate::GameEnded(status));
+            return Err(MoveError::GameOver);
+        }
+        self.moves.push(mv);
+        self.tip = self.tree.push_node(self.tip, mv);
+        if self.draw_offer == Some(mover.opposite()) {
+            self.draw_offer = None;
+        }
+        let low_ms = match (self.low_time_ms, before_ms, &self.clock) {
+            (Some(threshold), Some(before), Some(clock)) => {
+                let after = clock.remaining_ms(mover);
+                (before >= threshold && after < threshold).then_some(after)
+            }
+            _ => None,
+        };
+        let status = self.game_status();
+        if status.is_over() {
+            self.result = status.result();
+            self.draw_offer = None;
+            if let Some(clock) = &mut self.clock {
+                clock.stop();
+            }
+        }
+        if let Some((mv, san)) = announced {
+            self.announce_move(mover, mv, san, outcome, low_ms, status);
+        }
+        Ok(outcome)
+    }
+
+    /// Take back the last move and return it. Events recorded after it go too,
+    /// so a resignation or agreed draw is undone with it, as is a mate or draw
+    /// the move reached. Sidelines branching at the move are dropped, since the
+    /// mainline must end at the last move played. A fallen flag stays fallen;
+    /// otherwise the clock runs for the side to move again.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        self.board.unmake_move();
+        self.tip = self.tree.node(self.tip).parent().unwrap_or(self.tree.root());
+        self.tree.cut_after(self.tip);
+        let played = self.moves.len();
+        self.events.retain(|&(ply, _)| ply <= played);
+        self.result = self.game_status().result();
+        self.draw_offer = None;
+        if let Some(clock) = &mut self.clock {
+            clock.start(self.board.current_player());
+        }
+        Some(mv)
+    }
+
+    /// Call `listener` with every move played and event recorded from now on,
+    /// until it is unsubscribed. A clone of the game starts with no subscribers.
+    pub fn subscribe(&mut self, listener: impl FnMut(&GameUpdate) + Send + 'static) -> SubscriptionId {
+        self.listeners.add(Box::new(listener))
+    }
+
+    /// Stop calling a subscriber; false if it was not subscribed to this game.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.listeners.remove(id)
+    }
+
+    /// Tell subscribers about `mv`, just played by `mover`, the time it left
+    /// them if that fell below the threshold, and the status it left.
+    fn announce_move(
+        &mut self,
+        mover: Color,
+        mv: Move,
+        san: String,
+        outcome: MoveOutcome,
+        low_ms: Option<u64>,
+        status: GameStatus,
+    ) {
+        let san = notation::san_with_suffix(san, &self.board);
+        let mut updates = vec![GameUpdate::MovePlayed { mv, san, mover }];
+        if let Some(piece) = outcome.captured {
+            let square = if mv.en_passant { Square { rank: mv.from.rank, ..mv.to } } else { mv.to };
+            updates.push(GameUpdate::Capture { piece, color: mover.opposite(), square });
+        }
+        if let Some(piece) = mv.promotion {
+            updates.push(GameUpdate::Promotion { piece, square: mv.to });
+        }
+        if outcome.check {
+            updates.push(GameUpdate::Check(mover.opposite()));
+        }
+        if let Some(remaining_ms) = low_ms {
+            updates.push(GameUpdate::ClockLow { color: mover, remaining_ms });
+        }
+        if status.is_over() {
+            updates.push(GameUpdate::GameEnded(status));
+        }
+        for update in &updates {
+            self.listeners.notify(update);
+        }
+    }
+
+    /// Draw offers, resignations, and adjudications, each with the number of
+    /// moves played before it.
+    pub fn events(&self) -> &[(usize, GameEvent)] {
+        &self.events
+    }
+
+    /// Side whose draw offer is standing. An offer lapses when the opponent
+    /// moves instead of answering it.
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    /// Offer a draw for `color`. A standing offer from the opponent is accepted
+    /// instead. Returns the result, which is a draw only if the offer was agreed.
+    pub fn offer_draw(&mut self, color: Color) -> Result<GameResult, ActionError> {
+        if self.draw_offer == Some(color.opposite()) {
+            return self.accept_draw(color);
+        }
+        self.check_ongoing()?;
+        self.draw_offer = Some(color);
+        self.record(GameEvent::DrawOffered(color));
+        Ok(self.result)
+    }
+
+    /// Accept the opponent's standing draw offer.
+    pub fn accept_draw(&mut self, color: Color) -> Result<GameResult, ActionError> {
+        self.check_ongoing()?;
+        if self.draw_offer != Some(color.opposite()) {
+            return Err(ActionError::NoDrawOffer);
+        }
+        self.finish(GameEvent::DrawAccepted(color), GameResult::Draw);
+        Ok(self.result)
+    }
+
+    /// Decline the opponent's standing draw offer.
+    pub fn decline_draw(&mut self, color: Color) -> Result<(), ActionError> {
+        self.check_ongoing()?;
+        if self.draw_offer != Some(color.opposite()) {
+            return Err(ActionError::NoDrawOffer);
+        }
+        self.draw_offer = None;
+        self.record(GameEvent::DrawDeclined(color));
+        Ok(())
+    }
+
+    /// Resign for `color` and return the result.
+    pub fn resign(&mut self, color: Color) -> Result<GameResult, ActionError> {
+        self.check_ongoing()?;
+        let result = match color {
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins,
+        };
+        self.finish(GameEvent::Resigned(color), result);
+        Ok(result)
+    }
+
+    /// End the game with `result` without playing it out, e.g. when an arbiter
+    /// rules on an adjourned game or an engine match calls a dead position.
+    pub fn adjudicate(&mut self, result: GameResult, reason: &str) -> Result<(), ActionError> {
+        self.check_ongoing()?;
+        if result == GameResult::Ongoing {
+            return Err(ActionError::NoResult);
+        }
+        self.finish(GameEvent::Adjudicated { result, reason: reason.to_string() }, result);
+        Ok(())
+    }
+
+    fn check_ongoing(&self) -> Result<(), ActionError> {
+        if self.result != GameResult::Ongoing || self.game_status().is_over() {
+            return Err(ActionError::GameOver);
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, event: GameEvent) {
+        self.listeners.notify(&GameUpdate::Event(event.clone()));
+        self.events.push((self.moves.len(), event));
+    }
+
+    fn finish(&mut self, event: GameEvent, result: GameResult) {
+        self.record(event);
+        self.result = result;
+        self.draw_offer = None;
+        if let Some(clock) = &mut self.clock {
+            clock.stop();
+        }
+        let status = self.game_status();
+        self.listeners.notify(&GameUpdate::GameEnded(status));
+    }
+
+    /// Whether the game is over: a resignation, agreed draw, or adjudication
+    /// first, then a fallen flag, then the position. A flag fall is a draw when
+    /// the opponent has nothing to mate with.
+    pub fn game_status(&self) -> GameStatus {
+        if let Some(status) = self.events.iter().rev().find_map(|(_, event)| event.status()) {
+            return status;
+        }
+        if let Some(loser) = self.clock.as_ref().and_then(Clock::flagged) {
+            let winner = loser.opposite();
+            return if self.board.has_mating_material(winner) {
+                GameStatus::TimeForfeit { winner }
+            } else {
+                GameStatus::TimeoutDraw
+            };
+        }
+        self.board.game_status()
+    }
+
+    /// Every position of the game, from the start through the current one.
+    pub fn positions(&self) -> Vec<Board> {
+        let mut board = self.start_position().clone();
+        let mut positions = vec![board.clone()];
+        for &mv in &self.moves {
+            board.make_move(mv);
+            positions.push(board.clone());
+        }
+        positions
+    }
+
+    /// Walk the game from its start one ply at a time, leaving the live board
+    /// alone: the position before each move, the move, its SAN, and the number
+    /// of the full move it belongs to.
+    pub fn replay(&self) -> impl Iterator<Item = (Board, Move, String, i32)> + '_ {
+        let mut board = self.start_position().clone();
+        self.moves.iter().map(move |&mv| {
+            let before = board.clone();
+            board.make_move(mv);
+            let san = mv.to_san(&before);
+            let number = before.fullmove_number();
+            (before, mv, san, number)
+        })
+    }
+
+    /// The game as a UCI "position" command for an external engine: "position
+    /// startpos moves e2e4 ..." from the standard start, "position fen ..."
+    /// from any other, with no "moves" part before the first move.
+    pub fn to_uci_position(&self) -> String {
+        let start = self.start_position().to_fen();
+        let mut command =
+            if start == STARTING_FEN { "position startpos".to_string() } else { format!("position fen {}", start) };
+        if !self.moves.is_empty() {
+            command.push_str(" moves");
+            for mv in &self.moves {
+                command.push(' ');
+                command.push_str(&mv.to_uci());
+            }
+        }
+        command
+    }
+
+    /// Proto GameState snapshot of every position, for downstream tooling.
+    pub fn snapshots(&self) -> Vec<proto::GameState> {
+        self.positions().iter().map(Board::to_proto).collect()
+    }
+
+    /// Pieces captured so far, in the order they were taken.
+    pub fn captured_pieces(&self) -> Vec<CapturedPiece> {
+        self.board.captured_pieces()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TimeControl;
+    use crate::pieces::PieceType;
+    use crate::sq;
+
+    #[test]
+    fn test_play_and_replay_positions() {
+        let mut game = Game::new();
+        game.play(Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        game.play(Move::new(sq!("d7"), sq!("d5"))).unwrap();
+        game.play(Move::new(sq!("e4"), sq!("d5"))).unwrap();
+        assert!(game.play(Move::new(sq!("e2"), sq!("e4"))).is_err());
+        assert_eq!(game.moves().len(), 3);
+
+        let positions = game.positions();
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions[3].to_fen(), game.board().to_fen());
+        assert_eq!(game.snapshots()[1].moves.len(), 1);
+        assert_eq!(game.captured_pieces()[0].piece_type, PieceType::Pawn);
+        assert_eq!(game.tree().mainline_moves(), game.moves());
+    }
+
+    #[test]
+    fn test_replay_walks_the_mainline() {
+        let mut game = Game::new();
+        for (from, to) in [(sq!("e2"), sq!("e4")), (sq!("e7"), sq!("e5")), (sq!("g1"), sq!("f3"))] {
+            game.play(Move::new(from, to)).unwrap();
+        }
+        let fen = game.board().to_fen();
+        let plies: Vec<_> = game.replay().collect();
+        let sans: Vec<&str> = plies.iter().map(|(_, _, san, _)| san.as_str()).collect();
+        assert_eq!(sans, ["e4", "e5", "Nf3"]);
+        assert_eq!(plies.iter().map(|ply| ply.3).collect::<Vec<_>>(), [1, 1, 2]);
+        assert_eq!(plies[0].0.to_fen(), Board::starting_position().to_fen());
+        assert_eq!(plies[2].1, Move::new(sq!("g1"), sq!("f3")));
+        assert_eq!(game.board().to_fen(), fen);
+    }
+
+    #[test]
+    fn test_uci_position_command() {
+        let mut game = Game::new();
+        assert_eq!(game.to_uci_position(), "position startpos");
+        game.play(Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        game.play(Move::new(sq!("e7"), sq!("e5"))).unwrap();
+        assert_eq!(game.to_uci_position(), "position startpos moves e2e4 e7e5");
+
+        let fen = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        let mut promoting = Game::from_position(Board::from_fen(fen).unwrap());
+        promoting.play(Move::with_promotion(sq!("a7"), sq!("a8"), PieceType::Queen)).unwrap();
+        assert_eq!(promoting.to_uci_position(), format!("position fen {} moves a7a8q", fen));
+    }
+
+    #[test]
+    fn test_game_from_tree_follows_mainline() {
+        let mut tree = GameTree::new(Board::starting_position());
+        let e4 = tree.add_move(tree.root(), Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        tree.add_move(tree.root(), Move::new(sq!("d2"), sq!("d4"))).unwrap();
+        tree.add_move(e4, Move::new(sq!("c7"), sq!("c5"))).unwrap();
+
+        let mut game = Game::from_tree(tree);
+        assert_eq!(game.moves(), &[Move::new(sq!("e2"), sq!("e4")), Move::new(sq!("c7"), sq!("c5"))]);
+        game.play(Move::new(sq!("g1"), sq!("f3"))).unwrap();
+        assert_eq!(game.tree().mainline().len(), 3);
+        assert_eq!(game.tree().variations(game.tree().root()).len(), 1);
+    }
+
+    #[test]
+    fn test_tags_keep_order() {
+        let mut game = Game::new();
+        game.set_tag("Event", "Casual");
+        game.set_tag("Site", "Home");
+        game.set_tag("Event", "Club");
+        assert_eq!(game.tag("Event"), Some("Club"));
+        assert_eq!(game.tags()[0].0, "Event");
+        assert_eq!(game.tag("Round"), None);
+    }
+
+    #[test]
+    fn test_clock_charges_mover_and_reports_flag() {
+        let mut game = Game::new();
+        game.set_clock(Some(Clock::new(TimeControl::Increment { base_ms: 10_000, increment_ms: 1_000 })));
+        game.play_timed(Move::new(sq!("e2"), sq!("e4")), 3_000).unwrap();
+        let clock = game.clock().unwrap();
+        assert_eq!(clock.remaining_ms(Color::White), 8_000);
+        assert_eq!(clock.running(), Some(Color::Black));
+        assert_eq!(game.game_status(), GameStatus::Ongoing);
+
+        let restored = Game::try_from_proto(game.to_proto()).unwrap();
+        assert_eq!(restored.clock().unwrap().remaining_ms(Color::White), 8_000);
+        assert_eq!(restored.board().to_fen(), game.board().to_fen());
+
+        // A move made after the flag fell does not stand.
+        assert_eq!(game.play_timed(Move::new(sq!("e7"), sq!("e5")), 20_000), Err(MoveError::GameOver));
+        assert_eq!(game.game_status(), GameStatus::TimeForfeit { winner: Color::White });
+        assert_eq!(game.result(), GameResult::WhiteWins);
+        assert_eq!(game.moves().len(), 1);
+        assert_eq!(game.board().current_player(), Color::Black);
+
+        // A lone king cannot win on time.
+        let mut bare = Game::from_position(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1").unwrap());
+        bare.set_clock(Some(Clock::new(TimeControl::Classical { base_ms: 1_000, moves: 0 })));
+        assert_eq!(bare.play_timed(Move::new(sq!("e8"), sq!("d8")), 5_000), Err(MoveError::GameOver));
+        assert_eq!(bare.game_status(), GameStatus::TimeForfeit { winner: Color::White });
+        let mut lone = Game::from_position(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap());
+        lone.set_clock(Some(Clock::new(TimeControl::Classical { base_ms: 1_000, moves: 0 })));
+        assert_eq!(lone.play_timed(Move::new(sq!("e2"), sq!("e4")), 5_000), Err(MoveError::GameOver));
+        assert_eq!(lone.game_status(), GameStatus::TimeoutDraw);
+        assert_eq!(lone.result(), GameResult::Draw);
+    }
+
+    #[test]
+    fn test_draw_offers_resignation_and_adjudication() {
+        let mut game = Game::new();
+        assert_eq!(game.accept_draw(Color::Black), Err(ActionError::NoDrawOffer));
+        assert_eq!(game.offer_draw(Color::White), Ok(GameResult::Ongoing));
+        game.play(Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        assert_eq!(game.draw_offer(), Some(Color::White));
+        game.play(Move::new(sq!("e7"), sq!("e5"))).unwrap();
+        assert_eq!(game.draw_offer(), None);
+
+        game.offer_draw(Color::Black).unwrap();
+        game.decline_draw(Color::White).unwrap();
+        game.offer_draw(Color::Black).unwrap();
+        assert_eq!(game.offer_draw(Color::White), Ok(GameResult::Draw));
+        assert_eq!(game.game_status(), GameStatus::DrawAgreed);
+        assert_eq!(game.resign(Color::White), Err(ActionError::GameOver));
+        assert_eq!(game.events().len(), 5);
+        assert_eq!(game.events()[4], (2, GameEvent::DrawAccepted(Color::White)));
+
+        let mut resigned = Game::new();
+        assert_eq!(resigned.resign(Color::White), Ok(GameResult::BlackWins));
+        assert_eq!(resigned.game_status(), GameStatus::Resignation { winner: Color::Black });
+
+        let mut adjudicated = Game::new();
+        assert_eq!(adjudicated.adjudicate(GameResult::Ongoing, ""), Err(ActionError::NoResult));
+        adjudicated.adjudicate(GameResult::Draw, "dead position").unwrap();
+        assert_eq!(adjudicated.game_status().result(), GameResult::Draw);
+        assert_eq!(adjudicated.events()[0].1.to_string(), "Adjudicated 1/2-1/2: dead position");
+    }
+
+    #[test]
+    fn test_undo_takes_back_move_and_later_events() {
+        let mut game = Game::new();
+        assert_eq!(game.undo(), None);
+        let moves = [
+            Move::new(sq!("f2"), sq!("f3")),
+            Move::new(sq!("e7"), sq!("e5")),
+            Move::new(sq!("g2"), sq!("g4")),
+            Move::new(sq!("d8"), sq!("h4")),
+        ];
+        for mv in moves {
+            game.play(mv).unwrap();
+        }
+        assert_eq!(game.result(), GameResult::BlackWins);
+
+        assert_eq!(game.undo(), Some(moves[3]));
+        assert_eq!(game.result(), GameResult::Ongoing);
+        assert_eq!(game.moves(), &moves[..3]);
+        assert_eq!(game.tree().mainline_moves(), &moves[..3]);
+        game.resign(Color::Black).unwrap();
+        assert_eq!(game.undo(), Some(moves[2]));
+        assert!(game.events().is_empty());
+        assert_eq!(game.game_status(), GameStatus::Ongoing);
+        assert_eq!(game.board().to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/8/5P2/PPPPP1PP/RNBQKBNR w KQkq e6 0 2");
+
+        game.play(Move::new(sq!("d2"), sq!("d4"))).unwrap();
+        assert!(game.tree().variations(game.tree().mainline()[1]).is_empty());
+    }
+
+    #[test]
+    fn test_result_tokens() {
+        for result in [GameResult::WhiteWins, GameResult::BlackWins, GameResult::Draw, GameResult::Ongoing] {
+            assert_eq!(GameResult::from_pgn(result.to_pgn()), Some(result));
+        }
+        assert_eq!(GameResult::BlackWins.winner(), Some(Color::Black));
+    }
+}
@@ -0,0 +1,281 @@
+// Move trees: a mainline with recursive variations, comments, NAGs, evals,
+// clock times, and free-form key-value annotations.
+// Each node is the position reached by one move; the first child continues the mainline.
+
+use crate::board::Board;
+use crate::moves::{Move, MoveError};
+
+/// Handle to a node in a [`GameTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// One move in the tree with its annotations. The root node has no move.
+#[derive(Debug, Clone, Default)]
+pub struct Node {
+    mv: Option<Move>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    comment: Option<String>,
+    nags: Vec<u8>,
+    eval: Option<i32>,
+    clock_ms: Option<u64>,
+    annotations: Vec<(String, String)>,
+}
+
+impl Node {
+    /// The move leading to this node; `None` for the root.
+    pub fn mv(&self) -> Option<Move> {
+        self.mv
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    /// Replies to this move; the first continues the line, the rest are variations.
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    /// Comment following the move (or preceding the game, for the root).
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /// Numeric Annotation Glyphs, e.g. 1 for "!" and 2 for "?".
+    pub fn nags(&self) -> &[u8] {
+        &self.nags
+    }
+
+    /// Engine evaluation of the position after the move, in centipawns from
+    /// White's point of view; mates as in [`mate_distance`](crate::engine::mate_distance).
+    pub fn eval(&self) -> Option<i32> {
+        self.eval
+    }
+
+    /// Time left on the mover's clock after the move.
+    pub fn clock_ms(&self) -> Option<u64> {
+        self.clock_ms
+    }
+
+    /// Other annotations as key-value pairs, in the order they were set.
+    pub fn annotations(&self) -> &[(String, String)] {
+        &self.annotations
+    }
+
+    /// The reply continuing this line, if any.
+    pub fn next(&self) -> Option<NodeId> {
+        self.children.first().copied()
+    }
+}
+
+/// A tree of moves from a starting position.
+#[derive(Debug, Clone)]
+pub struct GameTree {
+    start: Board,
+    nodes: Vec<Node>,
+}
+
+impl GameTree {
+    pub fn new(start: Board) -> Self {
+        GameTree {
+            start,
+            nodes: vec![Node::default()],
+        }
+    }
+
+    /// Position before any move.
+    pub fn start_position(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// Alternatives to the mainline reply after `id`.
+    pub fn variations(&self, id: NodeId) -> &[NodeId] {
+        self.node(id).children.get(1..).unwrap_or_default()
+    }
+
+    /// Other replies to the same move as `id`, in order; empty for the root.
+    pub fn siblings(&self, id: NodeId) -> Vec<NodeId> {
+        let Some(parent) = self.node(id).parent else {
+            return Vec::new();
+        };
+        self.node(parent).children.iter().copied().filter(|&c| c != id).collect()
+    }
+
+    /// Add `mv` as a reply after `parent`, becoming the mainline if it is the first.
+    /// A move that is already present returns the existing node.
+    pub fn add_move(&mut self, parent: NodeId, mv: Move) -> Result<NodeId, MoveError> {
+        if !self.node(parent).children.iter().any(|&c| self.node(c).mv == Some(mv)) {
+            self.board_at(parent).apply_move(mv)?;
+        }
+        Ok(self.push_node(parent, mv))
+    }
+
+    /// Add a move already known to be legal after `parent`, or return the node
+    /// that already has it.
+    pub(crate) fn push_node(&mut self, parent: NodeId, mv: Move) -> NodeId {
+        if let Some(&existing) = self.node(parent).children.iter().find(|&&c| self.node(c).mv == Some(mv)) {
+            return existing;
+        }
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            mv: Some(mv),
+            parent: Some(parent),
+            ..Default::default()
+        });
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+
+    /// Detach every reply after `id`, sidelines included. The nodes stay in the
+    /// arena so ids remain valid, but no path from the root reaches them.
+    pub(crate) fn cut_after(&mut self, id: NodeId) {
+        self.nodes[id.0].children.clear();
+    }
+
+    pub fn set_comment(&mut self, id: NodeId, comment: Option<String>) {
+        self.nodes[id.0].comment = comment;
+    }
+
+    pub fn add_nag(&mut self, id: NodeId, nag: u8) {
+        self.nodes[id.0].nags.push(nag);
+    }
+
+    pub fn set_eval(&mut self, id: NodeId, eval: Option<i32>) {
+        self.nodes[id.0].eval = eval;
+    }
+
+    pub fn set_clock_ms(&mut self, id: NodeId, clock_ms: Option<u64>) {
+        self.nodes[id.0].clock_ms = clock_ms;
+    }
+
+    /// Set the annotation `key`, replacing any value it had.
+    pub fn set_annotation(&mut self, id: NodeId, key: &str, value: &str) {
+        let annotations = &mut self.nodes[id.0].annotations;
+        match annotations.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => annotations.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// Make the line through `id` the mainline at every branch above it.
+    pub fn promote_to_mainline(&mut self, id: NodeId) {
+        let mut child = id;
+        while let Some(parent) = self.node(child).parent {
+            let siblings = &mut self.nodes[parent.0].children;
+            if let Some(pos) = siblings.iter().position(|&c| c == child) {
+                let promoted = siblings.remove(pos);
+                siblings.insert(0, promoted);
+            }
+            child = parent;
+        }
+    }
+
+    /// Nodes from the first move down to `id`.
+    pub fn path(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.node(current).parent {
+            path.push(current);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Mainline nodes, following the first child from the root.
+    pub fn mainline(&self) -> Vec<NodeId> {
+        let mut line = Vec::new();
+        let mut current = self.root();
+        while let Some(&next) = self.node(current).children.first() {
+            line.push(next);
+            current = next;
+        }
+        line
+    }
+
+    /// Moves of the mainline.
+    pub fn mainline_moves(&self) -> Vec<Move> {
+        self.mainline().iter().filter_map(|&id| self.node(id).mv).collect()
+    }
+
+    /// Position after the move at `id`.
+    pub fn board_at(&self, id: NodeId) -> Board {
+        let mut board = self.start.clone();
+        for node in self.path(id) {
+            if let Some(mv) = self.node(node).mv {
+                board.make_move(mv);
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_mainline_and_variations() {
+        let mut tree = GameTree::new(Board::starting_position());
+        let e4 = tree.add_move(tree.root(), Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        let d4 = tree.add_move(tree.root(), Move::new(sq!("d2"), sq!("d4"))).unwrap();
+        let e5 = tree.add_move(e4, Move::new(sq!("e7"), sq!("e5"))).unwrap();
+        let d5 = tree.add_move(d4, Move::new(sq!("d7"), sq!("d5"))).unwrap();
+
+        assert_eq!(tree.mainline(), vec![e4, e5]);
+        assert_eq!(tree.variations(tree.root()), &[d4]);
+        assert_eq!(tree.path(d5), vec![d4, d5]);
+        assert_eq!(tree.add_move(tree.root(), Move::new(sq!("e2"), sq!("e4"))).unwrap(), e4);
+        assert!(tree.add_move(e5, Move::new(sq!("e4"), sq!("e5"))).is_err());
+        assert_eq!(
+            tree.board_at(d5).to_fen(),
+            "rnbqkbnr/ppp1pppp/8/3p4/3P4/8/PPP1PPPP/RNBQKBNR w KQkq d6 0 2"
+        );
+
+        tree.promote_to_mainline(d5);
+        assert_eq!(tree.mainline(), vec![d4, d5]);
+        assert_eq!(tree.variations(tree.root()), &[e4]);
+    }
+
+    #[test]
+    fn test_annotations() {
+        let mut tree = GameTree::new(Board::starting_position());
+        let e4 = tree.add_move(tree.root(), Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        tree.set_comment(e4, Some("best by test".to_string()));
+        tree.add_nag(e4, 1);
+        assert_eq!(tree.node(e4).comment(), Some("best by test"));
+        assert_eq!(tree.node(e4).nags(), &[1]);
+        assert_eq!(tree.node(e4).parent(), Some(tree.root()));
+        tree.set_eval(e4, Some(30));
+        assert_eq!(tree.node(e4).eval(), Some(30));
+        tree.set_annotation(e4, "engine", "rchess");
+        tree.set_annotation(e4, "depth", "12");
+        tree.set_annotation(e4, "engine", "rchess 0.1");
+        assert_eq!(
+            tree.node(e4).annotations(),
+            &[("engine".to_string(), "rchess 0.1".to_string()), ("depth".to_string(), "12".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut tree = GameTree::new(Board::starting_position());
+        let e4 = tree.add_move(tree.root(), Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        let d4 = tree.add_move(tree.root(), Move::new(sq!("d2"), sq!("d4"))).unwrap();
+        let c4 = tree.add_move(tree.root(), Move::new(sq!("c2"), sq!("c4"))).unwrap();
+        assert_eq!(tree.node(tree.root()).next(), Some(e4));
+        assert_eq!(tree.node(e4).next(), None);
+        assert_eq!(tree.siblings(d4), vec![e4, c4]);
+        assert!(tree.siblings(tree.root()).is_empty());
+    }
+}
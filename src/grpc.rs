@@ -0,0 +1,797 @@
+// gRPC front-end: the rchess.v1.GameService from proto/service.proto, served with tonic.
+// GameServiceServer routes requests to any GameService implementation; GameStore is
+// the default one, holding games in memory for as long as the process runs.
+// Each stored game has a broadcast channel that WatchGame streams subscribe to,
+// and keeps every update it published so a dropped stream can resume after the
+// last update it saw. Players say which side they play when they watch; a store
+// with an abandonment timeout ends the game against a player whose streams have
+// all been closed for longer than that. Games in progress can be autosaved to a
+// directory and recovered when the server restarts.
+// `routes` serves a GameService together with the grpc.reflection.v1 service, so
+// tools such as grpcurl can discover the API.
+//
+// The server glue is written out by hand in the shape tonic-build generates, so the
+// build only needs protoc and prost.
+
+// Status is large, but it is the error type every tonic handler returns.
+#![allow(clippy::result_large_err)]
+
+use crate::autosave::Autosave;
+use crate::board::Board;
+use crate::clock::{Clock, TimeControl};
+use crate::game::{Game, GameResult};
+use crate::moves::{Move, MoveError};
+use crate::pieces::Color;
+use crate::rchess::v1::{self as proto};
+use crate::save::SaveError;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::{Stream, StreamExt};
+use tonic::codegen::{empty_body, http, Body, BoxFuture, BoxStream, Context, Poll, Service, StdError};
+use tonic::server::NamedService;
+use tonic::transport::server::Routes;
+use tonic::{Request, Response, Status};
+
+/// Fully qualified service name, as used in request paths.
+pub const SERVICE_NAME: &str = "rchess.v1.GameService";
+
+/// Updates a game buffers for each spectator; one that falls further behind is dropped.
+pub const UPDATE_BUFFER: usize = 64;
+
+/// Handlers for the GameService RPCs.
+#[tonic::async_trait]
+pub trait GameService: Send + Sync + 'static {
+    async fn create_game(
+        &self,
+        request: Request<proto::CreateGameRequest>,
+    ) -> Result<Response<proto::CreateGameResponse>, Status>;
+
+    async fn get_game_state(
+        &self,
+        request: Request<proto::GetGameStateRequest>,
+    ) -> Result<Response<proto::GetGameStateResponse>, Status>;
+
+    async fn list_legal_moves(
+        &self,
+        request: Request<proto::ListLegalMovesRequest>,
+    ) -> Result<Response<proto::ListLegalMovesResponse>, Status>;
+
+    async fn make_move(
+        &self,
+        request: Request<proto::MakeMoveRequest>,
+    ) -> Result<Response<proto::MakeMoveResponse>, Status>;
+
+    async fn resign_game(
+        &self,
+        request: Request<proto::ResignGameRequest>,
+    ) -> Result<Response<proto::ResignGameResponse>, Status>;
+
+    /// Stream returned by `watch_game`.
+    type WatchGameStream: Stream<Item = Result<proto::GameUpdate, Status>> + Send + 'static;
+
+    async fn watch_game(
+        &self,
+        request: Request<proto::WatchGameRequest>,
+    ) -> Result<Response<Self::WatchGameStream>, Status>;
+}
+
+/// Tower service that decodes gRPC requests and dispatches them to a GameService.
+/// Add it to a `tonic::transport::Server` with `add_service`.
+#[derive(Debug)]
+pub struct GameServiceServer<T> {
+    inner: Arc<T>,
+}
+
+impl<T: GameService> GameServiceServer<T> {
+    pub fn new(inner: T) -> Self {
+        GameServiceServer::from_arc(Arc::new(inner))
+    }
+
+    pub fn from_arc(inner: Arc<T>) -> Self {
+        GameServiceServer { inner }
+    }
+}
+
+impl<T> Clone for GameServiceServer<T> {
+    fn clone(&self) -> Self {
+        GameServiceServer {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> NamedService for GameServiceServer<T> {
+    const NAME: &'static str = SERVICE_NAME;
+}
+
+/// Expand to a future answering `req` with the unary handler `$method`.
+macro_rules! unary {
+    ($inner:expr, $req:expr, $method:ident, $request:ty, $response:ty) => {{
+        struct Handler<T>(Arc<T>);
+
+        impl<T: GameService> tonic::server::UnaryService<$request> for Handler<T> {
+            type Response = $response;
+            type Future = BoxFuture<Response<$response>, Status>;
+
+            fn call(&mut self, request: Request<$request>) -> Self::Future {
+                let inner = Arc::clone(&self.0);
+                Box::pin(async move { inner.$method(request).await })
+            }
+        }
+
+        let handler = Handler($inner);
+        let req = $req;
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+            Ok(grpc.unary(handler, req).await)
+        })
+    }};
+}
+
+impl<T, B> Service<http::Request<B>> for GameServiceServer<T>
+where
+    T: GameService,
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let inner = Arc::clone(&self.inner);
+        match req.uri().path() {
+            "/rchess.v1.GameService/CreateGame" => unary!(
+                inner,
+                req,
+                create_game,
+                proto::CreateGameRequest,
+                proto::CreateGameResponse
+            ),
+            "/rchess.v1.GameService/GetGameState" => unary!(
+                inner,
+                req,
+                get_game_state,
+                proto::GetGameStateRequest,
+                proto::GetGameStateResponse
+            ),
+            "/rchess.v1.GameService/ListLegalMoves" => unary!(
+                inner,
+                req,
+                list_legal_moves,
+                proto::ListLegalMovesRequest,
+                proto::ListLegalMovesResponse
+            ),
+            "/rchess.v1.GameService/MakeMove" => unary!(
+                inner,
+                req,
+                make_move,
+                proto::MakeMoveRequest,
+                proto::MakeMoveResponse
+            ),
+            "/rchess.v1.GameService/ResignGame" => unary!(
+                inner,
+                req,
+                resign_game,
+                proto::ResignGameRequest,
+                proto::ResignGameResponse
+            ),
+            "/rchess.v1.GameService/WatchGame" => {
+                struct Handler<T>(Arc<T>);
+
+                impl<T: GameService> tonic::server::ServerStreamingService<proto::WatchGameRequest> for Handler<T> {
+                    type Response = proto::GameUpdate;
+                    type ResponseStream = T::WatchGameStream;
+                    type Future = BoxFuture<Response<T::WatchGameStream>, Status>;
+
+                    fn call(&mut self, request: Request<proto::WatchGameRequest>) -> Self::Future {
+                        let inner = Arc::clone(&self.0);
+                        Box::pin(async move { inner.watch_game(request).await })
+                    }
+                }
+
+                let handler = Handler(inner);
+                Box::pin(async move {
+                    let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                    Ok(grpc.server_streaming(handler, req).await)
+                })
+            }
+            _ => Box::pin(async move {
+                Ok(http::Response::builder()
+                    .status(200)
+                    .header("grpc-status", (tonic::Code::Unimplemented as i32).to_string())
+                    .header("content-type", "application/grpc")
+                    .body(empty_body())
+                    .expect("static response parts are valid"))
+            }),
+        }
+    }
+}
+
+/// The game service `service` and a v1 reflection service describing the
+/// rchess.v1 API, ready for `tonic::transport::Server::builder().add_routes`.
+pub fn routes<T: GameService>(service: T) -> Result<Routes, tonic_reflection::server::Error> {
+    let reflection = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
+        .build_v1()?;
+    Ok(Routes::new(GameServiceServer::new(service)).add_service(reflection))
+}
+
+/// Which players have a stream open, indexed by color.
+#[derive(Debug, Default)]
+struct Presence {
+    streams: [usize; 2],
+    /// When the player's last stream closed; None while connected, and for a
+    /// player who never connected.
+    absent_since: [Option<Instant>; 2],
+}
+
+fn lock_presence(presence: &Mutex<Presence>) -> MutexGuard<'_, Presence> {
+    presence.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A player's open stream, counted in their game's presence until dropped.
+struct PresencePermit {
+    presence: Arc<Mutex<Presence>>,
+    color: Color,
+}
+
+impl PresencePermit {
+    fn new(presence: Arc<Mutex<Presence>>, color: Color) -> Self {
+        {
+            let mut present = lock_presence(&presence);
+            present.streams[color as usize] += 1;
+            present.absent_since[color as usize] = None;
+        }
+        PresencePermit { presence, color }
+    }
+}
+
+impl Drop for PresencePermit {
+    fn drop(&mut self) {
+        let mut present = lock_presence(&self.presence);
+        let streams = &mut present.streams[self.color as usize];
+        *streams = streams.saturating_sub(1);
+        if *streams == 0 {
+            present.absent_since[self.color as usize] = Some(Instant::now());
+        }
+    }
+}
+
+/// A stored game, the channel its spectators listen on, and what was sent on it.
+#[derive(Debug)]
+struct Session {
+    game: Game,
+    /// Dropped once the game is over, which ends every watch stream.
+    updates: Option<broadcast::Sender<proto::GameUpdate>>,
+    /// Every update published, in order; sequence number n is at index n - 1.
+    history: Vec<proto::GameUpdate>,
+    presence: Arc<Mutex<Presence>>,
+}
+
+impl Session {
+    fn new(game: Game) -> Self {
+        let updates = (game.result() == GameResult::Ongoing).then(|| broadcast::channel(UPDATE_BUFFER).0);
+        Session { game, updates, history: Vec::new(), presence: Arc::default() }
+    }
+
+    /// Number `update` and send it to current spectators.
+    fn publish(&mut self, mut update: proto::GameUpdate) {
+        update.sequence = self.history.len() as u64 + 1;
+        self.history.push(update.clone());
+        if let Some(updates) = &self.updates {
+            // Sending only fails when nobody is watching.
+            let _ = updates.send(update);
+        }
+        if self.game.result() != GameResult::Ongoing {
+            self.updates = None;
+        }
+    }
+
+    /// End the game against a player whose streams have all been closed for
+    /// longer than `timeout`; if both have, against the one gone longer.
+    fn check_abandoned(&mut self, timeout: Duration) {
+        if self.game.result() != GameResult::Ongoing {
+            return;
+        }
+        let now = Instant::now();
+        let gone = {
+            let present = lock_presence(&self.presence);
+            [Color::White, Color::Black]
+                .into_iter()
+                .filter_map(|color| Some((present.absent_since[color as usize]?, color)))
+                .filter(|&(since, _)| now.duration_since(since) >= timeout)
+                .min_by_key(|&(since, _)| since)
+        };
+        let Some((_, color)) = gone else {
+            return;
+        };
+        let result = match color {
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins,
+        };
+        if self.game.adjudicate(result, &format!("{} abandoned the game", color)).is_ok() {
+            self.publish(proto::GameUpdate {
+                result: result.to_proto(),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// In-memory session store: every game created through the service, by id.
+#[derive(Debug, Default)]
+pub struct GameStore {
+    games: Mutex<HashMap<String, Session>>,
+    next_id: AtomicU64,
+    /// How long a player may have no stream open before losing; None never.
+    abandon_after: Option<Duration>,
+}
+
+impl GameStore {
+    pub fn new() -> Self {
+        GameStore::default()
+    }
+
+    /// End games against players who leave for longer than `timeout`. Games
+    /// are checked whenever a request names them and by [`reap_abandoned`].
+    ///
+    /// [`reap_abandoned`]: GameStore::reap_abandoned
+    pub fn with_abandon_timeout(mut self, timeout: Duration) -> Self {
+        self.abandon_after = Some(timeout);
+        self
+    }
+
+    /// Check every game for abandonment, e.g. from a periodic server task.
+    pub fn reap_abandoned(&self) {
+        if let Some(timeout) = self.abandon_after {
+            for session in self.lock().values_mut() {
+                session.check_abandoned(timeout);
+            }
+        }
+    }
+
+    /// Whether the player of `color` in game `id` has a stream open; None for
+    /// an unknown game.
+    pub fn is_connected(&self, id: &str, color: Color) -> Option<bool> {
+        let games = self.lock();
+        let session = games.get(id)?;
+        Some(lock_presence(&session.presence).streams[color as usize] > 0)
+    }
+
+    /// Save every game in progress to `autosave` and remove every other save
+    /// there, e.g. from a periodic server task; see [`Autosave::sync`].
+    pub fn autosave(&self, autosave: &Autosave) -> Result<(), SaveError> {
+        let games: Vec<(String, Game)> =
+            self.lock().iter().map(|(id, session)| (id.clone(), session.game.clone())).collect();
+        autosave.sync(games)
+    }
+
+    /// Add every game saved in `autosave` and move the saves to the games' new
+    /// ids. Call this before autosaving into the directory, which would
+    /// otherwise remove the saves. Returns the new ids.
+    pub fn recover(&self, autosave: &Autosave) -> Result<Vec<String>, SaveError> {
+        let games = autosave.interrupted()?.iter().map(|id| autosave.recover(id)).collect::<Result<Vec<Game>, _>>()?;
+        let ids = games.into_iter().map(|game| self.insert(game)).collect();
+        self.autosave(autosave)?;
+        Ok(ids)
+    }
+
+    /// Number of games held.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Add a game and return its id.
+    pub fn insert(&self, game: Game) -> String {
+        let id = (self.next_id.fetch_add(1, Ordering::Relaxed) + 1).to_string();
+        self.lock().insert(id.clone(), Session::new(game));
+        id
+    }
+
+    /// Run `f` on the session with `id`, or fail with NOT_FOUND. An abandoned
+    /// game is ended first.
+    fn with_session<R>(&self, id: &str, f: impl FnOnce(&mut Session) -> Result<R, Status>) -> Result<R, Status> {
+        let mut games = self.lock();
+        let session = games
+            .get_mut(id)
+            .ok_or_else(|| Status::not_found(format!("no game with id {:?}", id)))?;
+        if let Some(timeout) = self.abandon_after {
+            session.check_abandoned(timeout);
+        }
+        f(session)
+    }
+
+    /// Run `f` on the game with `id`, or fail with NOT_FOUND.
+    fn with_game<R>(&self, id: &str, f: impl FnOnce(&mut Game) -> Result<R, Status>) -> Result<R, Status> {
+        self.with_session(id, |session| f(&mut session.game))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Session>> {
+        // A handler that panicked mid-update leaves at worst one game half-written.
+        self.games.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[tonic::async_trait]
+impl GameService for GameStore {
+    async fn create_game(
+        &self,
+        request: Request<proto::CreateGameRequest>,
+    ) -> Result<Response<proto::CreateGameResponse>, Status> {
+        let request = request.into_inner();
+        let fen = request.fen;
+        let board = if fen.is_empty() {
+            Board::starting_position()
+        } else {
+            Board::from_fen(&fen).map_err(|err| Status::invalid_argument(format!("bad FEN: {}", err)))?
+        };
+        let violations = board.validate();
+        if !violations.is_empty() {
+            let reasons: Vec<String> = violations.iter().map(|violation| violation.to_string()).collect();
+            return Err(Status::invalid_argument(format!("impossible position: {}", reasons.join("; "))));
+        }
+        let mut game = Game::from_position(board);
+        game.set_clock(request.time_control.as_ref().map(|control| Clock::new(TimeControl::from_proto(control))));
+        let state = game.to_proto();
+        let game_id = self.insert(game);
+        Ok(Response::new(proto::CreateGameResponse {
+            game_id,
+            state: Some(state),
+        }))
+    }
+
+    async fn get_game_state(
+        &self,
+        request: Request<proto::GetGameStateRequest>,
+    ) -> Result<Response<proto::GetGameStateResponse>, Status> {
+        self.with_game(&request.into_inner().game_id, |game| {
+            Ok(Response::new(proto::GetGameStateResponse {
+                state: Some(game.to_proto()),
+                result: game.result().to_proto(),
+            }))
+        })
+    }
+
+    async fn list_legal_moves(
+        &self,
+        request: Request<proto::ListLegalMovesRequest>,
+    ) -> Result<Response<proto::ListLegalMovesResponse>, Status> {
+        self.with_game(&request.into_inner().game_id, |game| {
+            let moves = if game.result() == GameResult::Ongoing {
+                let board = game.board();
+                board.legal_moves(board.current_player()).iter().map(Move::to_proto).collect()
+            } else {
+                Vec::new()
+            };
+            Ok(Response::new(proto::ListLegalMovesResponse { moves }))
+        })
+    }
+
+    async fn make_move(
+        &self,
+        request: Request<proto::MakeMoveRequest>,
+    ) -> Result<Response<proto::MakeMoveResponse>, Status> {
+        let request = request.into_inner();
+        let mv = request
+            .r#move
+            .as_ref()
+            .and_then(Move::from_proto)
+            .ok_or_else(|| Status::invalid_argument("move needs valid from and to squares"))?;
+        self.with_session(&request.game_id, |session| {
+            let game = &mut session.game;
+            let played = game.board().annotate_move(mv);
+            let outcome = game.play(mv).map_err(move_status)?;
+            let response = proto::MakeMoveResponse {
+                state: Some(game.to_proto()),
+                result: game.result().to_proto(),
+                check: outcome.check,
+            };
+            session.publish(proto::GameUpdate {
+                r#move: Some(played.to_proto()),
+                result: response.result,
+                check: outcome.check,
+                ..Default::default()
+            });
+            Ok(Response::new(response))
+        })
+    }
+
+    async fn resign_game(
+        &self,
+        request: Request<proto::ResignGameRequest>,
+    ) -> Result<Response<proto::ResignGameResponse>, Status> {
+        let request = request.into_inner();
+        let color = match proto::Color::try_from(request.color) {
+            Ok(proto::Color::White) => Color::White,
+            Ok(proto::Color::Black) => Color::Black,
+            _ => return Err(Status::invalid_argument("color must be WHITE or BLACK")),
+        };
+        self.with_session(&request.game_id, |session| {
+            let result = session
+                .game
+                .resign(color)
+                .map_err(|_| Status::failed_precondition("the game is over"))?
+                .to_proto();
+            session.publish(proto::GameUpdate {
+                result,
+                ..Default::default()
+            });
+            Ok(Response::new(proto::ResignGameResponse { result }))
+        })
+    }
+
+    type WatchGameStream = BoxStream<proto::GameUpdate>;
+
+    async fn watch_game(
+        &self,
+        request: Request<proto::WatchGameRequest>,
+    ) -> Result<Response<Self::WatchGameStream>, Status> {
+        let request = request.into_inner();
+        let player = Color::try_from_proto(request.player).ok();
+        // Subscribe under the same lock as the first updates so none falls between them.
+        let (first, receiver, permit) = self.with_session(&request.game_id, |session| {
+            let first = match request.resume_after {
+                0 => {
+                    let board = session.game.board();
+                    vec![proto::GameUpdate {
+                        state: Some(board.to_proto()),
+                        r#move: None,
+                        result: session.game.result().to_proto(),
+                        check: board.is_in_check(board.current_player()),
+                        sequence: session.history.len() as u64,
+                    }]
+                }
+                last => usize::try_from(last)
+                    .ok()
+                    .and_then(|last| session.history.get(last..))
+                    .ok_or_else(|| Status::out_of_range(format!("no update {} to resume after", last)))?
+                    .to_vec(),
+            };
+            let permit = player.map(|color| PresencePermit::new(Arc::clone(&session.presence), color));
+            Ok((first, session.updates.as_ref().map(broadcast::Sender::subscribe), permit))
+        })?;
+        let updates = BroadcastStream::new(receiver.unwrap_or_else(closed_receiver)).map(|update| {
+            update.map_err(|BroadcastStreamRecvError::Lagged(missed)| {
+                Status::data_loss(format!("watcher fell {} updates behind; resume after the last one received", missed))
+            })
+        });
+        // A player's permit lives as long as the stream, however the stream ends.
+        let stream = tokio_stream::iter(first.into_iter().map(Ok)).chain(updates).map(move |update| {
+            let _held = &permit;
+            update
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// A receiver whose channel is already closed, for games that are over.
+fn closed_receiver() -> broadcast::Receiver<proto::GameUpdate> {
+    broadcast::channel(1).1
+}
+
+fn move_status(err: MoveError) -> Status {
+    match err {
+        MoveError::GameOver => Status::failed_precondition("the game is over"),
+        err => Status::invalid_argument(format!("illegal move: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pieces::{PieceType, Square};
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::Waker;
+
+    /// The store never waits on anything, so one poll finishes each call.
+    fn ready<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("store call did not complete"),
+        }
+    }
+
+    fn create(store: &GameStore, fen: &str) -> String {
+        let request = Request::new(proto::CreateGameRequest { fen: fen.to_string(), ..Default::default() });
+        ready(store.create_game(request)).unwrap().into_inner().game_id
+    }
+
+    fn play(store: &GameStore, id: &str, from: &str, to: &str) -> Result<proto::MakeMoveResponse, Status> {
+        let mv = Move::new(Square::from_algebraic(from).unwrap(), Square::from_algebraic(to).unwrap());
+        let request = Request::new(proto::MakeMoveRequest {
+            game_id: id.to_string(),
+            r#move: Some(mv.to_proto()),
+        });
+        ready(store.make_move(request)).map(Response::into_inner)
+    }
+
+    #[test]
+    fn test_create_list_and_move() {
+        let store = GameStore::new();
+        let id = create(&store, "");
+        let request = Request::new(proto::ListLegalMovesRequest { game_id: id.clone() });
+        let moves = ready(store.list_legal_moves(request)).unwrap().into_inner().moves;
+        assert_eq!(moves.len(), 20);
+
+        let response = play(&store, &id, "e2", "e4").unwrap();
+        assert_eq!(response.state.unwrap().current_player, Color::Black.to_proto());
+        assert_eq!(response.result, proto::GameResult::Ongoing as i32);
+
+        let err = play(&store, &id, "e2", "e4").unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        let err = play(&store, "missing", "e7", "e5").unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[test]
+    fn test_checkmate_and_resignation_end_the_game() {
+        let store = GameStore::new();
+        let id = create(&store, "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let response = play(&store, &id, "a1", "a8").unwrap();
+        assert!(response.check);
+        assert_eq!(response.result, proto::GameResult::WhiteWins as i32);
+        let err = play(&store, &id, "g8", "h8").unwrap_err();
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+
+        let id = create(&store, "");
+        let request = Request::new(proto::ResignGameRequest {
+            game_id: id.clone(),
+            color: Color::White.to_proto(),
+        });
+        let result = ready(store.resign_game(request)).unwrap().into_inner().result;
+        assert_eq!(result, proto::GameResult::BlackWins as i32);
+        let request = Request::new(proto::GetGameStateRequest { game_id: id });
+        let state = ready(store.get_game_state(request)).unwrap().into_inner();
+        assert_eq!(state.result, proto::GameResult::BlackWins as i32);
+        assert_eq!(store.len(), 2);
+    }
+
+    /// Everything a watch stream has buffered, up to its end.
+    fn drain(mut stream: BoxStream<proto::GameUpdate>) -> Vec<proto::GameUpdate> {
+        let mut updates = Vec::new();
+        while let Some(update) = ready(stream.next()) {
+            updates.push(update.unwrap());
+        }
+        updates
+    }
+
+    #[test]
+    fn test_watch_streams_snapshot_then_moves_until_game_ends() {
+        let store = GameStore::new();
+        let id = create(&store, "");
+        let watch = |id: &str| {
+            let request = Request::new(proto::WatchGameRequest { game_id: id.to_string(), ..Default::default() });
+            ready(store.watch_game(request)).unwrap().into_inner()
+        };
+        let stream = watch(&id);
+        play(&store, &id, "e2", "e4").unwrap();
+        play(&store, &id, "d7", "d5").unwrap();
+        play(&store, &id, "e4", "d5").unwrap();
+        let request = Request::new(proto::ResignGameRequest {
+            game_id: id.clone(),
+            color: Color::Black.to_proto(),
+        });
+        ready(store.resign_game(request)).unwrap();
+
+        let updates = drain(stream);
+        assert_eq!(updates.len(), 5);
+        assert!(updates[0].state.is_some() && updates[0].r#move.is_none());
+        let capture = updates[3].r#move.as_ref().unwrap();
+        assert_eq!(capture.captured_piece_type, PieceType::Pawn.to_proto());
+        assert!(updates[4].r#move.is_none());
+        assert_eq!(updates[4].result, proto::GameResult::WhiteWins as i32);
+
+        // Watching a finished game yields its final state and ends.
+        let updates = drain(watch(&id));
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].result, proto::GameResult::WhiteWins as i32);
+    }
+
+    fn watch(
+        store: &GameStore,
+        id: &str,
+        resume_after: u64,
+        player: Option<Color>,
+    ) -> Result<BoxStream<proto::GameUpdate>, Status> {
+        let request = Request::new(proto::WatchGameRequest {
+            game_id: id.to_string(),
+            resume_after,
+            player: player.map_or(0, |color| color.to_proto()),
+        });
+        ready(store.watch_game(request)).map(Response::into_inner)
+    }
+
+    #[test]
+    fn test_resume_replays_missed_updates() {
+        let store = GameStore::new();
+        let id = create(&store, "");
+        play(&store, &id, "e2", "e4").unwrap();
+        play(&store, &id, "d7", "d5").unwrap();
+        let resumed = watch(&store, &id, 1, None).unwrap();
+        let request = Request::new(proto::ResignGameRequest {
+            game_id: id.clone(),
+            color: Color::Black.to_proto(),
+        });
+        ready(store.resign_game(request)).unwrap();
+
+        let updates = drain(resumed);
+        assert_eq!(updates.iter().map(|update| update.sequence).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(updates[0].state.is_none() && updates[0].r#move.is_some());
+        let snapshot = drain(watch(&store, &id, 0, None).unwrap());
+        assert_eq!((snapshot.len(), snapshot[0].sequence), (1, 3));
+        assert_eq!(watch(&store, &id, 4, None).unwrap_err().code(), tonic::Code::OutOfRange);
+    }
+
+    #[test]
+    fn test_abandoned_player_loses() {
+        let store = GameStore::new().with_abandon_timeout(Duration::ZERO);
+        let id = create(&store, "");
+        let stream = watch(&store, &id, 0, Some(Color::Black)).unwrap();
+        assert_eq!(store.is_connected(&id, Color::Black), Some(true));
+        assert_eq!(store.is_connected(&id, Color::White), Some(false));
+        play(&store, &id, "e2", "e4").unwrap();
+
+        // White never connected, so only Black can abandon the game.
+        drop(stream);
+        let request = Request::new(proto::GetGameStateRequest { game_id: id.clone() });
+        let state = ready(store.get_game_state(request)).unwrap().into_inner();
+        assert_eq!(state.result, proto::GameResult::WhiteWins as i32);
+        assert_eq!(store.is_connected(&id, Color::Black), Some(false));
+    }
+
+    #[test]
+    fn test_bad_fen_is_rejected() {
+        let store = GameStore::new();
+        let request = Request::new(proto::CreateGameRequest { fen: "not a fen".to_string(), ..Default::default() });
+        let err = ready(store.create_game(request)).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        let kingless = "8/8/8/8/8/8/8/4K3 w - - 0 1".to_string();
+        let request = Request::new(proto::CreateGameRequest { fen: kingless, ..Default::default() });
+        let err = ready(store.create_game(request)).unwrap_err();
+        assert_eq!(err.message(), "impossible position: Black has no king");
+        assert!(store.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reflection_lists_game_service() {
+        use tokio_stream::wrappers::TcpListenerStream;
+        use tonic_reflection::pb::v1::server_reflection_client::ServerReflectionClient;
+        use tonic_reflection::pb::v1::server_reflection_request::MessageRequest;
+        use tonic_reflection::pb::v1::server_reflection_response::MessageResponse;
+        use tonic_reflection::pb::v1::ServerReflectionRequest;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tonic::transport::Server::builder().add_routes(routes(GameStore::new()).unwrap());
+        tokio::spawn(server.serve_with_incoming(TcpListenerStream::new(listener)));
+
+        let mut client = ServerReflectionClient::connect(format!("http://{}", address)).await.unwrap();
+        let request = ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(MessageRequest::ListServices(String::new())),
+        };
+        let mut responses = client.server_reflection_info(tokio_stream::once(request)).await.unwrap().into_inner();
+        let response = responses.message().await.unwrap().unwrap();
+        let Some(MessageResponse::ListServicesResponse(list)) = response.message_response else {
+            panic!("expected a service list, got {:?}", response.message_response);
+        };
+        let names: Vec<&str> = list.service.iter().map(|service| service.name.as_str()).collect();
+        assert!(names.contains(&SERVICE_NAME));
+    }
+}
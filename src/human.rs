@@ -0,0 +1,127 @@
+// Human-like move choice for training partners. Rather than always playing the
+// best line and occasionally blundering outright, the engine searches several
+// lines and samples among those close to the best, with weights falling off
+// exponentially in how much each line gives away. Lower skill widens the
+// spread, so weaker settings make the small, plausible mistakes people make.
+
+use crate::board::Board;
+use crate::engine::{AnalysisOptions, Engine, SearchLimits, SearchResult};
+use crate::moves::Move;
+
+/// Highest skill level; at this level the best line is always played.
+pub const MAX_SKILL: u8 = 20;
+
+/// Scores beyond this many centipawns either way are treated alike, so a mate
+/// counts about as much as a rook.
+const SCORE_CAP: i32 = 1000;
+
+/// Move chooser for one skill level, with its own random number stream.
+#[derive(Debug, Clone)]
+pub struct HumanPlay {
+    skill: u8,
+    state: u64,
+}
+
+impl HumanPlay {
+    /// A chooser at `skill` (0 to [`MAX_SKILL`]), sampling from the stream
+    /// seeded by `seed`, so a game can be replayed.
+    pub fn new(skill: u8, seed: u64) -> Self {
+        HumanPlay { skill: skill.min(MAX_SKILL), state: seed }
+    }
+
+    pub fn skill(&self) -> u8 {
+        self.skill
+    }
+
+    /// Spread of the sampling in centipawns: a line this much worse than the
+    /// best is chosen about e^-1 times as often. 0 at the top skill, 200 at 0.
+    pub fn temperature(&self) -> f64 {
+        f64::from(MAX_SKILL - self.skill) * 10.0
+    }
+
+    /// Number of lines searched to choose from: 1 at the top skill, 6 at 0.
+    pub fn multipv(&self) -> usize {
+        1 + usize::from(MAX_SKILL - self.skill) / 4
+    }
+
+    /// Search `board` for the side to move and choose a move among its best lines.
+    pub fn choose(&mut self, engine: &mut Engine, board: &Board, limits: SearchLimits) -> Option<Move> {
+        let lines = engine.analyse(board, AnalysisOptions { multipv: self.multipv(), limits });
+        self.pick(&lines)
+    }
+
+    /// Choose among `lines`, best first as [`Engine::analyse`] returns them.
+    /// Lines more than two temperatures worse than the best are never chosen.
+    pub fn pick(&mut self, lines: &[SearchResult]) -> Option<Move> {
+        let best = lines.first()?;
+        let temperature = self.temperature();
+        if temperature == 0.0 {
+            return best.best_move;
+        }
+        let cap = |score: i32| f64::from(score.clamp(-SCORE_CAP, SCORE_CAP));
+        let candidates: Vec<(Move, f64)> = lines
+            .iter()
+            .filter_map(|line| Some((line.best_move?, cap(best.score) - cap(line.score))))
+            .filter(|&(_, loss)| loss <= 2.0 * temperature)
+            .map(|(mv, loss)| (mv, (-loss / temperature).exp()))
+            .collect();
+        let total: f64 = candidates.iter().map(|(_, weight)| weight).sum();
+        let mut target = self.next_unit() * total;
+        for &(mv, weight) in &candidates {
+            if target < weight {
+                return Some(mv);
+            }
+            target -= weight;
+        }
+        candidates.last().map(|&(mv, _)| mv)
+    }
+
+    /// A uniform sample from [0, 1), by SplitMix64.
+    fn next_unit(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    fn line(mv: Move, score: i32) -> SearchResult {
+        SearchResult { best_move: Some(mv), score, depth: 4, nodes: 0, time_ms: 0, pv: vec![mv] }
+    }
+
+    #[test]
+    fn test_sampling_stays_near_the_best_line() {
+        let (e4, d4, f3) = (
+            Move::new(sq!("e2"), sq!("e4")),
+            Move::new(sq!("d2"), sq!("d4")),
+            Move::new(sq!("f2"), sq!("f3")),
+        );
+        let lines = [line(e4, 40), line(d4, 35), line(f3, -400)];
+
+        let mut strongest = HumanPlay::new(MAX_SKILL, 1);
+        assert_eq!(strongest.multipv(), 1);
+        assert!((0..50).all(|_| strongest.pick(&lines) == Some(e4)));
+
+        let mut weakest = HumanPlay::new(0, 1);
+        assert_eq!(weakest.multipv(), 6);
+        let picks: Vec<Move> = (0..200).filter_map(|_| weakest.pick(&lines)).collect();
+        assert!(picks.contains(&e4) && picks.contains(&d4));
+        assert!(!picks.contains(&f3));
+        assert_eq!(weakest.pick(&[]), None);
+    }
+
+    #[test]
+    fn test_choose_plays_a_legal_move() {
+        let board = Board::starting_position();
+        let mut human = HumanPlay::new(10, 7);
+        let mv = human.choose(&mut Engine::new(), &board, SearchLimits::depth(2)).unwrap();
+        assert!(board.legal_moves(board.current_player()).contains(&mv));
+    }
+}
@@ -0,0 +1,222 @@
+// Localization of user-facing strings the crate generates.
+// A small built-in catalog; English matches the Display impls.
+
+use crate::game::GameResult;
+use crate::pieces::{Color, PieceType};
+use crate::status::GameStatus;
+
+/// Supported output languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+}
+
+impl Locale {
+    /// Parse a BCP 47 language tag such as "de" or "fr-CA" by its primary language.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::English),
+            "de" => Some(Locale::German),
+            "fr" => Some(Locale::French),
+            "es" => Some(Locale::Spanish),
+            _ => None,
+        }
+    }
+}
+
+/// Types with a localized human-readable name.
+pub trait Localize {
+    fn localize(&self, locale: Locale) -> String;
+}
+
+impl Localize for Color {
+    fn localize(&self, locale: Locale) -> String {
+        let text = match (locale, self) {
+            (Locale::English, Color::White) => "White",
+            (Locale::English, Color::Black) => "Black",
+            (Locale::German, Color::White) => "Weiß",
+            (Locale::German, Color::Black) => "Schwarz",
+            (Locale::French, Color::White) => "Blancs",
+            (Locale::French, Color::Black) => "Noirs",
+            (Locale::Spanish, Color::White) => "Blancas",
+            (Locale::Spanish, Color::Black) => "Negras",
+        };
+        text.to_string()
+    }
+}
+
+impl Localize for PieceType {
+    fn localize(&self, locale: Locale) -> String {
+        let text = match (locale, self) {
+            (Locale::English, _) => return self.to_string(),
+            (Locale::German, PieceType::King) => "König",
+            (Locale::German, PieceType::Queen) => "Dame",
+            (Locale::German, PieceType::Rook) => "Turm",
+            (Locale::German, PieceType::Bishop) => "Läufer",
+            (Locale::German, PieceType::Knight) => "Springer",
+            (Locale::German, PieceType::Pawn) => "Bauer",
+            (Locale::French, PieceType::King) => "Roi",
+            (Locale::French, PieceType::Queen) => "Dame",
+            (Locale::French, PieceType::Rook) => "Tour",
+            (Locale::French, PieceType::Bishop) => "Fou",
+            (Locale::French, PieceType::Knight) => "Cavalier",
+            (Locale::French, PieceType::Pawn) => "Pion",
+            (Locale::Spanish, PieceType::King) => "Rey",
+            (Locale::Spanish, PieceType::Queen) => "Dama",
+            (Locale::Spanish, PieceType::Rook) => "Torre",
+            (Locale::Spanish, PieceType::Bishop) => "Alfil",
+            (Locale::Spanish, PieceType::Knight) => "Caballo",
+            (Locale::Spanish, PieceType::Pawn) => "Peón",
+        };
+        text.to_string()
+    }
+}
+
+impl Localize for GameResult {
+    fn localize(&self, locale: Locale) -> String {
+        let text = match (locale, self) {
+            (Locale::English, GameResult::WhiteWins) => "White wins",
+            (Locale::English, GameResult::BlackWins) => "Black wins",
+            (Locale::English, GameResult::Draw) => "Draw",
+            (Locale::English, GameResult::Ongoing) => "Game in progress",
+            (Locale::German, GameResult::WhiteWins) => "Weiß gewinnt",
+            (Locale::German, GameResult::BlackWins) => "Schwarz gewinnt",
+            (Locale::German, GameResult::Draw) => "Remis",
+            (Locale::German, GameResult::Ongoing) => "Partie läuft",
+            (Locale::French, GameResult::WhiteWins) => "Les Blancs gagnent",
+            (Locale::French, GameResult::BlackWins) => "Les Noirs gagnent",
+            (Locale::French, GameResult::Draw) => "Partie nulle",
+            (Locale::French, GameResult::Ongoing) => "Partie en cours",
+            (Locale::Spanish, GameResult::WhiteWins) => "Ganan las blancas",
+            (Locale::Spanish, GameResult::BlackWins) => "Ganan las negras",
+            (Locale::Spanish, GameResult::Draw) => "Tablas",
+            (Locale::Spanish, GameResult::Ongoing) => "Partida en curso",
+        };
+        text.to_string()
+    }
+}
+
+impl Localize for GameStatus {
+    /// Variant win reasons are not translated.
+    fn localize(&self, locale: Locale) -> String {
+        let result = self.result().localize(locale);
+        // French and Spanish continue the sentence in lowercase; German nouns keep their capital.
+        let continued = match locale {
+            Locale::German => result.clone(),
+            _ => {
+                let mut chars = result.chars();
+                chars.next().map_or_else(String::new, |first| first.to_lowercase().chain(chars).collect())
+            }
+        };
+        let text = match (locale, self) {
+            (Locale::English, _) => return self.to_string(),
+            (_, GameStatus::Ongoing) => return result,
+            (_, GameStatus::VariantWin { reason, .. }) => return format!("{} ({})", result, reason),
+            (Locale::German, GameStatus::Checkmate { .. }) => "Schachmatt",
+            (Locale::German, GameStatus::Stalemate) => "Patt",
+            (Locale::German, GameStatus::FiftyMoveRule) => "Remis durch die 50-Züge-Regel",
+            (Locale::German, GameStatus::ThreefoldRepetition) => "Remis durch dreifache Stellungswiederholung",
+            (Locale::German, GameStatus::TimeForfeit { .. }) => "Zeitüberschreitung",
+            (Locale::German, GameStatus::TimeoutDraw) => "Remis durch Zeitüberschreitung bei ungenügendem Material",
+            (Locale::German, GameStatus::Resignation { .. }) => "Aufgabe",
+            (Locale::German, GameStatus::DrawAgreed) => "Remis nach Vereinbarung",
+            (Locale::German, GameStatus::Adjudicated { .. }) => "Schiedsrichterentscheid",
+            (Locale::French, GameStatus::Checkmate { .. }) => "Échec et mat",
+            (Locale::French, GameStatus::Stalemate) => "Pat",
+            (Locale::French, GameStatus::FiftyMoveRule) => "Nulle par la règle des cinquante coups",
+            (Locale::French, GameStatus::ThreefoldRepetition) => "Nulle par triple répétition",
+            (Locale::French, GameStatus::TimeForfeit { .. }) => "Temps dépassé",
+            (Locale::French, GameStatus::TimeoutDraw) => "Nulle au temps, matériel insuffisant pour mater",
+            (Locale::French, GameStatus::Resignation { .. }) => "Abandon",
+            (Locale::French, GameStatus::DrawAgreed) => "Nulle par accord mutuel",
+            (Locale::French, GameStatus::Adjudicated { .. }) => "Décision arbitrale",
+            (Locale::Spanish, GameStatus::Checkmate { .. }) => "Jaque mate",
+            (Locale::Spanish, GameStatus::Stalemate) => "Ahogado",
+            (Locale::Spanish, GameStatus::FiftyMoveRule) => "Tablas por la regla de los cincuenta movimientos",
+            (Locale::Spanish, GameStatus::ThreefoldRepetition) => "Tablas por triple repetición",
+            (Locale::Spanish, GameStatus::TimeForfeit { .. }) => "Tiempo agotado",
+            (Locale::Spanish, GameStatus::TimeoutDraw) => "Tablas por tiempo, material insuficiente para dar mate",
+            (Locale::Spanish, GameStatus::Resignation { .. }) => "Abandono",
+            (Locale::Spanish, GameStatus::DrawAgreed) => "Tablas por acuerdo",
+            (Locale::Spanish, GameStatus::Adjudicated { .. }) => "Decisión arbitral",
+        };
+        match self {
+            GameStatus::Checkmate { .. }
+            | GameStatus::Stalemate
+            | GameStatus::TimeForfeit { .. }
+            | GameStatus::Resignation { .. }
+            | GameStatus::Adjudicated { .. } => {
+                format!("{}, {}", text, continued)
+            }
+            _ => text.to_string(),
+        }
+    }
+}
+
+/// Localized piece name with its color, e.g. "White Queen", "Weiße Dame", "Dame blanche".
+pub fn piece_name(color: Color, piece_type: PieceType, locale: Locale) -> String {
+    let noun = piece_type.localize(locale);
+    // Grammatically feminine nouns take a different color adjective.
+    let feminine = match locale {
+        Locale::English => false,
+        Locale::German => piece_type == PieceType::Queen,
+        Locale::French | Locale::Spanish => matches!(piece_type, PieceType::Queen | PieceType::Rook),
+    };
+    let adjective = match (locale, color, feminine) {
+        (Locale::English, _, _) => return format!("{} {}", color, noun),
+        (Locale::German, Color::White, true) => "Weiße",
+        (Locale::German, Color::White, false) => "Weißer",
+        (Locale::German, Color::Black, true) => "Schwarze",
+        (Locale::German, Color::Black, false) => "Schwarzer",
+        (Locale::French, Color::White, true) => "blanche",
+        (Locale::French, Color::White, false) => "blanc",
+        (Locale::French, Color::Black, true) => "noire",
+        (Locale::French, Color::Black, false) => "noir",
+        (Locale::Spanish, Color::White, true) => "blanca",
+        (Locale::Spanish, Color::White, false) => "blanco",
+        (Locale::Spanish, Color::Black, true) => "negra",
+        (Locale::Spanish, Color::Black, false) => "negro",
+    };
+    // German puts the adjective first; French and Spanish put it after the noun.
+    if locale == Locale::German {
+        format!("{} {}", adjective, noun)
+    } else {
+        format!("{} {}", noun, adjective)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_tag() {
+        assert_eq!(Locale::from_tag("de-AT"), Some(Locale::German));
+        assert_eq!(Locale::from_tag("FR"), Some(Locale::French));
+        assert_eq!(Locale::from_tag("xx"), None);
+    }
+
+    #[test]
+    fn test_english_matches_display() {
+        assert_eq!(piece_name(Color::White, PieceType::King, Locale::English), "White King");
+        assert_eq!(PieceType::Knight.localize(Locale::English), PieceType::Knight.to_string());
+        assert_eq!(GameResult::Draw.localize(Locale::German), "Remis");
+        let mate = GameStatus::Checkmate { winner: Color::White };
+        assert_eq!(mate.localize(Locale::English), mate.to_string());
+        assert_eq!(mate.localize(Locale::French), "Échec et mat, les Blancs gagnent");
+        assert_eq!(GameStatus::Stalemate.localize(Locale::German), "Patt, Remis");
+    }
+
+    #[test]
+    fn test_gendered_piece_names() {
+        assert_eq!(piece_name(Color::White, PieceType::Queen, Locale::German), "Weiße Dame");
+        assert_eq!(piece_name(Color::Black, PieceType::Rook, Locale::German), "Schwarzer Turm");
+        assert_eq!(piece_name(Color::White, PieceType::Rook, Locale::French), "Tour blanche");
+        assert_eq!(piece_name(Color::Black, PieceType::Knight, Locale::Spanish), "Caballo negro");
+    }
+}
@@ -0,0 +1,213 @@
+// King-and-pawn versus king bitbase.
+// Generated once by retrograde analysis on first use, so KPK endings are always exact.
+
+use crate::board::Board;
+use crate::endgame::EndgameClass;
+use crate::pieces::{Color, PieceType, Square};
+use std::sync::OnceLock;
+
+// Positions are normalized so the strong side is White with its pawn on files a..d.
+// Index layout: white king (6 bits), black king (6 bits), side to move (1 bit),
+// pawn file a..d (2 bits), pawn rank 7..2 (3 bits).
+const MAX_INDEX: usize = 2 * 24 * 64 * 64;
+
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+static BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+
+/// Whether the side with the pawn wins with best play.
+/// Returns false for illegal placements (overlapping or adjacent kings, and so on).
+pub fn probe(
+    strong_king: Square,
+    strong_pawn: Square,
+    weak_king: Square,
+    strong_to_move: bool,
+    strong: Color,
+) -> bool {
+    let normalize = |sq: Square| {
+        let rank = if strong == Color::White { sq.rank } else { 7 - sq.rank };
+        let file = if strong_pawn.file >= 4 { 7 - sq.file } else { sq.file };
+        rank * 8 + file
+    };
+    let (wk, psq, bk) = (normalize(strong_king), normalize(strong_pawn), normalize(weak_king));
+    if psq / 8 == 0 || psq / 8 == 7 {
+        return false;
+    }
+    let idx = index(if strong_to_move { 0 } else { 1 }, bk, wk, psq);
+    let bits = BITBASE.get_or_init(generate);
+    bits[idx / 64] & (1 << (idx % 64)) != 0
+}
+
+impl Board {
+    /// For KPK positions, whether the side with the pawn wins with best play.
+    pub fn kpk_win(&self) -> Option<bool> {
+        let Some(EndgameClass::Kpk { strong }) = self.endgame_class() else {
+            return None;
+        };
+        let find = |color: Color, piece_type: PieceType| {
+            self.pieces_of_color(color)
+                .iter()
+                .find(|p| self.piece_type(p) == Some(piece_type))
+                .and_then(|p| self.piece_square(p))
+        };
+        Some(probe(
+            find(strong, PieceType::King)?,
+            find(strong, PieceType::Pawn)?,
+            find(strong.opposite(), PieceType::King)?,
+            self.current_player() == strong,
+            strong,
+        ))
+    }
+}
+
+fn index(stm: usize, bk: u8, wk: u8, psq: u8) -> usize {
+    let file = (psq % 8) as usize;
+    let rank = (psq / 8) as usize;
+    wk as usize | (bk as usize) << 6 | stm << 12 | file << 13 | (6 - rank) << 15
+}
+
+fn distance(a: u8, b: u8) -> u8 {
+    let df = (a % 8).abs_diff(b % 8);
+    let dr = (a / 8).abs_diff(b / 8);
+    df.max(dr)
+}
+
+fn king_moves(sq: u8) -> impl Iterator<Item = u8> {
+    (0..64u8).filter(move |&t| distance(sq, t) == 1)
+}
+
+fn pawn_attacks(psq: u8, target: u8) -> bool {
+    target / 8 == psq / 8 + 1 && (target % 8).abs_diff(psq % 8) == 1
+}
+
+/// Decode an index into (side to move, white king, black king, pawn square).
+fn decode(idx: usize) -> (usize, u8, u8, u8) {
+    let wk = (idx & 0x3f) as u8;
+    let bk = ((idx >> 6) & 0x3f) as u8;
+    let stm = (idx >> 12) & 1;
+    let file = ((idx >> 13) & 3) as u8;
+    let rank = 6 - ((idx >> 15) & 7) as u8;
+    (stm, wk, bk, rank * 8 + file)
+}
+
+fn initial_result(stm: usize, wk: u8, bk: u8, psq: u8) -> u8 {
+    let push = psq + 8;
+    if distance(wk, bk) <= 1 || wk == psq || bk == psq || (stm == 0 && pawn_attacks(psq, bk)) {
+        INVALID
+    } else if stm == 0
+        && psq / 8 == 6
+        && wk != push
+        && (distance(bk, push) > 1 || distance(wk, push) == 1)
+    {
+        // The pawn promotes without being captured.
+        WIN
+    } else if stm == 1
+        && (king_moves(bk).all(|t| distance(wk, t) <= 1 || pawn_attacks(psq, t))
+            || (distance(bk, psq) == 1 && distance(wk, psq) > 1))
+    {
+        // Stalemate, or the black king takes the undefended pawn.
+        DRAW
+    } else {
+        UNKNOWN
+    }
+}
+
+fn classify(db: &[u8], stm: usize, wk: u8, bk: u8, psq: u8) -> u8 {
+    let (good, bad) = if stm == 0 { (WIN, DRAW) } else { (DRAW, WIN) };
+    let mut r = INVALID;
+    if stm == 0 {
+        for t in king_moves(wk) {
+            r |= db[index(1, bk, t, psq)];
+        }
+        if psq / 8 < 6 {
+            r |= db[index(1, bk, wk, psq + 8)];
+        }
+        if psq / 8 == 1 && psq + 8 != wk && psq + 8 != bk {
+            r |= db[index(1, bk, wk, psq + 16)];
+        }
+    } else {
+        for t in king_moves(bk) {
+            r |= db[index(0, t, wk, psq)];
+        }
+    }
+    if r & good != 0 {
+        good
+    } else if r & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
+
+fn generate() -> Vec<u64> {
+    let mut db: Vec<u8> = (0..MAX_INDEX)
+        .map(|idx| {
+            let (stm, wk, bk, psq) = decode(idx);
+            initial_result(stm, wk, bk, psq)
+        })
+        .collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for idx in 0..MAX_INDEX {
+            if db[idx] == UNKNOWN {
+                let (stm, wk, bk, psq) = decode(idx);
+                let result = classify(&db, stm, wk, bk, psq);
+                if result != UNKNOWN {
+                    db[idx] = result;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let mut bits = vec![0u64; MAX_INDEX / 64];
+    for (idx, &result) in db.iter().enumerate() {
+        if result == WIN {
+            bits[idx / 64] |= 1 << (idx % 64);
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_king_on_sixth_in_front_wins() {
+        // White Ke6, Pe5 against Ke8 wins with either side to move.
+        assert!(probe(sq!("e6"), sq!("e5"), sq!("e8"), true, Color::White));
+        assert!(probe(sq!("e6"), sq!("e5"), sq!("e8"), false, Color::White));
+    }
+
+    #[test]
+    fn test_stalemate_is_draw() {
+        // White Ke6, Pe7 against Ke8 with Black to move is stalemate.
+        assert!(!probe(sq!("e6"), sq!("e7"), sq!("e8"), false, Color::White));
+    }
+
+    #[test]
+    fn test_rook_pawn_with_defender_in_corner_draws() {
+        assert!(!probe(sq!("c4"), sq!("a4"), sq!("b8"), true, Color::White));
+        // Same position mirrored onto the h-file.
+        assert!(!probe(sq!("f4"), sq!("h4"), sq!("g8"), true, Color::White));
+    }
+
+    #[test]
+    fn test_board_kpk_win() {
+        let board = Board::from_fen("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(board.kpk_win(), Some(true));
+    }
+
+    #[test]
+    fn test_black_strong_side() {
+        // Mirror of the winning position with colors swapped.
+        assert!(probe(sq!("e3"), sq!("e4"), sq!("e1"), true, Color::Black));
+    }
+}
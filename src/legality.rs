@@ -0,0 +1,287 @@
+// Explanations for rejected moves. `Board::apply_move` stops at the first rule
+// a move breaks and says little more than "cannot move there" or "leaves the
+// king in check"; `Board::try_move` plays the move the same way, and when it is
+// rejected looks at the position again to say which part of the rule failed: a
+// piece in the way, a castling right already lost, a pinned piece, and so on.
+// `Board::explain_illegal` gives the same answer without playing the move.
+
+use crate::board::Board;
+use crate::moves::{CastlingSide, Move, MoveError, MoveOutcome};
+use crate::pieces::{Color, PieceType, Square};
+use std::fmt;
+
+/// Why `Board::try_move` rejected a move, in enough detail to show a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalMoveReason {
+    /// There is no piece on the source square.
+    NoPiece(Square),
+    /// The piece on the source square belongs to the side not to move.
+    NotYourTurn { color: Color },
+    /// The target square holds one of the mover's own pieces.
+    OwnPieceOnTarget(Square),
+    /// The piece does not move that way, wherever the other pieces stand.
+    Unreachable { piece: PieceType, from: Square, to: Square },
+    /// The piece could make the move on an empty board, but the piece on
+    /// `blocker` stands in the way.
+    PathBlocked { piece: PieceType, blocker: Square },
+    /// A pawn may only move diagonally to capture, and there is nothing to
+    /// capture on the target square.
+    NothingToCapture(Square),
+    /// The king is in check and the move does not get it out.
+    KingInCheck,
+    /// The king would step onto a square the opponent attacks.
+    IntoCheck(Square),
+    /// The move would open a line onto the king, as moving a pinned piece does.
+    ExposesKing,
+    /// The king or that rook has moved, so castling on that side is over.
+    CastlingRightsLost(CastlingSide),
+    /// The king is in check, and a king may not castle out of check.
+    CastlingOutOfCheck,
+    /// The king would pass through or land on an attacked square.
+    CastlingThroughCheck(Square),
+    /// A pawn reaching the last rank must name a promotion piece.
+    PromotionRequired,
+    /// The promotion piece is not a queen, rook, bishop, or knight, or the move is not a promotion.
+    InvalidPromotion(PieceType),
+    /// The board's variant forbids the move, for the stated reason.
+    VariantRule(&'static str),
+    /// A drop of a piece type the mover's pocket does not hold.
+    NotInPocket(PieceType),
+    /// A drop onto a square that is not empty.
+    SquareOccupied(Square),
+    /// The game already has a result.
+    GameOver,
+}
+
+impl fmt::Display for IllegalMoveReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IllegalMoveReason::NoPiece(square) => write!(f, "no piece on {}", square),
+            IllegalMoveReason::NotYourTurn { color } => write!(f, "it is not {}'s turn", color),
+            IllegalMoveReason::OwnPieceOnTarget(square) => write!(f, "{} holds a piece of your own", square),
+            IllegalMoveReason::Unreachable { piece, from, to } => {
+                write!(f, "{} on {} cannot move to {}", piece, from, to)
+            }
+            IllegalMoveReason::PathBlocked { piece, blocker } => {
+                write!(f, "{} is blocked by the piece on {}", piece, blocker)
+            }
+            IllegalMoveReason::NothingToCapture(square) => {
+                write!(f, "a pawn moves diagonally only to capture, and {} is empty", square)
+            }
+            IllegalMoveReason::KingInCheck => write!(f, "the king is in check and the move does not stop it"),
+            IllegalMoveReason::IntoCheck(square) => write!(f, "the king would be in check on {}", square),
+            IllegalMoveReason::ExposesKing => write!(f, "move would expose the king to check"),
+            IllegalMoveReason::CastlingRightsLost(side) => {
+                let side = match side {
+                    CastlingSide::Kingside => "kingside",
+                    CastlingSide::Queenside => "queenside",
+                };
+                write!(f, "castling {} is no longer allowed", side)
+            }
+            IllegalMoveReason::CastlingOutOfCheck => write!(f, "cannot castle out of check"),
+            IllegalMoveReason::CastlingThroughCheck(square) => write!(f, "cannot castle through check on {}", square),
+            IllegalMoveReason::PromotionRequired => write!(f, "pawn move to the last rank needs a promotion piece"),
+            IllegalMoveReason::InvalidPromotion(piece) => write!(f, "cannot promote to {}", piece),
+            IllegalMoveReason::VariantRule(reason) => write!(f, "{}", reason),
+            IllegalMoveReason::NotInPocket(piece) => write!(f, "no {} in the pocket", piece),
+            IllegalMoveReason::SquareOccupied(square) => write!(f, "{} is occupied", square),
+            IllegalMoveReason::GameOver => write!(f, "the game is over"),
+        }
+    }
+}
+
+impl std::error::Error for IllegalMoveReason {}
+
+impl Board {
+    /// Play `mv` for the side to move as [`apply_move`](Board::apply_move)
+    /// does, or leave the board unchanged and say why the move is illegal.
+    pub fn try_move(&mut self, mv: Move) -> Result<MoveOutcome, IllegalMoveReason> {
+        self.apply_move(mv).map_err(|error| self.explain(mv, error))
+    }
+
+    /// Why `mv` is illegal for the side to move, or `None` if it is legal.
+    /// The board is left as it is, for UIs checking a move before playing it.
+    pub fn explain_illegal(&self, mv: Move) -> Option<IllegalMoveReason> {
+        self.clone().try_move(mv).err()
+    }
+
+    /// Refine the error `apply_move` gave for `mv`.
+    fn explain(&self, mv: Move, error: MoveError) -> IllegalMoveReason {
+        match error {
+            MoveError::NoPiece(square) => IllegalMoveReason::NoPiece(square),
+            MoveError::NotYourTurn { color } => IllegalMoveReason::NotYourTurn { color },
+            MoveError::IllegalPieceMove { piece, from, to } => self.explain_unreachable(piece, from, to),
+            MoveError::LeavesKingInCheck => {
+                let color = self.current_player();
+                if mv.drop.is_none() && self.piece_at_typed(mv.from) == Some((PieceType::King, color)) {
+                    IllegalMoveReason::IntoCheck(mv.to)
+                } else if self.is_in_check(color) {
+                    IllegalMoveReason::KingInCheck
+                } else {
+                    IllegalMoveReason::ExposesKing
+                }
+            }
+            MoveError::PromotionRequired => IllegalMoveReason::PromotionRequired,
+            MoveError::InvalidPromotion(piece) => IllegalMoveReason::InvalidPromotion(piece),
+            MoveError::VariantRule(reason) => IllegalMoveReason::VariantRule(reason),
+            MoveError::NotInPocket(piece) => IllegalMoveReason::NotInPocket(piece),
+            MoveError::SquareOccupied(square) => IllegalMoveReason::SquareOccupied(square),
+            MoveError::GameOver => IllegalMoveReason::GameOver,
+        }
+    }
+
+    /// Why `piece` on `from` cannot reach `to` in this position.
+    fn explain_unreachable(&self, piece: PieceType, from: Square, to: Square) -> IllegalMoveReason {
+        let color = self.current_player();
+        let home_rank = match color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        if piece == PieceType::King && from == (Square { file: 4, rank: home_rank }) && to.rank == home_rank {
+            match to.file {
+                6 => return self.explain_castling(CastlingSide::Kingside, from),
+                2 => return self.explain_castling(CastlingSide::Queenside, from),
+                _ => {}
+            }
+        }
+        if self.piece_at_typed(to).is_some_and(|(_, owner)| owner == color) {
+            return IllegalMoveReason::OwnPieceOnTarget(to);
+        }
+
+        let unreachable = IllegalMoveReason::Unreachable { piece, from, to };
+        let df = i32::from(to.file) - i32::from(from.file);
+        let dr = i32::from(to.rank) - i32::from(from.rank);
+        let blocker = |steps: i32, step_file: i32, step_rank: i32| {
+            (1..=steps)
+                .filter_map(|i| from.offset(i * step_file, i * step_rank))
+                .find(|&square| self.piece_at(square).is_some())
+                .map_or(unreachable, |blocker| IllegalMoveReason::PathBlocked { piece, blocker })
+        };
+        match piece {
+            PieceType::Pawn => {
+                let forward = match color {
+                    Color::White => 1,
+                    Color::Black => -1,
+                };
+                match (df, dr * forward) {
+                    (0, 1) => blocker(1, 0, forward),
+                    (0, 2) if from.rank == color.pawn_start_rank() => blocker(2, 0, forward),
+                    (-1 | 1, 1) if self.piece_at(to).is_none() => IllegalMoveReason::NothingToCapture(to),
+                    _ => unreachable,
+                }
+            }
+            PieceType::Rook | PieceType::Bishop | PieceType::Queen => {
+                let straight = df == 0 || dr == 0;
+                let diagonal = df.abs() == dr.abs();
+                let aligned = match piece {
+                    PieceType::Rook => straight,
+                    PieceType::Bishop => diagonal,
+                    _ => straight || diagonal,
+                };
+                if aligned && (df, dr) != (0, 0) {
+                    blocker(df.abs().max(dr.abs()) - 1, df.signum(), dr.signum())
+                } else {
+                    unreachable
+                }
+            }
+            _ => unreachable,
+        }
+    }
+
+    /// Why the king on `from` cannot castle towards `side`; mirrors the checks
+    /// in `castling_moves`.
+    fn explain_castling(&self, side: CastlingSide, from: Square) -> IllegalMoveReason {
+        let color = self.current_player();
+        let rights = self.castling_rights();
+        let (allowed, rook_file, empty, crossed): (bool, u8, &[u8], &[u8]) = match (color, side) {
+            (Color::White, CastlingSide::Kingside) => (rights.white_kingside, 7, &[5, 6], &[5, 6]),
+            (Color::White, CastlingSide::Queenside) => (rights.white_queenside, 0, &[1, 2, 3], &[3, 2]),
+            (Color::Black, CastlingSide::Kingside) => (rights.black_kingside, 7, &[5, 6], &[5, 6]),
+            (Color::Black, CastlingSide::Queenside) => (rights.black_queenside, 0, &[1, 2, 3], &[3, 2]),
+        };
+        let on_rank = |file: u8| Square { file, rank: from.rank };
+        if !allowed || self.piece_at_typed(on_rank(rook_file)) != Some((PieceType::Rook, color)) {
+            return IllegalMoveReason::CastlingRightsLost(side);
+        }
+        if let Some(&file) = empty.iter().find(|&&file| self.piece_at(on_rank(file)).is_some()) {
+            return IllegalMoveReason::PathBlocked { piece: PieceType::King, blocker: on_rank(file) };
+        }
+        if self.is_in_check(color) {
+            return IllegalMoveReason::CastlingOutOfCheck;
+        }
+        match crossed.iter().find(|&&file| self.is_square_attacked(on_rank(file), color.opposite())) {
+            Some(&file) => IllegalMoveReason::CastlingThroughCheck(on_rank(file)),
+            None => IllegalMoveReason::Unreachable { piece: PieceType::King, from, to: on_rank(crossed[1]) },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    fn reason(fen: &str, from: &str, to: &str) -> IllegalMoveReason {
+        let mut board = Board::from_fen(fen).unwrap();
+        let before = board.to_fen();
+        let reason = board.try_move(Move::new(Square::from_algebraic(from).unwrap(), Square::from_algebraic(to).unwrap())).unwrap_err();
+        assert_eq!(board.to_fen(), before);
+        reason
+    }
+
+    #[test]
+    fn test_reasons_for_pieces_that_cannot_get_there() {
+        let start = crate::fen::STARTING_FEN;
+        assert_eq!(reason(start, "e4", "e5"), IllegalMoveReason::NoPiece(sq!("e4")));
+        assert_eq!(reason(start, "e7", "e5"), IllegalMoveReason::NotYourTurn { color: Color::Black });
+        assert_eq!(reason(start, "d1", "d2"), IllegalMoveReason::OwnPieceOnTarget(sq!("d2")));
+        assert_eq!(
+            reason(start, "g1", "g3"),
+            IllegalMoveReason::Unreachable { piece: PieceType::Knight, from: sq!("g1"), to: sq!("g3") }
+        );
+        assert_eq!(
+            reason(start, "a1", "a5"),
+            IllegalMoveReason::PathBlocked { piece: PieceType::Rook, blocker: sq!("a2") }
+        );
+        let blocked_pawn = "4k3/8/8/8/8/4n3/4P3/4K3 w - - 0 1";
+        assert_eq!(
+            reason(blocked_pawn, "e2", "e4"),
+            IllegalMoveReason::PathBlocked { piece: PieceType::Pawn, blocker: sq!("e3") }
+        );
+        assert_eq!(reason(blocked_pawn, "e2", "d3"), IllegalMoveReason::NothingToCapture(sq!("d3")));
+        assert_eq!(reason(blocked_pawn, "e2", "e3").to_string(), "Pawn is blocked by the piece on e3");
+
+        let board = Board::starting_position();
+        assert_eq!(board.explain_illegal(Move::new(sq!("e2"), sq!("e4"))), None);
+        assert_eq!(board.explain_illegal(Move::new(sq!("e2"), sq!("e5"))), Some(IllegalMoveReason::Unreachable {
+            piece: PieceType::Pawn,
+            from: sq!("e2"),
+            to: sq!("e5")
+        }));
+        assert_eq!(board.to_fen(), start);
+
+        let mut board = Board::starting_position();
+        assert!(board.try_move(Move::new(sq!("e2"), sq!("e4"))).is_ok());
+        assert_eq!(board.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    }
+
+    #[test]
+    fn test_reasons_for_king_safety_and_castling() {
+        let pinned = "4k3/4r3/8/8/8/8/4N3/4K2R w - - 0 1";
+        assert_eq!(reason(pinned, "e2", "c3"), IllegalMoveReason::ExposesKing);
+        assert_eq!(reason(pinned, "e1", "e2"), IllegalMoveReason::OwnPieceOnTarget(sq!("e2")));
+        assert_eq!(reason(pinned, "e1", "g1"), IllegalMoveReason::CastlingRightsLost(CastlingSide::Kingside));
+
+        let check = "4k3/8/8/4r3/8/8/8/R3K2R w KQ - 0 1";
+        assert_eq!(reason(check, "a1", "a8"), IllegalMoveReason::KingInCheck);
+        assert_eq!(reason(check, "e1", "e2"), IllegalMoveReason::IntoCheck(sq!("e2")));
+        assert_eq!(reason(check, "e1", "c1"), IllegalMoveReason::CastlingOutOfCheck);
+
+        let crossed = "4k3/8/8/8/8/5r2/8/RN2K2R w KQ - 0 1";
+        assert_eq!(reason(crossed, "e1", "g1"), IllegalMoveReason::CastlingThroughCheck(sq!("f1")));
+        assert_eq!(
+            reason(crossed, "e1", "c1"),
+            IllegalMoveReason::PathBlocked { piece: PieceType::King, blocker: sq!("b1") }
+        );
+    }
+}
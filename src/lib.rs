@@ -4,11 +4,87 @@
 pub mod rchess {
     pub mod v1 {
         include!(concat!(env!("OUT_DIR"), "/rchess.v1.rs"));
+
+        /// Encoded FileDescriptorSet for the rchess.v1 API, for reflection services and SDK generators.
+        pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("rchess_descriptor");
     }
 }
 
 pub mod pieces;
 pub mod board;
+pub mod analysis;
+pub mod annotate;
+pub mod announce;
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+pub mod attacks;
+pub mod autosave;
+pub mod bench;
+pub mod bitboard;
+pub mod book;
+pub mod builder;
+pub mod cli;
+pub mod clock;
+#[cfg(feature = "online")]
+pub mod cloud;
+pub mod diff;
+pub mod endgame;
+pub mod engine;
+pub mod error;
+pub mod eval;
+pub mod eval_cache;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fen;
+pub mod game;
+pub mod game_tree;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod human;
+pub mod i18n;
+pub mod kpk;
+pub mod legality;
+pub mod manager;
+pub mod material;
+pub mod moves;
+pub mod movegen;
+pub mod nnue;
+pub mod notation;
+pub mod observer;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+#[cfg(feature = "online")]
+pub mod online;
+pub mod openings;
+pub mod perft;
+pub mod pgn;
+pub mod pocket;
+pub mod positional;
+#[cfg(feature = "grpc")]
+pub mod rate_limit;
+pub mod record;
+pub mod render;
+pub mod report;
+pub mod save;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod snapshot;
+pub mod status;
+#[cfg(feature = "tablebase")]
+pub mod tablebase;
+pub mod time_manager;
+pub mod transform;
+pub mod tt;
+pub mod uci;
+pub mod validate;
+pub mod variant;
+pub mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod xboard;
+pub mod zobrist;
 
 /// Return a short greeting string. Kept minimal so unit tests are easy.
 pub fn greet() -> String {
@@ -23,4 +99,14 @@ mod tests {
     fn greet_returns_expected() {
         assert_eq!(greet(), "Hello, world!");
     }
+
+    #[test]
+    fn file_descriptor_set_describes_api() {
+        use prost::Message;
+
+        let set = prost_types::FileDescriptorSet::decode(rchess::v1::FILE_DESCRIPTOR_SET).unwrap();
+        let board = set.file.iter().find(|f| f.name() == "board.proto").unwrap();
+        assert_eq!(board.package(), "rchess.v1");
+        assert!(board.message_type.iter().any(|m| m.name() == "GameState"));
+    }
 }
@@ -1,4 +1,45 @@
-fn main() {
+use std::io::{self, BufRead, IsTerminal, Read};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() -> io::Result<()> {
     // Delegate to library code so core logic is testable in `src/lib.rs`.
-    println!("{}", rchess::greet());
+    // "rchess play", or no argument on a terminal, starts interactive play;
+    // "rchess uci" and "rchess xboard" force a protocol; "rchess bench" runs the
+    // benchmark, and "rchess analyze" and "rchess annotate" print engine reports.
+    // "rchess play --autosave DIR" saves the game as it goes, and "rchess recover
+    // DIR [ID]" lists or resumes the games left there by a crash.
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    match std::env::args().nth(1).as_deref() {
+        Some("play") if args.len() == 2 && args[0] == "--autosave" => {
+            let autosave = rchess::autosave::Autosave::new(&args[1])?;
+            let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            let id = format!("play-{}-{}", started, std::process::id());
+            return rchess::cli::run_autosaved(io::stdin().lock(), io::stdout(), autosave, &id);
+        }
+        Some("play") => return rchess::cli::run(io::stdin().lock(), io::stdout()),
+        Some("uci") => return rchess::uci::run(io::stdin().lock(), io::stdout()),
+        Some("xboard") => return rchess::xboard::run(io::stdin().lock(), io::stdout()),
+        Some("bench") => return rchess::bench::run(io::stdout()),
+        Some("analyze") => return rchess::report::analyze(&args, io::stdout()),
+        Some("annotate") => return rchess::report::annotate(&args, io::stdin().lock(), io::stdout()),
+        Some("recover") => return rchess::cli::recover(&args, io::stdin().lock(), io::stdout()),
+        Some(other) => {
+            eprintln!("usage: rchess [play|uci|xboard|bench|analyze|annotate|recover] (unknown mode {:?})", other);
+            std::process::exit(2);
+        }
+        None if io::stdin().is_terminal() => return rchess::cli::run(io::stdin().lock(), io::stdout()),
+        None => {}
+    }
+
+    // GUIs announce their protocol with the first command: "xboard" for CECP,
+    // anything else (normally "uci") is handled as UCI.
+    let mut stdin = io::stdin().lock();
+    let mut first = String::new();
+    stdin.read_line(&mut first)?;
+    let input = first.as_bytes().chain(stdin);
+    if first.trim() == "xboard" {
+        rchess::xboard::run(input, io::stdout())
+    } else {
+        rchess::uci::run(input, io::stdout())
+    }
 }
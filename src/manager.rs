@@ -0,0 +1,458 @@
+// Hosting many games at once. GameManager owns every game by id, seats players,
+// and routes moves, resignations, and draw offers to the right game. Each game
+// has its own lock, so servers can play unrelated games in parallel; the map of
+// games is only locked long enough to find one. Games nobody has touched for a
+// while can be evicted in one sweep, and games in progress can be autosaved and
+// recovered after a crash.
+
+use crate::autosave::Autosave;
+use crate::game::{ActionError, Game, GameResult};
+use crate::moves::{Move, MoveError, MoveOutcome};
+use crate::pieces::Color;
+use crate::save::SaveError;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Identifies a game within its manager.
+pub type GameId = u64;
+
+/// Why a manager operation was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManagerError {
+    /// No game has this id.
+    NotFound(GameId),
+    /// Another player holds the seat.
+    SeatTaken(Color),
+    /// Both seats are held by other players.
+    GameFull,
+    /// The player has no seat in the game.
+    NotSeated,
+    /// The player's side is not the side to move.
+    NotYourTurn,
+    /// The game refused a draw offer, resignation, or adjudication, or is over.
+    Action(ActionError),
+    /// The move is not legal in the current position.
+    Move(MoveError),
+}
+
+impl fmt::Display for ManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ManagerError::NotFound(id) => write!(f, "no game with id {}", id),
+            ManagerError::SeatTaken(color) => write!(f, "the {} seat is taken", color),
+            ManagerError::GameFull => write!(f, "both seats are taken"),
+            ManagerError::NotSeated => write!(f, "player is not seated in this game"),
+            ManagerError::NotYourTurn => write!(f, "it is not this player's turn"),
+            ManagerError::Action(e) => write!(f, "{}", e),
+            ManagerError::Move(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ManagerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ManagerError::Action(e) => Some(e),
+            ManagerError::Move(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ActionError> for ManagerError {
+    fn from(e: ActionError) -> Self {
+        ManagerError::Action(e)
+    }
+}
+
+impl From<MoveError> for ManagerError {
+    /// A move refused because the game is over reports like any other action on it.
+    fn from(e: MoveError) -> Self {
+        match e {
+            MoveError::GameOver => ManagerError::Action(ActionError::GameOver),
+            e => ManagerError::Move(e),
+        }
+    }
+}
+
+/// A hosted game and who sits at it.
+#[derive(Debug)]
+struct Session {
+    game: Game,
+    /// Players by seat, white first.
+    seats: [Option<String>; 2],
+    /// When the game was created or last reached through the manager.
+    last_active: Instant,
+}
+
+impl Session {
+    fn seat(color: Color) -> usize {
+        match color {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+
+    fn color_of(&self, player: &str) -> Option<Color> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| self.seats[Session::seat(color)].as_deref() == Some(player))
+    }
+}
+
+type Sessions = HashMap<GameId, Arc<Mutex<Session>>>;
+
+/// Owns any number of games, each behind its own lock. Share one between
+/// threads with an `Arc`.
+#[derive(Debug, Default)]
+pub struct GameManager {
+    games: RwLock<Sessions>,
+    next_id: AtomicU64,
+}
+
+impl GameManager {
+    pub fn new() -> Self {
+        GameManager::default()
+    }
+
+    /// Number of games held.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Ids of every game held, in creation order.
+    pub fn ids(&self) -> Vec<GameId> {
+        let mut ids: Vec<GameId> = self.read().keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Host `game`, with both seats empty, and return its id.
+    pub fn create(&self, game: Game) -> GameId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let session = Session { game, seats: [None, None], last_active: Instant::now() };
+        self.write().insert(id, Arc::new(Mutex::new(session)));
+        id
+    }
+
+    /// Stop hosting a game and hand it back.
+    pub fn remove(&self, id: GameId) -> Option<Game> {
+        let session = self.write().remove(&id)?;
+        Some(into_game(session))
+    }
+
+    /// Stop hosting every game that has gone `max_idle` without a call on it,
+    /// and hand them back in creation order. A game some call is using right
+    /// now is never idle.
+    pub fn evict_idle(&self, max_idle: Duration) -> Vec<(GameId, Game)> {
+        let is_idle = |session: &Mutex<Session>| match session.try_lock() {
+            Ok(session) => session.last_active.elapsed() >= max_idle,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().last_active.elapsed() >= max_idle,
+            Err(TryLockError::WouldBlock) => false,
+        };
+        let mut games = self.write();
+        let mut idle: Vec<GameId> = games.iter().filter(|(_, session)| is_idle(session)).map(|(&id, _)| id).collect();
+        idle.sort_unstable();
+        let evicted: Vec<(GameId, Arc<Mutex<Session>>)> =
+            idle.into_iter().filter_map(|id| Some((id, games.remove(&id)?))).collect();
+        drop(games);
+        evicted.into_iter().map(|(id, session)| (id, into_game(session))).collect()
+    }
+
+    /// Save every game in progress to `autosave` and remove every other save
+    /// there; see [`Autosave::sync`]. Saving does not count as activity.
+    pub fn autosave(&self, autosave: &Autosave) -> Result<(), SaveError> {
+        let sessions: Vec<(GameId, Arc<Mutex<Session>>)> =
+            self.read().iter().map(|(&id, session)| (id, Arc::clone(session))).collect();
+        autosave.sync(sessions.into_iter().map(|(id, session)| (id.to_string(), lock(&session).game.clone())))
+    }
+
+    /// Autosave every `interval` on a background thread until the manager is
+    /// dropped. A failed autosave leaves the previous saves in place and is
+    /// tried again at the next interval.
+    pub fn spawn_autosave(self: &Arc<Self>, autosave: Autosave, interval: Duration) -> JoinHandle<()> {
+        let manager = Arc::downgrade(self);
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                let _ = manager.autosave(&autosave);
+            }
+        })
+    }
+
+    /// Host every game saved in `autosave`, with both seats empty, and move the
+    /// saves to the games' new ids. Call this before autosaving into the
+    /// directory, which would otherwise remove the saves. Returns the new ids.
+    pub fn recover(&self, autosave: &Autosave) -> Result<Vec<GameId>, SaveError> {
+        let games = autosave.interrupted()?.iter().map(|id| autosave.recover(id)).collect::<Result<Vec<Game>, _>>()?;
+        let ids = games.into_iter().map(|game| self.create(game)).collect();
+        self.autosave(autosave)?;
+        Ok(ids)
+    }
+
+    /// Seat `player` at the first free seat, white first. A player already
+    /// seated keeps their seat.
+    pub fn join(&self, id: GameId, player: &str) -> Result<Color, ManagerError> {
+        self.with_session(id, |session| {
+            if let Some(color) = session.color_of(player) {
+                return Ok(color);
+            }
+            let color = [Color::White, Color::Black]
+                .into_iter()
+                .find(|&color| session.seats[Session::seat(color)].is_none())
+                .ok_or(ManagerError::GameFull)?;
+            session.seats[Session::seat(color)] = Some(player.to_string());
+            Ok(color)
+        })
+    }
+
+    /// Seat `player` as `color`.
+    pub fn join_as(&self, id: GameId, player: &str, color: Color) -> Result<(), ManagerError> {
+        self.with_session(id, |session| match &session.seats[Session::seat(color)] {
+            Some(seated) if seated == player => Ok(()),
+            Some(_) => Err(ManagerError::SeatTaken(color)),
+            None if session.color_of(player).is_some() => Err(ManagerError::SeatTaken(color.opposite())),
+            None => {
+                session.seats[Session::seat(color)] = Some(player.to_string());
+                Ok(())
+            }
+        })
+    }
+
+    /// Player in `color`'s seat, if any.
+    pub fn player(&self, id: GameId, color: Color) -> Result<Option<String>, ManagerError> {
+        self.with_session(id, |session| Ok(session.seats[Session::seat(color)].clone()))
+    }
+
+    /// Play `mv` for `player`, who must be seated on the side to move. Moving
+    /// declines a draw offer from the opponent, and a move that ends the game
+    /// records the result.
+    pub fn play(&self, id: GameId, player: &str, mv: Move) -> Result<MoveOutcome, ManagerError> {
+        self.with_seat(id, player, |game, color| {
+            if color != game.board().current_player() {
+                return Err(ManagerError::NotYourTurn);
+            }
+            Ok(game.play(mv)?)
+        })
+    }
+
+    /// Resign for `player` and return the result.
+    pub fn resign(&self, id: GameId, player: &str) -> Result<GameResult, ManagerError> {
+        self.with_seat(id, player, |game, color| game.resign(color))
+    }
+
+    /// Offer a draw for `player`. If the opponent's offer is already standing,
+    /// the two agree and the game is drawn. Returns the result either way.
+    pub fn offer_draw(&self, id: GameId, player: &str) -> Result<GameResult, ManagerError> {
+        self.with_seat(id, player, |game, color| game.offer_draw(color))
+    }
+
+    /// Accept the opponent's standing draw offer.
+    pub fn accept_draw(&self, id: GameId, player: &str) -> Result<GameResult, ManagerError> {
+        self.with_seat(id, player, |game, color| game.accept_draw(color))
+    }
+
+    /// Decline the opponent's standing draw offer.
+    pub fn decline_draw(&self, id: GameId, player: &str) -> Result<(), ManagerError> {
+        self.with_seat(id, player, |game, color| game.decline_draw(color))
+    }
+
+    /// Side whose draw offer is standing, if any.
+    pub fn draw_offer(&self, id: GameId) -> Result<Option<Color>, ManagerError> {
+        self.with_game(id, Game::draw_offer)
+    }
+
+    /// End a game with `result` without playing it out; see [`Game::adjudicate`].
+    pub fn adjudicate(&self, id: GameId, result: GameResult, reason: &str) -> Result<(), ManagerError> {
+        self.with_session(id, |session| Ok(session.game.adjudicate(result, reason)?))
+    }
+
+    /// Run `f` on a game without changing it, under that game's lock.
+    pub fn with_game<R>(&self, id: GameId, f: impl FnOnce(&Game) -> R) -> Result<R, ManagerError> {
+        self.with_session(id, |session| Ok(f(&session.game)))
+    }
+
+    /// A copy of a game as it stands.
+    pub fn game(&self, id: GameId) -> Result<Game, ManagerError> {
+        self.with_game(id, Game::clone)
+    }
+
+    /// Run `f` on a game for the side `player` is seated on.
+    fn with_seat<R, E: Into<ManagerError>>(
+        &self,
+        id: GameId,
+        player: &str,
+        f: impl FnOnce(&mut Game, Color) -> Result<R, E>,
+    ) -> Result<R, ManagerError> {
+        self.with_session(id, |session| {
+            let color = session.color_of(player).ok_or(ManagerError::NotSeated)?;
+            f(&mut session.game, color).map_err(Into::into)
+        })
+    }
+
+    /// Run `f` on the session with `id`, holding only that game's lock. Every
+    /// call counts as activity, reads included.
+    fn with_session<R>(
+        &self,
+        id: GameId,
+        f: impl FnOnce(&mut Session) -> Result<R, ManagerError>,
+    ) -> Result<R, ManagerError> {
+        let session = self.read().get(&id).cloned().ok_or(ManagerError::NotFound(id))?;
+        let mut session = lock(&session);
+        session.last_active = Instant::now();
+        f(&mut session)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Sessions> {
+        self.games.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Sessions> {
+        self.games.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn lock(session: &Mutex<Session>) -> MutexGuard<'_, Session> {
+    // A call that panicked mid-update leaves at worst one game half-written.
+    session.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The game of a session no longer in the map.
+fn into_game(session: Arc<Mutex<Session>>) -> Game {
+    match Arc::try_unwrap(session) {
+        Ok(session) => session.into_inner().unwrap_or_else(PoisonError::into_inner).game,
+        // A call that found the game before it was removed still holds it.
+        Err(shared) => lock(&shared).game.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_seats_turns_and_resignation() {
+        let manager = GameManager::new();
+        let id = manager.create(Game::new());
+        assert_eq!(manager.join(id, "alice"), Ok(Color::White));
+        assert_eq!(manager.join(id, "alice"), Ok(Color::White));
+        assert_eq!(manager.join_as(id, "carol", Color::White), Err(ManagerError::SeatTaken(Color::White)));
+        assert_eq!(manager.join(id, "bob"), Ok(Color::Black));
+        assert_eq!(manager.join(id, "carol"), Err(ManagerError::GameFull));
+
+        assert_eq!(manager.play(id, "bob", Move::new(sq!("e7"), sq!("e5"))), Err(ManagerError::NotYourTurn));
+        assert_eq!(manager.play(id, "carol", Move::new(sq!("e2"), sq!("e4"))), Err(ManagerError::NotSeated));
+        assert!(matches!(manager.play(id, "alice", Move::new(sq!("e2"), sq!("e5"))), Err(ManagerError::Move(_))));
+        manager.play(id, "alice", Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        assert_eq!(manager.with_game(id, |game| game.moves().len()), Ok(1));
+
+        assert_eq!(manager.resign(id, "bob"), Ok(GameResult::WhiteWins));
+        let reply = Move::new(sq!("e7"), sq!("e5"));
+        assert_eq!(manager.play(id, "bob", reply), Err(ManagerError::Action(ActionError::GameOver)));
+        assert_eq!(manager.play(99, "bob", Move::new(sq!("e7"), sq!("e5"))), Err(ManagerError::NotFound(99)));
+        assert_eq!(manager.remove(id).map(|game| game.result()), Some(GameResult::WhiteWins));
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn test_draw_offers_lapse_on_a_move() {
+        let manager = GameManager::new();
+        let id = manager.create(Game::new());
+        manager.join(id, "alice").unwrap();
+        manager.join(id, "bob").unwrap();
+        assert_eq!(manager.accept_draw(id, "bob"), Err(ManagerError::Action(ActionError::NoDrawOffer)));
+
+        assert_eq!(manager.offer_draw(id, "alice"), Ok(GameResult::Ongoing));
+        manager.play(id, "alice", Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        assert_eq!(manager.draw_offer(id), Ok(Some(Color::White)));
+        manager.play(id, "bob", Move::new(sq!("e7"), sq!("e5"))).unwrap();
+        assert_eq!(manager.draw_offer(id), Ok(None));
+
+        manager.offer_draw(id, "bob").unwrap();
+        assert_eq!(manager.decline_draw(id, "alice"), Ok(()));
+        manager.offer_draw(id, "bob").unwrap();
+        assert_eq!(manager.offer_draw(id, "alice"), Ok(GameResult::Draw));
+        assert_eq!(manager.adjudicate(id, GameResult::WhiteWins, ""), Err(ManagerError::Action(ActionError::GameOver)));
+    }
+
+    #[test]
+    fn test_games_play_in_parallel() {
+        let manager = GameManager::new();
+        let ids: Vec<GameId> = (0..4).map(|_| manager.create(Game::new())).collect();
+        std::thread::scope(|scope| {
+            for &id in &ids {
+                let manager = &manager;
+                scope.spawn(move || {
+                    manager.join(id, "white").unwrap();
+                    manager.join(id, "black").unwrap();
+                    let moves = [
+                        ("white", sq!("f2"), sq!("f3")),
+                        ("black", sq!("e7"), sq!("e5")),
+                        ("white", sq!("g2"), sq!("g4")),
+                        ("black", sq!("d8"), sq!("h4")),
+                    ];
+                    for (player, from, to) in moves {
+                        manager.play(id, player, Move::new(from, to)).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(manager.ids(), ids);
+        for id in ids {
+            assert_eq!(manager.game(id).unwrap().result(), GameResult::BlackWins);
+        }
+    }
+
+    #[test]
+    fn test_idle_games_are_evicted() {
+        let manager = GameManager::new();
+        let stale = manager.create(Game::new());
+        let busy = manager.create(Game::new());
+        std::thread::sleep(Duration::from_millis(30));
+        manager.join(busy, "alice").unwrap();
+
+        assert!(manager.evict_idle(Duration::from_secs(3600)).is_empty());
+        let evicted = manager.evict_idle(Duration::from_millis(20));
+        assert_eq!(evicted.iter().map(|(id, _)| *id).collect::<Vec<_>>(), [stale]);
+        assert_eq!(manager.ids(), [busy]);
+        assert_eq!(manager.evict_idle(Duration::ZERO).len(), 1);
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn test_autosaved_games_are_recovered() {
+        let dir = std::env::temp_dir().join(format!("rchess-manager-autosave-test-{}", std::process::id()));
+        let autosave = Autosave::new(&dir).unwrap();
+        let manager = GameManager::new();
+        let finished = manager.create(Game::new());
+        let playing = manager.create(Game::new());
+        for id in [finished, playing] {
+            manager.join(id, "alice").unwrap();
+            manager.play(id, "alice", Move::new(sq!("d2"), sq!("d4"))).unwrap();
+        }
+        manager.resign(finished, "alice").unwrap();
+        manager.autosave(&autosave).unwrap();
+
+        let restarted = GameManager::new();
+        let recovered = restarted.recover(&autosave);
+        let saves = autosave.interrupted();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let ids = recovered.unwrap();
+        assert_eq!(ids, [1]);
+        assert_eq!(saves.unwrap(), ["1"]);
+        assert_eq!(restarted.game(1).unwrap().board().to_fen(), manager.game(playing).unwrap().board().to_fen());
+        assert_eq!(restarted.player(1, Color::White), Ok(None));
+    }
+}
@@ -0,0 +1,145 @@
+// Material accounting: per-type piece counts and total value for each side, the
+// balance between them, and signatures like "KRPP vs KR". Counts are read off the
+// board's bitboards, which apply_move keeps up to date, so they cost a handful
+// of popcounts rather than a scan of the piece list.
+
+use crate::board::Board;
+use crate::pieces::{Color, PieceType};
+
+/// Non-king piece counts for one side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MaterialCount {
+    pub queens: u32,
+    pub rooks: u32,
+    pub bishops: u32,
+    pub knights: u32,
+    pub pawns: u32,
+}
+
+impl MaterialCount {
+    /// Number of pieces of the given type (kings are not counted).
+    pub fn count(&self, piece_type: PieceType) -> u32 {
+        match piece_type {
+            PieceType::King => 0,
+            PieceType::Queen => self.queens,
+            PieceType::Rook => self.rooks,
+            PieceType::Bishop => self.bishops,
+            PieceType::Knight => self.knights,
+            PieceType::Pawn => self.pawns,
+        }
+    }
+
+    /// Total material value in centipawns.
+    pub fn value(&self) -> i32 {
+        [
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::Pawn,
+        ]
+        .iter()
+        .map(|t| self.count(*t) as i32 * t.value())
+        .sum()
+    }
+
+    /// Number of queens, rooks, bishops, and knights.
+    pub fn pieces(&self) -> u32 {
+        self.queens + self.rooks + self.bishops + self.knights
+    }
+
+    /// Pieces written strongest first, one letter each, e.g. "QRRBNPPP". Kings
+    /// are not included.
+    pub fn signature(&self) -> String {
+        [
+            (PieceType::Queen, 'Q'),
+            (PieceType::Rook, 'R'),
+            (PieceType::Bishop, 'B'),
+            (PieceType::Knight, 'N'),
+            (PieceType::Pawn, 'P'),
+        ]
+        .iter()
+        .flat_map(|&(t, letter)| std::iter::repeat_n(letter, self.count(t) as usize))
+        .collect()
+    }
+}
+
+impl Board {
+    /// Count the on-board material of one side.
+    pub fn material(&self, color: Color) -> MaterialCount {
+        let count = |piece_type| self.bitboards().pieces(piece_type, color).count();
+        MaterialCount {
+            queens: count(PieceType::Queen),
+            rooks: count(PieceType::Rook),
+            bishops: count(PieceType::Bishop),
+            knights: count(PieceType::Knight),
+            pawns: count(PieceType::Pawn),
+        }
+    }
+
+    /// White's material minus Black's, in centipawns.
+    pub fn material_balance(&self) -> i32 {
+        self.material(Color::White).value() - self.material(Color::Black).value()
+    }
+
+    /// Both sides' material, White first, e.g. "KQRRBNPPP vs KQRBNPP". A side
+    /// with no king on the board, as after an atomic explosion, has no "K".
+    pub fn material_signature(&self) -> String {
+        let side = |color| {
+            let king = if self.king_square(color).is_some() { "K" } else { "" };
+            format!("{}{}", king, self.material(color).signature())
+        };
+        format!("{} vs {}", side(Color::White), side(Color::Black))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+    use crate::pieces::{King, Knight, Pawn, Queen, Square};
+    use crate::rchess::v1::{self as proto};
+
+    #[test]
+    fn test_material_counts_and_value() {
+        let sq = |file, rank| Square::new(file, rank).unwrap();
+        let kinds = vec![
+            proto::piece::Kind::King(King::new(Color::White, sq(4, 0)).to_proto()),
+            proto::piece::Kind::Queen(Queen::new(Color::White, sq(3, 0)).to_proto()),
+            proto::piece::Kind::Pawn(Pawn::new(Color::White, sq(0, 1)).to_proto()),
+            proto::piece::Kind::Pawn(Pawn::new(Color::White, sq(1, 1)).to_proto()),
+            proto::piece::Kind::Knight(Knight::new(Color::Black, sq(1, 7)).to_proto()),
+        ];
+        let board = Board::try_from_proto(proto::GameState {
+            board: Some(proto::Board {
+                pieces: kinds
+                    .into_iter()
+                    .map(|kind| proto::Piece {
+                        kind: Some(kind),
+                        ..Default::default()
+                    })
+                    .collect(),
+            }),
+            current_player: Color::White.to_proto(),
+            ..Default::default()
+        }).unwrap();
+
+        let white = board.material(Color::White);
+        assert_eq!(white.count(PieceType::Pawn), 2);
+        assert_eq!(white.queens, 1);
+        assert_eq!(white.value(), 1100);
+        assert_eq!(board.material(Color::Black).value(), 320);
+        assert_eq!(board.material_balance(), 780);
+        assert_eq!(board.material_signature(), "KQPP vs N");
+    }
+
+    #[test]
+    fn test_signature_follows_captures() {
+        let mut board = Board::from_fen("4k3/8/8/3p4/4P3/8/8/R3K3 w Q - 0 1").unwrap();
+        assert_eq!(board.material_signature(), "KRP vs KP");
+        board.apply_move(Move::from_uci("e4d5", &board).unwrap()).unwrap();
+        assert_eq!(board.material_signature(), "KRP vs K");
+        assert_eq!(board.material_balance(), 600);
+        assert_eq!(Board::starting_position().material_signature(), "KQRRBBNNPPPPPPPP vs KQRRBBNNPPPPPPPP");
+    }
+}
@@ -0,0 +1,285 @@
+// Allocation-free move generation for hot loops such as search and perft.
+// Targets come straight from the bitboards, and in variants whose only rule is
+// king safety each move is tested against the opponent's attacks on the updated
+// occupancy instead of being played on a scratch board.
+
+use crate::bitboard::{self, Bitboard};
+use crate::board::Board;
+use crate::moves::Move;
+use crate::pieces::{Color, PieceType, Square};
+use crate::pocket::{POCKET_TYPES, Pocket};
+use std::fmt;
+
+/// Moves a [`MoveList`] holds without touching the heap. No chess position has
+/// more than 218 legal moves; only drop variants can go past this.
+pub const MAX_MOVES: usize = 256;
+
+/// Piece types a pawn may promote to, strongest first.
+const PROMOTION_TYPES: [PieceType; 4] = [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+
+/// A list of moves kept on the stack, for [`Board::generate_into`].
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+    // Moves past MAX_MOVES, which only drop variants reach.
+    spill: Vec<Move>,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        let empty = Move::new(Square { file: 0, rank: 0 }, Square { file: 0, rank: 0 });
+        MoveList { moves: [empty; MAX_MOVES], len: 0, spill: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len + self.spill.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.spill.clear();
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        if self.len < MAX_MOVES {
+            self.moves[self.len] = mv;
+            self.len += 1;
+        } else {
+            self.spill.push(mv);
+        }
+    }
+
+    /// The move at `index`, in the order the moves were pushed.
+    pub fn get(&self, index: usize) -> Option<Move> {
+        if index < self.len {
+            Some(self.moves[index])
+        } else {
+            self.spill.get(index - self.len).copied()
+        }
+    }
+
+    pub fn contains(&self, mv: &Move) -> bool {
+        self.iter().any(|m| m == *mv)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Move> + '_ {
+        self.moves[..self.len].iter().chain(&self.spill).copied()
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        MoveList::new()
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Extend<Move> for MoveList {
+    fn extend<I: IntoIterator<Item = Move>>(&mut self, moves: I) {
+        for mv in moves {
+            self.push(mv);
+        }
+    }
+}
+
+impl Board {
+    /// Legal moves for `color`, generated lazily: the same moves as
+    /// [`legal_moves`](Board::legal_moves), with piece moves in the same order
+    /// and drops grouped by piece type. Variants with rules beyond king safety
+    /// keep one scratch copy of the board for testing moves.
+    pub fn legal_moves_iter(&self, color: Color) -> impl Iterator<Item = Move> + '_ {
+        let pocket = if self.variant().has_drops() { self.pocket(color) } else { Pocket::default() };
+        let empty = !self.bitboards().occupied();
+        let piece_moves = self.bitboards().color(color).squares().flat_map(move |from| {
+            let is_pawn = self.bitboards().pieces(PieceType::Pawn, color).contains(from);
+            self.piece_targets(from, color).squares().flat_map(move |to| self.moves_to(from, to, is_pawn))
+        });
+        let drops = POCKET_TYPES
+            .into_iter()
+            .filter(move |&piece_type| pocket.count(piece_type) > 0)
+            .flat_map(move |piece_type| {
+                empty
+                    .squares()
+                    .filter(move |&to| self.variant().check_drop(piece_type, to, color).is_ok())
+                    .map(move |to| Move::new_drop(piece_type, to))
+            });
+        let mut scratch: Option<Board> = None;
+        piece_moves.chain(drops).filter(move |&mv| {
+            if self.variant().king_safety_only() {
+                return self.keeps_king_safe(mv, color);
+            }
+            let scratch = scratch.get_or_insert_with(|| self.clone());
+            scratch.make_move(mv);
+            let legal = self.variant().check_legal(mv, color, scratch).is_ok();
+            scratch.unmake_move();
+            legal
+        })
+    }
+
+    /// Replace the contents of `moves` with the legal moves for `color`, in
+    /// [`legal_moves_iter`](Board::legal_moves_iter) order.
+    pub fn generate_into(&self, color: Color, moves: &mut MoveList) {
+        moves.clear();
+        moves.extend(self.legal_moves_iter(color));
+    }
+
+    /// Pseudo-legal destinations of the piece on `from`, castling included.
+    pub(crate) fn piece_targets(&self, from: Square, color: Color) -> Bitboard {
+        let bb = self.bitboards();
+        let own = bb.color(color);
+        let Some((piece_type, _)) = self.piece_at_typed(from) else {
+            return Bitboard::EMPTY;
+        };
+        if piece_type != PieceType::Pawn {
+            let targets = self.attacks_from(from) & !own;
+            return match piece_type {
+                PieceType::King => targets | self.castling_targets(color),
+                _ => targets,
+            };
+        }
+        let (direction, en_passant_rank) = match color {
+            Color::White => (1, 5),
+            Color::Black => (-1, 2),
+        };
+        let occupied = bb.occupied();
+        let mut targets = Bitboard::EMPTY;
+        if let Some(one) = from.offset(0, direction)
+            && !occupied.contains(one)
+        {
+            targets.insert(one);
+            if let Some(two) = from.offset(0, 2 * direction)
+                && from.rank == color.pawn_start_rank()
+                && !occupied.contains(two)
+            {
+                targets.insert(two);
+            }
+        }
+        let mut victims = bb.color(color.opposite());
+        if let Some(target) = self.en_passant_target()
+            && target.rank == en_passant_rank
+        {
+            victims.insert(target);
+        }
+        targets | (bitboard::pawn_attacks(from, color) & victims)
+    }
+
+    /// The move or moves from `from` to `to`: one for each promotion piece when
+    /// a pawn reaches the last rank.
+    fn moves_to(&self, from: Square, to: Square, is_pawn: bool) -> impl Iterator<Item = Move> + use<> {
+        let promotes = is_pawn && (to.rank == 0 || to.rank == 7);
+        let base = self.annotate_move(Move::new(from, to));
+        let moves: [Option<Move>; 4] = if promotes {
+            PROMOTION_TYPES.map(|piece_type| Some(Move { promotion: Some(piece_type), ..base }))
+        } else {
+            [Some(base), None, None, None]
+        };
+        moves.into_iter().flatten()
+    }
+
+    /// Whether `mv`, pseudo-legal for `color`, leaves `color`'s king out of check,
+    /// judged on the occupancy after the move without playing it.
+    fn keeps_king_safe(&self, mv: Move, color: Color) -> bool {
+        let bb = self.bitboards();
+        let Some(king) = bb.pieces(PieceType::King, color).squares().next() else {
+            return true;
+        };
+        let opponent = color.opposite();
+        let mut occupied = bb.occupied();
+        let mut captured = Bitboard::from_square(mv.to);
+        if mv.drop.is_none() {
+            occupied.remove(mv.from);
+            if mv.en_passant {
+                let victim = Square { file: mv.to.file, rank: mv.from.rank };
+                occupied.remove(victim);
+                captured = Bitboard::from_square(victim);
+            }
+        }
+        occupied.insert(mv.to);
+        // Castling moves the rook too, but the king never crosses an attacked
+        // square, so the rook cannot be what shields it.
+        let king = if mv.drop.is_none() && mv.from == king { mv.to } else { king };
+        let enemy = |piece_type| bb.pieces(piece_type, opponent) & !captured;
+        let queens = enemy(PieceType::Queen);
+        ((bitboard::pawn_attacks(king, color) & enemy(PieceType::Pawn))
+            | (bitboard::knight_attacks(king) & enemy(PieceType::Knight))
+            | (bitboard::king_attacks(king) & enemy(PieceType::King))
+            | (bitboard::rook_attacks(king, occupied) & (enemy(PieceType::Rook) | queens))
+            | (bitboard::bishop_attacks(king, occupied) & (enemy(PieceType::Bishop) | queens)))
+            .is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generators_match_reference_counts() {
+        // Published perft(1) counts, so no generator is checked against itself.
+        for (fen, count) in [
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 48),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 14),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 44),
+            // In check from the d4 pawn: eight king moves or exd3 en passant.
+            ("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1", 9),
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let color = board.current_player();
+            let lazy: Vec<Move> = board.legal_moves_iter(color).collect();
+            assert_eq!(lazy.len(), count, "{}", fen);
+            assert_eq!(board.legal_moves(color).len(), count, "{}", fen);
+            let mut list = MoveList::new();
+            board.generate_into(color, &mut list);
+            assert_eq!(list.len(), count, "{}", fen);
+            assert_eq!(list.iter().collect::<Vec<_>>(), lazy, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_double_push_follows_start_rank_not_has_moved() {
+        use crate::rchess::v1::{self as proto};
+        use crate::sq;
+
+        // Flip every pawn's has_moved flag: e2 claims to have moved, d3 not to.
+        let mut state = Board::from_fen("4k3/8/8/8/8/3P4/4P3/4K3 w - - 0 1").unwrap().to_proto();
+        for piece in &mut state.board.as_mut().unwrap().pieces {
+            if let Some(proto::piece::Kind::Pawn(pawn)) = &mut piece.kind {
+                pawn.has_moved = !pawn.has_moved;
+            }
+        }
+        let board = Board::try_from_proto(state).unwrap();
+        let moves: Vec<Move> = board.legal_moves_iter(Color::White).collect();
+        assert!(moves.contains(&Move::new(sq!("e2"), sq!("e4"))));
+        assert!(!moves.contains(&Move::new(sq!("d3"), sq!("d5"))));
+        assert_eq!(board.pawn_moves(sq!("e2"), Color::White), vec![sq!("e3"), sq!("e4")]);
+        assert_eq!(board.pawn_moves(sq!("d3"), Color::White), vec![sq!("d4")]);
+
+        let mut played = board.clone();
+        assert!(played.apply_move(Move::new(sq!("e2"), sq!("e4"))).is_ok());
+        assert!(board.clone().apply_move(Move::new(sq!("d3"), sq!("d5"))).is_err());
+    }
+
+    #[test]
+    fn test_move_list_spills_past_capacity() {
+        let mut list = MoveList::new();
+        let mv = Move::new(Square { file: 4, rank: 1 }, Square { file: 4, rank: 3 });
+        for _ in 0..MAX_MOVES + 3 {
+            list.push(mv);
+        }
+        assert_eq!(list.len(), MAX_MOVES + 3);
+        assert_eq!(list.get(MAX_MOVES + 2), Some(mv));
+        assert_eq!(list.get(MAX_MOVES + 3), None);
+        list.clear();
+        assert!(list.is_empty() && !list.contains(&mv));
+    }
+}
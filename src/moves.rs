@@ -0,0 +1,241 @@
+// Move representation and the result of applying a move to a Board.
+
+use crate::pieces::{Color, PieceType, Square};
+use crate::rchess::v1::{self as proto};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Which side of the board a king castles towards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CastlingSide {
+    Kingside,
+    Queenside,
+}
+
+impl CastlingSide {
+    /// Create from a proto CastlingSide value.
+    pub fn from_proto(value: i32) -> Option<Self> {
+        match proto::CastlingSide::try_from(value).ok()? {
+            proto::CastlingSide::Kingside => Some(CastlingSide::Kingside),
+            proto::CastlingSide::Queenside => Some(CastlingSide::Queenside),
+            proto::CastlingSide::Unspecified => None,
+        }
+    }
+
+    /// Convert to a proto CastlingSide value.
+    pub fn to_proto(&self) -> i32 {
+        match self {
+            CastlingSide::Kingside => proto::CastlingSide::Kingside as i32,
+            CastlingSide::Queenside => proto::CastlingSide::Queenside as i32,
+        }
+    }
+}
+
+/// A move of one piece from one square to another, or a drop of a piece from the
+/// pocket in variants such as crazyhouse. A drop has `from` equal to `to`.
+///
+/// `from`, `to`, `promotion`, and `drop` identify the move; the remaining fields describe
+/// it in the position it is played from. They are filled in by move generation
+/// (see `Board::annotate_move`) and are ignored by equality and hashing, so a
+/// bare `Move::new(from, to)` matches the generated move.
+#[derive(Debug, Clone, Copy)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    /// Piece a pawn promotes to on reaching the last rank.
+    pub promotion: Option<PieceType>,
+    /// Type of the moving piece, if known.
+    pub piece: Option<PieceType>,
+    /// Type of the piece captured, including a pawn taken en passant.
+    pub capture: Option<PieceType>,
+    /// Set when the move is a king's castling move.
+    pub castling: Option<CastlingSide>,
+    /// Whether the move captures en passant.
+    pub en_passant: bool,
+    /// Type of the piece placed from the pocket, for drop moves.
+    pub drop: Option<PieceType>,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square) -> Self {
+        Move {
+            from,
+            to,
+            promotion: None,
+            piece: None,
+            capture: None,
+            castling: None,
+            en_passant: false,
+            drop: None,
+        }
+    }
+
+    /// A drop of a `piece_type` from the mover's pocket onto `to`.
+    pub fn new_drop(piece_type: PieceType, to: Square) -> Self {
+        Move {
+            piece: Some(piece_type),
+            drop: Some(piece_type),
+            ..Move::new(to, to)
+        }
+    }
+
+    /// A pawn move onto the last rank that promotes to `piece_type`.
+    pub fn with_promotion(from: Square, to: Square, piece_type: PieceType) -> Self {
+        Move {
+            promotion: Some(piece_type),
+            ..Move::new(from, to)
+        }
+    }
+
+    /// Whether the move captures a piece. Only known for annotated moves.
+    pub fn is_capture(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// Whether the move drops a piece from the pocket.
+    pub fn is_drop(&self) -> bool {
+        self.drop.is_some()
+    }
+
+    /// Create from a proto Move. A drop needs no `from` square.
+    pub fn from_proto(mv: &proto::Move) -> Option<Self> {
+        let to = mv.to.as_ref().and_then(Square::from_proto)?;
+        let drop = PieceType::from_proto(mv.drop_piece_type);
+        let from = match mv.from.as_ref().and_then(Square::from_proto) {
+            Some(from) => from,
+            None if drop.is_some() => to,
+            None => return None,
+        };
+        Some(Move {
+            from,
+            to,
+            promotion: PieceType::from_proto(mv.promotion_piece_type),
+            piece: PieceType::from_proto(mv.piece_type),
+            capture: PieceType::from_proto(mv.captured_piece_type),
+            castling: CastlingSide::from_proto(mv.castling),
+            en_passant: mv.en_passant,
+            drop,
+        })
+    }
+
+    /// Convert to proto Move.
+    pub fn to_proto(&self) -> proto::Move {
+        proto::Move {
+            from: (!self.is_drop()).then(|| self.from.to_proto()),
+            to: Some(self.to.to_proto()),
+            promotion_piece_type: self.promotion.map_or(0, |p| p.to_proto()),
+            piece_type: self.piece.map_or(0, |p| p.to_proto()),
+            captured_piece_type: self.capture.map_or(0, |p| p.to_proto()),
+            castling: self.castling.map_or(0, |side| side.to_proto()),
+            en_passant: self.en_passant,
+            drop_piece_type: self.drop.map_or(0, |p| p.to_proto()),
+        }
+    }
+}
+
+impl PartialEq for Move {
+    fn eq(&self, other: &Self) -> bool {
+        (self.from, self.to, self.promotion, self.drop) == (other.from, other.to, other.promotion, other.drop)
+    }
+}
+
+impl Eq for Move {}
+
+impl Hash for Move {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.from, self.to, self.promotion, self.drop).hash(state);
+    }
+}
+
+/// What happened when a move was applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveOutcome {
+    /// Type of the piece that was captured, if any.
+    pub captured: Option<PieceType>,
+    /// Whether the move gives check to the opponent.
+    pub check: bool,
+}
+
+/// Why a move was rejected by `Board::apply_move`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// There is no piece on the source square.
+    NoPiece(Square),
+    /// The piece on the source square belongs to the side not to move.
+    NotYourTurn { color: Color },
+    /// The piece cannot reach the target square under its movement rules.
+    IllegalPieceMove { piece: PieceType, from: Square, to: Square },
+    /// The move would leave the mover's own king in check.
+    LeavesKingInCheck,
+    /// A pawn reaching the last rank must name a promotion piece.
+    PromotionRequired,
+    /// The promotion piece is not a queen, rook, bishop, or knight, or the move is not a promotion.
+    InvalidPromotion(PieceType),
+    /// The board's variant forbids the move, for the stated reason.
+    VariantRule(&'static str),
+    /// A drop of a piece type the mover's pocket does not hold.
+    NotInPocket(PieceType),
+    /// A drop onto a square that is not empty.
+    SquareOccupied(Square),
+    /// The game already has a result. Only a `Game` knows this; a bare board
+    /// never returns it.
+    GameOver,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::NoPiece(square) => write!(f, "no piece on {}", square),
+            MoveError::NotYourTurn { color } => write!(f, "it is not {}'s turn", color),
+            MoveError::IllegalPieceMove { piece, from, to } => {
+                write!(f, "{} on {} cannot move to {}", piece, from, to)
+            }
+            MoveError::LeavesKingInCheck => write!(f, "move would leave the king in check"),
+            MoveError::PromotionRequired => write!(f, "pawn move to the last rank needs a promotion piece"),
+            MoveError::InvalidPromotion(piece) => write!(f, "cannot promote to {}", piece),
+            MoveError::VariantRule(reason) => write!(f, "{}", reason),
+            MoveError::NotInPocket(piece) => write!(f, "no {} in the pocket", piece),
+            MoveError::SquareOccupied(square) => write!(f, "{} is occupied", square),
+            MoveError::GameOver => write!(f, "the game is over"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_proto_round_trip() {
+        let mv = Move::new(Square::new(4, 1).unwrap(), Square::new(4, 3).unwrap());
+        assert_eq!(Move::from_proto(&mv.to_proto()), Some(mv));
+        let promotion = Move::with_promotion(Square::new(0, 6).unwrap(), Square::new(0, 7).unwrap(), PieceType::Knight);
+        assert_eq!(promotion.to_proto().promotion_piece_type, 5);
+        assert_eq!(Move::from_proto(&promotion.to_proto()), Some(promotion));
+        let drop = Move::new_drop(PieceType::Knight, Square::new(5, 2).unwrap());
+        assert_eq!(drop.to_proto().from, None);
+        assert_eq!(Move::from_proto(&drop.to_proto()), Some(drop));
+        assert_ne!(drop, Move::new(drop.from, drop.to));
+    }
+
+    #[test]
+    fn test_metadata_round_trips_and_is_ignored_by_eq() {
+        let mut mv = Move::new(Square::new(4, 4).unwrap(), Square::new(3, 5).unwrap());
+        mv.piece = Some(PieceType::Pawn);
+        mv.capture = Some(PieceType::Pawn);
+        mv.en_passant = true;
+        let decoded = Move::from_proto(&mv.to_proto()).unwrap();
+        assert_eq!(decoded.piece, Some(PieceType::Pawn));
+        assert!(decoded.is_capture() && decoded.en_passant);
+        assert_eq!(decoded.castling, None);
+        assert_eq!(decoded, Move::new(mv.from, mv.to));
+    }
+
+    #[test]
+    fn test_move_error_display() {
+        let err = MoveError::NoPiece(Square::new(4, 3).unwrap());
+        assert_eq!(err.to_string(), "no piece on e4");
+    }
+}
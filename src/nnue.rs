@@ -0,0 +1,332 @@
+// NNUE evaluation: a small neural network whose first layer is updated
+// incrementally as the search makes and unmakes moves.
+//
+// The network is the common perspective design 768 -> N (x2) -> 1. Each side has
+// an accumulator of N hidden values over 768 inputs, one per piece seen from that
+// side: own or enemy, piece type, and square, flipped vertically for Black. The
+// output layer takes both accumulators, the side to move's first, through a
+// clipped ReLU. Files hold the raw little-endian i16 values in the order bullet's
+// simple trainer writes them:
+//
+//   feature weights [768][N], feature biases [N], output weights [2N], output bias
+//
+// padded with zeros to a multiple of 64 bytes; N follows from the file length.
+// Hidden values are quantized by QA = 255 and output weights by QB = 64, and the
+// output is scaled by 400 to centipawns.
+
+use crate::bitboard::PieceBitboards;
+use crate::board::Board;
+use crate::pieces::{Color, PieceType};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Input features per perspective: 2 colors x 6 piece types x 64 squares.
+pub const INPUTS: usize = 768;
+
+const QA: i32 = 255;
+const QB: i32 = 64;
+const SCALE: i32 = 400;
+
+/// The piece types in input order within each color's block.
+pub(crate) const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::Pawn,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Queen,
+    PieceType::King,
+];
+
+/// Why a network could not be read.
+#[derive(Debug)]
+pub enum NnueError {
+    Io(io::Error),
+    /// The data is not the length of any 768 -> N -> 1 network.
+    Size(usize),
+}
+
+impl fmt::Display for NnueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NnueError::Io(e) => write!(f, "{}", e),
+            NnueError::Size(len) => write!(f, "network length {} does not match any hidden layer size", len),
+        }
+    }
+}
+
+impl std::error::Error for NnueError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NnueError::Io(e) => Some(e),
+            NnueError::Size(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for NnueError {
+    fn from(e: io::Error) -> Self {
+        NnueError::Io(e)
+    }
+}
+
+/// A loaded network.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Network {
+    hidden: usize,
+    /// `INPUTS` rows of `hidden` weights.
+    feature_weights: Vec<i16>,
+    feature_bias: Vec<i16>,
+    /// Side to move's half, then the other side's.
+    output_weights: Vec<i16>,
+    output_bias: i16,
+}
+
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Network").field("hidden", &self.hidden).finish()
+    }
+}
+
+impl Network {
+    /// Read a network file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Network, NnueError> {
+        Network::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Read a network from any reader.
+    pub fn read<R: Read>(mut input: R) -> Result<Network, NnueError> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        // INPUTS * N + N + 2 * N + 1 values of two bytes, then under 64 bytes of padding.
+        let per_hidden = 2 * (INPUTS + 3);
+        let hidden = data.len().saturating_sub(2) / per_hidden;
+        let used = hidden * per_hidden + 2;
+        if hidden == 0 || data.len() - used >= 64 {
+            return Err(NnueError::Size(data.len()));
+        }
+        let mut values = data.chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]]));
+        let mut take = |n: usize| values.by_ref().take(n).collect::<Vec<_>>();
+        Ok(Network {
+            hidden,
+            feature_weights: take(INPUTS * hidden),
+            feature_bias: take(hidden),
+            output_weights: take(2 * hidden),
+            output_bias: take(1)[0],
+        })
+    }
+
+    /// Number of hidden values per accumulator.
+    pub fn hidden_size(&self) -> usize {
+        self.hidden
+    }
+
+    /// Score of `board` in centipawns from the side to move's point of view,
+    /// computing the accumulators from scratch.
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        self.output(&self.refresh(board.bitboards()), board.current_player())
+    }
+
+    fn refresh(&self, pieces: &PieceBitboards) -> Accumulator {
+        let mut accumulator = Accumulator {
+            values: [self.feature_bias.clone(), self.feature_bias.clone()],
+        };
+        for color in [Color::White, Color::Black] {
+            for piece_type in PIECE_TYPES {
+                for square in pieces.pieces(piece_type, color).squares() {
+                    let square = square.index() as usize;
+                    self.add_feature(&mut accumulator, color, piece_type, square);
+                }
+            }
+        }
+        accumulator
+    }
+
+    fn add_feature(&self, accumulator: &mut Accumulator, color: Color, piece_type: PieceType, square: usize) {
+        for (perspective, values) in [Color::White, Color::Black].into_iter().zip(&mut accumulator.values) {
+            let row = &self.feature_weights[feature(perspective, color, piece_type, square) * self.hidden..][..self.hidden];
+            for (value, weight) in values.iter_mut().zip(row) {
+                *value = value.wrapping_add(*weight);
+            }
+        }
+    }
+
+    fn remove_feature(&self, accumulator: &mut Accumulator, color: Color, piece_type: PieceType, square: usize) {
+        for (perspective, values) in [Color::White, Color::Black].into_iter().zip(&mut accumulator.values) {
+            let row = &self.feature_weights[feature(perspective, color, piece_type, square) * self.hidden..][..self.hidden];
+            for (value, weight) in values.iter_mut().zip(row) {
+                *value = value.wrapping_sub(*weight);
+            }
+        }
+    }
+
+    fn output(&self, accumulator: &Accumulator, side_to_move: Color) -> i32 {
+        let (us, them) = match side_to_move {
+            Color::White => (&accumulator.values[0], &accumulator.values[1]),
+            Color::Black => (&accumulator.values[1], &accumulator.values[0]),
+        };
+        let (our_weights, their_weights) = self.output_weights.split_at(self.hidden);
+        let layer = |values: &[i16], weights: &[i16]| -> i32 {
+            values.iter().zip(weights).map(|(&v, &w)| i32::from(v).clamp(0, QA) * i32::from(w)).sum()
+        };
+        let sum = layer(us, our_weights) + layer(them, their_weights) + i32::from(self.output_bias);
+        sum * SCALE / (QA * QB)
+    }
+}
+
+/// Input index of a piece seen from `perspective`.
+pub(crate) fn feature(perspective: Color, color: Color, piece_type: PieceType, square: usize) -> usize {
+    let square = match perspective {
+        Color::White => square,
+        Color::Black => square ^ 56,
+    };
+    let type_index = match piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+    usize::from(color != perspective) * 384 + type_index * 64 + square
+}
+
+/// Hidden values from White's and from Black's side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Accumulator {
+    values: [Vec<i16>; 2],
+}
+
+/// Accumulators along the search path, one per ply, each with the placement it
+/// was computed for. A move's update is the difference between placements, so
+/// captures, promotions, castling, and variant side effects need no special
+/// cases.
+#[derive(Debug, Clone)]
+pub(crate) struct NnueStack {
+    network: Arc<Network>,
+    // Entries past `len` are kept to reuse their buffers.
+    entries: Vec<(PieceBitboards, Accumulator)>,
+    len: usize,
+}
+
+impl NnueStack {
+    pub(crate) fn new(network: Arc<Network>, board: &Board) -> Self {
+        let accumulator = network.refresh(board.bitboards());
+        NnueStack {
+            network,
+            entries: vec![(board.bitboards().clone(), accumulator)],
+            len: 1,
+        }
+    }
+
+    /// Update for a move just made on `board`.
+    pub(crate) fn push(&mut self, board: &Board) {
+        if self.len == self.entries.len() {
+            let top = self.entries[self.len - 1].clone();
+            self.entries.push(top);
+        }
+        let (done, rest) = self.entries.split_at_mut(self.len);
+        let (before, previous) = &done[self.len - 1];
+        let (placement, next) = &mut rest[0];
+        for (values, previous) in next.values.iter_mut().zip(&previous.values) {
+            values.clone_from(previous);
+        }
+        let after = board.bitboards();
+        for color in [Color::White, Color::Black] {
+            for piece_type in PIECE_TYPES {
+                let (old, new) = (before.pieces(piece_type, color), after.pieces(piece_type, color));
+                for square in (old & !new).squares() {
+                    self.network.remove_feature(next, color, piece_type, square.index() as usize);
+                }
+                for square in (new & !old).squares() {
+                    self.network.add_feature(next, color, piece_type, square.index() as usize);
+                }
+            }
+        }
+        placement.clone_from(after);
+        self.len += 1;
+    }
+
+    /// Go back to the position before the last pushed move.
+    pub(crate) fn pop(&mut self) {
+        self.len = (self.len - 1).max(1);
+    }
+
+    /// Score of the current position from `side_to_move`'s point of view.
+    pub(crate) fn evaluate(&self, side_to_move: Color) -> i32 {
+        self.network.output(&self.entries[self.len - 1].1, side_to_move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, EvalBackend, SearchLimits};
+    use crate::moves::Move;
+
+    /// A network of the given size with pseudo-random weights, as file bytes.
+    fn random_network(hidden: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let count = INPUTS * hidden + hidden + 2 * hidden + 1;
+        let mut data = Vec::new();
+        for _ in 0..count {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            data.extend(((state % 61) as i16 - 30).to_le_bytes());
+        }
+        data.resize(data.len().next_multiple_of(64), 0);
+        data
+    }
+
+    #[test]
+    fn test_hidden_size_follows_from_length() {
+        let network = Network::read(random_network(16).as_slice()).unwrap();
+        assert_eq!(network.hidden_size(), 16);
+        assert!(matches!(Network::read(&[0u8; 100][..]), Err(NnueError::Size(100))));
+        let mut long = random_network(16);
+        long.extend([0; 64]);
+        assert!(matches!(Network::read(long.as_slice()), Err(NnueError::Size(_))));
+    }
+
+    #[test]
+    fn test_incremental_updates_match_a_refresh() {
+        let network = Arc::new(Network::read(random_network(8).as_slice()).unwrap());
+        // Castling, a capture, en passant, and a promotion with capture.
+        let fen = "r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut stack = NnueStack::new(Arc::clone(&network), &board);
+        for uci in ["e5d6", "e8g8", "e1c1", "g8g7", "b7a8q"] {
+            let mv = Move::from_uci(uci, &board).unwrap();
+            board.make_move(mv);
+            stack.push(&board);
+            assert_eq!(stack.evaluate(board.current_player()), network.evaluate(&board), "after {}", uci);
+        }
+        for _ in 0..5 {
+            board.unmake_move();
+            stack.pop();
+        }
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(stack.evaluate(board.current_player()), network.evaluate(&board));
+    }
+
+    #[test]
+    fn test_engine_searches_with_a_network_file() {
+        let path = std::env::temp_dir().join(format!("rchess-nnue-test-{}.bin", std::process::id()));
+        std::fs::write(&path, random_network(4)).unwrap();
+        let mut engine = Engine::new();
+        assert!(engine.set_eval(EvalBackend::Nnue(path.with_extension("missing"))).is_err());
+        engine.set_eval(EvalBackend::Nnue(path.clone())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(engine.network().map(Network::hidden_size), Some(4));
+
+        // Mate is found whatever the network thinks of the position.
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = engine.best_move(&board, SearchLimits::depth(3));
+        assert_eq!(result.best_move.map(|mv| mv.to_uci()), Some("a1a8".to_string()));
+        engine.set_eval(EvalBackend::Classical).unwrap();
+        assert!(engine.network().is_none());
+    }
+}
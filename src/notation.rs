@@ -0,0 +1,332 @@
+// Move notations: Standard Algebraic Notation (SAN) and UCI long algebraic.
+// SAN is relative to a position: disambiguation and check suffixes depend on the board.
+
+use crate::board::Board;
+use crate::moves::Move;
+use crate::pieces::{PieceType, Square};
+use std::fmt;
+
+/// Why a move string could not be turned into a move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    /// The text is not syntactically valid in the notation.
+    Invalid(String),
+    /// No legal move in the position matches.
+    NoMatch(String),
+    /// Several legal moves match and the text does not say which.
+    Ambiguous(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::Invalid(s) => write!(f, "invalid move notation: {}", s),
+            NotationError::NoMatch(s) => write!(f, "no legal move matches {}", s),
+            NotationError::Ambiguous(s) => write!(f, "ambiguous move {}", s),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+impl Move {
+    /// Format this move in SAN for the position it is played from, e.g. "Nbd2",
+    /// "exd6", "O-O", "e8=Q+". The move is assumed to be legal.
+    pub fn to_san(&self, board: &Board) -> String {
+        let mut after = board.clone();
+        after.make_move(*self);
+        san_with_suffix(san_body(self, board), &after)
+    }
+
+    /// Parse a SAN move in the context of `board`. Check and annotation suffixes
+    /// ("+", "#", "!", "?") are ignored, and castling may use letter O or digit 0.
+    /// Drops are written "N@f3"; a pawn drop may leave out the "P".
+    pub fn from_san(text: &str, board: &Board) -> Result<Move, NotationError> {
+        let invalid = || NotationError::Invalid(text.to_string());
+        let san = text.trim().trim_end_matches(['+', '#', '!', '?']);
+        let legal = board.legal_moves(board.current_player());
+
+        if let Some((piece, square)) = san.split_once('@') {
+            let mut chars = piece.chars();
+            let piece_type = match (chars.next(), chars.next()) {
+                (None, _) => PieceType::Pawn,
+                (Some(c), None) if c.is_ascii_uppercase() => PieceType::from_letter(c).ok_or_else(invalid)?,
+                _ => return Err(invalid()),
+            };
+            let to = Square::from_algebraic(square).ok_or_else(invalid)?;
+            let mv = Move::new_drop(piece_type, to);
+            return legal
+                .into_iter()
+                .find(|legal| *legal == mv)
+                .ok_or_else(|| NotationError::NoMatch(text.to_string()));
+        }
+
+        let castle_file = match san {
+            "O-O" | "0-0" => Some(6),
+            "O-O-O" | "0-0-0" => Some(2),
+            _ => None,
+        };
+        if let Some(file) = castle_file {
+            return legal
+                .into_iter()
+                .find(|mv| mv.castling.is_some() && mv.to.file == file)
+                .ok_or_else(|| NotationError::NoMatch(text.to_string()));
+        }
+
+        // Split off the promotion piece: "e8=Q", or the informal "e8Q".
+        let (body, promotion) = match san.split_once('=') {
+            Some((body, piece)) => {
+                let mut chars = piece.chars();
+                match (chars.next().and_then(PieceType::from_letter), chars.next()) {
+                    (Some(p), None) => (body, Some(p)),
+                    _ => return Err(invalid()),
+                }
+            }
+            None => match san.char_indices().last() {
+                Some((i, c)) if c.is_ascii_uppercase() => {
+                    (&san[..i], Some(PieceType::from_letter(c).ok_or_else(invalid)?))
+                }
+                _ => (san, None),
+            },
+        };
+
+        if body.len() < 2 || !body.is_ascii() {
+            return Err(invalid());
+        }
+        let to = Square::from_algebraic(&body[body.len() - 2..]).ok_or_else(invalid)?;
+        let prefix = &body[..body.len() - 2];
+        let (piece_type, qualifiers) = match prefix.chars().next() {
+            Some(c) if c.is_ascii_uppercase() => {
+                (PieceType::from_letter(c).ok_or_else(invalid)?, &prefix[1..])
+            }
+            _ => (PieceType::Pawn, prefix),
+        };
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in qualifiers.trim_end_matches('x').chars() {
+            match c {
+                'a'..='h' if from_file.is_none() => from_file = Some(c as u8 - b'a'),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let mut matches = legal.into_iter().filter(|mv| {
+            mv.to == to
+                && mv.promotion == promotion
+                && mv.piece == Some(piece_type)
+                && from_file.is_none_or(|f| mv.from.file == f)
+                && from_rank.is_none_or(|r| mv.from.rank == r)
+        });
+        match (matches.next(), matches.next()) {
+            (Some(mv), None) => Ok(mv),
+            (Some(_), Some(_)) => Err(NotationError::Ambiguous(text.to_string())),
+            (None, _) => Err(NotationError::NoMatch(text.to_string())),
+        }
+    }
+}
+
+impl Move {
+    /// Format in UCI long algebraic notation: "e2e4", "e7e8q". Castling is the king's
+    /// two-square move ("e1g1"), and a drop names the piece: "N@f3".
+    pub fn to_uci(&self) -> String {
+        if let Some(piece_type) = self.drop {
+            return format!("{}@{}", piece_type.letter(), self.to.to_algebraic());
+        }
+        let mut uci = format!("{}{}", self.from.to_algebraic(), self.to.to_algebraic());
+        if let Some(promotion) = self.promotion {
+            uci.push(promotion.letter().to_ascii_lowercase());
+        }
+        uci
+    }
+
+    /// Parse UCI long algebraic notation without looking at a board, so the move
+    /// may be illegal.
+    pub(crate) fn parse_uci(text: &str) -> Option<Move> {
+        let uci = text.trim();
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return None;
+        }
+        if uci.len() == 4 && uci.as_bytes()[1] == b'@' {
+            let piece_type = uci.chars().next().and_then(PieceType::from_letter)?;
+            return Some(Move::new_drop(piece_type, Square::from_algebraic(&uci[2..4])?));
+        }
+        let from = Square::from_algebraic(&uci[0..2])?;
+        let to = Square::from_algebraic(&uci[2..4])?;
+        let promotion = match uci[4..].chars().next() {
+            Some(c) => Some(PieceType::from_letter(c)?),
+            None => None,
+        };
+        Some(Move { promotion, ..Move::new(from, to) })
+    }
+
+    /// Parse UCI long algebraic notation and check that it is legal on `board`.
+    pub fn from_uci(text: &str, board: &Board) -> Result<Move, NotationError> {
+        let mv = Move::parse_uci(text).ok_or_else(|| NotationError::Invalid(text.to_string()))?;
+        board
+            .legal_moves(board.current_player())
+            .into_iter()
+            .find(|legal| *legal == mv)
+            .ok_or_else(|| NotationError::NoMatch(text.to_string()))
+    }
+}
+
+/// Type of the piece a move picks up, before any promotion.
+fn moved_type(mv: &Move, board: &Board) -> Option<PieceType> {
+    board.piece_at_typed(mv.from).map(|(piece_type, _)| piece_type)
+}
+
+/// `san` with "+" or "#" appended when the side to move in `after`, the position
+/// the move led to, is in check or mated.
+pub(crate) fn san_with_suffix(mut san: String, after: &Board) -> String {
+    let opponent = after.current_player();
+    if after.is_in_check(opponent) {
+        san.push(if after.legal_moves(opponent).is_empty() { '#' } else { '+' });
+    }
+    san
+}
+
+/// SAN without the check or mate suffix.
+pub(crate) fn san_body(mv: &Move, board: &Board) -> String {
+    if let Some(piece_type) = mv.drop {
+        return format!("{}@{}", piece_type.letter(), mv.to.to_algebraic());
+    }
+    let Some(piece_type) = moved_type(mv, board) else {
+        return String::new();
+    };
+    if piece_type == PieceType::King && mv.from.file.abs_diff(mv.to.file) == 2 {
+        return if mv.to.file > mv.from.file { "O-O" } else { "O-O-O" }.to_string();
+    }
+
+    let mut san = String::new();
+    // A pawn moving diagonally always captures, including en passant.
+    let capture = board.piece_at(mv.to).is_some()
+        || (piece_type == PieceType::Pawn && mv.from.file != mv.to.file);
+    let file = |sq: Square| (b'a' + sq.file) as char;
+    let rank = |sq: Square| (b'1' + sq.rank) as char;
+
+    if piece_type == PieceType::Pawn {
+        if capture {
+            san.push(file(mv.from));
+        }
+    } else {
+        san.push(piece_type.letter());
+        // Name the origin file, else rank, else both, when another piece of the
+        // same type could also reach the target.
+        let rivals: Vec<Square> = board
+            .legal_moves(board.current_player())
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to && other.from != mv.from && moved_type(other, board) == Some(piece_type)
+            })
+            .map(|other| other.from)
+            .collect();
+        if !rivals.is_empty() {
+            let file_clash = rivals.iter().any(|sq| sq.file == mv.from.file);
+            let rank_clash = rivals.iter().any(|sq| sq.rank == mv.from.rank);
+            if !file_clash {
+                san.push(file(mv.from));
+            } else if !rank_clash {
+                san.push(rank(mv.from));
+            } else {
+                san.push(file(mv.from));
+                san.push(rank(mv.from));
+            }
+        }
+    }
+
+    if capture {
+        san.push('x');
+    }
+    san.push_str(&mv.to.to_algebraic());
+    if let Some(promotion) = mv.promotion {
+        san.push('=');
+        san.push(promotion.letter());
+    }
+    san
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san_moves(board: &Board) -> Vec<String> {
+        board
+            .legal_moves(board.current_player())
+            .iter()
+            .map(|mv| mv.to_san(board))
+            .collect()
+    }
+
+    #[test]
+    fn test_san_round_trip_all_legal_moves() {
+        let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        for mv in board.legal_moves(board.current_player()) {
+            let san = mv.to_san(&board);
+            assert_eq!(Move::from_san(&san, &board), Ok(mv), "{}", san);
+        }
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        // Knights on b1 and f3 both reach d2; rooks on e1 and e3 both reach e2.
+        let board = Board::from_fen("k7/8/8/8/8/4RN2/8/1N2R1K1 w - - 0 1").unwrap();
+        let moves = san_moves(&board);
+        assert!(moves.contains(&"Nbd2".to_string()));
+        assert!(moves.contains(&"Nfd2".to_string()));
+        assert!(moves.contains(&"R1e2".to_string()));
+        assert!(moves.contains(&"R3e2".to_string()));
+        assert_eq!(Move::from_san("Nd2", &board), Err(NotationError::Ambiguous("Nd2".to_string())));
+    }
+
+    #[test]
+    fn test_castling_promotion_and_mate() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let moves = san_moves(&board);
+        assert!(moves.contains(&"O-O".to_string()));
+        assert!(moves.contains(&"O-O-O".to_string()));
+        assert_eq!(Move::from_san("0-0", &board).unwrap().to, Square::from_algebraic("g1").unwrap());
+
+        let board = Board::from_fen("7k/P7/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+        let promotion = Move::from_san("a8=Q", &board).unwrap();
+        assert_eq!(promotion.promotion, Some(PieceType::Queen));
+        assert_eq!(promotion.to_san(&board), "a8=Q#");
+        assert_eq!(Move::from_san("a8N", &board).unwrap().to_san(&board), "a8=N");
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(Move::from_san("Ra8", &board).unwrap().to_san(&board), "Ra8+");
+    }
+
+    #[test]
+    fn test_uci_round_trip() {
+        let board = Board::starting_position();
+        for mv in board.legal_moves(board.current_player()) {
+            assert_eq!(Move::from_uci(&mv.to_uci(), &board), Ok(mv));
+        }
+        let board = Board::from_fen("7k/P7/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+        let mv = Move::from_uci("a7a8n", &board).unwrap();
+        assert_eq!(mv.promotion, Some(PieceType::Knight));
+        assert_eq!(mv.to_uci(), "a7a8n");
+    }
+
+    #[test]
+    fn test_uci_validation() {
+        let board = Board::starting_position();
+        assert_eq!(Move::from_uci("e2e5", &board), Err(NotationError::NoMatch("e2e5".to_string())));
+        assert_eq!(Move::from_uci("e7e5", &board), Err(NotationError::NoMatch("e7e5".to_string())));
+        assert_eq!(Move::from_uci("e2", &board), Err(NotationError::Invalid("e2".to_string())));
+        assert_eq!(Move::from_uci("e2e4x", &board), Err(NotationError::Invalid("e2e4x".to_string())));
+        // A promotion piece on a non-promoting move is not a legal move.
+        assert!(Move::from_uci("e2e4q", &board).is_err());
+    }
+
+    #[test]
+    fn test_pawn_captures() {
+        let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let mv = Move::from_san("exd6", &board).unwrap();
+        assert_eq!(mv.to_san(&board), "exd6");
+        assert_eq!(Move::from_san("e4", &board), Err(NotationError::NoMatch("e4".to_string())));
+        assert_eq!(Move::from_san("Zz9", &board), Err(NotationError::Invalid("Zz9".to_string())));
+    }
+}
@@ -0,0 +1,173 @@
+// Change notifications for a Game. A UI subscribes a callback and is told what
+// each move did, a capture, a promotion, a check, the end of the game, as it
+// happens, instead of comparing whole positions to find out. A move reports
+// MovePlayed first and then its consequences, in the order of the variants.
+
+use crate::game::GameEvent;
+use crate::moves::Move;
+use crate::pieces::{Color, PieceType, Square};
+use crate::status::GameStatus;
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+
+/// Something a subscriber to [`Game::subscribe`](crate::game::Game::subscribe)
+/// is told about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameUpdate {
+    /// A move was played, with its metadata filled in as by
+    /// [`Board::annotate_move`](crate::board::Board::annotate_move).
+    MovePlayed { mv: Move, san: String, mover: Color },
+    /// `color` lost the piece on `square`; for en passant, the square the
+    /// pawn stood on rather than the one the capturer moved to.
+    Capture { piece: PieceType, color: Color, square: Square },
+    Promotion { piece: PieceType, square: Square },
+    /// `color`'s king is in check.
+    Check(Color),
+    /// `color`'s move took their clock below the game's low-time threshold;
+    /// see [`Game::set_low_time`](crate::game::Game::set_low_time).
+    ClockLow { color: Color, remaining_ms: u64 },
+    /// A draw offer, resignation, or adjudication was recorded.
+    Event(GameEvent),
+    /// The game is over, by the move or event just reported.
+    GameEnded(GameStatus),
+}
+
+/// Names a subscription, to cancel it with
+/// [`Game::unsubscribe`](crate::game::Game::unsubscribe).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Listener = Box<dyn FnMut(&GameUpdate) + Send>;
+
+/// A game's subscribers. Cloning a game does not copy them: the callbacks
+/// belong to whoever is watching the original.
+#[derive(Default)]
+pub(crate) struct Listeners {
+    next_id: u64,
+    // The mutexes keep Game Sync for callbacks that are only Send; they are
+    // only ever reached through &mut, so never contended.
+    listeners: Vec<(SubscriptionId, Mutex<Listener>)>,
+}
+
+impl Listeners {
+    pub(crate) fn add(&mut self, listener: Listener) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.listeners.push((id, Mutex::new(listener)));
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: SubscriptionId) -> bool {
+        let before = self.listeners.len();
+        self.listeners.retain(|(subscription, _)| *subscription != id);
+        self.listeners.len() != before
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    /// Tell every subscriber, in the order they subscribed.
+    pub(crate) fn notify(&mut self, update: &GameUpdate) {
+        for (_, listener) in &mut self.listeners {
+            (listener.get_mut().unwrap_or_else(PoisonError::into_inner))(update);
+        }
+    }
+}
+
+impl Clone for Listeners {
+    fn clone(&self) -> Self {
+        Listeners::default()
+    }
+}
+
+impl fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Listeners({})", self.listeners.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::game::Game;
+    use std::sync::{Arc, Mutex};
+
+    fn recorder(game: &mut Game) -> (SubscriptionId, Arc<Mutex<Vec<GameUpdate>>>) {
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&updates);
+        let id = game.subscribe(move |update| sink.lock().unwrap().push(update.clone()));
+        (id, updates)
+    }
+
+    fn play(game: &mut Game, san: &str) {
+        let mv = Move::from_san(san, game.board()).unwrap();
+        game.play(mv).unwrap();
+    }
+
+    #[test]
+    fn test_moves_report_captures_promotions_and_checks() {
+        let mut game = Game::from_position(Board::from_fen("3rk3/2P5/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap());
+        let (_, updates) = recorder(&mut game);
+        play(&mut game, "exd6");
+        play(&mut game, "Kf7");
+        play(&mut game, "cxd8=N+");
+
+        let updates = updates.lock().unwrap();
+        let squares = |name| Square::from_algebraic(name).unwrap();
+        assert!(matches!(&updates[0], GameUpdate::MovePlayed { mv, san, mover: Color::White } if mv.en_passant && san == "exd6"));
+        assert_eq!(updates[1], GameUpdate::Capture { piece: PieceType::Pawn, color: Color::Black, square: squares("d5") });
+        assert!(matches!(&updates[2], GameUpdate::MovePlayed { mover: Color::Black, .. }));
+        assert_eq!(
+            updates[4..],
+            [
+                GameUpdate::Capture { piece: PieceType::Rook, color: Color::Black, square: squares("d8") },
+                GameUpdate::Promotion { piece: PieceType::Knight, square: squares("d8") },
+                GameUpdate::Check(Color::Black),
+            ]
+        );
+        assert_eq!(updates.len(), 7);
+    }
+
+    #[test]
+    fn test_game_end_events_and_unsubscribe() {
+        let mut game = Game::new();
+        let (id, updates) = recorder(&mut game);
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            play(&mut game, san);
+        }
+        let mate = GameUpdate::GameEnded(GameStatus::Checkmate { winner: Color::Black });
+        assert_eq!(updates.lock().unwrap().last(), Some(&mate));
+        assert!(game.unsubscribe(id));
+        assert!(!game.unsubscribe(id));
+
+        let mut resigned = Game::new();
+        let (_, headlines) = recorder(&mut resigned);
+        resigned.clone().resign(Color::Black).unwrap();
+        resigned.resign(Color::White).unwrap();
+        assert_eq!(
+            *headlines.lock().unwrap(),
+            [
+                GameUpdate::Event(GameEvent::Resigned(Color::White)),
+                GameUpdate::GameEnded(GameStatus::Resignation { winner: Color::Black }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clock_low_fires_once_per_crossing() {
+        use crate::clock::{Clock, TimeControl};
+
+        let mut game = Game::new();
+        game.set_clock(Some(Clock::new(TimeControl::Classical { base_ms: 60_000, moves: 0 })));
+        game.set_low_time(Some(30_000));
+        let (_, updates) = recorder(&mut game);
+        for (san, elapsed_ms) in [("e4", 20_000), ("e5", 1_000), ("Nf3", 15_000), ("Nc6", 1_000), ("Bc4", 5_000)] {
+            let mv = Move::from_san(san, game.board()).unwrap();
+            game.play_timed(mv, elapsed_ms).unwrap();
+        }
+        let low: Vec<_> = updates.lock().unwrap().iter().filter(|u| matches!(u, GameUpdate::ClockLow { .. })).cloned().collect();
+        assert_eq!(low, [GameUpdate::ClockLow { color: Color::White, remaining_ms: 25_000 }]);
+    }
+}
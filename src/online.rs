@@ -0,0 +1,150 @@
+// Online tablebase probing through the lichess tablebase API, for endgames the
+// local Syzygy files cannot answer (none loaded, or too many pieces for them).
+//
+// A probe is one blocking HTTP request, so the engine only makes it at the root.
+// Answers are cached by position; requests closer together than the minimum
+// interval are refused rather than queued, and the caller falls back to searching.
+
+use crate::board::Board;
+use crate::moves::Move;
+use crate::tablebase::Wdl;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The lichess endpoint for standard chess.
+pub const LICHESS_ENDPOINT: &str = "https://tablebase.lichess.ovh/standard";
+
+/// Most pieces, kings included, the lichess tables cover.
+pub const MAX_PIECES: usize = 7;
+
+/// Shortest time between two requests unless configured otherwise.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A client for a lichess-compatible tablebase server.
+#[derive(Debug)]
+pub struct OnlineTablebase {
+    endpoint: String,
+    interval: Duration,
+    /// Answers by FEN; None where the server had no result to give.
+    cache: Mutex<HashMap<String, Option<(Move, Wdl)>>>,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl Default for OnlineTablebase {
+    fn default() -> Self {
+        OnlineTablebase::new(LICHESS_ENDPOINT)
+    }
+}
+
+impl OnlineTablebase {
+    /// A client for the server at `endpoint`, which takes the position as its
+    /// "fen" query parameter.
+    pub fn new(endpoint: &str) -> Self {
+        OnlineTablebase {
+            endpoint: endpoint.to_string(),
+            interval: DEFAULT_INTERVAL,
+            cache: Mutex::new(HashMap::new()),
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Make requests at most once per `interval`.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Whether the server can answer for `board`: a standard chess position
+    /// with few enough pieces and no castling rights.
+    pub fn covers(&self, board: &Board) -> bool {
+        let rights = board.castling_rights();
+        board.variant().name() == "standard"
+            && board.bitboards().occupied().count() as usize <= MAX_PIECES
+            && !(rights.white_kingside || rights.white_queenside || rights.black_kingside || rights.black_queenside)
+    }
+
+    /// The server's best move for `board` and the result it keeps, as
+    /// [`Tablebase::best_move`](crate::tablebase::Tablebase::best_move) gives
+    /// them. `None` if the position is not covered, the request is refused by
+    /// the rate limit or fails, or the server does not know the result.
+    pub fn best_move(&self, board: &Board) -> Option<(Move, Wdl)> {
+        if !self.covers(board) {
+            return None;
+        }
+        let fen = board.to_fen();
+        if let Some(&answer) = self.cache.lock().unwrap_or_else(|e| e.into_inner()).get(&fen) {
+            return answer;
+        }
+        if !self.allow_request() {
+            return None;
+        }
+        let body = ureq::get(&self.endpoint).query("fen", &fen).timeout(TIMEOUT).call().ok()?.into_string().ok()?;
+        let answer = parse_response(&body, board);
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).insert(fen, answer);
+        answer
+    }
+
+    /// Take the next request slot if the interval since the last one has passed.
+    fn allow_request(&self) -> bool {
+        let mut last = self.last_request.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        if last.is_some_and(|last| now.duration_since(last) < self.interval) {
+            return false;
+        }
+        *last = Some(now);
+        true
+    }
+}
+
+/// The first (best) move of a server response and the position's category,
+/// both checked against `board`. Categories the server is unsure of, such as
+/// "maybe-win" or "unknown", give `None`.
+fn parse_response(body: &str, board: &Board) -> Option<(Move, Wdl)> {
+    let response: Value = serde_json::from_str(body).ok()?;
+    let wdl = match response["category"].as_str()? {
+        "win" => Wdl::Win,
+        "cursed-win" => Wdl::CursedWin,
+        "draw" => Wdl::Draw,
+        "blessed-loss" => Wdl::BlessedLoss,
+        "loss" => Wdl::Loss,
+        _ => return None,
+    };
+    let mv = Move::from_uci(response["moves"][0]["uci"].as_str()?, board).ok()?;
+    Some((mv, wdl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_parse_response() {
+        let board = Board::from_fen("4k3/8/4K3/8/8/8/8/7R w - - 0 1").unwrap();
+        let body = r#"{"category":"win","dtz":1,"dtm":1,"moves":[{"uci":"h1h8","san":"Rh8#","category":"loss"}]}"#;
+        assert_eq!(parse_response(body, &board), Some((Move::new(sq!("h1"), sq!("h8")), Wdl::Win)));
+        assert_eq!(parse_response(r#"{"category":"maybe-win","moves":[{"uci":"h1h8"}]}"#, &board), None);
+        assert_eq!(parse_response(r#"{"category":"win","moves":[{"uci":"h1a2"}]}"#, &board), None);
+        assert_eq!(parse_response("not json", &board), None);
+    }
+
+    #[test]
+    fn test_cache_and_rate_limit() {
+        // Nothing listens on the discard port, so only cached answers come back.
+        let online = OnlineTablebase::new("http://127.0.0.1:9").with_interval(Duration::from_secs(60));
+        let board = Board::from_fen("4k3/8/4K3/8/8/8/8/7R w - - 0 1").unwrap();
+        assert!(online.covers(&board));
+        assert!(!online.covers(&Board::starting_position()));
+
+        let answer = Some((Move::new(sq!("h1"), sq!("h8")), Wdl::Win));
+        online.cache.lock().unwrap().insert(board.to_fen(), answer);
+        assert_eq!(online.best_move(&board), answer);
+
+        assert!(online.allow_request());
+        assert!(!online.allow_request());
+    }
+}
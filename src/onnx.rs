@@ -0,0 +1,116 @@
+// ONNX evaluation: a value network in an ONNX file, run with tract.
+//
+// The model takes one float input of shape [1, 12, 8, 8]: twelve planes of the
+// side to move's pieces then the opponent's, each pawn, knight, bishop, rook,
+// queen, king, indexed by rank then file and flipped vertically when Black is
+// to move, so the side to move always plays up the board. These are the same
+// 768 inputs as the NNUE network's. Its first output is the side to move's
+// expected score in [-1, 1], mapped to centipawns as 400 * atanh(v).
+//
+// Unlike NNUE nothing is updated incrementally: every leaf runs the whole model.
+
+use crate::board::Board;
+use crate::nnue::{INPUTS, PIECE_TYPES, feature};
+use crate::pieces::Color;
+use std::fmt;
+use std::path::Path;
+use tract_onnx::prelude::*;
+
+const SCALE: f32 = 400.0;
+
+/// Outputs are clamped to this before scaling, keeping scores below the
+/// tablebase and mate scores.
+const MAX_VALUE: f32 = 0.999;
+
+/// Why a model could not be loaded.
+#[derive(Debug)]
+pub struct OnnxError(TractError);
+
+impl fmt::Display for OnnxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for OnnxError {}
+
+impl From<TractError> for OnnxError {
+    fn from(e: TractError) -> Self {
+        OnnxError(e)
+    }
+}
+
+/// A loaded and optimized value network.
+pub struct OnnxModel {
+    plan: TypedRunnableModel<TypedModel>,
+}
+
+impl fmt::Debug for OnnxModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OnnxModel").finish_non_exhaustive()
+    }
+}
+
+impl OnnxModel {
+    /// Load the model at `path`, checking that it accepts the input planes and
+    /// gives a value for the starting position.
+    pub fn open(path: impl AsRef<Path>) -> Result<OnnxModel, OnnxError> {
+        let plan = tract_onnx::onnx()
+            .model_for_path(path)?
+            .with_input_fact(0, f32::fact([1, 12, 8, 8]).into())?
+            .into_optimized()?
+            .into_runnable()?;
+        let model = OnnxModel { plan };
+        model.value(&Board::starting_position())?;
+        Ok(model)
+    }
+
+    /// Score of `board` in centipawns from the side to move's point of view.
+    /// Falls back to the classical evaluation if the model fails to run.
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        match self.value(board) {
+            Ok(value) => (value.clamp(-MAX_VALUE, MAX_VALUE).atanh() * SCALE).round() as i32,
+            Err(_) => crate::eval::evaluate(board),
+        }
+    }
+
+    fn value(&self, board: &Board) -> TractResult<f32> {
+        let input = Tensor::from_shape(&[1, 12, 8, 8], &planes(board))?;
+        let outputs = self.plan.run(tvec!(input.into()))?;
+        let value = outputs.first().map(|output| output.as_slice::<f32>()).transpose()?;
+        value.and_then(|values| values.first().copied()).ok_or_else(|| TractError::msg("model has no output value"))
+    }
+}
+
+/// The model input for `board`: 1.0 where the plane's piece stands.
+pub fn planes(board: &Board) -> Vec<f32> {
+    let perspective = board.current_player();
+    let mut planes = vec![0.0; INPUTS];
+    for color in [Color::White, Color::Black] {
+        for piece_type in PIECE_TYPES {
+            for square in board.bitboards().pieces(piece_type, color).squares() {
+                planes[feature(perspective, color, piece_type, square.index() as usize)] = 1.0;
+            }
+        }
+    }
+    planes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_planes_are_from_the_side_to_move() {
+        let white = Board::starting_position();
+        let black = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(planes(&white), planes(&black));
+
+        let planes = planes(&white);
+        assert_eq!(planes.iter().filter(|&&value| value == 1.0).count(), 32);
+        // Own king on e1 in plane 5; the opponent's on e8 in plane 11.
+        assert_eq!(planes[5 * 64 + 4], 1.0);
+        assert_eq!(planes[11 * 64 + 60], 1.0);
+        assert!(OnnxModel::open("no-such-model.onnx").is_err());
+    }
+}
@@ -0,0 +1,119 @@
+// Opening classification: ECO code and name of the line a game follows.
+//
+// The database in openings/eco.tsv lists one line per row, tab-separated: ECO
+// code, name, and the moves in SAN from the starting position. Lines are matched
+// by the position they reach, ignoring move counters and the en-passant square,
+// so transpositions into a known line are recognised; a game is classified by
+// the last position along it that the database knows.
+
+use crate::board::Board;
+use crate::game::Game;
+use crate::moves::Move;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const ECO_TSV: &str = include_str!("openings/eco.tsv");
+
+/// A named opening line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opening {
+    /// ECO code, "A00" to "E99".
+    pub eco: &'static str,
+    /// Family and variation, e.g. "Sicilian Defense: Najdorf Variation".
+    pub name: &'static str,
+    /// The defining moves, e.g. "1. e4 c5 2. c3".
+    pub moves: &'static str,
+}
+
+static OPENINGS: OnceLock<HashMap<String, Opening>> = OnceLock::new();
+
+fn openings() -> &'static HashMap<String, Opening> {
+    OPENINGS.get_or_init(|| {
+        let mut openings = HashMap::new();
+        for row in ECO_TSV.lines().skip(1) {
+            let mut fields = row.split('\t');
+            let (Some(eco), Some(name), Some(moves)) = (fields.next(), fields.next(), fields.next()) else {
+                continue;
+            };
+            let mut board = Board::starting_position();
+            for san in moves.split_whitespace().filter(|token| !token.ends_with('.')) {
+                let mv = Move::from_san(san, &board).unwrap_or_else(|e| panic!("eco.tsv {}: {}", eco, e));
+                board.make_move(mv);
+            }
+            openings.entry(position_key(&board)).or_insert(Opening { eco, name, moves });
+        }
+        openings
+    })
+}
+
+/// The placement, side to move, and castling fields of the FEN.
+fn position_key(board: &Board) -> String {
+    board.to_fen().split(' ').take(3).collect::<Vec<_>>().join(" ")
+}
+
+/// The opening whose line ends in exactly this position, if any.
+pub fn lookup(board: &Board) -> Option<Opening> {
+    openings().get(&position_key(board)).copied()
+}
+
+/// The opening of the last position along `moves` from `start` that the
+/// database knows.
+pub fn classify_moves(start: &Board, moves: &[Move]) -> Option<Opening> {
+    let mut board = start.clone();
+    let mut opening = lookup(&board);
+    for &mv in moves {
+        board.make_move(mv);
+        opening = lookup(&board).or(opening);
+    }
+    opening
+}
+
+/// The opening of the game's main line.
+pub fn classify(game: &Game) -> Option<Opening> {
+    classify_moves(game.start_position(), game.moves())
+}
+
+impl Game {
+    /// Classify the game and set its "ECO" and "Opening" tags for PGN export.
+    /// Leaves the tags alone when the opening is not recognised.
+    pub fn tag_opening(&mut self) -> Option<Opening> {
+        let opening = classify(self)?;
+        self.set_tag("ECO", opening.eco);
+        self.set_tag("Opening", opening.name);
+        Some(opening)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(sans: &[&str]) -> Game {
+        let mut game = Game::new();
+        for san in sans {
+            let mv = Move::from_san(san, game.board()).unwrap();
+            game.play(mv).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn test_every_line_reaches_its_own_position() {
+        let rows = ECO_TSV.lines().skip(1).filter(|row| !row.is_empty()).count();
+        assert_eq!(openings().len(), rows);
+        assert_eq!(lookup(&Board::starting_position()), None);
+    }
+
+    #[test]
+    fn test_classify_follows_the_game_and_transpositions() {
+        let mut najdorf = play(&["e4", "c5", "Nf3", "d6", "d4", "cxd4", "Nxd4", "Nf6", "Nc3", "a6", "Be3"]);
+        let opening = najdorf.tag_opening().unwrap();
+        assert_eq!((opening.eco, opening.name), ("B90", "Sicilian Defense: Najdorf Variation"));
+        assert_eq!(najdorf.tag("ECO"), Some("B90"));
+
+        // The Queen's Gambit Declined reached from the English.
+        let english = play(&["c4", "e6", "Nc3", "d5", "d4"]);
+        assert_eq!(classify(&english).map(|opening| opening.eco), Some("D31"));
+        assert_eq!(classify(&play(&["a3"])), None);
+    }
+}
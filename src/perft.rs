@@ -0,0 +1,194 @@
+// Perft: count the leaf nodes of the legal move tree to a fixed depth.
+// Comparing against published counts is the standard check of move generation;
+// the standard positions and their counts ship in PERFT_SUITE so that a build
+// can check itself with verify_movegen. With the parallel feature the root
+// moves are counted on rayon's thread pool. The MoveStrategy types let the
+// same count run with copy-make or make/unmake, to compare their cost.
+
+use crate::board::Board;
+use crate::movegen::MoveList;
+use crate::moves::Move;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::fmt;
+
+/// A standard perft position and its published node counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerftCase {
+    pub name: &'static str,
+    pub fen: &'static str,
+    /// Node counts by depth, starting at depth 1.
+    pub nodes: &'static [u64],
+}
+
+/// The positions from https://www.chessprogramming.org/Perft_Results.
+pub const PERFT_SUITE: &[PerftCase] = &[
+    PerftCase {
+        name: "initial position",
+        fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        nodes: &[20, 400, 8_902, 197_281],
+    },
+    PerftCase {
+        // Castling, en passant, promotions, and pins all at once.
+        name: "Kiwipete",
+        fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        nodes: &[48, 2_039, 97_862, 4_085_603],
+    },
+    PerftCase {
+        // Rook endgame with en-passant discovered checks along the rank.
+        name: "position 3",
+        fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        nodes: &[14, 191, 2_812, 43_238, 674_624],
+    },
+    PerftCase {
+        name: "position 4",
+        fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        nodes: &[6, 264, 9_467, 422_333],
+    },
+    PerftCase {
+        name: "position 5",
+        fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        nodes: &[44, 1_486, 62_379, 2_103_487],
+    },
+    PerftCase {
+        name: "position 6",
+        fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        nodes: &[46, 2_079, 89_890, 3_894_594],
+    },
+];
+
+/// How a tree walk moves from a position to a child and back.
+pub trait MoveStrategy {
+    /// Run `visit` on the position after `mv`, leaving `board` as it was.
+    /// `mv` must be legal in `board`.
+    fn descend<R>(board: &mut Board, mv: Move, visit: impl FnOnce(&mut Board) -> R) -> R;
+}
+
+/// Copy the board for every child; the parent is never modified.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CopyMake;
+
+/// Make the move in place and take it back afterwards, as the engine does.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MakeUnmake;
+
+impl MoveStrategy for CopyMake {
+    fn descend<R>(board: &mut Board, mv: Move, visit: impl FnOnce(&mut Board) -> R) -> R {
+        let mut child = board.clone();
+        child.make_move(mv);
+        visit(&mut child)
+    }
+}
+
+impl MoveStrategy for MakeUnmake {
+    fn descend<R>(board: &mut Board, mv: Move, visit: impl FnOnce(&mut Board) -> R) -> R {
+        board.make_move(mv);
+        let result = visit(board);
+        board.unmake_move();
+        result
+    }
+}
+
+/// A suite position whose count at some depth is not the published one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerftMismatch {
+    pub name: &'static str,
+    pub depth: u32,
+    pub expected: u64,
+    pub found: u64,
+}
+
+impl fmt::Display for PerftMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at depth {}: expected {} nodes, found {}", self.name, self.depth, self.expected, self.found)
+    }
+}
+
+/// Run every PERFT_SUITE position to `max_depth`, or as deep as it has counts
+/// for, and report each depth whose count is wrong. Depth 3 takes well under a
+/// second in a release build; the deepest counts take minutes.
+pub fn verify_movegen(max_depth: u32) -> Result<(), Vec<PerftMismatch>> {
+    let mut mismatches = Vec::new();
+    for case in PERFT_SUITE {
+        let board = Board::from_fen(case.fen).expect("suite FENs are valid");
+        for (depth, &expected) in (1..=max_depth).zip(case.nodes) {
+            let found = board.perft(depth);
+            if found != expected {
+                mismatches.push(PerftMismatch { name: case.name, depth, expected, found });
+            }
+        }
+    }
+    if mismatches.is_empty() { Ok(()) } else { Err(mismatches) }
+}
+
+impl Board {
+    /// Number of legal move sequences of length `depth` from this position.
+    pub fn perft(&self, depth: u32) -> u64 {
+        #[cfg(feature = "parallel")]
+        if depth > 1 {
+            return self.perft_divide(depth).iter().map(|(_, nodes)| nodes).sum();
+        }
+        count::<MakeUnmake>(&mut self.clone(), depth)
+    }
+
+    /// Perft on one thread, walking the tree with strategy `S`.
+    pub fn perft_with<S: MoveStrategy>(&self, depth: u32) -> u64 {
+        count::<S>(&mut self.clone(), depth)
+    }
+
+    /// Perft split by root move, in the order `legal_moves` returns them.
+    /// Useful for locating a move generation bug by diffing against another engine.
+    pub fn perft_divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        let moves = self.legal_moves(self.current_player());
+        let divide = |&mv: &Move| {
+            let mut board = self.clone();
+            board.make_move(mv);
+            (mv, count::<MakeUnmake>(&mut board, depth - 1))
+        };
+        #[cfg(feature = "parallel")]
+        return moves.par_iter().map(divide).collect();
+        #[cfg(not(feature = "parallel"))]
+        moves.iter().map(divide).collect()
+    }
+}
+
+fn count<S: MoveStrategy>(board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut moves = MoveList::new();
+    board.generate_into(board.current_player(), &mut moves);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves
+        .iter()
+        .map(|mv| S::descend(board, mv, |child| count::<S>(child, depth - 1)))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_divide_sums_to_perft() {
+        let board = Board::starting_position();
+        let divide = board.perft_divide(2);
+        assert_eq!(divide.len(), 20);
+        assert!(divide.iter().all(|(_, nodes)| *nodes == 20));
+        assert_eq!(divide.iter().map(|(_, n)| n).sum::<u64>(), board.perft(2));
+        assert_eq!(board.perft(0), 1);
+        assert!(board.perft_divide(0).is_empty());
+    }
+
+    #[test]
+    fn test_strategies_agree() {
+        let board = Board::from_fen(PERFT_SUITE[1].fen).unwrap();
+        assert_eq!(board.perft_with::<CopyMake>(2), 2_039);
+        assert_eq!(board.perft_with::<MakeUnmake>(2), 2_039);
+    }
+}
@@ -0,0 +1,721 @@
+// PGN (Portable Game Notation) import and export.
+// Parses tag pairs and SAN movetext, with variations, comments, and NAGs, into Game records.
+// Evals, clock times, and other node annotations travel as "[%name value]"
+// commands inside comments, as Lichess and ChessBase write them.
+
+use crate::board::Board;
+use crate::engine::{MATE_SCORE, mate_distance, mate_moves};
+use crate::fen::{FenError, STARTING_FEN};
+use crate::game::{Game, GameResult};
+use crate::game_tree::{GameTree, NodeId};
+use crate::moves::Move;
+use crate::notation::NotationError;
+use crate::pieces::Color;
+use crate::status::GameStatus;
+use std::fmt;
+
+/// Line width `Game::to_pgn` wraps movetext at; the PGN standard recommends under 80.
+pub const DEFAULT_LINE_WIDTH: usize = 79;
+
+/// The Seven Tag Roster, in the order PGN requires, with the value used when unset.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// Why a PGN text could not be imported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    /// The text contains no game.
+    Empty,
+    /// A tag pair is not of the form `[Name "value"]`.
+    InvalidTag(String),
+    /// A `{` comment or `(` variation is never closed.
+    Unterminated(char),
+    /// The FEN tag does not describe a position.
+    InvalidFen(FenError),
+    /// A movetext move is not legal; `ply` counts from 1 for the first move of the game.
+    IllegalMove { ply: usize, san: String, error: NotationError },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::Empty => write!(f, "no game found"),
+            PgnError::InvalidTag(tag) => write!(f, "invalid tag pair: {}", tag),
+            PgnError::Unterminated(c) => write!(f, "unterminated '{}'", c),
+            PgnError::InvalidFen(e) => write!(f, "invalid FEN tag: {}", e),
+            PgnError::IllegalMove { ply, san, error } => {
+                write!(f, "ply {} ({}): {}", ply, san, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<FenError> for PgnError {
+    fn from(e: FenError) -> Self {
+        PgnError::InvalidFen(e)
+    }
+}
+
+/// Lexical pieces of PGN text.
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Tag(&'a str, String),
+    San(&'a str),
+    Comment(&'a str),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    Result(GameResult),
+}
+
+/// Suffix annotations and the NAGs they stand for.
+const SUFFIX_NAGS: [(&str, u8); 6] = [("!", 1), ("?", 2), ("!!", 3), ("??", 4), ("!?", 5), ("?!", 6)];
+
+/// Parse every game in a PGN database.
+pub fn parse_games(text: &str) -> Result<Vec<Game>, PgnError> {
+    let mut games = Vec::new();
+    let mut tags: Vec<(&str, String)> = Vec::new();
+    let mut movetext: Vec<Token> = Vec::new();
+
+    for token in tokenize(text)? {
+        match token {
+            // A tag after movetext starts the next game, even if the result was omitted.
+            Token::Tag(name, value) => {
+                if !movetext.is_empty() {
+                    games.push(build_game(&tags, &movetext, GameResult::Ongoing)?);
+                    tags.clear();
+                    movetext.clear();
+                }
+                tags.push((name, value));
+            }
+            Token::Result(result) => {
+                games.push(build_game(&tags, &movetext, result)?);
+                tags.clear();
+                movetext.clear();
+            }
+            other => movetext.push(other),
+        }
+    }
+    if !tags.is_empty() || !movetext.is_empty() {
+        games.push(build_game(&tags, &movetext, GameResult::Ongoing)?);
+    }
+    Ok(games)
+}
+
+impl Game {
+    /// Parse the first game of a PGN text.
+    pub fn from_pgn(text: &str) -> Result<Game, PgnError> {
+        parse_games(text)?.into_iter().next().ok_or(PgnError::Empty)
+    }
+
+    /// Export as PGN with movetext wrapped at [`DEFAULT_LINE_WIDTH`].
+    pub fn to_pgn(&self) -> String {
+        self.to_pgn_with_width(DEFAULT_LINE_WIDTH)
+    }
+
+    /// Export as PGN: the Seven Tag Roster, any other tags, then numbered SAN
+    /// movetext ending in the result. Draw offers, resignations, and adjudications
+    /// are written as comments after the move they followed, and a game that ended
+    /// off the board gets a Termination tag. A `width` of 0 keeps the movetext on
+    /// one line.
+    pub fn to_pgn_with_width(&self, width: usize) -> String {
+        self.write_pgn(width, &[])
+    }
+
+    /// PGN export with the tags in `overrides` replacing or adding to the game's own.
+    fn write_pgn(&self, width: usize, overrides: &[(&str, &str)]) -> String {
+        let tag = |name: &str| {
+            overrides.iter().find(|(n, _)| *n == name).map(|&(_, value)| value).or_else(|| self.tag(name))
+        };
+        let mut tags: Vec<(&str, String)> = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|&(name, default)| (name, tag(name).unwrap_or(default).to_string()))
+            .collect();
+        tags[6].1 = self.result().to_pgn().to_string();
+        let start_fen = self.start_position().to_fen();
+        if start_fen != STARTING_FEN && self.tag("FEN").is_none() {
+            tags.push(("SetUp", "1".to_string()));
+            tags.push(("FEN", start_fen));
+        }
+        let own = self.tags().iter().map(|(name, value)| (name.as_str(), value.as_str()));
+        for (name, value) in own.chain(overrides.iter().copied()) {
+            if !tags.iter().any(|(n, _)| *n == name) {
+                tags.push((name, tag(name).unwrap_or(value).to_string()));
+            }
+        }
+        let termination = match self.game_status() {
+            GameStatus::Resignation { .. } | GameStatus::DrawAgreed => Some("normal"),
+            GameStatus::TimeForfeit { .. } | GameStatus::TimeoutDraw => Some("time forfeit"),
+            GameStatus::Adjudicated { .. } => Some("adjudication"),
+            _ => None,
+        };
+        if let Some(termination) = termination
+            && self.tag("Termination").is_none()
+        {
+            tags.push(("Termination", termination.to_string()));
+        }
+
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, escaped));
+        }
+        pgn.push('\n');
+
+        let tree = self.tree();
+        let mainline = tree.mainline();
+        let notes: Vec<(NodeId, String)> = self
+            .events()
+            .iter()
+            .map(|(ply, event)| (ply.checked_sub(1).map_or(tree.root(), |i| mainline[i]), event.to_string()))
+            .collect();
+        let mut words = Vec::new();
+        if let Some(comment) = node_comment(tree, tree.root()) {
+            words.push(format!("{{{}}}", comment));
+        }
+        words.extend(notes_at(&notes, tree.root()));
+        if let Some(&first) = tree.node(tree.root()).children().first() {
+            write_line(tree, first, self.start_position(), &notes, &mut words);
+        }
+        words.push(self.result().to_pgn().to_string());
+
+        let mut line_len = 0;
+        for word in words {
+            if line_len > 0 {
+                if width > 0 && line_len + 1 + word.len() > width {
+                    pgn.push('\n');
+                    line_len = 0;
+                } else {
+                    pgn.push(' ');
+                    line_len += 1;
+                }
+            }
+            line_len += word.len();
+            pgn.push_str(&word);
+        }
+        pgn.push('\n');
+        pgn
+    }
+}
+
+/// One chapter of a study: its name and the game it shows, sidelines included.
+#[derive(Clone, Copy)]
+pub struct StudyChapter<'a> {
+    pub name: &'a str,
+    pub game: &'a Game,
+}
+
+/// Export a study as one PGN game per chapter, the way Lichess exports and
+/// imports studies: each chapter's Event is "Study: Chapter", and the board
+/// faces the side to move at the start of the chapter.
+pub fn study_to_pgn(study: &str, chapters: &[StudyChapter]) -> String {
+    chapters
+        .iter()
+        .map(|chapter| {
+            let event = format!("{}: {}", study, chapter.name);
+            let orientation = match chapter.game.start_position().current_player() {
+                Color::White => "white",
+                Color::Black => "black",
+            };
+            chapter.game.write_pgn(DEFAULT_LINE_WIDTH, &[("Event", &event), ("Orientation", orientation)])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The comment written after `node`: its eval, clock time, and other
+/// annotations as `[%name value]` commands, then its comment text.
+fn node_comment(tree: &GameTree, node: NodeId) -> Option<String> {
+    let node = tree.node(node);
+    let mut parts = Vec::new();
+    if let Some(eval) = node.eval().and_then(format_eval) {
+        parts.push(format!("[%eval {}]", eval));
+    }
+    if let Some(ms) = node.clock_ms() {
+        parts.push(format!("[%clk {}]", format_clock(ms)));
+    }
+    for (key, value) in node.annotations() {
+        parts.push(format!("[%{} {}]", key, value));
+    }
+    parts.extend(node.comment().map(str::to_string));
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// An eval in pawns ("0.35", "-1.20"), or "#3" / "#-2" for mates in moves.
+/// `None` once the game is mate, which has no distance left to write.
+fn format_eval(eval: i32) -> Option<String> {
+    if mate_distance(eval) == Some(0) {
+        return None;
+    }
+    Some(match mate_moves(eval) {
+        Some(moves) => format!("#{}", moves),
+        None => format!("{:.2}", f64::from(eval) / 100.0),
+    })
+}
+
+/// Inverse of [`format_eval`]. A mate in n moves becomes the score of a mate
+/// in 2n - 1 plies for the winner, or 2n plies for the loser.
+fn parse_eval(text: &str) -> Option<i32> {
+    if let Some(moves) = text.strip_prefix('#') {
+        let moves: i32 = moves.parse().ok().filter(|&moves| moves != 0)?;
+        let plies = if moves > 0 { moves.checked_mul(2)? - 1 } else { moves.checked_mul(-2)? };
+        let score = MATE_SCORE.checked_sub(plies).filter(|&score| mate_distance(score).is_some())?;
+        return Some(if moves > 0 { score } else { -score });
+    }
+    let pawns: f64 = text.parse().ok().filter(|pawns: &f64| pawns.is_finite())?;
+    Some((pawns * 100.0).round() as i32)
+}
+
+/// Clock time as H:MM:SS, with tenths when the time is not whole seconds.
+fn format_clock(ms: u64) -> String {
+    let (hours, minutes, seconds) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60);
+    match ms % 1000 {
+        0 => format!("{}:{:02}:{:02}", hours, minutes, seconds),
+        rest => format!("{}:{:02}:{:02}.{}", hours, minutes, seconds, rest / 100),
+    }
+}
+
+/// Parse H:MM:SS or H:MM:SS.s into milliseconds.
+fn parse_clock(text: &str) -> Option<u64> {
+    let mut fields = text.split(':');
+    let (hours, minutes, seconds) = (fields.next()?, fields.next()?, fields.next()?);
+    if fields.next().is_some() {
+        return None;
+    }
+    let minutes = hours.parse::<u64>().ok()?.checked_mul(60)?.checked_add(minutes.parse().ok()?)?;
+    let seconds: f64 = seconds.parse().ok().filter(|s: &f64| s.is_finite() && *s >= 0.0)?;
+    minutes.checked_mul(60_000)?.checked_add((seconds * 1000.0).round() as u64)
+}
+
+/// `[%name value]` commands in a comment, and the text around them.
+fn split_commands(text: &str) -> (Vec<(&str, &str)>, String) {
+    let mut commands = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[%") {
+        let Some(len) = rest[start..].find(']') else {
+            break;
+        };
+        plain.push_str(&rest[..start]);
+        plain.push(' ');
+        let body = rest[start + 2..start + len].trim();
+        let (name, value) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+        commands.push((name, value.trim()));
+        rest = &rest[start + len + 1..];
+    }
+    plain.push_str(rest);
+    (commands, plain.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Comments for the game events that followed the move at `node`.
+fn notes_at<'a>(notes: &'a [(NodeId, String)], node: NodeId) -> impl Iterator<Item = String> + 'a {
+    notes.iter().filter(move |(at, _)| *at == node).map(|(_, note)| format!("{{{}}}", note))
+}
+
+/// Append movetext for the line starting at `first`, played from `board`, with the
+/// sidelines branching off it in parentheses and `notes` after their moves.
+fn write_line(tree: &GameTree, first: NodeId, board: &Board, notes: &[(NodeId, String)], words: &mut Vec<String>) {
+    let mut board = board.clone();
+    let mut node = first;
+    // Black's moves need "N..." when they do not directly follow White's move.
+    let mut need_number = true;
+    while let Some(mv) = tree.node(node).mv() {
+        let number = board.fullmove_number();
+        match board.current_player() {
+            Color::White => words.push(format!("{}.", number)),
+            Color::Black if need_number => words.push(format!("{}...", number)),
+            Color::Black => {}
+        }
+        words.push(mv.to_san(&board));
+        need_number = false;
+        for nag in tree.node(node).nags() {
+            words.push(format!("${}", nag));
+        }
+        if let Some(comment) = node_comment(tree, node) {
+            words.push(format!("{{{}}}", comment));
+            need_number = true;
+        }
+        let start = words.len();
+        words.extend(notes_at(notes, node));
+        need_number |= words.len() > start;
+
+        // Alternatives to a mainline move follow it, each played from the same position.
+        if let Some(parent) = tree.node(node).parent()
+            && tree.node(parent).children().first() == Some(&node)
+        {
+            for &variation in tree.variations(parent) {
+                let start = words.len();
+                write_line(tree, variation, &board, notes, words);
+                words[start].insert(0, '(');
+                if let Some(last) = words.last_mut() {
+                    last.push(')');
+                }
+                need_number = true;
+            }
+        }
+
+        board.make_move(mv);
+        match tree.node(node).children().first() {
+            Some(&next) => node = next,
+            None => break,
+        }
+    }
+}
+
+/// Replay the movetext on the start position described by the tags.
+fn build_game(tags: &[(&str, String)], movetext: &[Token], result: GameResult) -> Result<Game, PgnError> {
+    let start = match tags.iter().find(|(name, _)| *name == "FEN") {
+        Some((_, fen)) => Board::from_fen(fen)?,
+        None => Board::starting_position(),
+    };
+    let mut tree = GameTree::new(start.clone());
+    let mut current = tree.root();
+    let mut board = start;
+    // Where to resume when each open variation closes.
+    let mut open: Vec<(NodeId, Board)> = Vec::new();
+
+    for token in movetext {
+        match token {
+            Token::San(san) => {
+                let ply = tree.path(current).len() + 1;
+                let illegal = |error| PgnError::IllegalMove { ply, san: san.to_string(), error };
+                let mv = Move::from_san(san, &board).map_err(illegal)?;
+                current = tree.push_node(current, mv);
+                board.make_move(mv);
+            }
+            Token::Comment(text) => {
+                let (commands, text) = split_commands(text);
+                // Commands that do not parse are kept as plain annotations.
+                for (name, value) in commands {
+                    if name == "eval"
+                        && let Some(eval) = parse_eval(value)
+                    {
+                        tree.set_eval(current, Some(eval));
+                    } else if name == "clk"
+                        && let Some(ms) = parse_clock(value)
+                    {
+                        tree.set_clock_ms(current, Some(ms));
+                    } else {
+                        tree.set_annotation(current, name, value);
+                    }
+                }
+                if !text.is_empty() {
+                    let comment = match tree.node(current).comment() {
+                        Some(existing) => format!("{} {}", existing, text),
+                        None => text,
+                    };
+                    tree.set_comment(current, Some(comment));
+                }
+            }
+            Token::Nag(nag) => tree.add_nag(current, *nag),
+            // A variation replaces the move just played.
+            Token::VariationStart => {
+                open.push((current, board.clone()));
+                current = tree.node(current).parent().unwrap_or(current);
+                board = tree.board_at(current);
+            }
+            Token::VariationEnd => {
+                if let Some((node, resume)) = open.pop() {
+                    current = node;
+                    board = resume;
+                }
+            }
+            Token::Tag(..) | Token::Result(_) => {}
+        }
+    }
+    if !open.is_empty() {
+        return Err(PgnError::Unterminated('('));
+    }
+
+    let mut game = Game::from_tree(tree);
+    for (name, value) in tags {
+        game.set_tag(name, value);
+    }
+    // An explicit Result tag wins over a missing or "*" terminator.
+    let tagged = game.tag("Result").and_then(GameResult::from_pgn);
+    game.set_result(match (result, tagged) {
+        (GameResult::Ongoing, Some(tagged)) => tagged,
+        _ => result,
+    });
+    Ok(game)
+}
+
+/// Split PGN text into tokens, dropping move numbers and escape lines.
+fn tokenize(text: &str) -> Result<Vec<Token<'_>>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '[' => {
+                let line = rest.lines().next().unwrap_or_default();
+                let end = rest.find(']').ok_or_else(|| PgnError::InvalidTag(line.to_string()))?;
+                tokens.push(parse_tag(&rest[..=end])?);
+                rest = &rest[end + 1..];
+            }
+            '{' => {
+                let end = rest.find('}').ok_or(PgnError::Unterminated('{'))?;
+                tokens.push(Token::Comment(rest[1..end].trim()));
+                rest = &rest[end + 1..];
+            }
+            // Rest-of-line comments, and "%" escape lines.
+            ';' | '%' => rest = rest.find('\n').map_or("", |end| &rest[end..]),
+            '(' | ')' => {
+                tokens.push(if c == '(' { Token::VariationStart } else { Token::VariationEnd });
+                rest = &rest[1..];
+            }
+            c if c.is_whitespace() => rest = &rest[c.len_utf8()..],
+            _ => {
+                let end = rest
+                    .find(|ch: char| ch.is_whitespace() || "[{;()".contains(ch))
+                    .unwrap_or(rest.len());
+                let word = &rest[..end];
+                rest = &rest[end..];
+                if let Some(result) = GameResult::from_pgn(word) {
+                    tokens.push(Token::Result(result));
+                    continue;
+                }
+                if let Some(nag) = word.strip_prefix('$') {
+                    tokens.extend(nag.parse().ok().map(Token::Nag));
+                    continue;
+                }
+                // Drop move numbers ("12." / "12..."), keeping any move glued to a
+                // number as in "12.e4", and turn "!" / "?" suffixes into NAGs.
+                let word = strip_move_number(word);
+                let san = word.trim_end_matches(['!', '?']);
+                if !san.is_empty() {
+                    tokens.push(Token::San(san));
+                }
+                let suffix = &word[san.len()..];
+                tokens.extend(SUFFIX_NAGS.iter().find(|(s, _)| *s == suffix).map(|&(_, nag)| Token::Nag(nag)));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// `word` without a leading move number ("12." or "12..."). The number needs
+/// its dot, so castling written with zeros ("0-0", "0-0-0") is left alone.
+fn strip_move_number(word: &str) -> &str {
+    let after_digits = word.trim_start_matches(|ch: char| ch.is_ascii_digit());
+    let after_dots = after_digits.trim_start_matches('.');
+    if after_digits.len() < word.len() && after_dots.len() < after_digits.len() { after_dots } else { word }
+}
+
+/// Parse `[Name "value"]`, unescaping `\"` and `\\` in the value.
+fn parse_tag(text: &str) -> Result<Token<'_>, PgnError> {
+    let invalid = || PgnError::InvalidTag(text.to_string());
+    let inner = text[1..text.len() - 1].trim();
+    let (name, quoted) = inner.split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let quoted = quoted.trim();
+    if name.is_empty() || quoted.len() < 2 || !quoted.starts_with('"') || !quoted.ends_with('"') {
+        return Err(invalid());
+    }
+    let mut value = String::new();
+    let mut chars = quoted[1..quoted.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next()),
+            _ => value.push(c),
+        }
+    }
+    Ok(Token::Tag(name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHOLARS_MATE: &str = r#"[Event "Casual Game"]
+[Site "?"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 e5 2. Bc4 {aiming at f7} Nc6 3. Qh5 Nf6?? (3... g6 4. Qf3) 4. Qxf7# 1-0
+"#;
+
+    #[test]
+    fn test_import_reconstructs_positions() {
+        let game = Game::from_pgn(SCHOLARS_MATE).unwrap();
+        assert_eq!(game.tag("White"), Some("Alice"));
+        assert_eq!(game.result(), GameResult::WhiteWins);
+        assert_eq!(game.moves().len(), 7);
+        assert_eq!(game.positions().len(), 8);
+        assert_eq!(
+            game.board().to_fen(),
+            "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4"
+        );
+        assert!(game.board().is_in_check(crate::pieces::Color::Black));
+    }
+
+    #[test]
+    fn test_multiple_games_and_fen_start() {
+        let text = format!(
+            "{}\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n[SetUp \"1\"]\n\n1.e4 Kd7 2.e5 $1 *\n",
+            SCHOLARS_MATE
+        );
+        let games = parse_games(&text).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].result(), GameResult::Ongoing);
+        assert_eq!(games[1].board().to_fen(), "8/3k4/8/4P3/8/8/8/4K3 b - - 0 2");
+    }
+
+    #[test]
+    fn test_castling_written_with_zeros() {
+        let game = Game::from_pgn("1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4.0-0 Nf6 5. d3 0-0 *").unwrap();
+        assert_eq!(game.moves().len(), 10);
+        assert_eq!(game.board().to_fen(), "r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQ1RK1 w - - 1 6");
+    }
+
+    #[test]
+    fn test_import_errors() {
+        assert_eq!(Game::from_pgn("").unwrap_err(), PgnError::Empty);
+        assert!(matches!(Game::from_pgn("[Event Casual]"), Err(PgnError::InvalidTag(_))));
+        assert_eq!(Game::from_pgn("1. e4 {oops").unwrap_err(), PgnError::Unterminated('{'));
+        assert!(matches!(
+            Game::from_pgn("1. e4 e5 2. Ke3 *"),
+            Err(PgnError::IllegalMove { ply: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let game = Game::from_pgn(SCHOLARS_MATE).unwrap();
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with(
+            "[Event \"Casual Game\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n"
+        ));
+        assert!(pgn.ends_with(
+            "\n\n1. e4 e5 2. Bc4 {aiming at f7} 2... Nc6 3. Qh5 Nf6 $4 (3... g6 4. Qf3) 4. Qxf7#\n1-0\n"
+        ));
+        let again = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(again.moves(), game.moves());
+        assert_eq!(again.tag("Black"), Some("Bob"));
+        assert_eq!(again.tags().len(), 7);
+    }
+
+    #[test]
+    fn test_export_from_position_and_wrapping() {
+        let start = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 7").unwrap();
+        let mut game = Game::from_position(start);
+        game.set_tag("Annotator", "A \"quoted\" name");
+        for san in ["Kd7", "e4", "Ke6", "e5"] {
+            let mv = Move::from_san(san, game.board()).unwrap();
+            game.play(mv).unwrap();
+        }
+        let pgn = game.to_pgn_with_width(12);
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 7\"]\n"));
+        assert!(pgn.contains("[Annotator \"A \\\"quoted\\\" name\"]"));
+        assert!(pgn.ends_with("\n\n7... Kd7 8.\ne4 Ke6 9. e5\n*\n"));
+        assert_eq!(Game::from_pgn(&pgn).unwrap().board().to_fen(), game.board().to_fen());
+    }
+
+    #[test]
+    fn test_export_records_offers_and_resignation() {
+        let mut game = Game::new();
+        for san in ["e4", "e5"] {
+            game.play(Move::from_san(san, game.board()).unwrap()).unwrap();
+            game.offer_draw(game.board().current_player().opposite()).unwrap();
+        }
+        game.decline_draw(Color::White).unwrap();
+        game.resign(Color::Black).unwrap();
+        let pgn = game.to_pgn_with_width(0);
+        assert!(pgn.contains("[Result \"1-0\"]\n[Termination \"normal\"]\n"));
+        assert!(pgn.ends_with(
+            "1. e4 {White offers a draw} 1... e5 {Black offers a draw} {White declines the draw} {Black resigns} 1-0\n"
+        ));
+    }
+
+    #[test]
+    fn test_variations_comments_and_nags() {
+        let pgn = "{Opening survey} 1. e4 $1 e5 (1... c5 {Sicilian} 2. Nf3 (2. Nc3) 2... d6) (1... e6) 2. Nf3?! *";
+        let game = Game::from_pgn(pgn).unwrap();
+        let tree = game.tree();
+        assert_eq!(game.moves().len(), 3);
+        assert_eq!(tree.node(tree.root()).comment(), Some("Opening survey"));
+
+        let mainline = tree.mainline();
+        assert_eq!(tree.node(mainline[0]).nags(), &[1]);
+        assert_eq!(tree.node(mainline[2]).nags(), &[6]);
+        let sidelines = tree.variations(mainline[0]);
+        assert_eq!(sidelines.len(), 2);
+        let c5 = sidelines[0];
+        assert_eq!(tree.node(c5).comment(), Some("Sicilian"));
+        let nf3 = tree.node(c5).children()[0];
+        assert_eq!(tree.variations(c5).len(), 1);
+        assert_eq!(tree.board_at(tree.node(nf3).children()[0]).fullmove_number(), 3);
+
+        let exported = game.to_pgn_with_width(0);
+        assert!(exported.ends_with(
+            "\n\n{Opening survey} 1. e4 $1 e5 (1... c5 {Sicilian} 2. Nf3 (2. Nc3) 2... d6) (1... e6) 2. Nf3 $6 *\n"
+        ));
+        let again = Game::from_pgn(&exported).unwrap();
+        assert_eq!(again.to_pgn_with_width(0), exported);
+    }
+
+    #[test]
+    fn test_eval_and_clock_commands() {
+        let pgn = "1. e4 { [%eval 0.3] [%clk 0:05:00] Best by test } e5 { [%eval #-2] [%clk 0:04:59.5] [%depth 18] } \
+                   2. Nf3 { [%eval mate] } *";
+        let game = Game::from_pgn(pgn).unwrap();
+        let tree = game.tree();
+        let [e4, e5, nf3] = tree.mainline()[..] else { panic!("three moves expected") };
+        assert_eq!(tree.node(e4).eval(), Some(30));
+        assert_eq!(tree.node(e4).clock_ms(), Some(300_000));
+        assert_eq!(tree.node(e4).comment(), Some("Best by test"));
+        assert_eq!(tree.node(e5).eval(), Some(-(MATE_SCORE - 4)));
+        assert_eq!(tree.node(e5).clock_ms(), Some(299_500));
+        assert_eq!(tree.node(e5).annotations(), &[("depth".to_string(), "18".to_string())]);
+        assert_eq!(tree.node(e5).comment(), None);
+        assert_eq!(tree.node(nf3).eval(), None);
+        assert_eq!(tree.node(nf3).annotations(), &[("eval".to_string(), "mate".to_string())]);
+
+        let exported = game.to_pgn_with_width(0);
+        assert!(exported.contains(
+            "1. e4 {[%eval 0.30] [%clk 0:05:00] Best by test} 1... e5 {[%eval #-2] [%clk 0:04:59.5] [%depth 18]}"
+        ));
+        assert_eq!(Game::from_pgn(&exported).unwrap().to_pgn_with_width(0), exported);
+    }
+
+    #[test]
+    fn test_study_export() {
+        let opening = Game::from_pgn("[White \"Alice\"] 1. e4 e5 (1... c5) *").unwrap();
+        let endgame = Game::from_position(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1").unwrap());
+        let pgn = study_to_pgn(
+            "Repertoire",
+            &[
+                StudyChapter { name: "Open games", game: &opening },
+                StudyChapter { name: "Pawn ending", game: &endgame },
+            ],
+        );
+        let games = parse_games(&pgn).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("Event"), Some("Repertoire: Open games"));
+        assert_eq!(games[0].tag("White"), Some("Alice"));
+        assert_eq!(games[0].tag("Orientation"), Some("white"));
+        assert_eq!(games[0].tree().variations(games[0].tree().mainline()[0]).len(), 1);
+        assert_eq!(games[1].tag("Event"), Some("Repertoire: Pawn ending"));
+        assert_eq!(games[1].tag("Orientation"), Some("black"));
+        assert_eq!(games[1].start_position().to_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn test_unbalanced_variation() {
+        assert_eq!(Game::from_pgn("1. e4 (1. d4 *").unwrap_err(), PgnError::Unterminated('('));
+    }
+
+    #[test]
+    fn test_tag_unescaping() {
+        let game = Game::from_pgn(r#"[Annotator "A \"quoted\" name"] *"#).unwrap();
+        assert_eq!(game.tag("Annotator"), Some(r#"A "quoted" name"#));
+    }
+}
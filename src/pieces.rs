@@ -1,8 +1,43 @@
 // Traits and business logic for chess pieces.
 // Piece structs wrap proto messages and implement the Piece trait.
 
+use crate::error::Error;
 use crate::rchess::v1::{self as proto};
 use std::fmt;
+use std::str::FromStr;
+
+/// A square, color, or piece type that could not be parsed from text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// What the text should have named, e.g. "square".
+    pub expected: &'static str,
+    pub text: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid {}: {:?}", self.expected, self.text)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn parse_error(expected: &'static str, text: &str) -> ParseError {
+    ParseError { expected, text: text.to_string() }
+}
+
+/// A square from its algebraic name, checked at compile time: `sq!("e4")`.
+#[macro_export]
+macro_rules! sq {
+    ($name:literal) => {
+        const {
+            match $crate::pieces::Square::from_algebraic($name) {
+                Some(square) => square,
+                None => panic!(concat!("invalid square: ", $name)),
+            }
+        }
+    };
+}
 
 /// Represents a square on the chessboard using file (column) and rank (row).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -13,7 +48,7 @@ pub struct Square {
 
 impl Square {
     /// Create a new square from file and rank (0-indexed).
-    pub fn new(file: u8, rank: u8) -> Option<Self> {
+    pub const fn new(file: u8, rank: u8) -> Option<Self> {
         if file <= 7 && rank <= 7 {
             Some(Square { file, rank })
         } else {
@@ -21,11 +56,33 @@ impl Square {
         }
     }
 
-    /// Create from a proto Position.
+    /// Create from an index 0..64, a1 = 0, b1 = 1, ..., h8 = 63.
+    pub const fn from_index(index: u8) -> Option<Self> {
+        if index < 64 {
+            Some(Square { file: index % 8, rank: index / 8 })
+        } else {
+            None
+        }
+    }
+
+    /// Index 0..64 of the square, a1 = 0, b1 = 1, ..., h8 = 63.
+    pub const fn index(&self) -> u8 {
+        self.rank * 8 + self.file
+    }
+
+    /// Create from a proto Position; None if its file or rank is outside 1..=8.
+    /// See `try_from_proto` for the reason.
     pub fn from_proto(pos: &proto::Position) -> Option<Self> {
-        let file = (pos.file as u8).saturating_sub(1); // proto file is 1-indexed
-        let rank = (pos.rank as u8).saturating_sub(1); // proto rank is 1-indexed
-        Square::new(file, rank)
+        Square::try_from_proto(pos).ok()
+    }
+
+    /// Create from a proto Position, rejecting files and ranks outside 1..=8
+    /// rather than clamping them.
+    pub fn try_from_proto(pos: &proto::Position) -> Result<Self, Error> {
+        if !(1..=8).contains(&pos.file) || !(1..=8).contains(&pos.rank) {
+            return Err(Error::InvalidSquare { file: pos.file, rank: pos.rank });
+        }
+        Ok(Square { file: pos.file as u8 - 1, rank: pos.rank as u8 - 1 })
     }
 
     /// Convert to proto Position.
@@ -38,6 +95,25 @@ impl Square {
         }
     }
 
+    /// The square `df` files and `dr` ranks away, if it is on the board.
+    pub fn offset(&self, df: i32, dr: i32) -> Option<Self> {
+        let file = self.file as i32 + df;
+        let rank = self.rank as i32 + dr;
+        if (0..=7).contains(&file) && (0..=7).contains(&rank) {
+            Square::new(file as u8, rank as u8)
+        } else {
+            None
+        }
+    }
+
+    /// Parse algebraic notation (e.g., "e4").
+    pub const fn from_algebraic(text: &str) -> Option<Self> {
+        match text.as_bytes() {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Square::new(*file - b'a', *rank - b'1'),
+            _ => None,
+        }
+    }
+
     /// Convert to algebraic notation (e.g., "e4").
     pub fn to_algebraic(&self) -> String {
         format!(
@@ -54,6 +130,15 @@ impl fmt::Display for Square {
     }
 }
 
+impl FromStr for Square {
+    type Err = ParseError;
+
+    /// Parse algebraic notation (e.g., "e4").
+    fn from_str(text: &str) -> Result<Self, ParseError> {
+        Square::from_algebraic(text).ok_or_else(|| parse_error("square", text))
+    }
+}
+
 /// Color of a piece.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Color {
@@ -70,15 +155,36 @@ impl Color {
         }
     }
 
+    /// Rank (0 = rank 1) this color's pawns start on. A pawn may advance two
+    /// squares from there and from nowhere else, whatever its proto `has_moved` says.
+    pub fn pawn_start_rank(&self) -> u8 {
+        match self {
+            Color::White => 1,
+            Color::Black => 6,
+        }
+    }
+
     /// Convert from proto Color.
+    #[deprecated(note = "reads unknown values as White; use `Color::try_from_proto`")]
     pub fn from_proto(proto_color: i32) -> Self {
+        Color::from_checked_proto(proto_color)
+    }
+
+    /// Convert from proto Color; UNSPECIFIED and unknown values are errors.
+    pub fn try_from_proto(proto_color: i32) -> Result<Self, Error> {
         match proto_color {
-            1 => Color::White,
-            2 => Color::Black,
-            _ => Color::White, // default
+            1 => Ok(Color::White),
+            2 => Ok(Color::Black),
+            _ => Err(Error::InvalidColor(proto_color)),
         }
     }
 
+    /// Convert a proto Color that has already been checked, as those of a board
+    /// from `Board::try_from_proto` have; anything else reads as White.
+    pub(crate) fn from_checked_proto(proto_color: i32) -> Self {
+        Color::try_from_proto(proto_color).unwrap_or(Color::White)
+    }
+
     /// Convert to proto Color.
     pub fn to_proto(&self) -> i32 {
         match self {
@@ -97,6 +203,19 @@ impl fmt::Display for Color {
     }
 }
 
+impl FromStr for Color {
+    type Err = ParseError;
+
+    /// Parse "white" or "black", or the FEN letters "w" and "b", in any case.
+    fn from_str(text: &str) -> Result<Self, ParseError> {
+        match text.to_ascii_lowercase().as_str() {
+            "white" | "w" => Ok(Color::White),
+            "black" | "b" => Ok(Color::Black),
+            _ => Err(parse_error("color", text)),
+        }
+    }
+}
+
 /// Piece type enum.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PieceType {
@@ -108,6 +227,81 @@ pub enum PieceType {
     Pawn,
 }
 
+impl PieceType {
+    /// Conventional material value in centipawns; the king counts as zero.
+    pub fn value(&self) -> i32 {
+        match self {
+            PieceType::King => 0,
+            PieceType::Queen => 900,
+            PieceType::Rook => 500,
+            PieceType::Bishop => 330,
+            PieceType::Knight => 320,
+            PieceType::Pawn => 100,
+        }
+    }
+
+    /// Create from the proto PieceType enum value; unspecified yields None.
+    pub fn from_proto(proto_type: i32) -> Option<Self> {
+        match proto_type {
+            1 => Some(PieceType::King),
+            2 => Some(PieceType::Queen),
+            3 => Some(PieceType::Rook),
+            4 => Some(PieceType::Bishop),
+            5 => Some(PieceType::Knight),
+            6 => Some(PieceType::Pawn),
+            _ => None,
+        }
+    }
+
+    /// Create from the proto PieceType enum value, with unspecified and unknown
+    /// values as errors.
+    pub fn try_from_proto(proto_type: i32) -> Result<Self, Error> {
+        PieceType::from_proto(proto_type).ok_or(Error::InvalidPieceType(proto_type))
+    }
+
+    /// Convert to the proto PieceType enum value.
+    pub fn to_proto(&self) -> i32 {
+        match self {
+            PieceType::King => 1,
+            PieceType::Queen => 2,
+            PieceType::Rook => 3,
+            PieceType::Bishop => 4,
+            PieceType::Knight => 5,
+            PieceType::Pawn => 6,
+        }
+    }
+
+    /// Whether a pawn may promote to this type.
+    pub fn is_promotion_target(&self) -> bool {
+        matches!(self, PieceType::Queen | PieceType::Rook | PieceType::Bishop | PieceType::Knight)
+    }
+
+    /// Uppercase letter used for this type in FEN and algebraic notation.
+    pub fn letter(&self) -> char {
+        match self {
+            PieceType::King => 'K',
+            PieceType::Queen => 'Q',
+            PieceType::Rook => 'R',
+            PieceType::Bishop => 'B',
+            PieceType::Knight => 'N',
+            PieceType::Pawn => 'P',
+        }
+    }
+
+    /// Parse a piece letter in either case.
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            'K' => Some(PieceType::King),
+            'Q' => Some(PieceType::Queen),
+            'R' => Some(PieceType::Rook),
+            'B' => Some(PieceType::Bishop),
+            'N' => Some(PieceType::Knight),
+            'P' => Some(PieceType::Pawn),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for PieceType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -121,13 +315,44 @@ impl fmt::Display for PieceType {
     }
 }
 
+impl FromStr for PieceType {
+    type Err = ParseError;
+
+    /// Parse a piece name such as "knight" or its letter, in any case.
+    fn from_str(text: &str) -> Result<Self, ParseError> {
+        let mut chars = text.chars();
+        if let (Some(letter), None) = (chars.next(), chars.next()) {
+            return PieceType::from_letter(letter).ok_or_else(|| parse_error("piece type", text));
+        }
+        [
+            PieceType::King,
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::Pawn,
+        ]
+        .into_iter()
+        .find(|piece_type| piece_type.to_string().eq_ignore_ascii_case(text))
+        .ok_or_else(|| parse_error("piece type", text))
+    }
+}
+
 /// Core trait for all chess pieces.
 pub trait Piece: fmt::Debug + Send + Sync {
     /// Return the color of the piece.
     fn color(&self) -> Color;
 
-    /// Return the current square the piece is on.
-    fn position(&self) -> Square;
+    /// Return the square the piece is on. Pieces from `try_from_proto_piece` or
+    /// a constructor always have one; a piece wrapped from an unchecked proto
+    /// may have none, or one off the board.
+    fn try_position(&self) -> Result<Square, Error>;
+
+    /// Return the square the piece is on, or a1 when it has none.
+    #[deprecated(note = "reads a missing or off-board position as a1; use `try_position`")]
+    fn position(&self) -> Square {
+        self.try_position().unwrap_or(Square { file: 0, rank: 0 })
+    }
 
     /// Return a type identifier for the piece.
     fn piece_type(&self) -> PieceType;
@@ -135,8 +360,8 @@ pub trait Piece: fmt::Debug + Send + Sync {
     /// Check if this piece can move to the given square (ignoring other pieces on the board).
     fn can_move_to(&self, target: Square) -> bool;
 
-    /// Get all valid moves for this piece given the current board state.
-    /// Considers piece blocking, pinning, check, castling legality, etc.
+    /// Get all pseudo-legal moves for this piece given the current board state.
+    /// Considers piece blocking but not pins or check; see `Board::legal_moves`.
     fn valid_moves(&self, board: &crate::board::Board) -> Vec<Square>;
 
     /// Check if a specific move to target is valid given the current board state.
@@ -150,6 +375,119 @@ pub trait Piece: fmt::Debug + Send + Sync {
     }
 }
 
+/// A typed piece of any kind, carrying its color and square.
+/// Dispatches the `Piece` methods statically, so no allocation is needed per piece.
+#[derive(Debug, Clone)]
+pub enum PieceKind {
+    King(King),
+    Queen(Queen),
+    Rook(Rook),
+    Bishop(Bishop),
+    Knight(Knight),
+    Pawn(Pawn),
+}
+
+impl PieceKind {
+    fn as_piece(&self) -> &dyn Piece {
+        match self {
+            PieceKind::King(k) => k,
+            PieceKind::Queen(q) => q,
+            PieceKind::Rook(r) => r,
+            PieceKind::Bishop(b) => b,
+            PieceKind::Knight(n) => n,
+            PieceKind::Pawn(p) => p,
+        }
+    }
+}
+
+impl Piece for PieceKind {
+    fn color(&self) -> Color {
+        self.as_piece().color()
+    }
+
+    fn try_position(&self) -> Result<Square, Error> {
+        self.as_piece().try_position()
+    }
+
+    fn piece_type(&self) -> PieceType {
+        self.as_piece().piece_type()
+    }
+
+    fn can_move_to(&self, target: Square) -> bool {
+        self.as_piece().can_move_to(target)
+    }
+
+    fn valid_moves(&self, board: &crate::board::Board) -> Vec<Square> {
+        self.as_piece().valid_moves(board)
+    }
+}
+
+/// Wrap a proto Piece in its typed piece struct, if it has a kind.
+#[deprecated(note = "an invalid color or position reads as White or a1; use `try_from_proto_piece`")]
+pub fn from_proto_piece(piece: &proto::Piece) -> Option<PieceKind> {
+    wrap_proto_piece(piece)
+}
+
+/// Wrap a proto Piece in its typed piece struct, checking that it has a kind,
+/// a color, and a position on the board.
+pub fn try_from_proto_piece(piece: &proto::Piece) -> Result<PieceKind, Error> {
+    let (color, position) = match piece.kind.as_ref().ok_or(Error::MissingField("piece.kind"))? {
+        proto::piece::Kind::King(k) => (k.color, &k.position),
+        proto::piece::Kind::Queen(q) => (q.color, &q.position),
+        proto::piece::Kind::Rook(r) => (r.color, &r.position),
+        proto::piece::Kind::Bishop(b) => (b.color, &b.position),
+        proto::piece::Kind::Knight(n) => (n.color, &n.position),
+        proto::piece::Kind::Pawn(p) => (p.color, &p.position),
+    };
+    Color::try_from_proto(color)?;
+    Square::try_from_proto(position.as_ref().ok_or(Error::MissingField("piece.position"))?)?;
+    wrap_proto_piece(piece).ok_or(Error::MissingField("piece.kind"))
+}
+
+/// Wrap a proto Piece without checking it, for pieces of an already-checked board.
+pub(crate) fn wrap_proto_piece(piece: &proto::Piece) -> Option<PieceKind> {
+    match piece.kind.as_ref()? {
+        proto::piece::Kind::King(k) => Some(PieceKind::King(King::from_proto(k.clone()))),
+        proto::piece::Kind::Queen(q) => Some(PieceKind::Queen(Queen::from_proto(q.clone()))),
+        proto::piece::Kind::Knight(n) => Some(PieceKind::Knight(Knight::from_proto(n.clone()))),
+        proto::piece::Kind::Bishop(b) => Some(PieceKind::Bishop(Bishop::from_proto(b.clone()))),
+        proto::piece::Kind::Pawn(p) => {
+            let pawn = Pawn::from_proto(p.clone());
+            // A promoted pawn keeps its proto kind but moves as its new type.
+            match pawn.promoted_to().filter(PieceType::is_promotion_target) {
+                Some(promoted) => {
+                    wrap_proto_piece(&new_proto_piece(promoted, pawn.color(), pawn.try_position().ok()?))
+                }
+                None => Some(PieceKind::Pawn(pawn)),
+            }
+        }
+        proto::piece::Kind::Rook(r) => Some(PieceKind::Rook(Rook::from_proto(r.clone()))),
+    }
+}
+
+/// The square of a proto position field, checked.
+fn square_of(position: Option<&proto::Position>) -> Result<Square, Error> {
+    Square::try_from_proto(position.ok_or(Error::MissingField("piece.position"))?)
+}
+
+/// Build a proto Piece of the given type and color standing unmoved on `square`.
+pub fn new_proto_piece(piece_type: PieceType, color: Color, square: Square) -> proto::Piece {
+    let kind = match piece_type {
+        PieceType::King => proto::piece::Kind::King(King::new(color, square).to_proto()),
+        PieceType::Queen => proto::piece::Kind::Queen(Queen::new(color, square).to_proto()),
+        PieceType::Rook => proto::piece::Kind::Rook(Rook::new(color, square).to_proto()),
+        PieceType::Bishop => proto::piece::Kind::Bishop(
+            Bishop::new(color, square, BishopSquareColor::of(square)).to_proto(),
+        ),
+        PieceType::Knight => proto::piece::Kind::Knight(Knight::new(color, square).to_proto()),
+        PieceType::Pawn => proto::piece::Kind::Pawn(Pawn::new(color, square).to_proto()),
+    };
+    proto::Piece {
+        kind: Some(kind),
+        ..Default::default()
+    }
+}
+
 /// King piece wrapping proto::King.
 #[derive(Debug, Clone)]
 pub struct King {
@@ -186,15 +524,11 @@ impl King {
 
 impl Piece for King {
     fn color(&self) -> Color {
-        Color::from_proto(self.inner.color)
+        Color::from_checked_proto(self.inner.color)
     }
 
-    fn position(&self) -> Square {
-        self.inner
-            .position
-            .as_ref()
-            .and_then(Square::from_proto)
-            .unwrap_or_else(|| Square::new(0, 0).unwrap())
+    fn try_position(&self) -> Result<Square, Error> {
+        square_of(self.inner.position.as_ref())
     }
 
     fn piece_type(&self) -> PieceType {
@@ -202,7 +536,9 @@ impl Piece for King {
     }
 
     fn can_move_to(&self, target: Square) -> bool {
-        let pos = self.position();
+        let Ok(pos) = self.try_position() else {
+            return false;
+        };
         let file_diff = (pos.file as i32 - target.file as i32).abs();
         let rank_diff = (pos.rank as i32 - target.rank as i32).abs();
         (file_diff <= 1 && rank_diff <= 1) && !(file_diff == 0 && rank_diff == 0)
@@ -210,17 +546,20 @@ impl Piece for King {
 
     fn valid_moves(&self, board: &crate::board::Board) -> Vec<Square> {
         let mut moves = Vec::new();
-        let pos = self.position();
 
         for file in 0..=7 {
             for rank in 0..=7 {
-                if let Some(target) = Square::new(file, rank) {
-                    if self.can_move_to(target) && board.is_empty_or_capturable(target, self.color()) {
-                        moves.push(target);
-                    }
+                if let Some(target) = Square::new(file, rank)
+                    && self.can_move_to(target)
+                    && board.is_empty_or_capturable(target, self.color())
+                {
+                    moves.push(target);
                 }
             }
         }
+        if !self.has_moved() {
+            moves.extend(board.castling_moves(self.color()));
+        }
         moves
     }
 }
@@ -252,15 +591,11 @@ impl Queen {
 
 impl Piece for Queen {
     fn color(&self) -> Color {
-        Color::from_proto(self.inner.color)
+        Color::from_checked_proto(self.inner.color)
     }
 
-    fn position(&self) -> Square {
-        self.inner
-            .position
-            .as_ref()
-            .and_then(Square::from_proto)
-            .unwrap_or_else(|| Square::new(0, 0).unwrap())
+    fn try_position(&self) -> Result<Square, Error> {
+        square_of(self.inner.position.as_ref())
     }
 
     fn piece_type(&self) -> PieceType {
@@ -268,7 +603,9 @@ impl Piece for Queen {
     }
 
     fn can_move_to(&self, target: Square) -> bool {
-        let pos = self.position();
+        let Ok(pos) = self.try_position() else {
+            return false;
+        };
         let file_diff = (pos.file as i32 - target.file as i32).abs();
         let rank_diff = (pos.rank as i32 - target.rank as i32).abs();
         (file_diff == 0 || rank_diff == 0 || file_diff == rank_diff)
@@ -276,46 +613,57 @@ impl Piece for Queen {
     }
 
     fn valid_moves(&self, board: &crate::board::Board) -> Vec<Square> {
-        board.sliding_piece_moves(self.position(), self.color(), &[
+        let Ok(pos) = self.try_position() else {
+            return Vec::new();
+        };
+        board.sliding_piece_moves(pos, self.color(), &[
             (0, 1), (0, -1), (1, 0), (-1, 0), // orthogonal
             (1, 1), (1, -1), (-1, 1), (-1, -1), // diagonal
         ])
     }
 }
 
-/// Rook piece wrapping proto state.
+/// Rook piece wrapping proto::Rook.
 #[derive(Debug, Clone)]
 pub struct Rook {
-    color: Color,
-    position: Square,
-    has_moved: bool,
+    inner: proto::Rook,
 }
 
 impl Rook {
     pub fn new(color: Color, position: Square) -> Self {
         Rook {
-            color,
-            position,
-            has_moved: false,
+            inner: proto::Rook {
+                color: color.to_proto(),
+                position: Some(position.to_proto()),
+                has_moved: false,
+            },
         }
     }
 
+    pub fn from_proto(proto: proto::Rook) -> Self {
+        Rook { inner: proto }
+    }
+
+    pub fn to_proto(&self) -> proto::Rook {
+        self.inner.clone()
+    }
+
     pub fn has_moved(&self) -> bool {
-        self.has_moved
+        self.inner.has_moved
     }
 
     pub fn mark_moved(&mut self) {
-        self.has_moved = true;
+        self.inner.has_moved = true;
     }
 }
 
 impl Piece for Rook {
     fn color(&self) -> Color {
-        self.color
+        Color::from_checked_proto(self.inner.color)
     }
 
-    fn position(&self) -> Square {
-        self.position
+    fn try_position(&self) -> Result<Square, Error> {
+        square_of(self.inner.position.as_ref())
     }
 
     fn piece_type(&self) -> PieceType {
@@ -323,13 +671,19 @@ impl Piece for Rook {
     }
 
     fn can_move_to(&self, target: Square) -> bool {
-        let file_diff = (self.position.file as i32 - target.file as i32).abs();
-        let rank_diff = (self.position.rank as i32 - target.rank as i32).abs();
+        let Ok(pos) = self.try_position() else {
+            return false;
+        };
+        let file_diff = (pos.file as i32 - target.file as i32).abs();
+        let rank_diff = (pos.rank as i32 - target.rank as i32).abs();
         (file_diff == 0 || rank_diff == 0) && !(file_diff == 0 && rank_diff == 0)
     }
 
     fn valid_moves(&self, board: &crate::board::Board) -> Vec<Square> {
-        board.sliding_piece_moves(self.position, self.color, &[
+        let Ok(pos) = self.try_position() else {
+            return Vec::new();
+        };
+        board.sliding_piece_moves(pos, self.color(), &[
             (0, 1), (0, -1), (1, 0), (-1, 0),
         ])
     }
@@ -343,7 +697,16 @@ pub enum BishopSquareColor {
 }
 
 impl BishopSquareColor {
-    fn to_proto(&self) -> i32 {
+    /// The color of the given square (a1 is dark).
+    pub fn of(square: Square) -> Self {
+        if (square.file + square.rank) % 2 == 1 {
+            BishopSquareColor::Light
+        } else {
+            BishopSquareColor::Dark
+        }
+    }
+
+    pub(crate) fn to_proto(self) -> i32 {
         match self {
             BishopSquareColor::Light => 1,
             BishopSquareColor::Dark => 2,
@@ -391,15 +754,11 @@ impl Bishop {
 
 impl Piece for Bishop {
     fn color(&self) -> Color {
-        Color::from_proto(self.inner.color)
+        Color::from_checked_proto(self.inner.color)
     }
 
-    fn position(&self) -> Square {
-        self.inner
-            .position
-            .as_ref()
-            .and_then(Square::from_proto)
-            .unwrap_or_else(|| Square::new(0, 0).unwrap())
+    fn try_position(&self) -> Result<Square, Error> {
+        square_of(self.inner.position.as_ref())
     }
 
     fn piece_type(&self) -> PieceType {
@@ -407,14 +766,19 @@ impl Piece for Bishop {
     }
 
     fn can_move_to(&self, target: Square) -> bool {
-        let pos = self.position();
+        let Ok(pos) = self.try_position() else {
+            return false;
+        };
         let file_diff = (pos.file as i32 - target.file as i32).abs();
         let rank_diff = (pos.rank as i32 - target.rank as i32).abs();
         file_diff == rank_diff && file_diff != 0
     }
 
     fn valid_moves(&self, board: &crate::board::Board) -> Vec<Square> {
-        board.sliding_piece_moves(self.position(), self.color(), &[
+        let Ok(pos) = self.try_position() else {
+            return Vec::new();
+        };
+        board.sliding_piece_moves(pos, self.color(), &[
             (1, 1), (1, -1), (-1, 1), (-1, -1),
         ])
     }
@@ -447,15 +811,11 @@ impl Knight {
 
 impl Piece for Knight {
     fn color(&self) -> Color {
-        Color::from_proto(self.inner.color)
+        Color::from_checked_proto(self.inner.color)
     }
 
-    fn position(&self) -> Square {
-        self.inner
-            .position
-            .as_ref()
-            .and_then(Square::from_proto)
-            .unwrap_or_else(|| Square::new(0, 0).unwrap())
+    fn try_position(&self) -> Result<Square, Error> {
+        square_of(self.inner.position.as_ref())
     }
 
     fn piece_type(&self) -> PieceType {
@@ -463,15 +823,19 @@ impl Piece for Knight {
     }
 
     fn can_move_to(&self, target: Square) -> bool {
-        let pos = self.position();
+        let Ok(pos) = self.try_position() else {
+            return false;
+        };
         let file_diff = (pos.file as i32 - target.file as i32).abs();
         let rank_diff = (pos.rank as i32 - target.rank as i32).abs();
         (file_diff == 2 && rank_diff == 1) || (file_diff == 1 && rank_diff == 2)
     }
 
     fn valid_moves(&self, board: &crate::board::Board) -> Vec<Square> {
+        let Ok(pos) = self.try_position() else {
+            return Vec::new();
+        };
         let mut moves = Vec::new();
-        let pos = self.position();
 
         let offsets = [
             (2, 1), (2, -1), (-2, 1), (-2, -1),
@@ -482,10 +846,9 @@ impl Piece for Knight {
             if let Some(target) = Square::new(
                 (pos.file as i32 + df) as u8,
                 (pos.rank as i32 + dr) as u8,
-            ) {
-                if board.is_empty_or_capturable(target, self.color()) {
-                    moves.push(target);
-                }
+            ) && board.is_empty_or_capturable(target, self.color())
+            {
+                moves.push(target);
             }
         }
         moves
@@ -528,26 +891,11 @@ impl Pawn {
     }
 
     pub fn promoted_to(&self) -> Option<PieceType> {
-        match self.inner.promoted_to {
-            1 => Some(PieceType::King),
-            2 => Some(PieceType::Queen),
-            3 => Some(PieceType::Rook),
-            4 => Some(PieceType::Bishop),
-            5 => Some(PieceType::Knight),
-            6 => Some(PieceType::Pawn),
-            _ => None,
-        }
+        PieceType::from_proto(self.inner.promoted_to)
     }
 
     pub fn set_promoted_to(&mut self, piece_type: PieceType) {
-        self.inner.promoted_to = match piece_type {
-            PieceType::King => 1,
-            PieceType::Queen => 2,
-            PieceType::Rook => 3,
-            PieceType::Bishop => 4,
-            PieceType::Knight => 5,
-            PieceType::Pawn => 6,
-        };
+        self.inner.promoted_to = piece_type.to_proto();
     }
 
     pub fn en_passant_vulnerable(&self) -> bool {
@@ -561,15 +909,11 @@ impl Pawn {
 
 impl Piece for Pawn {
     fn color(&self) -> Color {
-        Color::from_proto(self.inner.color)
+        Color::from_checked_proto(self.inner.color)
     }
 
-    fn position(&self) -> Square {
-        self.inner
-            .position
-            .as_ref()
-            .and_then(Square::from_proto)
-            .unwrap_or_else(|| Square::new(0, 0).unwrap())
+    fn try_position(&self) -> Result<Square, Error> {
+        square_of(self.inner.position.as_ref())
     }
 
     fn piece_type(&self) -> PieceType {
@@ -577,7 +921,9 @@ impl Piece for Pawn {
     }
 
     fn can_move_to(&self, target: Square) -> bool {
-        let pos = self.position();
+        let Ok(pos) = self.try_position() else {
+            return false;
+        };
         let direction = match self.color() {
             Color::White => 1i32,
             Color::Black => -1i32,
@@ -587,22 +933,17 @@ impl Piece for Pawn {
         let file_diff = (target.file as i32 - pos.file as i32).abs();
 
         if file_diff == 0 {
-            if rank_diff == direction {
-                true
-            } else if rank_diff == direction * 2 && !self.has_moved() {
-                true
-            } else {
-                false
-            }
-        } else if file_diff == 1 && rank_diff == direction {
-            true
+            rank_diff == direction || (rank_diff == direction * 2 && pos.rank == self.color().pawn_start_rank())
         } else {
-            false
+            file_diff == 1 && rank_diff == direction
         }
     }
 
     fn valid_moves(&self, board: &crate::board::Board) -> Vec<Square> {
-        board.pawn_moves(self.position(), self.color(), self.has_moved())
+        let Ok(pos) = self.try_position() else {
+            return Vec::new();
+        };
+        board.pawn_moves(pos, self.color())
     }
 }
 
@@ -618,6 +959,37 @@ mod tests {
         assert_eq!(sq.to_algebraic(), "e4");
     }
 
+    #[test]
+    fn test_parse_squares_colors_and_piece_types() {
+        assert_eq!("e4".parse::<Square>(), Ok(sq!("e4")));
+        assert_eq!(sq!("h8").index(), 63);
+        assert_eq!(Square::from_index(12), Some(sq!("e2")));
+        assert_eq!(Square::from_index(64), None);
+        assert_eq!("e9".parse::<Square>().unwrap_err().to_string(), "invalid square: \"e9\"");
+        assert_eq!("Black".parse::<Color>(), Ok(Color::Black));
+        assert_eq!("w".parse::<Color>(), Ok(Color::White));
+        assert!("red".parse::<Color>().is_err());
+        assert_eq!("n".parse::<PieceType>(), Ok(PieceType::Knight));
+        assert_eq!("QUEEN".parse::<PieceType>(), Ok(PieceType::Queen));
+        assert!("x".parse::<PieceType>().is_err());
+    }
+
+    #[test]
+    fn test_try_from_proto_rejects_invalid_values() {
+        assert_eq!(Color::try_from_proto(2), Ok(Color::Black));
+        assert_eq!(Color::try_from_proto(0), Err(Error::InvalidColor(0)));
+        assert_eq!(PieceType::try_from_proto(7), Err(Error::InvalidPieceType(7)));
+        assert_eq!(Square::try_from_proto(&sq!("h8").to_proto()), Ok(sq!("h8")));
+        let off_board = proto::Position { file: 0, rank: 3, ..Default::default() };
+        assert_eq!(Square::try_from_proto(&off_board), Err(Error::InvalidSquare { file: 0, rank: 3 }));
+        assert_eq!(Square::from_proto(&off_board), None);
+
+        let mut king = King::new(Color::White, sq!("e1")).to_proto();
+        king.position = None;
+        let piece = proto::Piece { kind: Some(proto::piece::Kind::King(king)), ..Default::default() };
+        assert_eq!(try_from_proto_piece(&piece).unwrap_err(), Error::MissingField("piece.position"));
+    }
+
     #[test]
     fn test_color_opposite() {
         assert_eq!(Color::White.opposite(), Color::Black);
@@ -648,11 +1020,26 @@ mod tests {
     }
 
     #[test]
-    fn test_pawn_after_first_move() {
+    fn test_try_position_reports_bad_protos() {
+        let unplaced = King::from_proto(proto::King { color: 1, position: None, has_moved: false });
+        assert_eq!(unplaced.try_position(), Err(Error::MissingField("piece.position")));
+        let position = Some(proto::Position { file: 9, rank: 1, ..Default::default() });
+        let off_board = King::from_proto(proto::King { color: 1, position, has_moved: false });
+        assert_eq!(off_board.try_position(), Err(Error::InvalidSquare { file: 9, rank: 1 }));
+        assert!(!off_board.can_move_to(Square::new(0, 0).unwrap()));
+        let e1 = Square::new(4, 0).unwrap();
+        assert_eq!(King::new(Color::White, e1).try_position(), Ok(e1));
+    }
+
+    #[test]
+    fn test_pawn_double_push_follows_start_rank() {
+        // A pawn back on its start rank may push two squares whatever has_moved says.
         let mut pawn = Pawn::new(Color::White, Square::new(4, 1).unwrap());
         pawn.mark_moved();
         assert!(pawn.can_move_to(Square::new(4, 2).unwrap()));
-        assert!(!pawn.can_move_to(Square::new(4, 3).unwrap()));
+        assert!(pawn.can_move_to(Square::new(4, 3).unwrap()));
+        let pawn = Pawn::new(Color::White, Square::new(4, 2).unwrap());
+        assert!(!pawn.can_move_to(Square::new(4, 4).unwrap()));
     }
 
     #[test]
@@ -0,0 +1,124 @@
+// Pieces in hand for drop variants such as crazyhouse. Board keeps one pocket per
+// color in its GameState; this module is the typed view used by move generation,
+// FEN, and the variant rules.
+
+use crate::pieces::PieceType;
+use crate::rchess::v1::{self as proto};
+
+/// Piece types a pocket can hold, in the order FEN lists them.
+pub const POCKET_TYPES: [PieceType; 5] =
+    [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight, PieceType::Pawn];
+
+/// How many pieces of each type one side holds in hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Pocket {
+    pub pawns: u32,
+    pub knights: u32,
+    pub bishops: u32,
+    pub rooks: u32,
+    pub queens: u32,
+}
+
+impl Pocket {
+    /// Pieces of `piece_type` held; always zero for kings.
+    pub fn count(&self, piece_type: PieceType) -> u32 {
+        match piece_type {
+            PieceType::Pawn => self.pawns,
+            PieceType::Knight => self.knights,
+            PieceType::Bishop => self.bishops,
+            PieceType::Rook => self.rooks,
+            PieceType::Queen => self.queens,
+            PieceType::King => 0,
+        }
+    }
+
+    fn slot(&mut self, piece_type: PieceType) -> Option<&mut u32> {
+        match piece_type {
+            PieceType::Pawn => Some(&mut self.pawns),
+            PieceType::Knight => Some(&mut self.knights),
+            PieceType::Bishop => Some(&mut self.bishops),
+            PieceType::Rook => Some(&mut self.rooks),
+            PieceType::Queen => Some(&mut self.queens),
+            PieceType::King => None,
+        }
+    }
+
+    /// Put a piece in hand. Kings cannot be held and are ignored.
+    pub fn add(&mut self, piece_type: PieceType) {
+        if let Some(count) = self.slot(piece_type) {
+            *count += 1;
+        }
+    }
+
+    /// Take a piece out of hand; false if there is none to take.
+    pub fn remove(&mut self, piece_type: PieceType) -> bool {
+        match self.slot(piece_type) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        POCKET_TYPES.iter().all(|&piece_type| self.count(piece_type) == 0)
+    }
+
+    /// Material value of the pieces in hand, in centipawns.
+    pub fn value(&self) -> i32 {
+        POCKET_TYPES.iter().map(|&piece_type| piece_type.value() * self.count(piece_type) as i32).sum()
+    }
+
+    /// Types with at least one piece in hand, in `POCKET_TYPES` order.
+    pub fn piece_types(&self) -> impl Iterator<Item = PieceType> + '_ {
+        POCKET_TYPES.into_iter().filter(|&piece_type| self.count(piece_type) > 0)
+    }
+
+    /// Create from a proto Pocket; a missing pocket is empty.
+    pub fn from_proto(pocket: Option<&proto::Pocket>) -> Self {
+        let Some(pocket) = pocket else {
+            return Pocket::default();
+        };
+        let count = |n: i32| n.max(0) as u32;
+        Pocket {
+            pawns: count(pocket.pawns),
+            knights: count(pocket.knights),
+            bishops: count(pocket.bishops),
+            rooks: count(pocket.rooks),
+            queens: count(pocket.queens),
+        }
+    }
+
+    /// Convert to proto Pocket.
+    pub fn to_proto(&self) -> proto::Pocket {
+        proto::Pocket {
+            pawns: self.pawns as i32,
+            knights: self.knights as i32,
+            bishops: self.bishops as i32,
+            rooks: self.rooks as i32,
+            queens: self.queens as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_remove_and_proto_round_trip() {
+        let mut pocket = Pocket::default();
+        assert!(pocket.is_empty());
+        pocket.add(PieceType::Knight);
+        pocket.add(PieceType::Knight);
+        pocket.add(PieceType::Pawn);
+        pocket.add(PieceType::King);
+        assert_eq!(pocket.count(PieceType::Knight), 2);
+        assert_eq!(pocket.piece_types().collect::<Vec<_>>(), vec![PieceType::Knight, PieceType::Pawn]);
+        assert!(pocket.remove(PieceType::Pawn));
+        assert!(!pocket.remove(PieceType::Pawn));
+        assert_eq!(Pocket::from_proto(Some(&pocket.to_proto())), pocket);
+        assert_eq!(Pocket::from_proto(None), Pocket::default());
+    }
+}
@@ -0,0 +1,217 @@
+// Positional feature queries: passed pawns, outposts, open files, mobility, and space.
+// Kept separate from evaluation so teaching tools and custom evaluators can reuse them.
+
+use crate::bitboard::{self, Bitboard};
+use crate::board::Board;
+use crate::pieces::{Color, PieceType, Square};
+
+/// Mobility of one piece: the squares it attacks that are not held by its own side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceMobility {
+    pub square: Square,
+    pub piece_type: PieceType,
+    pub color: Color,
+    pub moves: usize,
+}
+
+/// Mobility and space report for both sides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionMetrics {
+    /// Per-piece mobility in square order.
+    pub pieces: Vec<PieceMobility>,
+    pub white_mobility: usize,
+    pub black_mobility: usize,
+    /// Safe central squares (files c..f on the own second to fourth ranks) per side.
+    pub white_space: u32,
+    pub black_space: u32,
+}
+
+impl Board {
+    /// Squares of `color`'s pawns with no enemy pawn ahead on the same or adjacent files.
+    pub fn passed_pawns(&self, color: Color) -> Vec<Square> {
+        let enemy = self.pawn_squares(color.opposite());
+        let mut passed: Vec<Square> = self
+            .pawn_squares(color)
+            .into_iter()
+            .filter(|pawn| {
+                !enemy
+                    .iter()
+                    .any(|e| e.file.abs_diff(pawn.file) <= 1 && is_ahead(color, *pawn, e.rank))
+            })
+            .collect();
+        sort_squares(&mut passed);
+        passed
+    }
+
+    /// Squares in the enemy half that are defended by one of `color`'s pawns
+    /// and can never be attacked by an enemy pawn.
+    pub fn outposts(&self, color: Color) -> Vec<Square> {
+        let own = self.pawn_squares(color);
+        let enemy = self.pawn_squares(color.opposite());
+        let ranks = match color {
+            Color::White => 3..=5,
+            Color::Black => 2..=4,
+        };
+        let behind: i32 = match color {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+
+        let mut outposts = Vec::new();
+        for rank in ranks {
+            for file in 0..8u8 {
+                let square = Square { file, rank };
+                let defended = own.iter().any(|p| {
+                    p.file.abs_diff(file) == 1 && p.rank as i32 == rank as i32 + behind
+                });
+                let attackable = enemy
+                    .iter()
+                    .any(|e| e.file.abs_diff(file) == 1 && is_ahead(color, square, e.rank));
+                if defended && !attackable {
+                    outposts.push(square);
+                }
+            }
+        }
+        outposts
+    }
+
+    /// Files (0 = a) with no pawns of either color.
+    pub fn open_files(&self) -> Vec<u8> {
+        let pawns: Vec<Square> = self
+            .pawn_squares(Color::White)
+            .into_iter()
+            .chain(self.pawn_squares(Color::Black))
+            .collect();
+        (0..8).filter(|&f| !pawns.iter().any(|p| p.file == f)).collect()
+    }
+
+    /// Files (0 = a) with enemy pawns but none of `color`'s own.
+    pub fn semi_open_files(&self, color: Color) -> Vec<u8> {
+        let own = self.pawn_squares(color);
+        let enemy = self.pawn_squares(color.opposite());
+        (0..8)
+            .filter(|&f| !own.iter().any(|p| p.file == f) && enemy.iter().any(|p| p.file == f))
+            .collect()
+    }
+
+    /// Compute per-piece mobility and space for both sides.
+    pub fn metrics(&self) -> PositionMetrics {
+        let mut mobility = Vec::new();
+        for color in [Color::White, Color::Black] {
+            let own = self.bitboards().color(color);
+            for square in own.squares() {
+                if let Some((piece_type, _)) = self.piece_at_typed(square) {
+                    let moves = (self.attacks_from(square) & !own).count() as usize;
+                    mobility.push(PieceMobility { square, piece_type, color, moves });
+                }
+            }
+        }
+        mobility.sort_by_key(|m| (m.square.rank, m.square.file));
+
+        let total = |color: Color| {
+            mobility
+                .iter()
+                .filter(|m| m.color == color)
+                .map(|m| m.moves)
+                .sum()
+        };
+        PositionMetrics {
+            white_mobility: total(Color::White),
+            black_mobility: total(Color::Black),
+            white_space: self.space(Color::White),
+            black_space: self.space(Color::Black),
+            pieces: mobility,
+        }
+    }
+
+    /// Central squares on `color`'s second to fourth ranks that hold no own pawn
+    /// and are not attacked by an enemy pawn.
+    fn space(&self, color: Color) -> u32 {
+        let own = self.bitboards().pieces(PieceType::Pawn, color);
+        let enemy_attacks = self
+            .bitboards()
+            .pieces(PieceType::Pawn, color.opposite())
+            .squares()
+            .fold(Bitboard::EMPTY, |attacked, pawn| attacked | bitboard::pawn_attacks(pawn, color.opposite()));
+        let ranks = match color {
+            Color::White => 1..=3,
+            Color::Black => 4..=6,
+        };
+        let mut count = 0;
+        for rank in ranks {
+            for file in 2..=5u8 {
+                let square = Square { file, rank };
+                if !own.contains(square) && !enemy_attacks.contains(square) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    fn pawn_squares(&self, color: Color) -> Vec<Square> {
+        self.pieces_of_color(color)
+            .iter()
+            .filter(|p| self.piece_type(p) == Some(PieceType::Pawn))
+            .filter_map(|p| self.piece_square(p))
+            .collect()
+    }
+}
+
+/// Whether `rank` lies strictly ahead of `square` from `color`'s point of view.
+fn is_ahead(color: Color, square: Square, rank: u8) -> bool {
+    match color {
+        Color::White => rank > square.rank,
+        Color::Black => rank < square.rank,
+    }
+}
+
+fn sort_squares(squares: &mut [Square]) {
+    squares.sort_by_key(|sq| (sq.rank, sq.file));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_passed_pawns() {
+        // White a5 is passed; White d4 is stopped by Black e6; Black h6 is passed.
+        let board = Board::from_fen("8/8/4p2p/P7/3P4/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(board.passed_pawns(Color::White), vec![sq!("a5")]);
+        assert_eq!(board.passed_pawns(Color::Black), vec![sq!("h6")]);
+    }
+
+    #[test]
+    fn test_outposts() {
+        // White e4 supports d5 and f5; Black c7 can still challenge d5 via c6.
+        let board = Board::from_fen("8/2p5/8/8/4P3/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(board.outposts(Color::White), vec![sq!("f5")]);
+    }
+
+    #[test]
+    fn test_metrics_mobility_and_space() {
+        let metrics = Board::from_fen("8/8/8/4p3/8/8/3P4/6N1 w - - 0 1").unwrap().metrics();
+
+        let knight = metrics
+            .pieces
+            .iter()
+            .find(|m| m.piece_type == PieceType::Knight)
+            .unwrap();
+        // Ng1 reaches e2, f3 and h3.
+        assert_eq!(knight.moves, 3);
+        // Knight 3 plus c3 and e3, the squares the d2 pawn attacks.
+        assert_eq!(metrics.white_mobility, 5);
+        // c2..f4 is 12 squares, minus the d2 pawn and d4/f4 attacked by e5.
+        assert_eq!(metrics.white_space, 9);
+    }
+
+    #[test]
+    fn test_open_and_semi_open_files() {
+        let board = Board::from_fen("8/1pp5/8/8/8/8/PP6/8 w - - 0 1").unwrap();
+        assert_eq!(board.open_files(), vec![3, 4, 5, 6, 7]);
+        assert_eq!(board.semi_open_files(Color::White), vec![2]);
+        assert_eq!(board.semi_open_files(Color::Black), vec![0]);
+    }
+}
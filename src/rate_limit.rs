@@ -0,0 +1,280 @@
+// Abuse controls for the gRPC game service. RateLimited wraps any GameService
+// and holds each client to a RateLimitPolicy, so one misbehaving client cannot
+// degrade the server for the rest. Clients are told apart by the peer's IP
+// address; requests without one, such as in-process calls, share an allowance.
+//
+// Moves are limited by a token bucket refilled at the policy's rate, games by
+// how many the client created that are still in progress, and WatchGame streams
+// by how many the client has open. A request over a limit fails with
+// RESOURCE_EXHAUSTED and never reaches the wrapped service.
+
+// Status is large, but it is the error type every tonic handler returns.
+#![allow(clippy::result_large_err)]
+
+use crate::grpc::GameService;
+use crate::rchess::v1::{self as proto};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+use tokio_stream::StreamExt;
+use tonic::codegen::BoxStream;
+use tonic::{Request, Response, Status};
+
+/// Limits applied to each client.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitPolicy {
+    /// Moves a client may make per second, in bursts of up to this many.
+    pub moves_per_second: f64,
+    /// Games a client may have in progress at once.
+    pub max_games: usize,
+    /// WatchGame streams a client may have open at once.
+    pub max_streams: usize,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        RateLimitPolicy { moves_per_second: 5.0, max_games: 10, max_streams: 20 }
+    }
+}
+
+type ClientKey = Option<IpAddr>;
+
+/// What one client is using.
+#[derive(Debug, Default)]
+struct Usage {
+    /// Moves the client can make now, and when that was worked out.
+    tokens: f64,
+    refilled: Option<Instant>,
+    /// Ids of the games the client created, in progress when last checked.
+    games: Vec<String>,
+    streams: usize,
+}
+
+type Clients = Arc<Mutex<HashMap<ClientKey, Usage>>>;
+
+fn lock(clients: &Clients) -> MutexGuard<'_, HashMap<ClientKey, Usage>> {
+    clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn client_key<T>(request: &Request<T>) -> ClientKey {
+    request.remote_addr().map(|address| address.ip())
+}
+
+/// A GameService that enforces `policy` before passing requests to `inner`.
+#[derive(Debug)]
+pub struct RateLimited<T> {
+    inner: T,
+    policy: RateLimitPolicy,
+    clients: Clients,
+}
+
+impl<T: GameService> RateLimited<T> {
+    pub fn new(inner: T, policy: RateLimitPolicy) -> Self {
+        RateLimited { inner, policy, clients: Arc::default() }
+    }
+
+    pub fn policy(&self) -> &RateLimitPolicy {
+        &self.policy
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Spend one of the client's move tokens, after refilling them for the
+    /// time since the last move.
+    fn take_move(&self, key: ClientKey) -> Result<(), Status> {
+        let now = Instant::now();
+        let capacity = self.policy.moves_per_second.max(1.0);
+        let mut clients = lock(&self.clients);
+        let usage = clients.entry(key).or_default();
+        usage.tokens = match usage.refilled {
+            Some(last) => {
+                let refill = now.duration_since(last).as_secs_f64() * self.policy.moves_per_second;
+                (usage.tokens + refill).min(capacity)
+            }
+            None => capacity,
+        };
+        usage.refilled = Some(now);
+        if usage.tokens < 1.0 {
+            return Err(Status::resource_exhausted("too many moves; slow down"));
+        }
+        usage.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// The client's games that are still in progress.
+    async fn games_in_progress(&self, key: ClientKey) -> Vec<String> {
+        let games = lock(&self.clients).get(&key).map(|usage| usage.games.clone()).unwrap_or_default();
+        let mut ongoing = Vec::new();
+        for game_id in games {
+            let request = Request::new(proto::GetGameStateRequest { game_id: game_id.clone() });
+            let state = self.inner.get_game_state(request).await;
+            if state.is_ok_and(|state| state.get_ref().result == proto::GameResult::Ongoing as i32) {
+                ongoing.push(game_id);
+            }
+        }
+        ongoing
+    }
+}
+
+/// One open stream counted against a client; released when dropped.
+struct StreamPermit {
+    clients: Clients,
+    key: ClientKey,
+}
+
+impl Drop for StreamPermit {
+    fn drop(&mut self) {
+        if let Some(usage) = lock(&self.clients).get_mut(&self.key) {
+            usage.streams = usage.streams.saturating_sub(1);
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<T: GameService> GameService for RateLimited<T> {
+    async fn create_game(
+        &self,
+        request: Request<proto::CreateGameRequest>,
+    ) -> Result<Response<proto::CreateGameResponse>, Status> {
+        let key = client_key(&request);
+        let mut games = self.games_in_progress(key).await;
+        if games.len() >= self.policy.max_games {
+            return Err(Status::resource_exhausted(format!(
+                "{} games already in progress; finish one first",
+                games.len()
+            )));
+        }
+        let response = self.inner.create_game(request).await?;
+        games.push(response.get_ref().game_id.clone());
+        lock(&self.clients).entry(key).or_default().games = games;
+        Ok(response)
+    }
+
+    async fn get_game_state(
+        &self,
+        request: Request<proto::GetGameStateRequest>,
+    ) -> Result<Response<proto::GetGameStateResponse>, Status> {
+        self.inner.get_game_state(request).await
+    }
+
+    async fn list_legal_moves(
+        &self,
+        request: Request<proto::ListLegalMovesRequest>,
+    ) -> Result<Response<proto::ListLegalMovesResponse>, Status> {
+        self.inner.list_legal_moves(request).await
+    }
+
+    async fn make_move(
+        &self,
+        request: Request<proto::MakeMoveRequest>,
+    ) -> Result<Response<proto::MakeMoveResponse>, Status> {
+        self.take_move(client_key(&request))?;
+        self.inner.make_move(request).await
+    }
+
+    async fn resign_game(
+        &self,
+        request: Request<proto::ResignGameRequest>,
+    ) -> Result<Response<proto::ResignGameResponse>, Status> {
+        self.inner.resign_game(request).await
+    }
+
+    type WatchGameStream = BoxStream<proto::GameUpdate>;
+
+    async fn watch_game(
+        &self,
+        request: Request<proto::WatchGameRequest>,
+    ) -> Result<Response<Self::WatchGameStream>, Status> {
+        let key = client_key(&request);
+        {
+            let mut clients = lock(&self.clients);
+            let usage = clients.entry(key).or_default();
+            if usage.streams >= self.policy.max_streams {
+                return Err(Status::resource_exhausted(format!("{} streams already open", usage.streams)));
+            }
+            usage.streams += 1;
+        }
+        let permit = StreamPermit { clients: Arc::clone(&self.clients), key };
+        let updates = self.inner.watch_game(request).await?.into_inner();
+        // The permit lives as long as the stream, however the stream ends.
+        Ok(Response::new(Box::pin(updates.map(move |update| {
+            let _held = &permit;
+            update
+        }))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::GameStore;
+    use crate::moves::Move;
+    use crate::pieces::{Color, Square};
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    /// The store never waits on anything, so one poll finishes each call.
+    fn ready<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("store call did not complete"),
+        }
+    }
+
+    fn limited(policy: RateLimitPolicy) -> RateLimited<GameStore> {
+        RateLimited::new(GameStore::new(), policy)
+    }
+
+    fn create<T: GameService>(service: &T) -> Result<String, Status> {
+        let request = Request::new(proto::CreateGameRequest::default());
+        ready(service.create_game(request)).map(|response| response.into_inner().game_id)
+    }
+
+    fn play<T: GameService>(service: &T, id: &str, from: &str, to: &str) -> Result<(), Status> {
+        let mv = Move::new(Square::from_algebraic(from).unwrap(), Square::from_algebraic(to).unwrap());
+        let request = Request::new(proto::MakeMoveRequest { game_id: id.to_string(), r#move: Some(mv.to_proto()) });
+        ready(service.make_move(request)).map(|_| ())
+    }
+
+    #[test]
+    fn test_moves_are_rate_limited() {
+        let service = limited(RateLimitPolicy { moves_per_second: 2.0, ..Default::default() });
+        let id = create(&service).unwrap();
+        play(&service, &id, "e2", "e4").unwrap();
+        play(&service, &id, "e7", "e5").unwrap();
+        let err = play(&service, &id, "g1", "f3").unwrap_err();
+        assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+    }
+
+    #[test]
+    fn test_games_in_progress_are_limited() {
+        let service = limited(RateLimitPolicy { max_games: 1, ..Default::default() });
+        let id = create(&service).unwrap();
+        assert_eq!(create(&service).unwrap_err().code(), tonic::Code::ResourceExhausted);
+
+        let request = Request::new(proto::ResignGameRequest { game_id: id, color: Color::White.to_proto() });
+        ready(service.resign_game(request)).unwrap();
+        create(&service).unwrap();
+        assert_eq!(service.inner().len(), 2);
+    }
+
+    #[test]
+    fn test_open_streams_are_limited() {
+        let service = limited(RateLimitPolicy { max_streams: 1, ..Default::default() });
+        let id = create(&service).unwrap();
+        let watch = || {
+            let request = proto::WatchGameRequest { game_id: id.clone(), ..Default::default() };
+            ready(service.watch_game(Request::new(request)))
+        };
+        let stream = watch().unwrap();
+        assert_eq!(watch().unwrap_err().code(), tonic::Code::ResourceExhausted);
+        drop(stream);
+        watch().unwrap();
+        let missing = Request::new(proto::WatchGameRequest { game_id: "missing".to_string(), ..Default::default() });
+        assert_eq!(ready(service.watch_game(missing)).unwrap_err().code(), tonic::Code::NotFound);
+    }
+}
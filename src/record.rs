@@ -0,0 +1,197 @@
+// Annotated game records: a game's main line with the evals, NAGs, clock times,
+// comments, and annotations of its moves, as the proto AnnotatedGame. PGN
+// carries the same data in comments, rounded to what the "[%eval]" and "[%clk]"
+// commands can say; the proto keeps it exactly.
+//
+// Records carry a checksum of their content, so a stored game that was altered
+// or damaged can be detected; the "signing" feature adds signatures over the
+// same content (src/signing.rs).
+
+use crate::board::Board;
+use crate::error::Error;
+use crate::game::{Game, GameResult};
+use crate::game_tree::GameTree;
+use crate::moves::Move;
+use crate::rchess::v1::{self as proto};
+use prost::Message;
+use std::fmt;
+
+/// Why a record failed verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The record has no checksum.
+    NoChecksum,
+    /// The stored checksum is not the record's.
+    Checksum { stored: u64, computed: u64 },
+    /// The record has no signature by the expected key.
+    Unsigned,
+    /// A signature, by the named signer, is malformed or does not match the record.
+    BadSignature(String),
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntegrityError::NoChecksum => write!(f, "record has no checksum"),
+            IntegrityError::Checksum { stored, computed } => {
+                write!(f, "checksum {:016x} does not match the record's {:016x}", stored, computed)
+            }
+            IntegrityError::Unsigned => write!(f, "record is not signed by that key"),
+            IntegrityError::BadSignature(signer) => write!(f, "signature by {:?} does not match the record", signer),
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+/// The bytes the checksum and signatures cover: `record` as prost encodes it
+/// with the checksum and signatures cleared. That is the start position, tags,
+/// every move with its annotations, and the result.
+pub fn signed_content(record: &proto::AnnotatedGame) -> Vec<u8> {
+    let content = proto::AnnotatedGame { checksum: 0, signatures: Vec::new(), ..record.clone() };
+    content.encode_to_vec()
+}
+
+/// The checksum of `record`: the FNV-1a 64 hash of its signed content.
+pub fn checksum(record: &proto::AnnotatedGame) -> u64 {
+    signed_content(record).iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Check the checksum stored in `record` against its content.
+pub fn verify_checksum(record: &proto::AnnotatedGame) -> Result<(), IntegrityError> {
+    let computed = checksum(record);
+    match record.checksum {
+        0 => Err(IntegrityError::NoChecksum),
+        stored if stored != computed => Err(IntegrityError::Checksum { stored, computed }),
+        _ => Ok(()),
+    }
+}
+
+fn key_value((key, value): &(String, String)) -> proto::KeyValue {
+    proto::KeyValue { key: key.clone(), value: value.clone() }
+}
+
+impl Game {
+    /// Convert the main line and its annotations to a proto AnnotatedGame,
+    /// checksum included. Sidelines and game events other than the result are
+    /// left out.
+    pub fn to_annotated_proto(&self) -> proto::AnnotatedGame {
+        let tree = self.tree();
+        let moves = tree
+            .mainline()
+            .into_iter()
+            .filter_map(|id| {
+                let node = tree.node(id);
+                Some(proto::AnnotatedMove {
+                    r#move: Some(node.mv()?.to_proto()),
+                    eval: node.eval(),
+                    nags: node.nags().iter().map(|&nag| u32::from(nag)).collect(),
+                    clock_ms: node.clock_ms(),
+                    comment: node.comment().unwrap_or_default().to_string(),
+                    annotations: node.annotations().iter().map(key_value).collect(),
+                })
+            })
+            .collect();
+        let mut record = proto::AnnotatedGame {
+            start: Some(self.start_position().to_proto()),
+            tags: self.tags().iter().map(key_value).collect(),
+            moves,
+            result: self.result().to_proto(),
+            checksum: 0,
+            signatures: Vec::new(),
+        };
+        record.checksum = checksum(&record);
+        record
+    }
+
+    /// Create from a proto AnnotatedGame, checking the start position as
+    /// `Board::try_from_proto` does and every move for legality. NAGs above
+    /// 255 name no glyph and are dropped. The checksum and signatures are not
+    /// checked; see [`verify_checksum`].
+    pub fn try_from_annotated_proto(record: proto::AnnotatedGame) -> Result<Game, Error> {
+        let start = Board::try_from_proto(record.start.ok_or(Error::MissingField("start"))?)?;
+        let mut tree = GameTree::new(start);
+        let mut current = tree.root();
+        for annotated in record.moves {
+            let mv = annotated.r#move.as_ref().ok_or(Error::MissingField("move"))?;
+            let mv = Move::from_proto(mv).ok_or(Error::MissingField("move.to"))?;
+            current = tree.add_move(current, mv)?;
+            tree.set_eval(current, annotated.eval);
+            tree.set_clock_ms(current, annotated.clock_ms);
+            for nag in annotated.nags.into_iter().filter_map(|nag| u8::try_from(nag).ok()) {
+                tree.add_nag(current, nag);
+            }
+            if !annotated.comment.is_empty() {
+                tree.set_comment(current, Some(annotated.comment));
+            }
+            for annotation in annotated.annotations {
+                tree.set_annotation(current, &annotation.key, &annotation.value);
+            }
+        }
+        let mut game = Game::from_tree(tree);
+        for tag in record.tags {
+            game.set_tag(&tag.key, &tag.value);
+        }
+        game.set_result(GameResult::from_proto(record.result).unwrap_or(GameResult::Ongoing));
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pieces::Color;
+    use crate::sq;
+
+    #[test]
+    fn test_annotated_proto_round_trip() {
+        let mut game = Game::new();
+        game.set_tag("White", "Alice");
+        game.play(Move::new(sq!("e2"), sq!("e4"))).unwrap();
+        game.play(Move::new(sq!("e7"), sq!("e5"))).unwrap();
+        let e5 = game.tree().mainline()[1];
+        let tree = game.tree_mut();
+        tree.set_eval(e5, Some(-17));
+        tree.set_clock_ms(e5, Some(299_987));
+        tree.add_nag(e5, 6);
+        tree.set_comment(e5, Some("Symmetry".to_string()));
+        tree.set_annotation(e5, "depth", "20");
+        game.resign(Color::White).unwrap();
+
+        let record = game.to_annotated_proto();
+        assert_eq!(record.moves.len(), 2);
+        assert_eq!(record.moves[1].clock_ms, Some(299_987));
+
+        let restored = Game::try_from_annotated_proto(record.clone()).unwrap();
+        assert_eq!(restored.to_annotated_proto(), record);
+        assert_eq!(restored.tag("White"), Some("Alice"));
+        assert_eq!(restored.result(), GameResult::BlackWins);
+        let node = restored.tree().node(restored.tree().mainline()[1]);
+        assert_eq!((node.eval(), node.nags(), node.comment()), (Some(-17), &[6][..], Some("Symmetry")));
+
+        let mut illegal = record;
+        illegal.moves.swap(0, 1);
+        assert!(Game::try_from_annotated_proto(illegal).is_err());
+    }
+
+    #[test]
+    fn test_checksum_detects_changes() {
+        let mut game = Game::new();
+        game.play(Move::new(sq!("d2"), sq!("d4"))).unwrap();
+        game.play(Move::new(sq!("g8"), sq!("f6"))).unwrap();
+        game.resign(Color::Black).unwrap();
+        let record = game.to_annotated_proto();
+        assert_eq!(verify_checksum(&record), Ok(()));
+
+        let mut result = record.clone();
+        result.result = GameResult::BlackWins.to_proto();
+        assert!(matches!(verify_checksum(&result), Err(IntegrityError::Checksum { stored, .. }) if stored == record.checksum));
+        let mut moved = record.clone();
+        moved.moves[1].r#move = Some(Move::new(sq!("g8"), sq!("h6")).to_proto());
+        assert!(verify_checksum(&moved).is_err());
+        let unsealed = proto::AnnotatedGame { checksum: 0, ..record };
+        assert_eq!(verify_checksum(&unsealed), Err(IntegrityError::NoChecksum));
+    }
+}
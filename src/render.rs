@@ -0,0 +1,56 @@
+// Board rendering for display outside the engine.
+// `text` draws the board for terminals; `svg` draws diagrams for documents and web pages.
+// Both draw pieces from a PieceSet; SVG themes can also be loaded from a file.
+
+use crate::pieces::{Color, PieceType};
+
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod text;
+
+/// Characters pieces are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PieceSet {
+    /// FEN letters, uppercase for White and lowercase for Black.
+    Letters,
+    /// Solid figurines for both sides, told apart only by the color they are
+    /// drawn in.
+    #[default]
+    Solid,
+    /// Outlined figurines for White and solid ones for Black, which stay
+    /// distinct without color.
+    Outline,
+}
+
+impl PieceSet {
+    /// Parse a set's name: "letters", "solid", or "outline".
+    pub fn from_name(name: &str) -> Option<PieceSet> {
+        match name {
+            "letters" => Some(PieceSet::Letters),
+            "solid" => Some(PieceSet::Solid),
+            "outline" => Some(PieceSet::Outline),
+            _ => None,
+        }
+    }
+
+    /// The character for a piece of `piece_type` and `color`.
+    pub fn symbol(self, piece_type: PieceType, color: Color) -> char {
+        // The outlined figurines run from U+2654 (king) in the same order as
+        // the solid ones from U+265A.
+        let offset = match piece_type {
+            PieceType::King => 0,
+            PieceType::Queen => 1,
+            PieceType::Rook => 2,
+            PieceType::Bishop => 3,
+            PieceType::Knight => 4,
+            PieceType::Pawn => 5,
+        };
+        let first = match (self, color) {
+            (PieceSet::Letters, Color::White) => return piece_type.letter(),
+            (PieceSet::Letters, Color::Black) => return piece_type.letter().to_ascii_lowercase(),
+            (PieceSet::Outline, Color::White) => 0x2654,
+            (PieceSet::Solid, _) | (PieceSet::Outline, Color::Black) => 0x265A,
+        };
+        char::from_u32(first + offset).expect("figurines are valid characters")
+    }
+}
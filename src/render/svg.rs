@@ -0,0 +1,413 @@
+// SVG diagrams of a position: squares, pieces as Unicode glyphs, and optional
+// coordinates, highlighted squares, and arrows. The output is a standalone
+// <svg> element with no external references.
+//
+// A Theme file is text with one `key = value` setting per line; lines starting
+// with `#` are comments.
+// `base` picks a built-in theme to start from and must come first; the other keys
+// are Theme's fields, e.g.
+//
+//     base = blue
+//     dark = #4b7399
+//     pieces = outline
+//     arrow_width = 20
+
+use crate::board::Board;
+use crate::pieces::{Color, Square};
+use crate::render::PieceSet;
+use std::fmt::{self, Write};
+use std::io;
+use std::path::Path;
+
+/// How a diagram is drawn: colors as SVG color strings, and sizes as
+/// percentages of a square's side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub light: String,
+    pub dark: String,
+    /// Fill laid over highlighted squares; use a translucent color to keep the square visible.
+    pub highlight: String,
+    /// Default arrow color.
+    pub arrow: String,
+    pub pieces: PieceSet,
+    pub white_piece: String,
+    pub black_piece: String,
+    /// Stroke around both sides' pieces.
+    pub piece_outline: String,
+    /// Color of the coordinate labels; by default each takes the color of the
+    /// other kind of square.
+    pub coordinates: Option<String>,
+    pub arrow_width: u32,
+    /// Length of the arrowhead, which is half as wide as it is long.
+    pub arrow_head: u32,
+}
+
+/// Why a theme file could not be loaded.
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(io::Error),
+    /// A line that is not a `key = value` setting, by line number.
+    Syntax(usize),
+    UnknownKey { line: usize, key: String },
+    InvalidValue { line: usize, key: String, value: String },
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThemeError::Io(e) => write!(f, "{}", e),
+            ThemeError::Syntax(line) => write!(f, "line {}: expected `key = value`", line),
+            ThemeError::UnknownKey { line, key } => write!(f, "line {}: unknown setting '{}'", line, key),
+            ThemeError::InvalidValue { line, key, value } => write!(f, "line {}: invalid {} '{}'", line, key, value),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ThemeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ThemeError {
+    fn from(e: io::Error) -> Self {
+        ThemeError::Io(e)
+    }
+}
+
+impl Theme {
+    fn new(light: &str, dark: &str, highlight: &str, arrow: &str) -> Self {
+        Theme {
+            light: light.to_string(),
+            dark: dark.to_string(),
+            highlight: highlight.to_string(),
+            arrow: arrow.to_string(),
+            pieces: PieceSet::Solid,
+            white_piece: "#ffffff".to_string(),
+            black_piece: "#000000".to_string(),
+            piece_outline: "#000000".to_string(),
+            coordinates: None,
+            arrow_width: 15,
+            arrow_head: 45,
+        }
+    }
+
+    /// A built-in theme by name: "brown", "blue", or "green".
+    pub fn named(name: &str) -> Option<Theme> {
+        match name {
+            "brown" => Some(Theme::brown()),
+            "blue" => Some(Theme::blue()),
+            "green" => Some(Theme::green()),
+            _ => None,
+        }
+    }
+
+    /// Load the theme file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Theme, ThemeError> {
+        Theme::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse a theme file's contents. Settings not given keep the base
+    /// theme's values, or the default theme's when there is no `base`.
+    pub fn parse(text: &str) -> Result<Theme, ThemeError> {
+        let mut theme = Theme::default();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or(ThemeError::Syntax(line_number))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || ThemeError::InvalidValue { line: line_number, key: key.to_string(), value: value.to_string() };
+            let percent = || value.parse::<u32>().ok().filter(|&percent| percent <= 100).ok_or_else(invalid);
+            match key {
+                "base" => theme = Theme::named(value).ok_or_else(invalid)?,
+                "light" => theme.light = value.to_string(),
+                "dark" => theme.dark = value.to_string(),
+                "highlight" => theme.highlight = value.to_string(),
+                "arrow" => theme.arrow = value.to_string(),
+                "pieces" => theme.pieces = PieceSet::from_name(value).ok_or_else(invalid)?,
+                "white_piece" => theme.white_piece = value.to_string(),
+                "black_piece" => theme.black_piece = value.to_string(),
+                "piece_outline" => theme.piece_outline = value.to_string(),
+                "coordinates" => theme.coordinates = Some(value.to_string()),
+                "arrow_width" => theme.arrow_width = percent()?,
+                "arrow_head" => theme.arrow_head = percent()?,
+                _ => return Err(ThemeError::UnknownKey { line: line_number, key: key.to_string() }),
+            }
+        }
+        Ok(theme)
+    }
+
+    /// Wood tones, the default.
+    pub fn brown() -> Self {
+        Theme::new("#f0d9b5", "#b58863", "rgba(155, 199, 0, 0.41)", "rgba(21, 120, 27, 0.8)")
+    }
+
+    pub fn blue() -> Self {
+        Theme::new("#dee3e6", "#8ca2ad", "rgba(20, 85, 30, 0.4)", "rgba(0, 48, 136, 0.8)")
+    }
+
+    pub fn green() -> Self {
+        Theme::new("#ffffdd", "#86a666", "rgba(255, 255, 0, 0.4)", "rgba(200, 40, 40, 0.8)")
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::brown()
+    }
+}
+
+/// An arrow from the center of one square to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arrow {
+    pub from: Square,
+    pub to: Square,
+    /// Overrides the theme's arrow color.
+    pub color: Option<String>,
+}
+
+impl Arrow {
+    pub fn new(from: Square, to: Square) -> Self {
+        Arrow { from, to, color: None }
+    }
+}
+
+/// How to draw a diagram.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgOptions {
+    pub theme: Theme,
+    /// Side length of one square in pixels.
+    pub square_size: u32,
+    /// Draw from Black's side, with h8 in the bottom-left corner.
+    pub flipped: bool,
+    /// Label files along the bottom edge and ranks along the left edge.
+    pub coordinates: bool,
+    pub highlights: Vec<Square>,
+    pub arrows: Vec<Arrow>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            theme: Theme::default(),
+            square_size: 45,
+            flipped: false,
+            coordinates: true,
+            highlights: Vec::new(),
+            arrows: Vec::new(),
+        }
+    }
+}
+
+/// Render the position on `board` as an SVG document.
+pub fn render(board: &Board, options: &SvgOptions) -> String {
+    let size = options.square_size;
+    let side = size * 8;
+    let mut svg = String::new();
+    // Writing to a String cannot fail.
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{side}" height="{side}" viewBox="0 0 {side} {side}">"#
+    );
+
+    for rank in 0..8 {
+        for file in 0..8 {
+            let square = Square { file, rank };
+            let (x, y) = corner(square, options);
+            let light = (file + rank) % 2 == 1;
+            let fill = if light { &options.theme.light } else { &options.theme.dark };
+            let _ = write!(svg, r#"<rect x="{x}" y="{y}" width="{size}" height="{size}" fill="{fill}"/>"#);
+        }
+    }
+
+    for &square in &options.highlights {
+        let (x, y) = corner(square, options);
+        let _ = write!(
+            svg,
+            r#"<rect class="highlight" x="{x}" y="{y}" width="{size}" height="{size}" fill="{}"/>"#,
+            options.theme.highlight
+        );
+    }
+
+    if options.coordinates {
+        write_coordinates(&mut svg, options);
+    }
+
+    let font_size = size * 4 / 5;
+    for rank in 0..8 {
+        for file in 0..8 {
+            let square = Square { file, rank };
+            let Some((piece_type, color)) = board.piece_at_typed(square) else {
+                continue;
+            };
+            let (x, y) = corner(square, options);
+            let theme = &options.theme;
+            let fill = match color {
+                Color::White => &theme.white_piece,
+                Color::Black => &theme.black_piece,
+            };
+            let _ = write!(
+                svg,
+                r#"<text class="piece" x="{}" y="{}" font-size="{font_size}" text-anchor="middle" dominant-baseline="central" fill="{fill}" stroke="{}" stroke-width="1">{}</text>"#,
+                x + size / 2,
+                y + size / 2,
+                theme.piece_outline,
+                theme.pieces.symbol(piece_type, color)
+            );
+        }
+    }
+
+    for arrow in &options.arrows {
+        write_arrow(&mut svg, arrow, options);
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Top-left pixel of `square` in the diagram.
+fn corner(square: Square, options: &SvgOptions) -> (u32, u32) {
+    let (column, row) = if options.flipped {
+        (7 - square.file, square.rank)
+    } else {
+        (square.file, 7 - square.rank)
+    };
+    (column as u32 * options.square_size, row as u32 * options.square_size)
+}
+
+fn write_coordinates(svg: &mut String, options: &SvgOptions) {
+    let size = options.square_size;
+    let font_size = (size / 5).max(6);
+    let margin = size / 15 + 1;
+    for index in 0..8u8 {
+        // Files along the bottom row, ranks along the left column; each label takes
+        // the color of the other kind of square so it stays readable.
+        let file_square = if options.flipped { Square { file: index, rank: 7 } } else { Square { file: index, rank: 0 } };
+        let (x, y) = corner(file_square, options);
+        let _ = write!(
+            svg,
+            r#"<text class="coordinate" x="{}" y="{}" font-size="{font_size}" text-anchor="end" fill="{}">{}</text>"#,
+            x + size - margin,
+            y + size - margin,
+            label_color(file_square, options),
+            (b'a' + index) as char
+        );
+
+        let rank_square = if options.flipped { Square { file: 7, rank: index } } else { Square { file: 0, rank: index } };
+        let (x, y) = corner(rank_square, options);
+        let _ = write!(
+            svg,
+            r#"<text class="coordinate" x="{}" y="{}" font-size="{font_size}" fill="{}">{}</text>"#,
+            x + margin,
+            y + margin + font_size,
+            label_color(rank_square, options),
+            index + 1
+        );
+    }
+}
+
+fn label_color(square: Square, options: &SvgOptions) -> &str {
+    if let Some(color) = &options.theme.coordinates {
+        color
+    } else if (square.file + square.rank) % 2 == 1 {
+        &options.theme.dark
+    } else {
+        &options.theme.light
+    }
+}
+
+fn write_arrow(svg: &mut String, arrow: &Arrow, options: &SvgOptions) {
+    let size = options.square_size as f64;
+    let center = |square: Square| {
+        let (x, y) = corner(square, options);
+        (x as f64 + size / 2.0, y as f64 + size / 2.0)
+    };
+    let (x1, y1) = center(arrow.from);
+    let (x2, y2) = center(arrow.to);
+    let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+    if length == 0.0 {
+        return;
+    }
+    let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+    let head = size * options.theme.arrow_head as f64 / 100.0;
+    let width = size * options.theme.arrow_width as f64 / 100.0;
+    // The shaft stops where the head begins so the tip stays sharp.
+    let (bx, by) = (x2 - dx * head, y2 - dy * head);
+    let (px, py) = (-dy * head / 2.0, dx * head / 2.0);
+    let color = arrow.color.as_deref().unwrap_or(&options.theme.arrow);
+    let _ = write!(
+        svg,
+        r#"<g class="arrow" fill="{color}" stroke="{color}"><line x1="{x1:.1}" y1="{y1:.1}" x2="{bx:.1}" y2="{by:.1}" stroke-width="{width:.1}" stroke-linecap="round"/><polygon points="{x2:.1},{y2:.1} {:.1},{:.1} {:.1},{:.1}" stroke="none"/></g>"#,
+        bx + px,
+        by + py,
+        bx - px,
+        by - py
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn test_starting_position_diagram() {
+        let svg = render(&Board::starting_position(), &SvgOptions::default());
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="360" height="360""#));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<rect ").count(), 64);
+        assert_eq!(svg.matches(r#"class="piece""#).count(), 32);
+        assert_eq!(svg.matches(r#"class="coordinate""#).count(), 16);
+        // a1 is dark and sits in the bottom-left corner.
+        assert!(svg.contains(r##"<rect x="0" y="315" width="45" height="45" fill="#b58863"/>"##));
+    }
+
+    #[test]
+    fn test_flipped_with_highlights_and_arrows() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let options = SvgOptions {
+            theme: Theme::blue(),
+            square_size: 40,
+            flipped: true,
+            coordinates: false,
+            highlights: vec![sq("e1"), sq("e2")],
+            arrows: vec![Arrow::new(sq("e1"), sq("e2")), Arrow { color: Some("red".into()), ..Arrow::new(sq("a1"), sq("a1")) }],
+        };
+        let svg = render(&board, &options);
+        assert_eq!(svg.matches(r#"class="highlight""#).count(), 2);
+        assert!(!svg.contains("coordinate"));
+        // Flipped, e1 is in the top row, fourth column from the left.
+        assert!(svg.contains(r#"<rect class="highlight" x="120" y="0""#));
+        // A zero-length arrow draws nothing.
+        assert_eq!(svg.matches(r#"class="arrow""#).count(), 1);
+        assert!(svg.contains(&Theme::blue().arrow));
+    }
+
+    #[test]
+    fn test_theme_file() {
+        let text = "# house style\nbase = green\ndark = #4b7399\n\npieces = letters\ncoordinates = #222222\narrow_width = 20\n";
+        let theme = Theme::parse(text).unwrap();
+        assert_eq!(theme.light, Theme::green().light);
+        assert_eq!(theme.dark, "#4b7399");
+        assert_eq!((theme.pieces, theme.arrow_width, theme.arrow_head), (PieceSet::Letters, 20, 45));
+
+        let options = SvgOptions { theme, ..SvgOptions::default() };
+        let svg = render(&Board::starting_position(), &options);
+        assert!(svg.contains(r##"fill="#ffffff" stroke="#000000" stroke-width="1">K</text>"##));
+        assert_eq!(svg.matches(r##"fill="#222222""##).count(), 16);
+
+        assert!(matches!(Theme::parse("dark #000"), Err(ThemeError::Syntax(1))));
+        assert!(matches!(Theme::parse("\nborder = 2"), Err(ThemeError::UnknownKey { line: 2, .. })));
+        assert!(matches!(Theme::parse("arrow_head = 150"), Err(ThemeError::InvalidValue { line: 1, .. })));
+        assert!(matches!(Theme::load("no-such-theme.txt"), Err(ThemeError::Io(_))));
+    }
+}
@@ -0,0 +1,76 @@
+// Plain-text board diagrams for terminals: one row per rank, pieces as FEN letters
+// (uppercase White, lowercase Black) or another PieceSet, and '.' for empty squares.
+
+use crate::board::Board;
+use crate::pieces::{Color, Square};
+use crate::render::PieceSet;
+
+/// Render the board as text, with rank numbers on the left and files underneath.
+/// When `flipped`, the board is seen from Black's side.
+pub fn render(board: &Board, flipped: bool) -> String {
+    render_with(flipped, |square| square_char(board, square))
+}
+
+/// Render the board as text with pieces drawn from `pieces`, e.g. Unicode
+/// figurines for terminals that have them.
+pub fn render_with_pieces(board: &Board, flipped: bool, pieces: PieceSet) -> String {
+    render_with(flipped, |square| match board.piece_at_typed(square) {
+        Some((piece_type, color)) => pieces.symbol(piece_type, color),
+        None => '.',
+    })
+}
+
+/// The FEN letter of the piece on `square`, or '.' when it is empty.
+pub(crate) fn square_char(board: &Board, square: Square) -> char {
+    match board.piece_at_typed(square) {
+        Some((piece_type, Color::White)) => piece_type.letter(),
+        Some((piece_type, Color::Black)) => piece_type.letter().to_ascii_lowercase(),
+        None => '.',
+    }
+}
+
+/// Render the board frame with `cell` drawing each square.
+pub(crate) fn render_with(flipped: bool, cell: impl Fn(Square) -> char) -> String {
+    let order = |flip: bool| -> Vec<u8> { if flip { (0..8).collect() } else { (0..8).rev().collect() } };
+    let mut text = String::new();
+    for rank in order(flipped) {
+        text.push((b'1' + rank) as char);
+        text.push(' ');
+        for file in order(!flipped) {
+            text.push(' ');
+            text.push(cell(Square { file, rank }));
+        }
+        text.push('\n');
+    }
+    text.push_str("  ");
+    for file in order(!flipped) {
+        text.push(' ');
+        text.push((b'a' + file) as char);
+    }
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_both_sides() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        let white = render(&board, false);
+        let lines: Vec<&str> = white.lines().collect();
+        assert_eq!(lines[0], "8  . . . . k . . .");
+        assert_eq!(lines[7], "1  R . . . K . . .");
+        assert_eq!(lines[8], "   a b c d e f g h");
+
+        let black = render(&board, true);
+        let lines: Vec<&str> = black.lines().collect();
+        assert_eq!(lines[0], "1  . . . K . . . R");
+        assert_eq!(lines[8], "   h g f e d c b a");
+
+        let figurines = render_with_pieces(&board, false, PieceSet::Outline);
+        assert_eq!(figurines.lines().next(), Some("8  . . . . \u{265A} . . ."));
+        assert_eq!(render_with_pieces(&board, false, PieceSet::Letters), white);
+    }
+}
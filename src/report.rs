@@ -0,0 +1,245 @@
+// The "analyze" and "annotate" commands: search one position, or review every
+// move of a PGN game, and print the result as text or, with --json, as JSON
+// lines (one object per completed depth or per move) for scripts to consume.
+// Analysis can be answered at once from a local evaluation cache or, with the
+// online feature, the lichess cloud evaluations, before searching.
+
+use crate::annotate::{MoveAnnotation, Thresholds};
+use crate::board::Board;
+#[cfg(feature = "online")]
+use crate::cloud::CloudEval;
+use crate::engine::{Engine, SearchLimits, SearchResult, mate_moves};
+use crate::eval_cache::EvalCache;
+use crate::game::Game;
+use crate::moves::Move;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+/// Search depth when the command line gives none.
+pub const DEFAULT_DEPTH: u32 = 8;
+
+/// Options shared by both commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Options {
+    json: bool,
+    depth: u32,
+    /// Evaluation cache file to answer from and add to.
+    cache: Option<PathBuf>,
+    /// Ask the cloud evaluations before searching.
+    #[cfg(feature = "online")]
+    cloud: bool,
+    /// Arguments that are not options, e.g. the words of a FEN.
+    rest: Vec<String>,
+}
+
+fn parse_options(args: &[String]) -> io::Result<Options> {
+    let mut options = Options {
+        json: false,
+        depth: DEFAULT_DEPTH,
+        cache: None,
+        #[cfg(feature = "online")]
+        cloud: false,
+        rest: Vec::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--depth" => {
+                options.depth = args
+                    .next()
+                    .and_then(|depth| depth.parse().ok())
+                    .ok_or_else(|| invalid("--depth needs a number"))?;
+            }
+            "--cache" => options.cache = Some(args.next().ok_or_else(|| invalid("--cache needs a file"))?.into()),
+            #[cfg(feature = "online")]
+            "--cloud" => options.cloud = true,
+            _ => options.rest.push(arg.clone()),
+        }
+    }
+    Ok(options)
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+/// `rchess analyze [--json] [--depth N] [--cache FILE] [--cloud] [FEN]`:
+/// search the position (the starting position by default), reporting each
+/// completed depth. A search at least N deep or a mate in the cache, or with
+/// --cloud a cloud evaluation, is reported instead as one line. New results
+/// are added to the cache.
+pub fn analyze<W: Write>(args: &[String], mut output: W) -> io::Result<()> {
+    let options = parse_options(args)?;
+    let board = if options.rest.is_empty() {
+        Board::starting_position()
+    } else {
+        Board::from_fen(&options.rest.join(" ")).map_err(|err| invalid(format!("invalid FEN: {}", err)))?
+    };
+    let mut cache = options.cache.as_ref().map(EvalCache::open).transpose()?;
+    let write_result = |output: &mut W, result: &SearchResult| {
+        if options.json {
+            writeln!(output, "{}", search_json(result))
+        } else {
+            writeln!(output, "{}", search_text(result, &board))
+        }
+    };
+    if let Some(known) = cache.as_ref().and_then(|cache| cache.get(&board, options.depth)) {
+        return write_result(&mut output, &known);
+    }
+    let mut written = Ok(());
+    let result = match cloud_eval(&options, &board) {
+        Some(result) => {
+            written = write_result(&mut output, &result);
+            result
+        }
+        None => Engine::new().search_with_info(&board, SearchLimits::depth(options.depth), |result| {
+            if written.is_ok() {
+                written = write_result(&mut output, result);
+            }
+        }),
+    };
+    if let (Some(cache), Some(path)) = (&mut cache, &options.cache) {
+        cache.insert(&board, &result);
+        cache.save(path)?;
+    }
+    written
+}
+
+#[cfg(feature = "online")]
+fn cloud_eval(options: &Options, board: &Board) -> Option<SearchResult> {
+    options.cloud.then(|| CloudEval::default().lookup(board)).flatten()
+}
+
+#[cfg(not(feature = "online"))]
+fn cloud_eval(_: &Options, _: &Board) -> Option<SearchResult> {
+    None
+}
+
+/// `rchess annotate [--json] [--depth N]`: review the first game of the PGN
+/// read from `input`. Text output is the game as PGN with the judged moves
+/// marked; JSON output is one object per move.
+pub fn annotate<R: Read, W: Write>(args: &[String], mut input: R, mut output: W) -> io::Result<()> {
+    let options = parse_options(args)?;
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+    let mut game = Game::from_pgn(&text).map_err(|err| invalid(format!("invalid PGN: {}", err)))?;
+    let limits = SearchLimits::depth(options.depth);
+    let annotations = game.annotate(&mut Engine::new(), limits, &Thresholds::default());
+    if !options.json {
+        return write!(output, "{}", game.to_pgn());
+    }
+    for annotation in &annotations {
+        writeln!(output, "{}", annotation_json(annotation))?;
+    }
+    Ok(())
+}
+
+/// One completed iteration as a JSON object. Scores are from the side to
+/// move's point of view; "mate", in moves, is set instead of "cp" for forced mates.
+pub fn search_json(result: &SearchResult) -> String {
+    let score = match mate_moves(result.score) {
+        Some(moves) => format!("\"mate\":{}", moves),
+        None => format!("\"cp\":{}", result.score),
+    };
+    format!(
+        "{{\"depth\":{},{},\"nodes\":{},\"time_ms\":{},\"pv\":[{}]}}",
+        result.depth,
+        score,
+        result.nodes,
+        result.time_ms,
+        uci_list(&result.pv)
+    )
+}
+
+/// One reviewed move as a JSON object, with moves in UCI notation and the
+/// evaluation from White's point of view.
+pub fn annotation_json(annotation: &MoveAnnotation) -> String {
+    let best = annotation.best_move.map_or("null".to_string(), |mv| format!("\"{}\"", mv.to_uci()));
+    let judgement = annotation
+        .judgement
+        .map_or("null".to_string(), |judgement| format!("\"{}\"", judgement.to_string().to_lowercase()));
+    format!(
+        "{{\"ply\":{},\"color\":\"{}\",\"move\":\"{}\",\"best\":{},\"eval\":{},\"loss\":{},\"judgement\":{}}}",
+        annotation.index + 1,
+        annotation.mover.to_string().to_lowercase(),
+        annotation.mv.to_uci(),
+        best,
+        annotation.eval,
+        annotation.loss,
+        judgement
+    )
+}
+
+fn search_text(result: &SearchResult, board: &Board) -> String {
+    let score = match mate_moves(result.score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    let mut line = board.clone();
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|&mv| {
+            let san = mv.to_san(&line);
+            line.make_move(mv);
+            san
+        })
+        .collect();
+    format!("depth {} score {} nodes {} pv {}", result.depth, score, result.nodes, pv.join(" "))
+}
+
+fn uci_list(moves: &[Move]) -> String {
+    moves.iter().map(|mv| format!("\"{}\"", mv.to_uci())).collect::<Vec<_>>().join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_analyze_json_lines() {
+        let mut output = Vec::new();
+        analyze(&args(&["--json", "--depth", "3", "6k1/5ppp/8/8/8/8/8/R5K1", "w", "-", "-"]), &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("{\"depth\":1,"));
+        let last = lines.last().unwrap();
+        assert!(last.contains("\"mate\":1"));
+        assert!(last.contains("\"pv\":[\"a1a8\"]"));
+        assert!(analyze(&args(&["--depth"]), Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_analyze_answers_from_the_cache() {
+        let path = std::env::temp_dir().join(format!("rchess-eval-cache-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let words = ["--json", "--depth", "3", "--cache", path.to_str().unwrap(), "6k1/5ppp/8/8/8/8/8/R5K1", "w", "-", "-"];
+        let mut searched = Vec::new();
+        analyze(&args(&words), &mut searched).unwrap();
+        assert_eq!(EvalCache::open(&path).unwrap().len(), 1);
+
+        let mut cached = Vec::new();
+        analyze(&args(&words), &mut cached).unwrap();
+        let cached = String::from_utf8(cached).unwrap();
+        assert_eq!(cached.lines().count(), 1);
+        assert!(cached.contains("\"mate\":1") && cached.contains("\"nodes\":0"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_annotate_json_lines() {
+        let pgn = "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0";
+        let mut output = Vec::new();
+        annotate(&args(&["--json", "--depth", "3"]), pgn.as_bytes(), &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[5].starts_with("{\"ply\":6,\"color\":\"black\",\"move\":\"g8f6\","));
+        assert!(lines[5].ends_with("\"judgement\":\"blunder\"}"));
+    }
+}
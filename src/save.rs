@@ -0,0 +1,174 @@
+// Save files: a board's or game's GameState, move history and clock included, as
+// a length-prefixed protobuf message behind a magic header and a format version byte.
+//
+// Layout: b"RCHS", version (u8), varint length, prost-encoded GameState.
+// Loading dispatches on the version, so older layouts can be migrated when the
+// format changes.
+
+use crate::board::Board;
+use crate::error::Error;
+use crate::game::Game;
+use crate::rchess::v1::{self as proto};
+use prost::Message;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// First bytes of every save file.
+pub const MAGIC: [u8; 4] = *b"RCHS";
+
+/// Format version written by `save`.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Why a save file could not be read or written.
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    /// The file does not start with `MAGIC`.
+    NotASave,
+    /// The file was written by a newer, unknown format version.
+    UnsupportedVersion(u8),
+    /// The payload is not a valid GameState.
+    Decode(prost::DecodeError),
+    /// The GameState decodes but does not describe a position.
+    Invalid(Error),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::NotASave => write!(f, "not an rchess save file"),
+            SaveError::UnsupportedVersion(v) => write!(f, "unsupported save format version {}", v),
+            SaveError::Decode(e) => write!(f, "corrupt save data: {}", e),
+            SaveError::Invalid(e) => write!(f, "invalid saved position: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaveError::Io(e) => Some(e),
+            SaveError::Decode(e) => Some(e),
+            SaveError::Invalid(e) => Some(e),
+            SaveError::NotASave | SaveError::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<prost::DecodeError> for SaveError {
+    fn from(e: prost::DecodeError) -> Self {
+        SaveError::Decode(e)
+    }
+}
+
+impl From<Error> for SaveError {
+    fn from(e: Error) -> Self {
+        SaveError::Invalid(e)
+    }
+}
+
+impl Board {
+    /// Write the position and its move history to `path`, replacing the file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_save(&mut out)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Read a board written by `save`. Moves in the loaded history cannot be undone.
+    pub fn load(path: impl AsRef<Path>) -> Result<Board, SaveError> {
+        Board::read_save(BufReader::new(File::open(path)?))
+    }
+
+    /// Write the save format to any writer.
+    pub fn write_save<W: Write>(&self, out: W) -> Result<(), SaveError> {
+        write_state(out, &self.to_proto())
+    }
+
+    /// Read the save format from any reader.
+    pub fn read_save<R: Read>(input: R) -> Result<Board, SaveError> {
+        Ok(Board::try_from_proto(read_state(input)?)?)
+    }
+}
+
+impl Game {
+    /// Write the game's position, move history, and clock to `path`, replacing
+    /// the file. Tags, events, and the result are not saved.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        let mut out = BufWriter::new(File::create(path)?);
+        write_state(&mut out, &self.to_proto())?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Read a game written by `save`, as `Game::try_from_proto` restores it.
+    pub fn load(path: impl AsRef<Path>) -> Result<Game, SaveError> {
+        let state = read_state(BufReader::new(File::open(path)?))?;
+        Ok(Game::try_from_proto(state)?)
+    }
+}
+
+fn write_state<W: Write>(mut out: W, state: &proto::GameState) -> Result<(), SaveError> {
+    out.write_all(&MAGIC)?;
+    out.write_all(&[FORMAT_VERSION])?;
+    out.write_all(&state.encode_length_delimited_to_vec())?;
+    Ok(())
+}
+
+fn read_state<R: Read>(mut input: R) -> Result<proto::GameState, SaveError> {
+    let mut header = [0; 5];
+    match input.read_exact(&mut header) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Err(SaveError::NotASave),
+        result => result?,
+    }
+    if header[..4] != MAGIC {
+        return Err(SaveError::NotASave);
+    }
+    let mut payload = Vec::new();
+    input.read_to_end(&mut payload)?;
+    match header[4] {
+        1 => Ok(proto::GameState::decode_length_delimited(payload.as_slice())?),
+        version => Err(SaveError::UnsupportedVersion(version)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+    use crate::pieces::Square;
+
+    #[test]
+    fn test_round_trip_keeps_history() {
+        let mut board = Board::starting_position();
+        for (from, to) in [("e2", "e4"), ("c7", "c5"), ("g1", "f3")] {
+            let mv = Move::new(Square::from_algebraic(from).unwrap(), Square::from_algebraic(to).unwrap());
+            board.apply_move(mv).unwrap();
+        }
+        let path = std::env::temp_dir().join(format!("rchess-save-test-{}.bin", std::process::id()));
+        board.save(&path).unwrap();
+        let loaded = Board::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.to_fen(), board.to_fen());
+        assert_eq!(loaded.to_proto().moves, board.to_proto().moves);
+        assert_eq!(loaded.to_proto().moves.len(), 3);
+    }
+
+    #[test]
+    fn test_rejects_foreign_and_future_files() {
+        assert!(matches!(Board::read_save(&b"PK\x03\x04"[..]), Err(SaveError::NotASave)));
+        assert!(matches!(Board::read_save(&b"RCHS\x09\x00"[..]), Err(SaveError::UnsupportedVersion(9))));
+        assert!(matches!(Board::read_save(&b"RCHS\x01\x05\x0a"[..]), Err(SaveError::Decode(_))));
+    }
+}
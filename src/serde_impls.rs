@@ -0,0 +1,357 @@
+// Serialize/Deserialize for the core types, behind the "serde" feature.
+// Squares are algebraic strings ("e4"), colors and piece types lowercase names
+// ("white", "knight"), and moves and snapshots maps with snake_case field names.
+
+use crate::moves::{CastlingSide, Move};
+use crate::pieces::{Color, PieceType, Square};
+use crate::snapshot::{BoardSnapshot, SnapshotPiece};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Unexpected, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+/// Visitor for types written as a single string.
+struct TextVisitor<T> {
+    parse: fn(&str) -> Option<T>,
+    expecting: &'static str,
+}
+
+impl<T> Visitor<'_> for TextVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<T, E> {
+        (self.parse)(text).ok_or_else(|| E::invalid_value(Unexpected::Str(text), &self))
+    }
+}
+
+fn deserialize_text<'de, D: Deserializer<'de>, T>(
+    deserializer: D,
+    parse: fn(&str) -> Option<T>,
+    expecting: &'static str,
+) -> Result<T, D::Error> {
+    deserializer.deserialize_str(TextVisitor { parse, expecting })
+}
+
+impl Serialize for Square {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_algebraic())
+    }
+}
+
+impl<'de> Deserialize<'de> for Square {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_text(deserializer, Square::from_algebraic, "a square such as \"e4\"")
+    }
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(color_name(*self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let parse = |text: &str| [Color::White, Color::Black].into_iter().find(|&c| color_name(c) == text);
+        deserialize_text(deserializer, parse, "\"white\" or \"black\"")
+    }
+}
+
+fn piece_name(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::King => "king",
+        PieceType::Queen => "queen",
+        PieceType::Rook => "rook",
+        PieceType::Bishop => "bishop",
+        PieceType::Knight => "knight",
+        PieceType::Pawn => "pawn",
+    }
+}
+
+impl Serialize for PieceType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(piece_name(*self))
+    }
+}
+
+impl<'de> Deserialize<'de> for PieceType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let parse = |text: &str| {
+            [
+                PieceType::King,
+                PieceType::Queen,
+                PieceType::Rook,
+                PieceType::Bishop,
+                PieceType::Knight,
+                PieceType::Pawn,
+            ]
+            .into_iter()
+            .find(|&p| piece_name(p) == text)
+        };
+        deserialize_text(deserializer, parse, "a piece type such as \"knight\"")
+    }
+}
+
+impl Serialize for CastlingSide {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            CastlingSide::Kingside => "kingside",
+            CastlingSide::Queenside => "queenside",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for CastlingSide {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let parse = |text: &str| match text {
+            "kingside" => Some(CastlingSide::Kingside),
+            "queenside" => Some(CastlingSide::Queenside),
+            _ => None,
+        };
+        deserialize_text(deserializer, parse, "\"kingside\" or \"queenside\"")
+    }
+}
+
+const MOVE_FIELDS: &[&str] = &["from", "to", "promotion", "piece", "capture", "castling", "en_passant", "drop"];
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Move", MOVE_FIELDS.len())?;
+        state.serialize_field("from", &self.from)?;
+        state.serialize_field("to", &self.to)?;
+        state.serialize_field("promotion", &self.promotion)?;
+        state.serialize_field("piece", &self.piece)?;
+        state.serialize_field("capture", &self.capture)?;
+        state.serialize_field("castling", &self.castling)?;
+        state.serialize_field("en_passant", &self.en_passant)?;
+        state.serialize_field("drop", &self.drop)?;
+        state.end()
+    }
+}
+
+/// Reads a Move map. Only "from" and "to" are required, and "from" may be left
+/// out of a drop; the metadata fields default to unknown.
+struct MoveVisitor;
+
+impl<'de> Visitor<'de> for MoveVisitor {
+    type Value = Move;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a move map with \"from\" and \"to\" squares")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Move, A::Error> {
+        let (mut from, mut to) = (None, None);
+        let mut mv = Move::new(Square { file: 0, rank: 0 }, Square { file: 0, rank: 0 });
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "from" => from = Some(map.next_value()?),
+                "to" => to = Some(map.next_value()?),
+                "promotion" => mv.promotion = map.next_value()?,
+                "piece" => mv.piece = map.next_value()?,
+                "capture" => mv.capture = map.next_value()?,
+                "castling" => mv.castling = map.next_value()?,
+                "en_passant" => mv.en_passant = map.next_value()?,
+                "drop" => mv.drop = map.next_value()?,
+                _ => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+            }
+        }
+        mv.to = to.ok_or_else(|| de::Error::missing_field("to"))?;
+        mv.from = match from {
+            Some(from) => from,
+            None if mv.is_drop() => mv.to,
+            None => return Err(de::Error::missing_field("from")),
+        };
+        Ok(mv)
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Move", MOVE_FIELDS, MoveVisitor)
+    }
+}
+
+const PIECE_FIELDS: &[&str] = &["square", "color", "piece_type"];
+
+impl Serialize for SnapshotPiece {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SnapshotPiece", PIECE_FIELDS.len())?;
+        state.serialize_field("square", &self.square)?;
+        state.serialize_field("color", &self.color)?;
+        state.serialize_field("piece_type", &self.piece_type)?;
+        state.end()
+    }
+}
+
+struct SnapshotPieceVisitor;
+
+impl<'de> Visitor<'de> for SnapshotPieceVisitor {
+    type Value = SnapshotPiece;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a piece map with square, color, and piece_type")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<SnapshotPiece, A::Error> {
+        let (mut square, mut color, mut piece_type) = (None, None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "square" => square = Some(map.next_value()?),
+                "color" => color = Some(map.next_value()?),
+                "piece_type" => piece_type = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(SnapshotPiece {
+            square: square.ok_or_else(|| de::Error::missing_field("square"))?,
+            color: color.ok_or_else(|| de::Error::missing_field("color"))?,
+            piece_type: piece_type.ok_or_else(|| de::Error::missing_field("piece_type"))?,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for SnapshotPiece {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("SnapshotPiece", PIECE_FIELDS, SnapshotPieceVisitor)
+    }
+}
+
+const SNAPSHOT_FIELDS: &[&str] = &[
+    "fen",
+    "side_to_move",
+    "pieces",
+    "castling",
+    "en_passant",
+    "halfmove_clock",
+    "fullmove_number",
+    "check",
+];
+
+impl Serialize for BoardSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("BoardSnapshot", SNAPSHOT_FIELDS.len())?;
+        state.serialize_field("fen", &self.fen)?;
+        state.serialize_field("side_to_move", &self.side_to_move)?;
+        state.serialize_field("pieces", &self.pieces)?;
+        state.serialize_field("castling", &self.castling)?;
+        state.serialize_field("en_passant", &self.en_passant)?;
+        state.serialize_field("halfmove_clock", &self.halfmove_clock)?;
+        state.serialize_field("fullmove_number", &self.fullmove_number)?;
+        state.serialize_field("check", &self.check)?;
+        state.end()
+    }
+}
+
+/// Reads a BoardSnapshot map. The FEN is authoritative; the other fields fall
+/// back to what it says when missing.
+struct SnapshotVisitor;
+
+impl<'de> Visitor<'de> for SnapshotVisitor {
+    type Value = BoardSnapshot;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a board snapshot map with a fen field")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<BoardSnapshot, A::Error> {
+        let mut fen: Option<String> = None;
+        let mut side_to_move = None;
+        let mut pieces = None;
+        let mut castling = None;
+        let mut en_passant = None;
+        let mut halfmove_clock = None;
+        let mut fullmove_number = None;
+        let mut check = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "fen" => fen = Some(map.next_value()?),
+                "side_to_move" => side_to_move = Some(map.next_value()?),
+                "pieces" => pieces = Some(map.next_value()?),
+                "castling" => castling = Some(map.next_value()?),
+                "en_passant" => en_passant = Some(map.next_value()?),
+                "halfmove_clock" => halfmove_clock = Some(map.next_value()?),
+                "fullmove_number" => fullmove_number = Some(map.next_value()?),
+                "check" => check = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+            }
+        }
+        let fen = fen.ok_or_else(|| de::Error::missing_field("fen"))?;
+        let defaults = crate::board::Board::from_fen(&fen)
+            .map_err(|err| de::Error::invalid_value(Unexpected::Str(&fen), &err.to_string().as_str()))?
+            .snapshot();
+        Ok(BoardSnapshot {
+            side_to_move: side_to_move.unwrap_or(defaults.side_to_move),
+            pieces: pieces.unwrap_or(defaults.pieces),
+            castling: castling.unwrap_or(defaults.castling),
+            en_passant: en_passant.unwrap_or(defaults.en_passant),
+            halfmove_clock: halfmove_clock.unwrap_or(defaults.halfmove_clock),
+            fullmove_number: fullmove_number.unwrap_or(defaults.fullmove_number),
+            check: check.unwrap_or(defaults.check),
+            fen,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for BoardSnapshot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("BoardSnapshot", SNAPSHOT_FIELDS, SnapshotVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::value::{Error, MapDeserializer, StrDeserializer};
+
+    fn from_str<'de, T: Deserialize<'de>>(text: &'de str) -> Result<T, Error> {
+        T::deserialize(StrDeserializer::<Error>::new(text))
+    }
+
+    #[test]
+    fn test_text_values() {
+        assert_eq!(from_str::<Square>("e4"), Ok(Square::from_algebraic("e4").unwrap()));
+        assert_eq!(from_str::<Color>("black"), Ok(Color::Black));
+        assert_eq!(from_str::<PieceType>("knight"), Ok(PieceType::Knight));
+        assert_eq!(from_str::<CastlingSide>("queenside"), Ok(CastlingSide::Queenside));
+        assert!(from_str::<Square>("z9").is_err());
+        assert!(from_str::<Color>("White").is_err());
+    }
+
+    #[test]
+    fn test_move_from_map() {
+        let entries = [("from", "e7"), ("to", "e8"), ("comment", "ignored")];
+        let mv = Move::deserialize(MapDeserializer::<_, Error>::new(entries.into_iter())).unwrap();
+        assert_eq!(mv.from, Square::from_algebraic("e7").unwrap());
+        assert_eq!((mv.promotion, mv.piece, mv.en_passant), (None, None, false));
+
+        let missing = [("from", "e2")];
+        let err = Move::deserialize(MapDeserializer::<_, Error>::new(missing.into_iter())).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `to`");
+    }
+
+    #[test]
+    fn test_snapshot_fills_fields_from_fen() {
+        let entries = [("fen", "4k3/8/8/8/8/8/8/R3K3 b Q - 3 40")];
+        let snapshot = BoardSnapshot::deserialize(MapDeserializer::<_, Error>::new(entries.into_iter())).unwrap();
+        assert_eq!(snapshot.side_to_move, Color::Black);
+        assert_eq!((snapshot.castling.as_str(), snapshot.fullmove_number), ("Q", 40));
+        assert_eq!(snapshot.pieces.len(), 3);
+    }
+}
@@ -0,0 +1,75 @@
+// Ed25519 signatures on annotated game records, so tournament archives can be
+// proven untampered: the server and the players each sign a finished game's
+// record, and anyone holding their public keys can check it later.
+//
+// Signatures cover the same bytes as the record's checksum (record::signed_content)
+// and are kept in the record. They do not cover each other, so signers can sign
+// in any order.
+
+use crate::rchess::v1::{self as proto};
+use crate::record::{IntegrityError, signed_content};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+/// Sign `record` as `signer` with `key`, replacing any earlier signature by the
+/// same key.
+pub fn sign(record: &mut proto::AnnotatedGame, signer: &str, key: &SigningKey) {
+    let public_key = key.verifying_key().to_bytes().to_vec();
+    let signature = key.sign(&signed_content(record)).to_bytes().to_vec();
+    record.signatures.retain(|existing| existing.public_key != public_key);
+    record.signatures.push(proto::RecordSignature { signer: signer.to_string(), public_key, signature });
+}
+
+/// Check every signature in `record` and return how many there are.
+pub fn verify_signatures(record: &proto::AnnotatedGame) -> Result<usize, IntegrityError> {
+    let content = signed_content(record);
+    for signature in &record.signatures {
+        verify(signature, &content)?;
+    }
+    Ok(record.signatures.len())
+}
+
+/// Check that `key` signed `record`.
+pub fn verify_signed_by(record: &proto::AnnotatedGame, key: &VerifyingKey) -> Result<(), IntegrityError> {
+    let signature = record
+        .signatures
+        .iter()
+        .find(|signature| signature.public_key == key.as_bytes())
+        .ok_or(IntegrityError::Unsigned)?;
+    verify(signature, &signed_content(record))
+}
+
+fn verify(signature: &proto::RecordSignature, content: &[u8]) -> Result<(), IntegrityError> {
+    let bad = || IntegrityError::BadSignature(signature.signer.clone());
+    let public_key: [u8; 32] = signature.public_key.as_slice().try_into().map_err(|_| bad())?;
+    let key = VerifyingKey::from_bytes(&public_key).map_err(|_| bad())?;
+    let bytes = Signature::from_slice(&signature.signature).map_err(|_| bad())?;
+    key.verify(content, &bytes).map_err(|_| bad())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::moves::Move;
+    use crate::sq;
+
+    #[test]
+    fn test_signatures_prove_the_record() {
+        let mut game = Game::new();
+        game.play(Move::new(sq!("c2"), sq!("c4"))).unwrap();
+        let mut record = game.to_annotated_proto();
+        let server = SigningKey::from_bytes(&[1; 32]);
+        let player = SigningKey::from_bytes(&[2; 32]);
+        sign(&mut record, "server", &server);
+        sign(&mut record, "alice", &player);
+        sign(&mut record, "server", &server);
+        assert_eq!(verify_signatures(&record), Ok(2));
+        assert_eq!(verify_signed_by(&record, &player.verifying_key()), Ok(()));
+        let stranger = SigningKey::from_bytes(&[3; 32]);
+        assert_eq!(verify_signed_by(&record, &stranger.verifying_key()), Err(IntegrityError::Unsigned));
+
+        record.tags.push(proto::KeyValue { key: "Result".to_string(), value: "1-0".to_string() });
+        assert_eq!(verify_signatures(&record), Err(IntegrityError::BadSignature("alice".to_string())));
+        assert!(verify_signed_by(&record, &player.verifying_key()).is_err());
+    }
+}
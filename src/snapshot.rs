@@ -0,0 +1,87 @@
+// Flat view of a position for web clients: a piece list keyed by square plus the
+// FEN fields spelled out. With the "serde" feature it serializes to plain JSON.
+
+use crate::board::Board;
+use crate::fen::FenError;
+use crate::pieces::{Color, PieceType, Square};
+
+/// A piece and the square it stands on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotPiece {
+    pub square: Square,
+    pub color: Color,
+    pub piece_type: PieceType,
+}
+
+/// Everything needed to draw a position, without the proto piece list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardSnapshot {
+    pub fen: String,
+    pub side_to_move: Color,
+    /// Pieces in square order, a1 to h8.
+    pub pieces: Vec<SnapshotPiece>,
+    /// Castling field as in FEN, e.g. "KQkq" or "-".
+    pub castling: String,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: i32,
+    pub fullmove_number: i32,
+    /// Whether the side to move is in check.
+    pub check: bool,
+}
+
+impl BoardSnapshot {
+    /// Rebuild the position from the snapshot's FEN. History is not kept.
+    pub fn to_board(&self) -> Result<Board, FenError> {
+        Board::from_fen(&self.fen)
+    }
+}
+
+impl Board {
+    /// Snapshot of the current position.
+    pub fn snapshot(&self) -> BoardSnapshot {
+        let fen = self.to_fen();
+        let castling = fen.split(' ').nth(2).unwrap_or("-").to_string();
+        let pieces = (0..64)
+            .filter_map(|index| Square::new(index % 8, index / 8))
+            .filter_map(|square| {
+                self.piece_at_typed(square).map(|(piece_type, color)| SnapshotPiece {
+                    square,
+                    color,
+                    piece_type,
+                })
+            })
+            .collect();
+        let side_to_move = self.current_player();
+        BoardSnapshot {
+            side_to_move,
+            pieces,
+            castling,
+            en_passant: self.en_passant_target(),
+            halfmove_clock: self.halfmove_clock(),
+            fullmove_number: self.fullmove_number(),
+            check: self.is_in_check(side_to_move),
+            fen,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_of_starting_position() {
+        let snapshot = Board::starting_position().snapshot();
+        assert_eq!(snapshot.pieces.len(), 32);
+        assert_eq!(
+            snapshot.pieces[4],
+            SnapshotPiece {
+                square: Square::from_algebraic("e1").unwrap(),
+                color: Color::White,
+                piece_type: PieceType::King,
+            }
+        );
+        assert_eq!((snapshot.castling.as_str(), snapshot.en_passant, snapshot.check), ("KQkq", None, false));
+        assert_eq!(snapshot.to_board().unwrap().to_fen(), snapshot.fen);
+    }
+}
@@ -0,0 +1,181 @@
+// Whether a position ends the game, and how. The board's variant is asked first,
+// then checkmate and stalemate, then the fifty-move and repetition draws. Flag
+// falls, resignations, agreed draws, and adjudications come from the game rather
+// than the position; see Game::game_status.
+
+use crate::board::Board;
+use crate::game::GameResult;
+use crate::material::MaterialCount;
+use crate::pieces::Color;
+use std::fmt;
+
+/// Halfmoves without a capture or pawn move after which the game is drawn.
+pub const FIFTY_MOVE_PLIES: i32 = 100;
+
+/// How a position stands: still in play, or finished and why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate { winner: Color },
+    Stalemate,
+    /// A hundred halfmoves without a capture or pawn move.
+    FiftyMoveRule,
+    /// The same position with the same side to move for the third time.
+    ThreefoldRepetition,
+    /// Won under the variant's own rules, e.g. a third check in three-check.
+    VariantWin { winner: Color, reason: &'static str },
+    /// The loser's time ran out.
+    TimeForfeit { winner: Color },
+    /// A flag fell, but the other side has no material to mate with.
+    TimeoutDraw,
+    /// The loser resigned.
+    Resignation { winner: Color },
+    /// Both sides agreed to a draw.
+    DrawAgreed,
+    /// Settled by an arbiter or adjudicator rather than played out.
+    Adjudicated { result: GameResult },
+}
+
+impl GameStatus {
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::Ongoing
+    }
+
+    /// The winner, if the game ended decisively.
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            GameStatus::Checkmate { winner }
+            | GameStatus::VariantWin { winner, .. }
+            | GameStatus::TimeForfeit { winner }
+            | GameStatus::Resignation { winner } => Some(*winner),
+            GameStatus::Adjudicated { result } => result.winner(),
+            _ => None,
+        }
+    }
+
+    /// The game result this status amounts to.
+    pub fn result(&self) -> GameResult {
+        match (self.winner(), self) {
+            (Some(Color::White), _) => GameResult::WhiteWins,
+            (Some(Color::Black), _) => GameResult::BlackWins,
+            (None, GameStatus::Ongoing) => GameResult::Ongoing,
+            (None, GameStatus::Adjudicated { result }) => *result,
+            (None, _) => GameResult::Draw,
+        }
+    }
+}
+
+impl fmt::Display for GameStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameStatus::Ongoing => write!(f, "Game in progress"),
+            GameStatus::Checkmate { winner } => write!(f, "Checkmate, {} wins", winner),
+            GameStatus::Stalemate => write!(f, "Stalemate, draw"),
+            GameStatus::FiftyMoveRule => write!(f, "Draw by the fifty-move rule"),
+            GameStatus::ThreefoldRepetition => write!(f, "Draw by threefold repetition"),
+            GameStatus::VariantWin { winner, reason } => write!(f, "{} wins, {}", winner, reason),
+            GameStatus::TimeForfeit { winner } => write!(f, "{} wins on time", winner),
+            GameStatus::TimeoutDraw => write!(f, "Draw, time out against insufficient material"),
+            GameStatus::Resignation { winner } => write!(f, "{} resigns, {} wins", winner.opposite(), winner),
+            GameStatus::DrawAgreed => write!(f, "Draw by agreement"),
+            GameStatus::Adjudicated { result } => match result.winner() {
+                Some(winner) => write!(f, "{} wins by adjudication", winner),
+                None => write!(f, "Draw by adjudication"),
+            },
+        }
+    }
+}
+
+impl Board {
+    /// Status of the current position under the board's variant.
+    pub fn game_status(&self) -> GameStatus {
+        if let Some(status) = self.variant().outcome(self) {
+            return status;
+        }
+        let side = self.current_player();
+        if self.legal_moves(side).is_empty() {
+            return if self.is_in_check(side) {
+                GameStatus::Checkmate { winner: side.opposite() }
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+        if self.halfmove_clock() >= FIFTY_MOVE_PLIES {
+            GameStatus::FiftyMoveRule
+        } else if self.repetition_count() >= 3 {
+            GameStatus::ThreefoldRepetition
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+
+    /// Whether `color` could still mate, with the opponent's help: any pawn,
+    /// rook or queen does, as do two minor pieces, or one while the opponent has
+    /// something to block with.
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let own = self.material(color);
+        let minors = own.bishops + own.knights;
+        own.pawns + own.rooks + own.queens > 0
+            || minors >= 2
+            || (minors == 1 && self.material(color.opposite()) != MaterialCount::default())
+    }
+
+    /// How many times the current position has occurred, counting now. Only moves
+    /// made on this board since the last capture or pawn move are looked at.
+    pub fn repetition_count(&self) -> usize {
+        let key = self.zobrist_key();
+        let mut earlier = self.clone();
+        let mut count = 1;
+        // A position can only repeat every other ply.
+        for ply in 1..=self.halfmove_clock() {
+            if earlier.unmake_move().is_none() {
+                break;
+            }
+            if ply % 2 == 0 && earlier.zobrist_key() == key {
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+    use crate::pieces::Square;
+
+    fn play(board: &mut Board, moves: &[(&str, &str)]) {
+        for (from, to) in moves {
+            let mv = Move::new(Square::from_algebraic(from).unwrap(), Square::from_algebraic(to).unwrap());
+            board.apply_move(mv).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_mate_stalemate_and_fifty_moves() {
+        let mut board = Board::starting_position();
+        assert_eq!(board.game_status(), GameStatus::Ongoing);
+        play(&mut board, &[("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")]);
+        let status = board.game_status();
+        assert_eq!(status, GameStatus::Checkmate { winner: Color::Black });
+        assert_eq!(status.result(), GameResult::BlackWins);
+        assert_eq!(status.to_string(), "Checkmate, Black wins");
+
+        let stalemate = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(stalemate.game_status(), GameStatus::Stalemate);
+        let fifty = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 100 80").unwrap();
+        assert_eq!(fifty.game_status().result(), GameResult::Draw);
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let mut board = Board::starting_position();
+        let shuffle = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+        play(&mut board, &shuffle);
+        assert_eq!(board.repetition_count(), 2);
+        play(&mut board, &shuffle);
+        assert_eq!(board.repetition_count(), 3);
+        assert_eq!(board.game_status(), GameStatus::ThreefoldRepetition);
+    }
+}
@@ -0,0 +1,1349 @@
+// Syzygy endgame tablebases: win/draw/loss (.rtbw) and distance-to-zero (.rtbz)
+// files for positions with up to six pieces.
+//
+// Each file holds one table per side to move (and, with pawns, per file of the
+// leading pawn) of pair-compressed, Huffman-coded values indexed by a canonical
+// encoding of the piece placement. Decoding follows the reference prober. Files
+// are found by name when a directory is added; a table's header is read on its
+// first probe, and after that each lookup reads one compressed block.
+
+use crate::bitboard::{Bitboard, king_attacks};
+use crate::board::Board;
+use crate::game::GameResult;
+use crate::material::MaterialCount;
+use crate::moves::Move;
+use crate::pieces::{Color, PieceType, Square};
+use crate::status::FIFTY_MOVE_PLIES;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Most pieces, kings included, a probed position may have.
+pub const MAX_PIECES: usize = 6;
+
+/// File name extensions of the two table kinds.
+pub const WDL_EXTENSION: &str = "rtbw";
+pub const DTZ_EXTENSION: &str = "rtbz";
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+/// Result of a position for the side to move. Cursed wins and blessed losses are
+/// wins and losses that the fifty-move rule turns into draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Wdl {
+        match value {
+            ..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+
+    /// The same result seen from the other side.
+    pub fn flipped(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+/// Why tablebases could not be opened.
+#[derive(Debug)]
+pub enum TablebaseError {
+    Io(io::Error),
+    /// The directory holds no table files.
+    NoTables(PathBuf),
+}
+
+impl fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TablebaseError::Io(e) => write!(f, "{}", e),
+            TablebaseError::NoTables(dir) => write!(f, "no tablebase files in {}", dir.display()),
+        }
+    }
+}
+
+impl std::error::Error for TablebaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TablebaseError::Io(e) => Some(e),
+            TablebaseError::NoTables(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for TablebaseError {
+    fn from(e: io::Error) -> Self {
+        TablebaseError::Io(e)
+    }
+}
+
+/// White's and Black's material, the key tables are found by.
+type Material = (MaterialCount, MaterialCount);
+
+/// A set of Syzygy tables, found by name in one or more directories.
+#[derive(Default)]
+pub struct Tablebase {
+    wdl: HashMap<Material, Arc<Table>>,
+    dtz: HashMap<Material, Arc<Table>>,
+    max_pieces: usize,
+}
+
+impl fmt::Debug for Tablebase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tablebase")
+            .field("wdl_tables", &self.wdl_tables())
+            .field("dtz_tables", &self.dtz_tables())
+            .field("max_pieces", &self.max_pieces)
+            .finish()
+    }
+}
+
+impl Tablebase {
+    /// Open the tables in `dir`.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Tablebase, TablebaseError> {
+        let mut tablebase = Tablebase::default();
+        tablebase.add_directory(dir)?;
+        Ok(tablebase)
+    }
+
+    /// Add the tables in `dir`. Files whose name is not a material signature
+    /// such as `KRvKN.rtbw`, or whose header is not a Syzygy one, are skipped.
+    pub fn add_directory(&mut self, dir: impl AsRef<Path>) -> Result<(), TablebaseError> {
+        let dir = dir.as_ref();
+        let mut found = false;
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(kind) = path.extension().and_then(|ext| ext.to_str()).and_then(Kind::from_extension) else {
+                continue;
+            };
+            let Some((white, black)) = path.file_stem().and_then(|stem| stem.to_str()).and_then(parse_signature)
+            else {
+                continue;
+            };
+            let pieces = (white.pieces() + black.pieces() + 2) as usize;
+            if pieces > MAX_PIECES || !has_magic(&path, kind.magic()) {
+                continue;
+            }
+            let table = Arc::new(Table::new(kind, path, (white, black)));
+            let tables = match kind {
+                Kind::Wdl => &mut self.wdl,
+                Kind::Dtz => &mut self.dtz,
+            };
+            tables.insert(table.key, Arc::clone(&table));
+            tables.insert(table.key2, table);
+            self.max_pieces = self.max_pieces.max(pieces);
+            found = true;
+        }
+        if !found {
+            return Err(TablebaseError::NoTables(dir.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    /// Most pieces, kings included, of any table found.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// Number of win/draw/loss tables.
+    pub fn wdl_tables(&self) -> usize {
+        count_tables(&self.wdl)
+    }
+
+    /// Number of distance-to-zero tables.
+    pub fn dtz_tables(&self) -> usize {
+        count_tables(&self.dtz)
+    }
+
+    /// Whether the tables can answer for `board`: a standard chess position with
+    /// few enough pieces and no castling rights.
+    pub fn covers(&self, board: &Board) -> bool {
+        let rights = board.castling_rights();
+        board.variant().name() == "standard"
+            && board.bitboards().occupied().count() as usize <= self.max_pieces.max(2)
+            && !(rights.white_kingside || rights.white_queenside || rights.black_kingside || rights.black_queenside)
+    }
+
+    /// Win, draw, or loss for the side to move, assuming the halfmove clock was
+    /// just reset. `None` if a table needed is missing or unreadable.
+    pub fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        if !self.covers(board) {
+            return None;
+        }
+        let (wdl, _) = self.search(&mut board.clone(), false)?;
+        Some(Wdl::from_value(wdl))
+    }
+
+    /// Distance to zero in plies: how far the side to move is from the next
+    /// capture or pawn move along the best line, positive when winning, negative
+    /// when losing, and zero in a draw. Cursed wins and blessed losses are 100
+    /// plies further from zero than the fifty-move rule allows. The value may be
+    /// one ply longer than the true distance.
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.covers(board) {
+            return None;
+        }
+        self.dtz(&mut board.clone())
+    }
+
+    /// The move that keeps the best result given the halfmove clock, reaching
+    /// zero fastest when winning and slowest when losing, with the result it
+    /// keeps. `None` without legal moves or when the tables cannot answer.
+    pub fn best_move(&self, board: &Board) -> Option<(Move, Wdl)> {
+        if !self.covers(board) {
+            return None;
+        }
+        let clock = board.halfmove_clock();
+        let mut scratch = board.clone();
+        let mut best: Option<(Move, Wdl, i32)> = None;
+        for mv in board.legal_moves(board.current_player()) {
+            scratch.make_move(mv);
+            let dtz = if scratch.halfmove_clock() == 0 {
+                self.search(&mut scratch, false).map(|(wdl, _)| dtz_before_zeroing(-wdl))
+            } else {
+                self.dtz(&mut scratch).map(|dtz| -dtz - dtz.signum())
+            };
+            let mates = scratch.is_in_check(scratch.current_player())
+                && scratch.legal_moves(scratch.current_player()).is_empty();
+            scratch.unmake_move();
+            let dtz = if mates { 1 } else { dtz? };
+            let wdl = match dtz.signum() {
+                1 if dtz + clock <= FIFTY_MOVE_PLIES => Wdl::Win,
+                1 => Wdl::CursedWin,
+                -1 if -dtz + clock <= FIFTY_MOVE_PLIES => Wdl::Loss,
+                -1 => Wdl::BlessedLoss,
+                _ => Wdl::Draw,
+            };
+            // Faster wins and slower losses both mean a larger -dtz.
+            if best.is_none_or(|(_, best_wdl, best_dtz)| (wdl, -dtz) > (best_wdl, -best_dtz)) {
+                best = Some((mv, wdl, dtz));
+            }
+        }
+        best.map(|(mv, wdl, _)| (mv, wdl))
+    }
+
+    /// The result the tables give for `board` with perfect play, counting the
+    /// halfmove clock: a win that cannot reach a capture or pawn move in time
+    /// is a draw.
+    pub fn adjudicate(&self, board: &Board) -> Option<GameResult> {
+        let side = board.current_player();
+        let winner = match self.probe_wdl(board)? {
+            Wdl::Win => side,
+            Wdl::Loss => side.opposite(),
+            _ => return Some(GameResult::Draw),
+        };
+        if self.probe_dtz(board)?.abs() + board.halfmove_clock() > FIFTY_MOVE_PLIES {
+            return Some(GameResult::Draw);
+        }
+        Some(if winner == Color::White { GameResult::WhiteWins } else { GameResult::BlackWins })
+    }
+
+    /// Best WDL value (-2 to 2) of the position, trying captures (and with
+    /// `zeroing`, pawn moves) as well as the stored value, which may be a
+    /// "don't care" when one of those moves is best. Also reports whether a
+    /// zeroing move is best, in which case the DTZ table cannot be trusted.
+    fn search(&self, board: &mut Board, zeroing: bool) -> Option<(i32, bool)> {
+        let moves = board.legal_moves(board.current_player());
+        let mut best = -2;
+        let mut searched = 0;
+        for &mv in &moves {
+            if !(mv.is_capture() || zeroing && mv.piece == Some(PieceType::Pawn)) {
+                continue;
+            }
+            searched += 1;
+            board.make_move(mv);
+            let value = self.search(board, false).map(|(value, _)| -value);
+            board.unmake_move();
+            let value = value?;
+            if value > best {
+                best = value;
+                if value >= 2 {
+                    return Some((value, true));
+                }
+            }
+        }
+        // Having tried every move, the stored value adds nothing; it is also
+        // wrong when en passant is possible.
+        let exhausted = searched > 0 && searched == moves.len();
+        let value = if exhausted {
+            best
+        } else {
+            match self.probe_table(board, Kind::Wdl, 0)? {
+                Lookup::Value(value) => value,
+                Lookup::OtherSide => return None,
+            }
+        };
+        if best >= value {
+            return Some((best, best > 0 || exhausted));
+        }
+        Some((value, false))
+    }
+
+    fn dtz(&self, board: &mut Board) -> Option<i32> {
+        let (wdl, zeroing_best) = self.search(board, true)?;
+        if wdl == 0 {
+            return Some(0);
+        }
+        if zeroing_best {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        if let Lookup::Value(dtz) = self.probe_table(board, Kind::Dtz, wdl)? {
+            let cursed = if wdl.abs() == 1 { 100 } else { 0 };
+            return Some((dtz + cursed) * wdl.signum());
+        }
+
+        // The table only stores the other side to move: search one ply and take
+        // the best distance among the moves that keep the result.
+        let mut best = i32::MAX;
+        for mv in board.legal_moves(board.current_player()) {
+            let zeroing = mv.is_capture() || mv.piece == Some(PieceType::Pawn);
+            board.make_move(mv);
+            let dtz = if zeroing {
+                self.search(board, false).map(|(value, _)| -dtz_before_zeroing(value))
+            } else {
+                self.dtz(board).map(|dtz| -dtz)
+            };
+            let mates = dtz == Some(1)
+                && board.is_in_check(board.current_player())
+                && board.legal_moves(board.current_player()).is_empty();
+            board.unmake_move();
+            let mut dtz = dtz?;
+            if mates {
+                best = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < best && dtz.signum() == wdl.signum() {
+                best = dtz;
+            }
+        }
+        // Without legal moves the side to move is mated.
+        Some(if best == i32::MAX { -1 } else { best })
+    }
+
+    fn probe_table(&self, board: &Board, kind: Kind, wdl: i32) -> Option<Lookup> {
+        let material = material(board);
+        if material.0.pieces() + material.1.pieces() == 0 {
+            return Some(Lookup::Value(0));
+        }
+        let tables = match kind {
+            Kind::Wdl => &self.wdl,
+            Kind::Dtz => &self.dtz,
+        };
+        let table = tables.get(&material)?;
+        let data = table.data()?;
+        table.lookup(data, board, material, wdl).ok()
+    }
+}
+
+/// Each table is stored under both color assignments; count it once.
+fn count_tables(tables: &HashMap<Material, Arc<Table>>) -> usize {
+    tables.iter().filter(|(key, table)| **key == table.key).count()
+}
+
+/// DTZ of a position whose best move is a capture or pawn move with result `wdl`.
+fn dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        2 => 1,
+        1 => 101,
+        -1 => -101,
+        -2 => -1,
+        _ => 0,
+    }
+}
+
+fn material(board: &Board) -> Material {
+    let bitboards = board.bitboards();
+    let count = |color| {
+        let n = |piece_type| bitboards.pieces(piece_type, color).count();
+        MaterialCount {
+            queens: n(PieceType::Queen),
+            rooks: n(PieceType::Rook),
+            bishops: n(PieceType::Bishop),
+            knights: n(PieceType::Knight),
+            pawns: n(PieceType::Pawn),
+        }
+    };
+    (count(Color::White), count(Color::Black))
+}
+
+/// Material of a table name such as "KRPvKR": White's pieces, then Black's.
+fn parse_signature(name: &str) -> Option<Material> {
+    let (white, black) = name.split_once('v')?;
+    let side = |text: &str| {
+        let mut material = MaterialCount::default();
+        let mut kings = 0;
+        for c in text.chars() {
+            match c {
+                'K' => kings += 1,
+                'Q' => material.queens += 1,
+                'R' => material.rooks += 1,
+                'B' => material.bishops += 1,
+                'N' => material.knights += 1,
+                'P' => material.pawns += 1,
+                _ => return None,
+            }
+        }
+        (kings == 1).then_some(material)
+    };
+    Some((side(white)?, side(black)?))
+}
+
+fn has_magic(path: &Path, magic: [u8; 4]) -> bool {
+    let mut header = [0; 4];
+    File::open(path).and_then(|mut file| file.read_exact(&mut header)).is_ok() && header == magic
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Wdl,
+    Dtz,
+}
+
+impl Kind {
+    fn from_extension(ext: &str) -> Option<Kind> {
+        match ext {
+            WDL_EXTENSION => Some(Kind::Wdl),
+            DTZ_EXTENSION => Some(Kind::Dtz),
+            _ => None,
+        }
+    }
+
+    fn magic(self) -> [u8; 4] {
+        match self {
+            Kind::Wdl => WDL_MAGIC,
+            Kind::Dtz => DTZ_MAGIC,
+        }
+    }
+}
+
+/// What a table holds for a position.
+enum Lookup {
+    Value(i32),
+    /// A DTZ table that only stores the other side to move.
+    OtherSide,
+}
+
+// Table flags, per side and file.
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+// File flags, in the byte after the magic.
+const FILE_SPLIT: u8 = 1;
+const FILE_HAS_PAWNS: u8 = 2;
+
+/// One table file: what its name says, plus the header once it has been read.
+#[derive(Debug)]
+struct Table {
+    kind: Kind,
+    path: PathBuf,
+    /// Material as named by the file, with White the stronger side.
+    key: Material,
+    /// The same material with the colors swapped.
+    key2: Material,
+    piece_count: usize,
+    has_pawns: bool,
+    /// Whether some non-king piece is the only one of its kind and color.
+    has_unique_pieces: bool,
+    /// Pawns of the leading color, which has fewer, then of the other.
+    pawn_count: [u32; 2],
+    data: OnceLock<Option<TableData>>,
+}
+
+#[derive(Debug)]
+struct TableData {
+    file: Mutex<File>,
+    /// Indexed by side to move, then by the leading pawn's file for pawn tables.
+    pairs: Vec<Vec<PairsData>>,
+    /// DTZ value maps, indexed through `PairsData::map_idx`.
+    map: Vec<u8>,
+}
+
+/// Decoding parameters of one compressed table.
+#[derive(Debug, Clone, Default)]
+struct PairsData {
+    flags: u8,
+    block_size: u64,
+    /// A sparse index entry is stored for about every `span` values.
+    span: u64,
+    num_blocks: u64,
+    min_sym_len: u8,
+    /// Lowest symbol of each length, from `min_sym_len` up.
+    lowest_sym: Vec<u16>,
+    /// Left and right child of each symbol, twelve bits each.
+    btree: Vec<[u8; 3]>,
+    /// Values in each block, minus one.
+    block_length: Vec<u16>,
+    block_length_size: usize,
+    /// (block, offset in block) of every `span`-th value, offset by half a span.
+    sparse_index: Vec<(u32, u16)>,
+    sparse_index_size: usize,
+    /// Where the compressed blocks start in the file.
+    data_offset: u64,
+    /// Lowest code of each symbol length, left-aligned in 64 bits.
+    base64: Vec<u64>,
+    /// Values each symbol expands to, minus one.
+    symlen: Vec<u8>,
+    /// Piece codes in the order they are encoded.
+    pieces: [u8; MAX_PIECES],
+    /// Multiplier of each group's index in the position index.
+    group_idx: [u64; MAX_PIECES + 1],
+    /// Pieces per group, zero-terminated.
+    group_len: [usize; MAX_PIECES + 1],
+    /// Start of the win, loss, cursed-win and blessed-loss maps in a DTZ map.
+    map_idx: [usize; 4],
+}
+
+impl PairsData {
+    /// Decompress the value with index `idx`.
+    fn value(&self, table: &TableData, idx: u64) -> io::Result<i32> {
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            return Ok(self.min_sym_len as i32);
+        }
+        // Start from the nearest sparse index entry and walk block lengths to
+        // the block holding the value.
+        let &(block, offset) =
+            self.sparse_index.get((idx / self.span) as usize).ok_or_else(|| invalid("index out of range"))?;
+        let mut block = block as usize;
+        let mut offset = offset as i64 + (idx % self.span) as i64 - (self.span / 2) as i64;
+        let length = |block: usize| {
+            self.block_length.get(block).map(|&n| n as i64 + 1).ok_or_else(|| invalid("block out of range"))
+        };
+        while offset < 0 {
+            block = block.checked_sub(1).ok_or_else(|| invalid("block out of range"))?;
+            offset += length(block)?;
+        }
+        while offset >= length(block)? {
+            offset -= length(block)?;
+            block += 1;
+        }
+
+        // Find the Huffman symbol covering the offset. Codes of one length are
+        // consecutive, and longer codes are numerically lower.
+        let bytes = table.read_block(self, block)?;
+        let word = |at: usize| -> io::Result<u32> {
+            let chunk = bytes.get(at..at + 4).ok_or_else(|| invalid("block overrun"))?;
+            Ok(u32::from_be_bytes(chunk.try_into().unwrap()))
+        };
+        let symlen = |sym: usize| self.symlen.get(sym).map(|&n| n as i64).ok_or_else(|| invalid("symbol out of range"));
+        let pair = |sym: usize| self.btree.get(sym).copied().ok_or_else(|| invalid("symbol out of range"));
+        let min_len = self.min_sym_len as usize;
+        let mut buf = (word(0)? as u64) << 32 | word(4)? as u64;
+        let mut buf_bits = 64;
+        let mut next_word = 8;
+        let mut sym = loop {
+            let mut len = 0;
+            while buf < *self.base64.get(len).ok_or_else(|| invalid("code out of range"))? {
+                len += 1;
+            }
+            // At least 32 bits stay buffered, so no valid code is longer.
+            let code_len = len + min_len;
+            if code_len == 0 || code_len > 32 {
+                return Err(invalid("code length out of range"));
+            }
+            let lowest = *self.lowest_sym.get(len).ok_or_else(|| invalid("code out of range"))?;
+            let sym = ((buf - self.base64[len]) >> (64 - code_len)) as u16;
+            let sym = sym.wrapping_add(lowest) as usize;
+            let count = symlen(sym)? + 1;
+            if offset < count {
+                break sym;
+            }
+            offset -= count;
+            buf <<= code_len;
+            buf_bits -= code_len;
+            if buf_bits <= 32 {
+                buf_bits += 32;
+                buf |= (word(next_word)? as u64) << (64 - buf_bits);
+                next_word += 4;
+            }
+        };
+
+        // Expand pairs down to the single value at the offset. Each half is
+        // shorter than the pair, which a corrupt tree could otherwise loop on.
+        while symlen(sym)? != 0 {
+            let length = symlen(sym)?;
+            let left = left_symbol(pair(sym)?);
+            let count = symlen(left)? + 1;
+            let next = if offset < count {
+                left
+            } else {
+                offset -= count;
+                right_symbol(pair(sym)?)
+            };
+            if symlen(next)? >= length {
+                return Err(invalid("symbol tree out of order"));
+            }
+            sym = next;
+        }
+        Ok(left_symbol(pair(sym)?) as i32)
+    }
+}
+
+fn left_symbol(pair: [u8; 3]) -> usize {
+    ((pair[1] as usize & 0xF) << 8) | pair[0] as usize
+}
+
+fn right_symbol(pair: [u8; 3]) -> usize {
+    ((pair[2] as usize) << 4) | (pair[1] as usize >> 4)
+}
+
+impl Table {
+    fn new(kind: Kind, path: PathBuf, key: Material) -> Table {
+        let (white, black) = key;
+        let piece_count = (white.pieces() + black.pieces() + 2) as usize;
+        let unique = |m: MaterialCount| [m.queens, m.rooks, m.bishops, m.knights, m.pawns].contains(&1);
+        // The side with fewer pawns leads, as that compresses better.
+        let white_leads = black.pawns == 0 || (white.pawns > 0 && black.pawns >= white.pawns);
+        Table {
+            kind,
+            path,
+            key,
+            key2: (black, white),
+            piece_count,
+            has_pawns: white.pawns + black.pawns > 0,
+            has_unique_pieces: unique(white) || unique(black),
+            pawn_count: if white_leads { [white.pawns, black.pawns] } else { [black.pawns, white.pawns] },
+            data: OnceLock::new(),
+        }
+    }
+
+    /// The header, read on first use; `None` if the file is unreadable.
+    fn data(&self) -> Option<&TableData> {
+        self.data.get_or_init(|| self.load().ok()).as_ref()
+    }
+
+    fn load(&self) -> io::Result<TableData> {
+        let file = File::open(&self.path)?;
+        let mut input = Input { reader: BufReader::new(file.try_clone()?), position: 0 };
+        if input.bytes(4)? != self.kind.magic() {
+            return Err(invalid("not a Syzygy table"));
+        }
+        let flags = input.u8()?;
+        if (flags & FILE_HAS_PAWNS != 0) != self.has_pawns || (flags & FILE_SPLIT != 0) != (self.key != self.key2) {
+            return Err(invalid("header does not match the file name"));
+        }
+        let sides = if self.kind == Kind::Wdl && self.key != self.key2 { 2 } else { 1 };
+        let files = if self.has_pawns { 4 } else { 1 };
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut pairs = vec![vec![PairsData::default(); files]; sides];
+
+        for file in 0..files {
+            let first = input.u8()?;
+            let second = if both_pawns { input.u8()? } else { 0xFF };
+            let order = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            for k in 0..self.piece_count {
+                let byte = input.u8()?;
+                for (side, tables) in pairs.iter_mut().enumerate() {
+                    tables[file].pieces[k] = if side == 0 { byte & 0xF } else { byte >> 4 };
+                }
+            }
+            for (side, tables) in pairs.iter_mut().enumerate() {
+                self.set_groups(&mut tables[file], order[side], file);
+            }
+        }
+        input.align(2)?;
+
+        for file in 0..files {
+            for tables in &mut pairs {
+                set_sizes(&mut tables[file], &mut input)?;
+            }
+        }
+        let map = match self.kind {
+            Kind::Wdl => Vec::new(),
+            Kind::Dtz => read_dtz_map(&mut pairs[0], &mut input)?,
+        };
+        for file in 0..files {
+            for tables in &mut pairs {
+                let d = &mut tables[file];
+                d.sparse_index = (0..d.sparse_index_size).map(|_| Ok((input.u32()?, input.u16()?))).collect::<io::Result<_>>()?;
+            }
+        }
+        for file in 0..files {
+            for tables in &mut pairs {
+                let d = &mut tables[file];
+                d.block_length = (0..d.block_length_size).map(|_| input.u16()).collect::<io::Result<_>>()?;
+            }
+        }
+        // Compressed blocks follow, each table's aligned to 64 bytes.
+        let mut offset = input.position;
+        for file in 0..files {
+            for tables in &mut pairs {
+                let d = &mut tables[file];
+                offset = offset.next_multiple_of(64);
+                d.data_offset = offset;
+                offset += d.num_blocks * d.block_size;
+            }
+        }
+        Ok(TableData { file: Mutex::new(file), pairs, map })
+    }
+
+    /// Split the pieces into groups encoded together, and work out each group's
+    /// multiplier in the position index.
+    fn set_groups(&self, d: &mut PairsData, order: [u8; 2], file: usize) {
+        let indices = indices();
+        // Without pawns the kings, and a third unique piece if there is one,
+        // lead; with pawns the leading color's pawns do.
+        let mut first_len = if self.has_pawns {
+            0
+        } else if self.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+        let mut n = 0;
+        d.group_len[0] = 1;
+        for i in 1..self.piece_count {
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            } else {
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        d.group_len[n] = 0;
+
+        // The file gives the order the groups are encoded in: order[0] is the
+        // leading group, order[1] the other color's pawns.
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares = 64 - d.group_len[0] - if both_pawns { d.group_len[1] } else { 0 };
+        let mut idx = 1u64;
+        let mut k = 0;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                d.group_idx[0] = idx;
+                idx *= if self.has_pawns {
+                    indices.lead_pawns_size[d.group_len[0]][file]
+                } else if self.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] {
+                d.group_idx[1] = idx;
+                idx *= indices.binomial[d.group_len[1]][48 - d.group_len[0]];
+            } else {
+                d.group_idx[next] = idx;
+                idx *= indices.binomial[d.group_len[next]][free_squares];
+                free_squares -= d.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        d.group_idx[n] = idx;
+    }
+
+    /// Look up `board`, whose material is this table's.
+    fn lookup(&self, data: &TableData, board: &Board, material: Material, wdl: i32) -> io::Result<Lookup> {
+        let indices = indices();
+        // Tables are stored with White the stronger side, and symmetric ones
+        // with White to move only; otherwise swap colors and mirror the ranks.
+        let black = board.current_player() == Color::Black;
+        let flip = (self.key == self.key2 && black) || material != self.key;
+        let (flip_color, flip_squares) = if flip { (8, 56) } else { (0, 0) };
+        let stm = usize::from(flip ^ black);
+        let side_pairs = &data.pairs[stm.min(data.pairs.len() - 1)];
+
+        let bitboards = board.bitboards();
+        let mut squares = [0usize; MAX_PIECES];
+        let mut pieces = [0u8; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = Bitboard::EMPTY;
+        let mut file = 0;
+        if self.has_pawns {
+            // Pawn tables are split by the file of the leading pawn: the one
+            // nearest the edge, and the lowest of those.
+            let color = if data.pairs[0][0].pieces[0] ^ flip_color < 8 { Color::White } else { Color::Black };
+            lead_pawns = bitboards.pieces(PieceType::Pawn, color);
+            for square in lead_pawns.squares() {
+                squares[size] = Bitboard::index(square) ^ flip_squares;
+                size += 1;
+            }
+            let lead = (0..size).max_by_key(|&i| indices.map_pawns[squares[i]]).unwrap_or(0);
+            squares.swap(0, lead);
+            file = (squares[0] % 8).min(7 - squares[0] % 8);
+        }
+        let lead_count = size;
+
+        // DTZ tables store one side to move, except symmetric ones without pawns.
+        let one_sided = self.key != self.key2 || self.has_pawns;
+        if self.kind == Kind::Dtz && one_sided && (side_pairs[file].flags & FLAG_STM) as usize != stm {
+            return Ok(Lookup::OtherSide);
+        }
+
+        for square in Bitboard(bitboards.occupied().0 & !lead_pawns.0).squares() {
+            let (piece_type, color) = board.piece_at_typed(square).ok_or_else(|| invalid("empty square"))?;
+            squares[size] = Bitboard::index(square) ^ flip_squares;
+            pieces[size] = piece_code(piece_type, color) ^ flip_color;
+            size += 1;
+        }
+        let d = &side_pairs[file];
+
+        // Put the pieces in the table's order.
+        for i in lead_count..size.saturating_sub(1) {
+            if let Some(j) = (i + 1..size).find(|&j| pieces[j] == d.pieces[i]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+        let squares = &mut squares[..size];
+        // Mirror so that the leading piece is on files a-d.
+        if squares[0] % 8 > 3 {
+            squares.iter_mut().for_each(|sq| *sq ^= 7);
+        }
+
+        let mut idx;
+        if self.has_pawns {
+            idx = indices.lead_pawn_idx[lead_count][squares[0]];
+            squares[1..lead_count].sort_by_key(|&sq| indices.map_pawns[sq]);
+            for (i, &sq) in squares.iter().enumerate().take(lead_count).skip(1) {
+                idx += indices.binomial[i][indices.map_pawns[sq]];
+            }
+        } else {
+            // Without pawns, also bring the leading piece onto ranks 1-4 and the
+            // first leading piece off the a1-h8 diagonal below it.
+            if squares[0] / 8 > 3 {
+                squares.iter_mut().for_each(|sq| *sq ^= 56);
+            }
+            if let Some(i) = (0..d.group_len[0]).find(|&i| off_diagonal(squares[i]) != 0)
+                && off_diagonal(squares[i]) > 0
+            {
+                squares[i..].iter_mut().for_each(|sq| *sq = ((*sq >> 3) | (*sq << 3)) & 63);
+            }
+            idx = if self.has_unique_pieces {
+                unique_index(indices, squares)
+            } else {
+                indices.map_kk[indices.map_a1d1d4[squares[0]]][squares[1]] as u64
+            };
+        }
+        idx *= d.group_idx[0];
+
+        // The remaining groups are sets of squares, each numbered among those
+        // the earlier groups leave free.
+        let mut start = d.group_len[0];
+        let mut other_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        for next in 1..d.group_len.len() {
+            let len = d.group_len[next];
+            if len == 0 {
+                break;
+            }
+            squares[start..start + len].sort();
+            let mut n = 0;
+            for i in 0..len {
+                let sq = squares[start + i];
+                let below = squares[..start].iter().filter(|&&earlier| sq > earlier).count();
+                n += indices.binomial[i + 1][sq - below - if other_pawns { 8 } else { 0 }];
+            }
+            other_pawns = false;
+            idx += n * d.group_idx[next];
+            start += len;
+        }
+
+        let value = d.value(data, idx)?;
+        Ok(Lookup::Value(match self.kind {
+            Kind::Wdl => value - 2,
+            Kind::Dtz => dtz_value(data, d, value, wdl)?,
+        }))
+    }
+}
+
+/// Index of three unique leading pieces, the first below the a1-h8 diagonal
+/// on a1-d4 or, failing that, on the diagonal.
+fn unique_index(indices: &Indices, squares: &[usize]) -> u64 {
+    let adjust1 = usize::from(squares[1] > squares[0]);
+    let adjust2 = usize::from(squares[2] > squares[0]) + usize::from(squares[2] > squares[1]);
+    let index = if off_diagonal(squares[0]) != 0 {
+        (indices.map_a1d1d4[squares[0]] * 63 + squares[1] - adjust1) * 62 + squares[2] - adjust2
+    } else if off_diagonal(squares[1]) != 0 {
+        (6 * 63 + (squares[0] >> 3) * 28 + indices.map_b1h1h7[squares[1]]) * 62 + squares[2] - adjust2
+    } else if off_diagonal(squares[2]) != 0 {
+        6 * 63 * 62 + 4 * 28 * 62 + (squares[0] >> 3) * 7 * 28 + ((squares[1] >> 3) - adjust1) * 28
+            + indices.map_b1h1h7[squares[2]]
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + (squares[0] >> 3) * 7 * 6 + ((squares[1] >> 3) - adjust1) * 6
+            + (squares[2] >> 3)
+            - adjust2
+    };
+    index as u64
+}
+
+/// Turn a stored DTZ value into plies, through the table's value map if it has one.
+fn dtz_value(data: &TableData, d: &PairsData, value: i32, wdl: i32) -> io::Result<i32> {
+    // Map order in the file: win, loss, cursed win, blessed loss.
+    const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+    let mut value = value;
+    if d.flags & FLAG_MAPPED != 0 {
+        let at = d.map_idx[WDL_MAP[(wdl + 2) as usize]] + value as usize;
+        value = if d.flags & FLAG_WIDE != 0 {
+            let bytes = data.map.get(2 * at..2 * at + 2).ok_or_else(|| invalid("map out of range"))?;
+            u16::from_le_bytes([bytes[0], bytes[1]]) as i32
+        } else {
+            *data.map.get(at).ok_or_else(|| invalid("map out of range"))? as i32
+        };
+    }
+    // Values are stored in moves unless the flags say plies.
+    let in_moves = match wdl {
+        2 => d.flags & FLAG_WIN_PLIES == 0,
+        -2 => d.flags & FLAG_LOSS_PLIES == 0,
+        _ => true,
+    };
+    Ok(if in_moves { value * 2 } else { value } + 1)
+}
+
+impl TableData {
+    /// Read one compressed block, with a few bytes of slack for the decoder's
+    /// look-ahead.
+    fn read_block(&self, d: &PairsData, block: usize) -> io::Result<Vec<u8>> {
+        let len = d.block_size + 8;
+        let mut bytes = Vec::with_capacity(len as usize);
+        let mut file = self.file.lock().map_err(|_| invalid("table file lock poisoned"))?;
+        file.seek(SeekFrom::Start(d.data_offset + block as u64 * d.block_size))?;
+        file.by_ref().take(len).read_to_end(&mut bytes)?;
+        bytes.resize(len as usize, 0);
+        Ok(bytes)
+    }
+}
+
+/// Read a table's size fields, Huffman code lengths and symbol tree. The
+/// sparse index and block lengths come later in the file; only their sizes
+/// are read here.
+fn set_sizes<R: Read>(d: &mut PairsData, input: &mut Input<R>) -> io::Result<()> {
+    d.flags = input.u8()?;
+    if d.flags & FLAG_SINGLE_VALUE != 0 {
+        d.min_sym_len = input.u8()?;
+        return Ok(());
+    }
+    let groups = d.group_len.iter().position(|&len| len == 0).unwrap_or(MAX_PIECES);
+    let size = d.group_idx[groups];
+    let shift = |bits: u8| 1u64.checked_shl(bits as u32).ok_or_else(|| invalid("bad block size"));
+    d.block_size = shift(input.u8()?)?;
+    d.span = shift(input.u8()?)?;
+    let padding = input.u8()? as u64;
+    d.num_blocks = input.u32()? as u64;
+    d.sparse_index_size = size.div_ceil(d.span) as usize;
+    d.block_length_size = (d.num_blocks + padding) as usize;
+    let max_sym_len = input.u8()? as usize;
+    d.min_sym_len = input.u8()?;
+    let min_sym_len = d.min_sym_len as usize;
+    if min_sym_len == 0 || max_sym_len < min_sym_len || max_sym_len > 32 {
+        return Err(invalid("bad symbol lengths"));
+    }
+    let lengths = max_sym_len - min_sym_len + 1;
+    d.lowest_sym = (0..lengths).map(|_| input.u16()).collect::<io::Result<_>>()?;
+
+    // Canonical Huffman codes: longer codes have lower values, so the lowest
+    // code of each length, left-aligned, decreases with the length.
+    d.base64 = vec![0; lengths];
+    for i in (0..lengths - 1).rev() {
+        d.base64[i] = d.base64[i + 1].wrapping_add(d.lowest_sym[i] as u64).wrapping_sub(d.lowest_sym[i + 1] as u64) / 2;
+    }
+    for (i, base) in d.base64.iter_mut().enumerate() {
+        *base <<= 64 - i - min_sym_len;
+    }
+
+    // Recursive pairing: each symbol above the literals stands for a pair.
+    let symbols = input.u16()? as usize;
+    d.btree = (0..symbols).map(|_| Ok(input.bytes(3)?.try_into().unwrap())).collect::<io::Result<_>>()?;
+    if symbols % 2 == 1 {
+        input.u8()?;
+    }
+    d.symlen = vec![0; symbols];
+    let mut visited = vec![false; symbols];
+    for sym in 0..symbols {
+        if !visited[sym] {
+            d.symlen[sym] = symbol_length(&d.btree, &mut d.symlen, &mut visited, sym)?;
+        }
+    }
+    Ok(())
+}
+
+/// Number of values, minus one, `sym` expands to.
+fn symbol_length(btree: &[[u8; 3]], symlen: &mut [u8], visited: &mut [bool], sym: usize) -> io::Result<u8> {
+    visited[sym] = true;
+    let right = right_symbol(btree[sym]);
+    if right == 0xFFF {
+        return Ok(0);
+    }
+    let left = left_symbol(btree[sym]);
+    for child in [left, right] {
+        if child >= btree.len() {
+            return Err(invalid("symbol out of range"));
+        }
+        if !visited[child] {
+            symlen[child] = symbol_length(btree, symlen, visited, child)?;
+        }
+    }
+    Ok(symlen[left].wrapping_add(symlen[right]).wrapping_add(1))
+}
+
+/// Read the DTZ value maps of tables flagged as mapped, recording where each
+/// of their four maps starts.
+fn read_dtz_map<R: Read>(pairs: &mut [PairsData], input: &mut Input<R>) -> io::Result<Vec<u8>> {
+    let mut map = Vec::new();
+    for d in pairs.iter_mut().filter(|d| d.flags & FLAG_MAPPED != 0) {
+        if d.flags & FLAG_WIDE != 0 {
+            if input.position % 2 == 1 {
+                map.push(input.u8()?);
+            }
+            for start in &mut d.map_idx {
+                *start = map.len() / 2 + 1;
+                let len = input.u16()?;
+                map.extend(len.to_le_bytes());
+                map.extend(input.bytes(2 * len as usize)?);
+            }
+        } else {
+            for start in &mut d.map_idx {
+                *start = map.len() + 1;
+                let len = input.u8()?;
+                map.push(len);
+                map.extend(input.bytes(len as usize)?);
+            }
+        }
+    }
+    input.align(2)?;
+    Ok(map)
+}
+
+/// Little-endian reader that tracks its offset in the file, for alignment.
+struct Input<R> {
+    reader: R,
+    position: u64,
+}
+
+impl<R: Read> Input<R> {
+    fn bytes(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; len];
+        self.reader.read_exact(&mut bytes)?;
+        self.position += len as u64;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn align(&mut self, to: u64) -> io::Result<()> {
+        let padding = self.position.next_multiple_of(to) - self.position;
+        self.bytes(padding as usize).map(drop)
+    }
+}
+
+/// Piece code used in table headers: pawn 1 to king 6, plus 8 for Black.
+fn piece_code(piece_type: PieceType, color: Color) -> u8 {
+    let code = match piece_type {
+        PieceType::Pawn => 1,
+        PieceType::Knight => 2,
+        PieceType::Bishop => 3,
+        PieceType::Rook => 4,
+        PieceType::Queen => 5,
+        PieceType::King => 6,
+    };
+    if color == Color::Black { code + 8 } else { code }
+}
+
+/// Rank minus file: negative below the a1-h8 diagonal, zero on it.
+fn off_diagonal(square: usize) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+/// Lookup tables for the position encoding.
+struct Indices {
+    /// a2-h7 numbered so that the leading pawn has the highest number.
+    map_pawns: [usize; 64],
+    /// Squares below the a1-h8 diagonal, 0 to 27.
+    map_b1h1h7: [usize; 64],
+    /// The a1-d1-d4 triangle, diagonal last, 0 to 9.
+    map_a1d1d4: [usize; 64],
+    /// The 462 placements of two kings with the first in the triangle.
+    map_kk: [[usize; 64]; 10],
+    /// `binomial[k][n]`: ways to choose k of n.
+    binomial: [[u64; 64]; MAX_PIECES + 1],
+    lead_pawn_idx: [[u64; 64]; MAX_PIECES + 1],
+    lead_pawns_size: [[u64; 4]; MAX_PIECES + 1],
+}
+
+fn indices() -> &'static Indices {
+    static INDICES: OnceLock<Indices> = OnceLock::new();
+    INDICES.get_or_init(Indices::new)
+}
+
+impl Indices {
+    fn new() -> Indices {
+        let mut indices = Indices {
+            map_pawns: [0; 64],
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; MAX_PIECES + 1],
+            lead_pawn_idx: [[0; 64]; MAX_PIECES + 1],
+            lead_pawns_size: [[0; 4]; MAX_PIECES + 1],
+        };
+        let file = |sq: usize| sq % 8;
+
+        let mut code = 0;
+        for sq in (0..64).filter(|&sq| off_diagonal(sq) < 0) {
+            indices.map_b1h1h7[sq] = code;
+            code += 1;
+        }
+
+        let mut diagonal = Vec::new();
+        code = 0;
+        for sq in (0..=27).filter(|&sq| file(sq) <= 3) {
+            if off_diagonal(sq) < 0 {
+                indices.map_a1d1d4[sq] = code;
+                code += 1;
+            } else if off_diagonal(sq) == 0 {
+                diagonal.push(sq);
+            }
+        }
+        for sq in diagonal {
+            indices.map_a1d1d4[sq] = code;
+            code += 1;
+        }
+
+        // Kings may not touch, and with the first on the diagonal the second
+        // is not above it; placements with both on the diagonal come last.
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for idx in 0..10 {
+            // b1 is the square numbered 0.
+            for s1 in (0..=27).filter(|&s1| indices.map_a1d1d4[s1] == idx && (idx != 0 || s1 == 1)) {
+                let king = Square { file: s1 as u8 % 8, rank: s1 as u8 / 8 };
+                let near = king_attacks(king) | Bitboard::from_square(king);
+                for s2 in 0..64 {
+                    if near.0 & (1 << s2) != 0 || (off_diagonal(s1) == 0 && off_diagonal(s2) > 0) {
+                        continue;
+                    }
+                    if off_diagonal(s1) == 0 && off_diagonal(s2) == 0 {
+                        both_on_diagonal.push((idx, s2));
+                    } else {
+                        indices.map_kk[idx][s2] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, s2) in both_on_diagonal {
+            indices.map_kk[idx][s2] = code;
+            code += 1;
+        }
+
+        indices.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..=MAX_PIECES.min(n) {
+                let with = if k > 0 { indices.binomial[k - 1][n - 1] } else { 0 };
+                let without = if k < n { indices.binomial[k][n - 1] } else { 0 };
+                indices.binomial[k][n] = with + without;
+            }
+        }
+
+        // The leading pawn is nearest the edge, and the lowest on its file; the
+        // number of a pawn square is how many squares the other pawns may use.
+        let mut available = 47;
+        for lead in 1..MAX_PIECES {
+            for file in 0..4 {
+                let mut idx = 0;
+                for rank in 1..7 {
+                    let sq = rank * 8 + file;
+                    if lead == 1 {
+                        indices.map_pawns[sq] = available;
+                        indices.map_pawns[sq ^ 7] = available.saturating_sub(1);
+                        available = available.saturating_sub(2);
+                    }
+                    indices.lead_pawn_idx[lead][sq] = idx;
+                    idx += indices.binomial[lead - 1][indices.map_pawns[sq]];
+                }
+                indices.lead_pawns_size[lead][file] = idx;
+            }
+        }
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_tables() {
+        let indices = indices();
+        let kings: Vec<usize> = indices.map_kk.iter().flatten().copied().collect();
+        assert_eq!(kings.iter().max(), Some(&461));
+        assert_eq!(indices.map_a1d1d4.iter().max(), Some(&9));
+        assert_eq!(indices.map_b1h1h7.iter().max(), Some(&27));
+        assert_eq!(indices.map_pawns[8], 47);
+        assert_eq!(indices.map_pawns[15], 46);
+        assert_eq!(indices.binomial[2][48], 1128);
+        // One leading pawn on files a-d: six ranks each.
+        assert_eq!(indices.lead_pawns_size[1], [6, 6, 6, 6]);
+    }
+
+    /// KQvK tables whose every value is `white` with White to move and `black`
+    /// with Black to move, and whose DTZ, stored for White to move, is `dtz` moves.
+    fn single_value_tables(dir: &Path, white: u8, black: u8, dtz: Option<u8>) {
+        // Split into two sides, no pawns; group order; K, Q, K for both sides;
+        // padding to an even offset; then a single value per side.
+        let header = [FILE_SPLIT, 0x00, 0x66, 0x55, 0xEE, 0x00];
+        let mut data = WDL_MAGIC.to_vec();
+        data.extend(header);
+        data.extend([FLAG_SINGLE_VALUE, white, FLAG_SINGLE_VALUE, black]);
+        fs::write(dir.join("KQvK.rtbw"), data).unwrap();
+        if let Some(dtz) = dtz {
+            let mut data = DTZ_MAGIC.to_vec();
+            data.extend(header);
+            data.extend([FLAG_SINGLE_VALUE, dtz]);
+            fs::write(dir.join("KQvK.rtbz"), data).unwrap();
+        }
+        fs::write(dir.join("KRvK.rtbw"), b"not a table").unwrap();
+        fs::write(dir.join("README.txt"), b"").unwrap();
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rchess-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_open_and_probe_single_value_table() {
+        let dir = temp_dir("tablebase-probe");
+        single_value_tables(&dir, 4, 0, None);
+        let tablebase = Tablebase::open(&dir).unwrap();
+        assert_eq!((tablebase.wdl_tables(), tablebase.dtz_tables(), tablebase.max_pieces()), (1, 0, 3));
+
+        let probe = |fen: &str| tablebase.probe_wdl(&Board::from_fen(fen).unwrap());
+        assert_eq!(probe("8/8/8/3k4/8/8/8/K5Q1 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe("8/8/8/3k4/8/8/8/K5Q1 b - - 0 1"), Some(Wdl::Loss));
+        // Colors swapped: the stronger side is Black.
+        assert_eq!(probe("8/8/8/3K4/8/8/8/k5q1 b - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe("8/8/8/3k4/8/8/8/K7 w - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(probe("8/8/8/3k4/8/8/8/K5R1 w - - 0 1"), None);
+        // The queen can be taken, which draws whatever the table says.
+        assert_eq!(probe("8/8/8/8/8/8/8/Kq5k w - - 0 1"), Some(Wdl::Draw));
+        let board = Board::from_fen("8/8/8/3k4/8/8/8/K6Q b - - 0 1").unwrap();
+        assert_eq!(tablebase.adjudicate(&board), None);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(Tablebase::open(&dir), Err(TablebaseError::Io(_))));
+    }
+
+    #[test]
+    fn test_dtz_best_move_and_engine_play() {
+        use crate::engine::{Engine, SearchLimits, TABLEBASE_WIN};
+
+        let dir = temp_dir("tablebase-engine");
+        single_value_tables(&dir, 4, 0, Some(5));
+        let tablebase = Tablebase::open(&dir).unwrap();
+        let board = Board::from_fen("8/8/8/3k4/8/8/8/K5Q1 w - - 0 1").unwrap();
+        // Five moves to zero, stored in moves: eleven plies.
+        assert_eq!(tablebase.probe_dtz(&board), Some(11));
+        assert_eq!(tablebase.adjudicate(&board), Some(GameResult::WhiteWins));
+        let late = Board::from_fen("8/8/8/3k4/8/8/8/K5Q1 w - - 95 80").unwrap();
+        assert_eq!(tablebase.adjudicate(&late), Some(GameResult::Draw));
+
+        let mut engine = Engine::new();
+        engine.set_tablebase(Some(tablebase));
+        let result = engine.best_move(&board, SearchLimits::depth(3));
+        assert_eq!((result.score, result.depth), (TABLEBASE_WIN, 0));
+        // The chosen move does not give the queen away.
+        let mut after = board.clone();
+        after.make_move(result.best_move.unwrap());
+        assert_eq!(engine.tablebase().unwrap().probe_wdl(&after), Some(Wdl::Loss));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_decompresses_huffman_coded_values() {
+        // KQvK with White to move a single value, and with Black to move one
+        // block of one-bit codes: 0 for a loss, 1 for a draw.
+        let mut data = WDL_MAGIC.to_vec();
+        data.extend([FILE_SPLIT, 0x00, 0x66, 0x55, 0xEE, 0x00]);
+        data.extend([FLAG_SINGLE_VALUE, 4]);
+        // 4096-byte blocks, a sparse entry per 65536 values, one block, codes of
+        // length 1, lowest symbol 0; two literal symbols, values 0 and 2.
+        data.extend([0, 12, 16, 0, 1, 0, 0, 0, 1, 1, 0, 0, 2, 0]);
+        data.extend([0x00, 0xF0, 0xFF, 0x02, 0xF0, 0xFF]);
+        // Sparse entry: block 0, offset of value 32768; the block holds 31332 values.
+        data.extend([0, 0, 0, 0, 0x00, 0x80]);
+        data.extend((31332u16 - 1).to_le_bytes());
+        data.resize(64, 0);
+        let mut block = vec![0u8; 4096];
+        // Kb1, Qh8, ka8 is index (62 * 62) + 55 = 3899.
+        block[3899 / 8] = 0x80 >> (3899 % 8);
+        data.extend(block);
+
+        let dir = temp_dir("tablebase-huffman");
+        fs::write(dir.join("KQvK.rtbw"), data).unwrap();
+        let tablebase = Tablebase::open(&dir).unwrap();
+        let probe = |fen: &str| tablebase.probe_wdl(&Board::from_fen(fen).unwrap());
+        assert_eq!(probe("k6Q/8/8/8/8/8/8/1K6 b - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(probe("k5Q1/8/8/8/8/8/8/1K6 b - - 0 1"), Some(Wdl::Loss));
+        // Mirrored files and colors land on the same index.
+        assert_eq!(probe("6k1/8/8/8/8/8/8/q6K w - - 0 1"), Some(Wdl::Draw));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_code_tables_are_invalid_data() {
+        let dir = temp_dir("tablebase-corrupt");
+        let path = dir.join("corrupt.rtbw");
+        fs::write(&path, [0u8; 16]).unwrap();
+        let table = TableData { file: Mutex::new(File::open(&path).unwrap()), pairs: Vec::new(), map: Vec::new() };
+        let pairs = PairsData {
+            block_size: 8,
+            span: 1,
+            sparse_index: vec![(0, 0)],
+            block_length: vec![0],
+            min_sym_len: 1,
+            ..Default::default()
+        };
+        // No code lengths at all.
+        let err = pairs.value(&table, 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A pair symbol that expands to itself.
+        let looping = PairsData { base64: vec![0], lowest_sym: vec![0], symlen: vec![1], btree: vec![[0; 3]], ..pairs };
+        let err = looping.value(&table, 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
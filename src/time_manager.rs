@@ -0,0 +1,175 @@
+// Time management: how much of the clock to spend on the next move.
+// Strategies are pluggable behind the TimeManager trait so the search driver stays agnostic.
+
+use std::fmt;
+
+/// Clock state for the side to move, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeInfo {
+    pub remaining_ms: u64,
+    pub increment_ms: u64,
+    /// Moves until the next time control, if the control is not sudden death.
+    pub moves_to_go: Option<u32>,
+}
+
+/// Tunables shared by all strategies; these map onto UCI options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOptions {
+    /// Time reserved per move for GUI and network lag ("Move Overhead").
+    pub move_overhead_ms: u64,
+    /// When set, budgets are expressed in nodes at this rate instead of wall time ("nodestime").
+    pub nodes_per_ms: Option<u64>,
+}
+
+impl Default for TimeOptions {
+    fn default() -> Self {
+        TimeOptions {
+            move_overhead_ms: 30,
+            nodes_per_ms: None,
+        }
+    }
+}
+
+/// Unit a budget is measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetUnit {
+    Millis,
+    Nodes,
+}
+
+/// Budget for one move: the search aims to stop after `optimum` and must stop by `maximum`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeBudget {
+    pub optimum: u64,
+    pub maximum: u64,
+    pub unit: BudgetUnit,
+}
+
+/// A strategy for allocating thinking time.
+pub trait TimeManager: fmt::Debug + Send + Sync {
+    /// Allocate a budget for the next move at the given ply of the game.
+    fn allocate(&self, info: &TimeInfo, options: &TimeOptions, ply: u32) -> TimeBudget;
+
+    /// Adjust the budget after an iteration whose score fell by `score_drop` centipawns.
+    /// The default extends the optimum on fail-lows ("panic time") without exceeding the maximum.
+    fn on_fail_low(&self, budget: TimeBudget, score_drop: i32) -> TimeBudget {
+        if score_drop < PANIC_THRESHOLD_CP {
+            return budget;
+        }
+        // Extend by 50% per pawn lost, up to double the optimum.
+        let percent = (100 + score_drop as u64 / 2).min(200);
+        TimeBudget {
+            optimum: (budget.optimum * percent / 100).min(budget.maximum),
+            ..budget
+        }
+    }
+}
+
+/// Score drop that triggers a panic extension.
+pub const PANIC_THRESHOLD_CP: i32 = 30;
+
+/// Default strategy: spread remaining time over the expected number of moves left,
+/// add most of the increment, and allow overruns up to a safe fraction of the clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardTimeManager;
+
+/// Spend a fixed amount of time on every move (UCI `go movetime`).
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTimeManager {
+    pub move_time_ms: u64,
+}
+
+impl TimeManager for StandardTimeManager {
+    fn allocate(&self, info: &TimeInfo, options: &TimeOptions, ply: u32) -> TimeBudget {
+        let available = info.remaining_ms.saturating_sub(options.move_overhead_ms).max(1);
+        // Without a move count, assume fewer moves remain as the game goes on.
+        let moves_left = info
+            .moves_to_go
+            .unwrap_or_else(|| 40u32.saturating_sub(ply / 4).max(20))
+            .max(1) as u64;
+
+        let maximum = (available * 3 / 4).max(1);
+        let optimum = (available / moves_left + info.increment_ms * 3 / 4).min(maximum);
+        let maximum = (optimum * 5).min(maximum);
+        to_unit(optimum.max(1), maximum, options)
+    }
+}
+
+impl TimeManager for FixedTimeManager {
+    fn allocate(&self, _info: &TimeInfo, options: &TimeOptions, _ply: u32) -> TimeBudget {
+        let time = self.move_time_ms.saturating_sub(options.move_overhead_ms).max(1);
+        to_unit(time, time, options)
+    }
+
+    fn on_fail_low(&self, budget: TimeBudget, _score_drop: i32) -> TimeBudget {
+        budget
+    }
+}
+
+fn to_unit(optimum_ms: u64, maximum_ms: u64, options: &TimeOptions) -> TimeBudget {
+    match options.nodes_per_ms {
+        Some(rate) => TimeBudget {
+            optimum: optimum_ms * rate,
+            maximum: maximum_ms * rate,
+            unit: BudgetUnit::Nodes,
+        },
+        None => TimeBudget {
+            optimum: optimum_ms,
+            maximum: maximum_ms,
+            unit: BudgetUnit::Millis,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_allocation_respects_clock() {
+        let info = TimeInfo {
+            remaining_ms: 60_000,
+            increment_ms: 1_000,
+            moves_to_go: None,
+        };
+        let budget = StandardTimeManager.allocate(&info, &TimeOptions::default(), 0);
+        assert_eq!(budget.unit, BudgetUnit::Millis);
+        assert!(budget.optimum > 1_000 && budget.optimum < 5_000);
+        assert!(budget.maximum >= budget.optimum);
+        assert!(budget.maximum < 60_000);
+    }
+
+    #[test]
+    fn test_moves_to_go_divides_time() {
+        let info = TimeInfo {
+            remaining_ms: 10_030,
+            increment_ms: 0,
+            moves_to_go: Some(10),
+        };
+        let budget = StandardTimeManager.allocate(&info, &TimeOptions::default(), 0);
+        assert_eq!(budget.optimum, 1_000);
+    }
+
+    #[test]
+    fn test_node_time_mode() {
+        let options = TimeOptions {
+            move_overhead_ms: 0,
+            nodes_per_ms: Some(500),
+        };
+        let budget = FixedTimeManager { move_time_ms: 100 }.allocate(&TimeInfo::default(), &options, 0);
+        assert_eq!(budget.unit, BudgetUnit::Nodes);
+        assert_eq!(budget.optimum, 50_000);
+    }
+
+    #[test]
+    fn test_panic_extension_on_fail_low() {
+        let budget = TimeBudget {
+            optimum: 1_000,
+            maximum: 1_800,
+            unit: BudgetUnit::Millis,
+        };
+        assert_eq!(StandardTimeManager.on_fail_low(budget, 10), budget);
+        assert_eq!(StandardTimeManager.on_fail_low(budget, 100).optimum, 1_500);
+        assert_eq!(StandardTimeManager.on_fail_low(budget, 400).optimum, 1_800);
+    }
+}
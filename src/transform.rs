@@ -0,0 +1,182 @@
+// Symmetry transforms: the board mirrored left to right, flipped top to bottom,
+// or with the colors of the pieces exchanged, for augmenting training data and
+// for spotting positions that are the same up to symmetry.
+//
+// Castling rights and the en-passant target follow the pieces they belong to
+// where they still make sense. A right whose king or rook is no longer on its
+// home square is dropped, and so is an en-passant target the side to move could
+// not have been given, so every transformed board passes `Board::validate` when
+// the original does, and hashes like the same position reached in a game.
+// Without such rights or target each transform undoes itself; `from_other_side`
+// flips and swaps in one step, which keeps them.
+
+use crate::board::Board;
+use crate::moves::CastlingSide;
+use crate::pieces::{BishopSquareColor, Color, Square};
+use crate::rchess::v1::{self as proto};
+use crate::validate::Violation;
+use std::mem;
+
+impl Board {
+    /// The position with files a and h exchanged, b and g, and so on. Kingside
+    /// castling rights become queenside ones and the reverse.
+    pub fn mirrored_horizontal(&self) -> Board {
+        let mut state = self.transformed(|square| Square { file: 7 - square.file, ..square });
+        mem::swap(&mut state.white_kingside_castling, &mut state.white_queenside_castling);
+        mem::swap(&mut state.black_kingside_castling, &mut state.black_queenside_castling);
+        settled(state)
+    }
+
+    /// The position with ranks 1 and 8 exchanged, 2 and 7, and so on. Every
+    /// piece keeps its color, and each side keeps its castling rights.
+    pub fn flipped_vertical(&self) -> Board {
+        settled(self.flipped_state())
+    }
+
+    /// The position with every piece changed to the other color, on the same
+    /// square. The side to move, castling rights, checks given, pockets, and
+    /// clock times change sides with the pieces.
+    pub fn color_swapped(&self) -> Board {
+        let mut state = self.transformed(|square| square);
+        swap_colors(&mut state);
+        settled(state)
+    }
+
+    /// The same position seen from the other side: flipped top to bottom with
+    /// the colors swapped. Unlike the two transforms one after the other, this
+    /// keeps castling rights and the en-passant target.
+    pub fn from_other_side(&self) -> Board {
+        let mut state = self.flipped_state();
+        swap_colors(&mut state);
+        settled(state)
+    }
+
+    fn flipped_state(&self) -> proto::GameState {
+        self.transformed(|square| Square { rank: 7 - square.rank, ..square })
+    }
+
+    /// The proto state with every piece and the en-passant target moved by
+    /// `map`, and the move history dropped.
+    fn transformed(&self, map: impl Fn(Square) -> Square) -> proto::GameState {
+        let mut state = self.to_proto();
+        state.moves.clear();
+        let remap = |position: &mut Option<proto::Position>| {
+            if let Some(square) = position.as_ref().and_then(Square::from_proto) {
+                *position = Some(map(square).to_proto());
+            }
+        };
+        for piece in state.board.iter_mut().flat_map(|board| &mut board.pieces) {
+            match &mut piece.kind {
+                Some(proto::piece::Kind::King(k)) => remap(&mut k.position),
+                Some(proto::piece::Kind::Queen(q)) => remap(&mut q.position),
+                Some(proto::piece::Kind::Rook(r)) => remap(&mut r.position),
+                Some(proto::piece::Kind::Bishop(b)) => {
+                    remap(&mut b.position);
+                    if let Some(square) = b.position.as_ref().and_then(Square::from_proto) {
+                        b.square_color = BishopSquareColor::of(square).to_proto();
+                    }
+                }
+                Some(proto::piece::Kind::Knight(n)) => remap(&mut n.position),
+                Some(proto::piece::Kind::Pawn(p)) => remap(&mut p.position),
+                None => {}
+            }
+        }
+        remap(&mut state.en_passant_target);
+        state
+    }
+}
+
+/// Give every piece, and everything kept per side, to the other color.
+fn swap_colors(state: &mut proto::GameState) {
+    for piece in state.board.iter_mut().flat_map(|board| &mut board.pieces) {
+        if let Some(color) = piece_color_mut(piece) {
+            *color = Color::from_checked_proto(*color).opposite().to_proto();
+        }
+    }
+    state.current_player = Color::from_checked_proto(state.current_player).opposite().to_proto();
+    mem::swap(&mut state.white_kingside_castling, &mut state.black_kingside_castling);
+    mem::swap(&mut state.white_queenside_castling, &mut state.black_queenside_castling);
+    mem::swap(&mut state.white_checks_given, &mut state.black_checks_given);
+    mem::swap(&mut state.white_pocket, &mut state.black_pocket);
+    if let Some(clock) = &mut state.clock {
+        mem::swap(&mut clock.white_remaining_ms, &mut clock.black_remaining_ms);
+        mem::swap(&mut clock.white_moves, &mut clock.black_moves);
+        if clock.flagged != 0 {
+            clock.flagged = Color::from_checked_proto(clock.flagged).opposite().to_proto();
+        }
+    }
+}
+
+/// The board for a transformed `state`, without the castling rights and
+/// en-passant target the transform left impossible.
+fn settled(mut state: proto::GameState) -> Board {
+    for violation in Board::from_proto_unchecked(state.clone()).validate() {
+        match violation {
+            Violation::ImpossibleCastling { color, side } => {
+                let right = match (color, side) {
+                    (Color::White, CastlingSide::Kingside) => &mut state.white_kingside_castling,
+                    (Color::White, CastlingSide::Queenside) => &mut state.white_queenside_castling,
+                    (Color::Black, CastlingSide::Kingside) => &mut state.black_kingside_castling,
+                    (Color::Black, CastlingSide::Queenside) => &mut state.black_queenside_castling,
+                };
+                *right = false;
+            }
+            Violation::InvalidEnPassant(_) => state.en_passant_target = None,
+            _ => {}
+        }
+    }
+    Board::from_proto_unchecked(state)
+}
+
+fn piece_color_mut(piece: &mut proto::Piece) -> Option<&mut i32> {
+    match piece.kind.as_mut()? {
+        proto::piece::Kind::King(k) => Some(&mut k.color),
+        proto::piece::Kind::Queen(q) => Some(&mut q.color),
+        proto::piece::Kind::Rook(r) => Some(&mut r.color),
+        proto::piece::Kind::Bishop(b) => Some(&mut b.color),
+        proto::piece::Kind::Knight(n) => Some(&mut n.color),
+        proto::piece::Kind::Pawn(p) => Some(&mut p.color),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+    use crate::sq;
+
+    #[test]
+    fn test_transforms_remap_castling_and_en_passant() {
+        let board = Board::from_fen("r3k2r/8/8/3pP3/8/8/8/4K2R w Kq d6 0 3").unwrap();
+        // Castling rights go with kings that leave the e-file or their home rank,
+        // and the en-passant target with a double step the wrong side made.
+        assert_eq!(board.mirrored_horizontal().to_fen(), "r2k3r/8/8/3Pp3/8/8/8/R2K4 w - e6 0 3");
+        assert_eq!(board.flipped_vertical().to_fen(), "4K2R/8/8/8/3pP3/8/8/r3k2r w - - 0 3");
+        assert_eq!(board.color_swapped().to_fen(), "R3K2R/8/8/3Pp3/8/8/8/4k2r b - - 0 3");
+        for transform in [Board::mirrored_horizontal, Board::flipped_vertical, Board::color_swapped] {
+            assert!(transform(&board).validate().is_empty());
+        }
+
+        // Flipped and swapped at once, it is the same position for the other side.
+        let seen_from_black = board.from_other_side();
+        assert_eq!(seen_from_black.to_fen(), "4k2r/8/8/8/3Pp3/8/8/R3K2R b Qk d3 0 3");
+        assert!(seen_from_black.validate().is_empty());
+        let moves = seen_from_black.legal_moves(Color::Black);
+        assert!(moves.contains(&Move::new(sq!("e4"), sq!("d3"))));
+        assert_eq!(moves.len(), board.legal_moves(Color::White).len());
+        assert_eq!(seen_from_black.from_other_side().position_key(), board.position_key());
+    }
+
+    #[test]
+    fn test_each_transform_undoes_itself() {
+        let board = Board::from_fen("r1bq1rk1/pp2bppp/2n1pn2/2pp4/3P4/2PBPN2/PP1N1PPP/R1BQ1RK1 w - - 0 8").unwrap();
+        for transform in [Board::mirrored_horizontal, Board::flipped_vertical, Board::color_swapped] {
+            let once = transform(&board);
+            assert!(once.validate().is_empty());
+            let twice = transform(&once);
+            assert_eq!(twice.to_fen(), board.to_fen());
+            assert_eq!(twice.position_key(), board.position_key());
+        }
+        assert_ne!(board.mirrored_horizontal().position_key(), board.position_key());
+    }
+}
@@ -0,0 +1,173 @@
+// Transposition table: a fixed-size cache of search results keyed by Zobrist hash.
+// Each key maps to one slot. An entry from an older search is always replaced;
+// an entry from the current search only gives way to an equal or deeper one.
+// Slots are locked one at a time, so search threads can share the table.
+
+use crate::moves::Move;
+use std::fmt;
+use std::mem;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Table size used when none is configured, in megabytes.
+pub const DEFAULT_SIZE_MB: usize = 16;
+
+/// How a stored score relates to the true score of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The search completed inside the window; the score is exact.
+    Exact,
+    /// The search failed high; the true score is at least this.
+    Lower,
+    /// The search failed low; the true score is at most this.
+    Upper,
+}
+
+/// A stored search result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u32,
+    pub bound: Bound,
+    /// Search generation the entry was written in.
+    pub age: u8,
+}
+
+/// A fixed-size transposition table.
+pub struct TranspositionTable {
+    entries: Vec<Mutex<Option<Entry>>>,
+    age: u8,
+}
+
+impl Clone for TranspositionTable {
+    fn clone(&self) -> Self {
+        TranspositionTable {
+            entries: self.entries.iter().map(|slot| Mutex::new(*lock(slot))).collect(),
+            age: self.age,
+        }
+    }
+}
+
+impl fmt::Debug for TranspositionTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TranspositionTable")
+            .field("capacity", &self.capacity())
+            .field("age", &self.age)
+            .finish()
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_SIZE_MB)
+    }
+}
+
+impl TranspositionTable {
+    /// Create a table using about `size_mb` megabytes, with at least one slot.
+    pub fn new(size_mb: usize) -> Self {
+        let slots = (size_mb * 1024 * 1024 / mem::size_of::<Mutex<Option<Entry>>>()).max(1);
+        TranspositionTable {
+            entries: (0..slots).map(|_| Mutex::new(None)).collect(),
+            age: 0,
+        }
+    }
+
+    /// Number of slots.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Start a new search: entries written before now become replaceable.
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    /// Remove every entry.
+    pub fn clear(&mut self) {
+        for slot in &mut self.entries {
+            *slot.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+        }
+        self.age = 0;
+    }
+
+    /// The entry stored for `key`, if any.
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.slot(key).filter(|entry| entry.key == key)
+    }
+
+    /// Store a result, unless it would evict a deeper entry from the current search.
+    pub fn store(&self, key: u64, best_move: Option<Move>, score: i32, depth: u32, bound: Bound) {
+        let age = self.age;
+        let mut slot = self.slot(key);
+        let replace = match &*slot {
+            None => true,
+            Some(old) => old.key == key || old.age != age || depth >= old.depth,
+        };
+        if replace {
+            // Keep the old move when re-storing the same position without one.
+            let best_move =
+                best_move.or_else(|| slot.as_ref().filter(|old| old.key == key).and_then(|old| old.best_move));
+            *slot = Some(Entry {
+                key,
+                best_move,
+                score,
+                depth,
+                bound,
+                age,
+            });
+        }
+    }
+
+    /// Permille of slots filled by the current search (UCI `hashfull`).
+    pub fn hashfull(&self) -> u32 {
+        let sample = self.entries.len().min(1000);
+        let used = self.entries[..sample]
+            .iter()
+            .filter(|slot| lock(slot).is_some_and(|e| e.age == self.age))
+            .count();
+        (used * 1000 / sample) as u32
+    }
+
+    fn slot(&self, key: u64) -> MutexGuard<'_, Option<Entry>> {
+        lock(&self.entries[(key % self.entries.len() as u64) as usize])
+    }
+}
+
+/// Slots are written whole, so one whose lock a panicking thread poisoned
+/// still holds a valid entry.
+fn lock(slot: &Mutex<Option<Entry>>) -> MutexGuard<'_, Option<Entry>> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_probe() {
+        let tt = TranspositionTable::new(1);
+        assert!(tt.capacity() > 1000);
+        tt.store(42, None, 15, 3, Bound::Exact);
+        let entry = tt.probe(42).unwrap();
+        assert_eq!((entry.score, entry.depth, entry.bound), (15, 3, Bound::Exact));
+        assert!(tt.probe(43).is_none());
+    }
+
+    #[test]
+    fn test_replacement_prefers_depth_then_age() {
+        let mut tt = TranspositionTable::new(0);
+        assert_eq!(tt.capacity(), 1);
+        tt.store(1, None, 10, 5, Bound::Exact);
+        tt.store(2, None, 20, 2, Bound::Lower);
+        assert_eq!(tt.probe(1).map(|e| e.score), Some(10));
+
+        tt.new_search();
+        tt.store(2, None, 20, 2, Bound::Lower);
+        assert!(tt.probe(1).is_none());
+        assert_eq!(tt.probe(2).map(|e| e.bound), Some(Bound::Lower));
+        tt.clear();
+        assert!(tt.probe(2).is_none());
+    }
+}
@@ -0,0 +1,492 @@
+// UCI protocol front-end: reads commands, runs the engine on a search thread so
+// "stop", "ponderhit", and "isready" are answered while it thinks, and writes
+// replies. The "Debug Log File" option mirrors all of it to a file, with ">>"
+// before commands read and "<<" before lines written.
+
+use crate::board::Board;
+use crate::book::Book;
+use crate::engine::{AnalysisOptions, Engine, EvalBackend, SearchHandle, SearchLimits, SearchResult, mate_moves};
+use crate::moves::Move;
+use crate::pieces::Color;
+#[cfg(feature = "online")]
+use crate::online::OnlineTablebase;
+#[cfg(feature = "tablebase")]
+use crate::tablebase::Tablebase;
+use crate::time_manager::{TimeInfo, TimeOptions};
+use crate::tt::DEFAULT_SIZE_MB;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const ENGINE_NAME: &str = concat!("rchess ", env!("CARGO_PKG_VERSION"));
+pub const ENGINE_AUTHOR: &str = "the rchess developers";
+
+/// Read UCI commands from `input` until "quit" or end of input, writing replies to `output`.
+/// At end of input a running search is allowed to finish, unless it is infinite.
+pub fn run<R: BufRead, W: Write + Send + 'static>(input: R, output: W) -> io::Result<()> {
+    let mut session = Session::new(output);
+    for line in input.lines() {
+        if !session.handle(&line?)? {
+            return session.finish(true);
+        }
+    }
+    let infinite = session.infinite;
+    session.finish(infinite)
+}
+
+/// Parameters of a "go" command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GoOptions {
+    pub limits: SearchLimits,
+    /// Search until "stop", and only then report the best move; also set by
+    /// "ponder", where "ponderhit" lets the best move out as well.
+    pub infinite: bool,
+}
+
+impl GoOptions {
+    /// Parse the arguments after "go" for a position with `side` to move.
+    /// Unknown tokens are skipped.
+    pub fn parse(args: &[&str], side: Color) -> Self {
+        let mut options = GoOptions::default();
+        let mut clock = TimeInfo::default();
+        let mut has_clock = false;
+        let mut tokens = args.iter();
+        while let Some(&token) = tokens.next() {
+            let mut value = || tokens.next().and_then(|v| v.parse::<i64>().ok()).map(|v| v.max(0) as u64);
+            match (token, side) {
+                ("depth", _) => options.limits.depth = value().map(|v| v as u32),
+                ("nodes", _) => options.limits.nodes = value(),
+                ("movetime", _) => options.limits.move_time_ms = value(),
+                ("wtime", Color::White) | ("btime", Color::Black) => {
+                    clock.remaining_ms = value().unwrap_or(0);
+                    has_clock = true;
+                }
+                ("winc", Color::White) | ("binc", Color::Black) => clock.increment_ms = value().unwrap_or(0),
+                ("wtime" | "btime" | "winc" | "binc", _) => {
+                    value();
+                }
+                ("movestogo", _) => clock.moves_to_go = value().map(|v| v as u32),
+                ("infinite" | "ponder", _) => options.infinite = true,
+                _ => {}
+            }
+        }
+        if has_clock {
+            options.limits.clock = Some(clock);
+        }
+        options
+    }
+}
+
+/// Format a search report as a UCI "info" line.
+pub fn info_line(result: &SearchResult) -> String {
+    format_info(result, None)
+}
+
+/// Format line `rank` of a MultiPV report, counting from 1 for the best, as a
+/// UCI "info" line.
+pub fn multipv_info_line(rank: usize, result: &SearchResult) -> String {
+    format_info(result, Some(rank))
+}
+
+fn format_info(result: &SearchResult, rank: Option<usize>) -> String {
+    let score = match mate_moves(result.score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    let nps = result.nodes * 1000 / result.time_ms.max(1);
+    let multipv = rank.map_or(String::new(), |rank| format!(" multipv {}", rank));
+    let mut line = format!(
+        "info depth {}{} score {} nodes {} nps {} time {}",
+        result.depth, multipv, score, result.nodes, nps, result.time_ms
+    );
+    if !result.pv.is_empty() {
+        line.push_str(" pv");
+        for mv in &result.pv {
+            line.push(' ');
+            line.push_str(&mv.to_uci());
+        }
+    }
+    line
+}
+
+struct Session<W> {
+    out: Arc<Mutex<W>>,
+    board: Board,
+    // The engine moves into the search during "go" and comes back when it is joined.
+    engine: Option<Engine>,
+    search: Option<SearchHandle>,
+    stop: Arc<AtomicBool>,
+    /// Set while the best move of an infinite or ponder search is held back.
+    hold: Arc<AtomicBool>,
+    infinite: bool,
+    /// Lines to report per iteration; above 1 the search runs as `Engine::analyse`.
+    multipv: usize,
+    /// Set by "debug on": each search reports its position and limits as an info string.
+    debug: bool,
+    log: Arc<Mutex<Option<File>>>,
+}
+
+/// Append `line` to the debug log, if one is open, after a timestamp in
+/// seconds since the epoch. A failing log does not stop the session.
+fn log_line(log: &Mutex<Option<File>>, direction: &str, line: &str) {
+    let mut log = log.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(file) = log.as_mut() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let _ = writeln!(file, "{}.{:03} {} {}", now.as_secs(), now.subsec_millis(), direction, line);
+    }
+}
+
+impl<W: Write + Send + 'static> Session<W> {
+    fn new(output: W) -> Self {
+        let engine = Engine::new();
+        Session {
+            out: Arc::new(Mutex::new(output)),
+            board: Board::starting_position(),
+            stop: engine.stop_flag(),
+            engine: Some(engine),
+            search: None,
+            hold: Arc::new(AtomicBool::new(false)),
+            infinite: false,
+            multipv: 1,
+            debug: false,
+            log: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Handle one command line; returns false on "quit".
+    fn handle(&mut self, line: &str) -> io::Result<bool> {
+        log_line(&self.log, ">>", line);
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return Ok(true);
+        };
+        match command {
+            "uci" => {
+                self.send(&format!("id name {}", ENGINE_NAME))?;
+                self.send(&format!("id author {}", ENGINE_AUTHOR))?;
+                self.send(&format!("option name Hash type spin default {} min 1 max 4096", DEFAULT_SIZE_MB))?;
+                self.send("option name Clear Hash type button")?;
+                self.send("option name Move Overhead type spin default 30 min 0 max 5000")?;
+                self.send("option name nodestime type spin default 0 min 0 max 10000")?;
+                self.send("option name BookFile type string default <empty>")?;
+                self.send("option name EvalFile type string default <empty>")?;
+                self.send("option name MultiPV type spin default 1 min 1 max 256")?;
+                self.send("option name Ponder type check default false")?;
+                self.send("option name Debug Log File type string default <empty>")?;
+                #[cfg(feature = "tablebase")]
+                self.send("option name SyzygyPath type string default <empty>")?;
+                #[cfg(feature = "online")]
+                self.send("option name OnlineTablebase type check default false")?;
+                #[cfg(feature = "parallel")]
+                self.send("option name Threads type spin default 1 min 1 max 256")?;
+                self.send("uciok")?;
+            }
+            "isready" => self.send("readyok")?,
+            "debug" => match args.first() {
+                Some(&"on") => self.debug = true,
+                Some(&"off") => self.debug = false,
+                _ => self.send("info string expected debug on or debug off")?,
+            },
+            "ucinewgame" => {
+                self.engine()?.clear_hash();
+                self.board = Board::starting_position();
+            }
+            "setoption" => self.set_option(args)?,
+            "position" => self.set_position(args)?,
+            "go" => self.go(args)?,
+            "stop" => self.finish(true)?,
+            "ponderhit" => {
+                // The search goes on under its clock limits; the move is reported when it ends.
+                self.hold.store(false, Ordering::Relaxed);
+                self.infinite = false;
+            }
+            "quit" => return Ok(false),
+            _ => self.send(&format!("info string unknown command: {}", command))?,
+        }
+        Ok(true)
+    }
+
+    fn set_option(&mut self, args: &[&str]) -> io::Result<()> {
+        // "name <words...> [value <words...>]"; option names may contain spaces.
+        let text = args.join(" ");
+        let rest = text.strip_prefix("name ").unwrap_or(&text);
+        let (name, value) = match rest.split_once(" value ") {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (rest.trim(), ""),
+        };
+        let number = value.parse::<u64>().ok();
+        if name.eq_ignore_ascii_case("debug log file") {
+            let file = match value {
+                "" | "<empty>" => None,
+                path => match OpenOptions::new().create(true).append(true).open(path) {
+                    Ok(file) => Some(file),
+                    Err(err) => return self.send(&format!("info string cannot open log {}: {}", path, err)),
+                },
+            };
+            *self.log.lock().unwrap_or_else(|e| e.into_inner()) = file;
+            return Ok(());
+        }
+        if name.eq_ignore_ascii_case("bookfile") {
+            let book = match value {
+                "" | "<empty>" => None,
+                path => match Book::open(path) {
+                    Ok(book) => Some(book),
+                    Err(err) => return self.send(&format!("info string cannot read book {}: {}", path, err)),
+                },
+            };
+            self.engine()?.set_book(book);
+            return Ok(());
+        }
+        if name.eq_ignore_ascii_case("evalfile") {
+            let backend = match value {
+                "" | "<empty>" => EvalBackend::Classical,
+                #[cfg(feature = "onnx")]
+                path if path.ends_with(".onnx") => EvalBackend::Onnx(path.into()),
+                path => EvalBackend::Nnue(path.into()),
+            };
+            if let Err(err) = self.engine()?.set_eval(backend) {
+                return self.send(&format!("info string cannot read network {}: {}", value, err));
+            }
+            return Ok(());
+        }
+        #[cfg(feature = "tablebase")]
+        if name.eq_ignore_ascii_case("syzygypath") {
+            // Directories are separated as in PATH.
+            let mut tablebase = Tablebase::default();
+            if !matches!(value, "" | "<empty>") {
+                for dir in std::env::split_paths(value) {
+                    if let Err(err) = tablebase.add_directory(&dir) {
+                        return self.send(&format!("info string cannot read tablebases {}: {}", dir.display(), err));
+                    }
+                }
+            }
+            let tablebase = (tablebase.max_pieces() > 0).then_some(tablebase);
+            self.engine()?.set_tablebase(tablebase);
+            return Ok(());
+        }
+        if name.eq_ignore_ascii_case("multipv") {
+            match number {
+                Some(lines) => self.multipv = lines.clamp(1, 256) as usize,
+                None => return self.send(&format!("info string invalid MultiPV value: {}", value)),
+            }
+            return Ok(());
+        }
+        let engine = self.engine()?;
+        match (name.to_ascii_lowercase().as_str(), number) {
+            ("hash", Some(mb)) => engine.set_hash_size(mb.max(1) as usize),
+            ("clear hash", _) => engine.clear_hash(),
+            // Pondering needs nothing set up; the GUI decides when to send "go ponder".
+            ("ponder", _) => {}
+            #[cfg(feature = "online")]
+            ("onlinetablebase", _) => {
+                engine.set_online_tablebase(value.eq_ignore_ascii_case("true").then(OnlineTablebase::default))
+            }
+            #[cfg(feature = "parallel")]
+            ("threads", Some(threads)) => engine.set_threads(threads.clamp(1, 256) as usize),
+            ("move overhead", Some(ms)) => {
+                let options = *engine.time_options();
+                engine.set_time_options(TimeOptions { move_overhead_ms: ms, ..options });
+            }
+            ("nodestime", Some(rate)) => {
+                let options = *engine.time_options();
+                let nodes_per_ms = (rate > 0).then_some(rate);
+                engine.set_time_options(TimeOptions { nodes_per_ms, ..options });
+            }
+            _ => return self.send(&format!("info string unknown option: {}", name)),
+        }
+        Ok(())
+    }
+
+    fn set_position(&mut self, args: &[&str]) -> io::Result<()> {
+        let moves_at = args.iter().position(|&t| t == "moves").unwrap_or(args.len());
+        let mut board = match args.first() {
+            Some(&"startpos") => Board::starting_position(),
+            Some(&"fen") => match Board::from_fen(&args[1..moves_at].join(" ")) {
+                Ok(board) => board,
+                Err(err) => return self.send(&format!("info string invalid fen: {}", err)),
+            },
+            _ => return self.send("info string expected startpos or fen"),
+        };
+        for text in args.iter().skip(moves_at + 1) {
+            let applied = Move::from_uci(text, &board)
+                .map_err(|err| err.to_string())
+                .and_then(|mv| board.apply_move(mv).map_err(|err| err.to_string()));
+            if let Err(err) = applied {
+                return self.send(&format!("info string illegal move {}: {}", text, err));
+            }
+        }
+        self.board = board;
+        Ok(())
+    }
+
+    fn go(&mut self, args: &[&str]) -> io::Result<()> {
+        let options = GoOptions::parse(args, self.board.current_player());
+        self.engine()?;
+        let engine = self.engine.take().unwrap_or_default();
+        self.infinite = options.infinite;
+        self.hold.store(options.infinite, Ordering::Relaxed);
+
+        if self.debug {
+            self.send(&format!("info string searching {} with {:?}", self.board.to_fen(), options.limits))?;
+        }
+        let out = Arc::clone(&self.out);
+        let log = Arc::clone(&self.log);
+        let report = move |line: &str| {
+            log_line(&log, "<<", line);
+            let mut out = out.lock().unwrap_or_else(|e| e.into_inner());
+            // A closed output has nobody left to report to.
+            let _ = writeln!(out, "{}", line).and_then(|_| out.flush());
+        };
+        let multipv = self.multipv;
+        let on_iteration = {
+            let report = report.clone();
+            move |lines: &[SearchResult]| {
+                for (i, line) in lines.iter().enumerate() {
+                    report(&if multipv > 1 { multipv_info_line(i + 1, line) } else { info_line(line) });
+                }
+            }
+        };
+        let (stop, hold) = (Arc::clone(&self.stop), Arc::clone(&self.hold));
+        let on_finish = move |lines: &[SearchResult]| {
+            // An infinite or ponder search only reports its move once told to.
+            while hold.load(Ordering::Relaxed) && !stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let best_move = lines.first().and_then(|line| line.best_move);
+            report(&format!("bestmove {}", best_move.map_or("0000".to_string(), |m| m.to_uci())));
+        };
+        let options = AnalysisOptions { multipv, limits: options.limits };
+        self.search = Some(engine.start_with_info(&self.board, options, on_iteration, on_finish));
+        Ok(())
+    }
+
+    /// Wait for the running search, if any, stopping it first when `stop` is set.
+    fn finish(&mut self, stop: bool) -> io::Result<()> {
+        if let Some(search) = self.search.take() {
+            let (engine, _) = if stop { search.stop() } else { search.wait() };
+            self.engine = Some(engine);
+            self.infinite = false;
+        }
+        Ok(())
+    }
+
+    /// The engine, after letting any running search finish. Commands that change
+    /// engine state wait for the search rather than interrupting it.
+    fn engine(&mut self) -> io::Result<&mut Engine> {
+        let infinite = self.infinite;
+        self.finish(infinite)?;
+        Ok(self.engine.get_or_insert_with(Engine::new))
+    }
+
+    fn send(&self, line: &str) -> io::Result<()> {
+        log_line(&self.log, "<<", line);
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(out, "{}", line)?;
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output buffer the test keeps a handle to while the session owns a clone.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn session(input: &str) -> Vec<String> {
+        let buffer = SharedBuffer::default();
+        run(input.as_bytes(), buffer.clone()).unwrap();
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_handshake() {
+        let lines = session("uci\nisready\nquit\n");
+        assert!(lines[0].starts_with("id name rchess"));
+        assert!(lines.iter().any(|l| l.starts_with("option name Hash type spin")));
+        assert_eq!(lines[lines.len() - 2], "uciok");
+        assert_eq!(lines.last().unwrap(), "readyok");
+    }
+
+    #[test]
+    fn test_debug_log_mirrors_the_session() {
+        let path = std::env::temp_dir().join(format!("rchess-uci-log-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let lines = session(&format!(
+            "setoption name Debug Log File value {}\ndebug on\ngo depth 1\nisready\n",
+            path.display()
+        ));
+        assert!(lines[0].starts_with("info string searching rnbqkbnr/"));
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(log.lines().any(|l| l.ends_with(" >> debug on")));
+        assert!(log.lines().any(|l| l.contains(" << bestmove ")));
+        assert!(log.lines().any(|l| l.ends_with(" << readyok")));
+    }
+
+    #[test]
+    fn test_position_and_go_depth() {
+        let lines = session("position startpos moves e2e4 e7e5 d1h5 b8c6 f1c4 g8f6\ngo depth 2\n");
+        assert!(lines.iter().any(|l| l.starts_with("info depth 1 ")));
+        assert_eq!(lines.last().unwrap(), "bestmove h5f7");
+        assert!(lines.iter().any(|l| l.contains("score mate 1")));
+    }
+
+    #[test]
+    fn test_fen_position_and_bad_input() {
+        let lines = session("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1 moves a1a9\nposition fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo movetime 50\n");
+        assert!(lines[0].starts_with("info string illegal move a1a9"));
+        assert_eq!(lines.last().unwrap(), "bestmove a1a8");
+    }
+
+    #[test]
+    fn test_multipv_reports_ranked_lines() {
+        let lines = session("setoption name MultiPV value 2\nposition fen 4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1\ngo depth 2\n");
+        assert!(lines.iter().any(|l| l.starts_with("info depth 2 multipv 1 ") && l.contains(" pv d1d5")));
+        assert!(lines.iter().any(|l| l.starts_with("info depth 2 multipv 2 ")));
+        assert_eq!(lines.last().unwrap(), "bestmove d1d5");
+    }
+
+    #[test]
+    fn test_infinite_search_waits_for_stop() {
+        let lines = session("go infinite\nstop\n");
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("bestmove")).count(), 1);
+    }
+
+    #[test]
+    fn test_ponder_holds_the_move_until_ponderhit() {
+        let lines = session("go ponder depth 1\nisready\n");
+        let ready = lines.iter().position(|l| l == "readyok").unwrap();
+        assert!(lines[ready + 1].starts_with("bestmove "));
+
+        let lines = session("go ponder depth 2\nponderhit\n");
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("bestmove")).count(), 1);
+    }
+
+    #[test]
+    fn test_go_options_use_side_to_move_clock() {
+        let args = ["wtime", "1000", "btime", "60000", "binc", "500", "movestogo", "20"];
+        let options = GoOptions::parse(&args, Color::Black);
+        let clock = options.limits.clock.unwrap();
+        assert_eq!((clock.remaining_ms, clock.increment_ms, clock.moves_to_go), (60_000, 500, Some(20)));
+        assert!(!options.infinite);
+        assert!(GoOptions::parse(&["infinite"], Color::White).infinite);
+    }
+}
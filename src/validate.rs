@@ -0,0 +1,164 @@
+// Sanity checks on a position. Boards from FEN and from proto messages are taken
+// as given, so a server or loader facing untrusted input can ask for the list of
+// rules the position breaks before playing on it.
+
+use crate::bitboard::Bitboard;
+use crate::board::Board;
+use crate::moves::CastlingSide;
+use crate::pieces::{Color, PieceType, Square};
+use std::fmt;
+
+/// One way a position cannot arise in a legal game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// More than one piece stands on the square.
+    SharedSquare(Square),
+    MissingKing(Color),
+    ExtraKings { color: Color, count: usize },
+    /// A pawn on the first or last rank.
+    PawnOnBackRank(Square),
+    /// A castling right is held but the king or that rook has left its home square.
+    ImpossibleCastling { color: Color, side: CastlingSide },
+    /// The en-passant target is not the square a pawn of the side that just
+    /// moved passed over on a double step.
+    InvalidEnPassant(Square),
+    /// The side that just moved is in check, so the side to move could take the king.
+    OpponentInCheck(Color),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::SharedSquare(square) => write!(f, "more than one piece on {}", square),
+            Violation::MissingKing(color) => write!(f, "{} has no king", color),
+            Violation::ExtraKings { color, count } => write!(f, "{} has {} kings", color, count),
+            Violation::PawnOnBackRank(square) => write!(f, "pawn on {}", square),
+            Violation::ImpossibleCastling { color, side } => {
+                let side = match side {
+                    CastlingSide::Kingside => "kingside",
+                    CastlingSide::Queenside => "queenside",
+                };
+                write!(f, "{} cannot castle {} with the king or rook moved", color, side)
+            }
+            Violation::InvalidEnPassant(square) => write!(f, "no pawn can be taken en passant on {}", square),
+            Violation::OpponentInCheck(color) => write!(f, "{} is in check but not to move", color),
+        }
+    }
+}
+
+impl Board {
+    /// Every rule the position breaks, in the order of the `Violation` variants;
+    /// empty for a position that could arise in a game.
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        let mut seen = Bitboard::EMPTY;
+        let mut shared = Bitboard::EMPTY;
+        let mut kings = [0; 2];
+        let mut back_rank_pawns = Vec::new();
+        for (i, color) in [Color::White, Color::Black].into_iter().enumerate() {
+            for piece in self.pieces_of_color(color) {
+                let Some(square) = self.piece_square(piece) else {
+                    continue;
+                };
+                if seen.contains(square) {
+                    shared.insert(square);
+                }
+                seen.insert(square);
+                match self.piece_type(piece) {
+                    Some(PieceType::King) => kings[i] += 1,
+                    Some(PieceType::Pawn) if square.rank == 0 || square.rank == 7 => back_rank_pawns.push(square),
+                    _ => {}
+                }
+            }
+        }
+        violations.extend(shared.squares().map(Violation::SharedSquare));
+        for (i, color) in [Color::White, Color::Black].into_iter().enumerate() {
+            match kings[i] {
+                0 => violations.push(Violation::MissingKing(color)),
+                1 => {}
+                count => violations.push(Violation::ExtraKings { color, count }),
+            }
+        }
+        back_rank_pawns.sort_by_key(Square::index);
+        violations.extend(back_rank_pawns.into_iter().map(Violation::PawnOnBackRank));
+
+        let rights = self.castling_rights();
+        for (color, rank, kingside, queenside) in [
+            (Color::White, 0, rights.white_kingside, rights.white_queenside),
+            (Color::Black, 7, rights.black_kingside, rights.black_queenside),
+        ] {
+            let home = |file, piece_type| self.piece_at_typed(Square { file, rank }) == Some((piece_type, color));
+            for (allowed, side, rook_file) in [(kingside, CastlingSide::Kingside, 7), (queenside, CastlingSide::Queenside, 0)] {
+                if allowed && !(home(4, PieceType::King) && home(rook_file, PieceType::Rook)) {
+                    violations.push(Violation::ImpossibleCastling { color, side });
+                }
+            }
+        }
+
+        if let Some(target) = self.en_passant_target() {
+            // The side that just moved stepped from `start` over `target` to `landing`.
+            let mover = self.current_player().opposite();
+            let (start, landing) = match mover {
+                Color::White => (1, 3),
+                Color::Black => (6, 4),
+            };
+            let passed = target.rank == (start + landing) / 2
+                && self.piece_at(Square { file: target.file, rank: start }).is_none()
+                && self.piece_at(target).is_none()
+                && self.piece_at_typed(Square { file: target.file, rank: landing }) == Some((PieceType::Pawn, mover));
+            if !passed {
+                violations.push(Violation::InvalidEnPassant(target));
+            }
+        }
+
+        let waiting = self.current_player().opposite();
+        if self.is_in_check(waiting) {
+            violations.push(Violation::OpponentInCheck(waiting));
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rchess::v1::{self as proto};
+    use crate::sq;
+
+    #[test]
+    fn test_legal_positions_have_no_violations() {
+        assert!(Board::starting_position().validate().is_empty());
+        let board = Board::from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 2").unwrap();
+        assert!(board.validate().is_empty());
+    }
+
+    #[test]
+    fn test_violations_are_reported() {
+        // Two white kings, a pawn on the eighth rank, white castling rights with
+        // the rooks gone, a stray en-passant target, and Black to move while
+        // White is in check.
+        let board = Board::from_fen("P3k3/8/8/8/8/8/8/K1r1K3 b KQ a3 0 1").unwrap();
+        assert_eq!(
+            board.validate(),
+            vec![
+                Violation::ExtraKings { color: Color::White, count: 2 },
+                Violation::PawnOnBackRank(sq!("a8")),
+                Violation::ImpossibleCastling { color: Color::White, side: CastlingSide::Kingside },
+                Violation::ImpossibleCastling { color: Color::White, side: CastlingSide::Queenside },
+                Violation::InvalidEnPassant(sq!("a3")),
+                Violation::OpponentInCheck(Color::White),
+            ]
+        );
+        let no_black_king = Board::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(no_black_king.validate(), vec![Violation::MissingKing(Color::Black)]);
+
+        // Put the g1 knight's proto on f1, where the bishop stands.
+        let mut state = Board::starting_position().to_proto();
+        if let Some(proto::piece::Kind::Knight(knight)) = &mut state.board.as_mut().unwrap().pieces[6].kind {
+            knight.position = Some(sq!("f1").to_proto());
+        }
+        let board = Board::try_from_proto(state).unwrap();
+        assert_eq!(board.validate(), vec![Violation::SharedSquare(sq!("f1"))]);
+    }
+}
@@ -0,0 +1,375 @@
+// Rule variants. A Board holds one Variant, which move generation, apply_move,
+// game_status, and the search consult for what standard chess leaves fixed:
+// which pseudo-legal moves are legal, what else a capture removes, and extra ways
+// to win. Built-in variants are found by the name stored in GameState.variant.
+
+use crate::board::Board;
+use crate::moves::{Move, MoveError};
+use crate::pieces::{Color, PieceType, Square};
+use crate::status::GameStatus;
+use std::fmt;
+use std::sync::Arc;
+
+/// Rules that differ between chess variants. Every method has the standard-chess
+/// behaviour as its default.
+pub trait Variant: fmt::Debug + Send + Sync {
+    /// Name stored in GameState.variant, e.g. "atomic".
+    fn name(&self) -> &'static str;
+
+    /// Check `mv`, a pseudo-legal move by `color` that led to `after`. In standard
+    /// chess the mover's king must not be left in check.
+    fn check_legal(&self, mv: Move, color: Color, after: &Board) -> Result<(), MoveError> {
+        let _ = mv;
+        if after.is_in_check(color) {
+            Err(MoveError::LeavesKingInCheck)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Whether `check_legal` accepts exactly the moves that leave the mover's
+    /// king safe, and captures have no side effects, so move generation can test
+    /// moves without playing them.
+    fn king_safety_only(&self) -> bool {
+        true
+    }
+
+    /// Squares, in the position before `mv`, of pieces the move removes besides
+    /// the one it captures. Including `mv.from` removes the moving piece itself.
+    fn capture_side_effects(&self, board: &Board, mv: Move) -> Vec<Square> {
+        let _ = (board, mv);
+        Vec::new()
+    }
+
+    /// Whether the board should count the checks each side gives.
+    fn counts_checks(&self) -> bool {
+        false
+    }
+
+    /// Whether captured pieces go to the capturer's pocket and can be dropped
+    /// back onto the board.
+    fn has_drops(&self) -> bool {
+        false
+    }
+
+    /// Check a drop of `piece_type` by `color` onto the empty square `to`, before
+    /// it is played; whether it leaves the king safe is left to `check_legal`.
+    /// Only asked in variants with drops.
+    fn check_drop(&self, piece_type: PieceType, to: Square, color: Color) -> Result<(), MoveError> {
+        let _ = (piece_type, to, color);
+        Ok(())
+    }
+
+    /// A result reached under this variant's own rules. It is checked before
+    /// checkmate and the draw rules.
+    fn outcome(&self, board: &Board) -> Option<GameStatus> {
+        let _ = board;
+        None
+    }
+}
+
+/// Standard chess.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+}
+
+/// Atomic chess: a capture explodes the capturing piece and every piece other than
+/// a pawn next to the capture square. Blowing up the enemy king wins; a move that
+/// blows up your own king is illegal, so kings cannot capture. Kings standing next
+/// to each other cannot give check.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Atomic;
+
+impl Variant for Atomic {
+    fn name(&self) -> &'static str {
+        "atomic"
+    }
+
+    fn king_safety_only(&self) -> bool {
+        false
+    }
+
+    fn check_legal(&self, _mv: Move, color: Color, after: &Board) -> Result<(), MoveError> {
+        if after.king_square(color).is_none() {
+            return Err(MoveError::VariantRule("a move may not explode your own king"));
+        }
+        let Some(enemy) = after.king_square(color.opposite()) else {
+            return Ok(());
+        };
+        let own = after.king_square(color).unwrap_or(enemy);
+        let kings_touch = own.file.abs_diff(enemy.file) <= 1 && own.rank.abs_diff(enemy.rank) <= 1;
+        if !kings_touch && after.is_in_check(color) {
+            return Err(MoveError::LeavesKingInCheck);
+        }
+        Ok(())
+    }
+
+    fn capture_side_effects(&self, board: &Board, mv: Move) -> Vec<Square> {
+        if !board.annotate_move(mv).is_capture() {
+            return Vec::new();
+        }
+        let mut exploded = vec![mv.from];
+        for (df, dr) in NEIGHBOURS {
+            let Some(square) = Square::new((mv.to.file as i8 + df) as u8, (mv.to.rank as i8 + dr) as u8) else {
+                continue;
+            };
+            if square != mv.from
+                && let Some((piece_type, _)) = board.piece_at_typed(square)
+                && piece_type != PieceType::Pawn
+            {
+                exploded.push(square);
+            }
+        }
+        exploded
+    }
+
+    fn outcome(&self, board: &Board) -> Option<GameStatus> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| board.king_square(color).is_none())
+            .map(|loser| GameStatus::VariantWin {
+                winner: loser.opposite(),
+                reason: "king exploded",
+            })
+    }
+}
+
+const NEIGHBOURS: [(i8, i8); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+/// Three-check: giving check for the third time wins.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreeCheck;
+
+/// Checks a side must give to win at three-check.
+pub const CHECKS_TO_WIN: u32 = 3;
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "three-check"
+    }
+
+    fn counts_checks(&self) -> bool {
+        true
+    }
+
+    fn outcome(&self, board: &Board) -> Option<GameStatus> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| board.checks_given(color) >= CHECKS_TO_WIN)
+            .map(|winner| GameStatus::VariantWin {
+                winner,
+                reason: "third check",
+            })
+    }
+}
+
+/// King of the hill: bringing your king to d4, e4, d5, or e5 wins.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KingOfTheHill;
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "king-of-the-hill"
+    }
+
+    fn outcome(&self, board: &Board) -> Option<GameStatus> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| {
+                board
+                    .king_square(color)
+                    .is_some_and(|king| (3..=4).contains(&king.file) && (3..=4).contains(&king.rank))
+            })
+            .map(|winner| GameStatus::VariantWin {
+                winner,
+                reason: "king reached the center",
+            })
+    }
+}
+
+/// Crazyhouse: a captured piece changes sides and goes to the capturer's pocket,
+/// from where it can be dropped on any empty square instead of making a move.
+/// Pawns may not be dropped on the first or last rank. Checkmating with a drop is
+/// allowed unless `drop_mate_allowed` is turned off, as some house rules have it.
+#[derive(Debug, Clone, Copy)]
+pub struct Crazyhouse {
+    pub drop_mate_allowed: bool,
+}
+
+impl Default for Crazyhouse {
+    fn default() -> Self {
+        Crazyhouse { drop_mate_allowed: true }
+    }
+}
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "crazyhouse"
+    }
+
+    fn check_legal(&self, mv: Move, color: Color, after: &Board) -> Result<(), MoveError> {
+        if after.is_in_check(color) {
+            return Err(MoveError::LeavesKingInCheck);
+        }
+        if mv.is_drop() && !self.drop_mate_allowed && after.is_in_check(color.opposite()) {
+            // Judge the replies under the default rules, so that deciding whether
+            // this drop mates does not recurse into the replies' own drops.
+            let mut replies = after.clone();
+            replies.set_variant(Arc::new(Crazyhouse::default()));
+            if replies.legal_moves(color.opposite()).is_empty() {
+                return Err(MoveError::VariantRule("a drop may not give checkmate"));
+            }
+        }
+        Ok(())
+    }
+
+    fn king_safety_only(&self) -> bool {
+        self.drop_mate_allowed
+    }
+
+    fn has_drops(&self) -> bool {
+        true
+    }
+
+    fn check_drop(&self, piece_type: PieceType, to: Square, _color: Color) -> Result<(), MoveError> {
+        if piece_type == PieceType::Pawn && (to.rank == 0 || to.rank == 7) {
+            return Err(MoveError::VariantRule("pawns may not be dropped on the first or last rank"));
+        }
+        Ok(())
+    }
+}
+
+/// The built-in variant called `name`. An empty name is standard chess.
+pub fn from_name(name: &str) -> Option<Arc<dyn Variant>> {
+    match name {
+        "" | "standard" => Some(Arc::new(Standard)),
+        "atomic" => Some(Arc::new(Atomic)),
+        "three-check" => Some(Arc::new(ThreeCheck)),
+        "king-of-the-hill" => Some(Arc::new(KingOfTheHill)),
+        "crazyhouse" => Some(Arc::new(Crazyhouse::default())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    fn board(variant: &str, fen: &str) -> Board {
+        let mut board = Board::from_fen(fen).unwrap();
+        board.set_variant(from_name(variant).unwrap());
+        board
+    }
+
+    #[test]
+    fn test_atomic_capture_explodes_neighbours() {
+        // The knight takes on d5: it, the pawn it takes, and the bishop on e6 explode;
+        // the pawn on c6 survives.
+        let mut atomic = board("atomic", "4k3/8/2p1b3/3p4/8/4N3/8/4K3 w - - 0 1");
+        atomic.apply_move(Move::new(sq!("e3"), sq!("d5"))).unwrap();
+        assert_eq!(atomic.to_fen().split(' ').next(), Some("4k3/8/2p5/8/8/8/8/4K3"));
+        atomic.undo_move();
+        assert_eq!(atomic.to_fen(), "4k3/8/2p1b3/3p4/8/4N3/8/4K3 w - - 0 1");
+
+        // Exploding the enemy king wins, even though it also takes the queen.
+        let mut win = board("atomic", "3qk3/8/8/8/8/8/3Q4/4K3 w - - 0 1");
+        win.apply_move(Move::new(sq!("d2"), sq!("d8"))).unwrap();
+        assert_eq!(
+            win.game_status(),
+            GameStatus::VariantWin { winner: Color::White, reason: "king exploded" }
+        );
+    }
+
+    #[test]
+    fn test_atomic_forbids_exploding_own_king() {
+        let mut atomic = board("atomic", "4k3/8/8/8/8/8/3p4/4K3 w - - 0 1");
+        assert_eq!(
+            atomic.apply_move(Move::new(sq!("e1"), sq!("d2"))),
+            Err(MoveError::VariantRule("a move may not explode your own king"))
+        );
+        assert!(!atomic.legal_moves(Color::White).iter().any(|m| m.is_capture()));
+    }
+
+    #[test]
+    fn test_three_check_and_king_of_the_hill_wins() {
+        let mut three = board("three-check", "4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let moves = [
+            (sq!("a1"), sq!("a8")),
+            (sq!("e8"), sq!("e7")),
+            (sq!("a8"), sq!("a7")),
+            (sq!("e7"), sq!("e6")),
+            (sq!("a7"), sq!("a6")),
+        ];
+        for (from, to) in moves {
+            three.apply_move(Move::new(from, to)).unwrap();
+        }
+        assert_eq!(three.checks_given(Color::White), 3);
+        assert_eq!(
+            three.game_status(),
+            GameStatus::VariantWin { winner: Color::White, reason: "third check" }
+        );
+        let restored = Board::try_from_proto(three.to_proto()).unwrap();
+        assert_eq!(restored.variant().name(), "three-check");
+        assert_eq!(restored.checks_given(Color::White), 3);
+
+        let mut hill = board("king-of-the-hill", "4k3/8/8/8/8/4K3/8/8 w - - 0 1");
+        assert_eq!(hill.game_status(), GameStatus::Ongoing);
+        hill.apply_move(Move::new(sq!("e3"), sq!("d4"))).unwrap();
+        assert_eq!(hill.game_status().winner(), Some(Color::White));
+    }
+
+    #[test]
+    fn test_crazyhouse_captures_fill_the_pocket_and_drops() {
+        let mut house = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        house.set_variant(from_name("crazyhouse").unwrap());
+        house.apply_move(Move::new(sq!("e4"), sq!("d5"))).unwrap();
+        assert_eq!(house.pocket(Color::White).pawns, 1);
+        assert_eq!(house.to_fen(), "4k3/8/8/3P4/8/8/8/4K3[P] b - - 0 1");
+        house.apply_move(Move::new(sq!("e8"), sq!("d7"))).unwrap();
+
+        // A pawn may go on any empty square except the first and last ranks.
+        let drops: Vec<Move> = house.legal_moves(Color::White).into_iter().filter(Move::is_drop).collect();
+        assert_eq!(drops.len(), 48 - 2);
+        let e4 = sq!("e4");
+        let e8 = sq!("e8");
+        assert_eq!(
+            house.apply_move(Move::new_drop(PieceType::Pawn, e8)),
+            Err(MoveError::VariantRule("pawns may not be dropped on the first or last rank"))
+        );
+        assert_eq!(house.apply_move(Move::new_drop(PieceType::Knight, e4)), Err(MoveError::NotInPocket(PieceType::Knight)));
+        let drop = Move::from_san("@e6", &house).unwrap();
+        assert_eq!((drop.to_san(&house), drop.to_uci()), ("P@e6+".to_string(), "P@e6".to_string()));
+        house.apply_move(drop).unwrap();
+        assert!(house.pocket(Color::White).is_empty());
+        // Parsing the FEN back gives the same position and hash.
+        let fen = house.to_fen();
+        assert_eq!(fen, "8/3k4/4P3/3P4/8/8/8/4K3[] b - - 0 2");
+        assert_eq!(Board::from_fen(&fen).unwrap().zobrist_key(), house.zobrist_key());
+
+        house.undo_move();
+        assert_eq!(house.to_fen(), "8/3k4/8/3P4/8/8/8/4K3[P] w - - 1 2");
+        assert_eq!(Move::from_uci("P@e6", &house), Ok(drop));
+    }
+
+    #[test]
+    fn test_crazyhouse_drop_mate_can_be_forbidden() {
+        // R@a8 is a back-rank mate, and Black has nothing in hand to block with.
+        let fen = "6k1/5ppp/8/8/8/8/8/6K1[R] w - - 0 1";
+        let mate = Move::new_drop(PieceType::Rook, sq!("a8"));
+        let mut allowed = Board::from_fen(fen).unwrap();
+        assert!(allowed.legal_moves(Color::White).contains(&mate));
+        allowed.apply_move(mate).unwrap();
+        assert_eq!(allowed.game_status(), GameStatus::Checkmate { winner: Color::White });
+
+        let mut strict = Board::from_fen(fen).unwrap();
+        strict.set_variant(Arc::new(Crazyhouse { drop_mate_allowed: false }));
+        assert!(!strict.legal_moves(Color::White).contains(&mate));
+        assert_eq!(strict.apply_move(mate), Err(MoveError::VariantRule("a drop may not give checkmate")));
+    }
+}
@@ -0,0 +1,153 @@
+// Partial views of a board for one player: Kriegspiel, where each side sees only
+// its own pieces; blindfold training, where the board is blank; and a view of the
+// player's pieces and the squares they attack.
+//
+// The filtering happens here so that a server sends each client `to_proto()` of
+// its view and never the full state. Anything else that gives away where hidden
+// pieces stand or how they moved is withheld too: the move history, the opponent's
+// castling rights and pocket, and an en-passant target on a hidden square.
+
+use crate::bitboard::Bitboard;
+use crate::board::Board;
+use crate::pieces::{Color, PieceType, Square};
+use crate::rchess::v1::{self as proto};
+use crate::render::text;
+
+/// How much of the board a player sees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Every piece; the view is the board itself.
+    Full,
+    /// The player's own pieces only, as in Kriegspiel.
+    OwnPieces,
+    /// No pieces at all, for blindfold play.
+    Blindfold,
+    /// The player's own pieces and whatever stands on a square they attack.
+    Attacked,
+}
+
+/// The board as one player sees it under a [`Visibility`].
+#[derive(Debug, Clone, Copy)]
+pub struct BoardView<'a> {
+    board: &'a Board,
+    viewer: Color,
+    visibility: Visibility,
+}
+
+impl Board {
+    /// The board as `viewer` sees it under `visibility`.
+    pub fn view(&self, viewer: Color, visibility: Visibility) -> BoardView<'_> {
+        BoardView { board: self, viewer, visibility }
+    }
+}
+
+impl BoardView<'_> {
+    pub fn viewer(&self) -> Color {
+        self.viewer
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    /// Squares whose contents the viewer sees, empty or not.
+    pub fn visible_squares(&self) -> Bitboard {
+        let own = self.board.bitboards().color(self.viewer);
+        match self.visibility {
+            Visibility::Full => Bitboard(!0),
+            Visibility::OwnPieces => own,
+            Visibility::Blindfold => Bitboard::EMPTY,
+            Visibility::Attacked => own | self.board.attacked_squares(self.viewer),
+        }
+    }
+
+    /// The piece on `square`, if the viewer can see it.
+    pub fn piece_at(&self, square: Square) -> Option<(PieceType, Color)> {
+        if self.visible_squares().contains(square) { self.board.piece_at_typed(square) } else { None }
+    }
+
+    /// The GameState the viewer is allowed to have: pieces on hidden squares,
+    /// captured pieces, and the move history removed, and the opponent's castling
+    /// rights, pocket, and any en-passant target on a hidden square cleared.
+    pub fn to_proto(&self) -> proto::GameState {
+        let mut state = self.board.to_proto();
+        if self.visibility == Visibility::Full {
+            return state;
+        }
+        let visible = self.visible_squares();
+        if let Some(board) = &mut state.board {
+            board.pieces.retain(|piece| {
+                !piece.captured && self.board.piece_square(piece).is_some_and(|square| visible.contains(square))
+            });
+        }
+        state.moves.clear();
+        let target = state.en_passant_target.as_ref().and_then(Square::from_proto);
+        if target.is_some_and(|target| !visible.contains(target)) {
+            state.en_passant_target = None;
+        }
+        match self.viewer {
+            Color::White => {
+                state.black_kingside_castling = false;
+                state.black_queenside_castling = false;
+                state.black_pocket = None;
+            }
+            Color::Black => {
+                state.white_kingside_castling = false;
+                state.white_queenside_castling = false;
+                state.white_pocket = None;
+            }
+        }
+        state
+    }
+
+    /// Render the view as text like [`text::render`], from the viewer's side of
+    /// the board, with '?' on squares the viewer cannot see.
+    pub fn render(&self) -> String {
+        let visible = self.visible_squares();
+        text::render_with(self.viewer == Color::Black, |square| {
+            if visible.contains(square) { text::square_char(self.board, square) } else { '?' }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sq;
+
+    #[test]
+    fn test_own_pieces_view_hides_the_opponent() {
+        let board = Board::from_fen("r3k3/8/8/3pP3/8/8/8/4K2R w Kq d6 0 3").unwrap();
+        let view = board.view(Color::White, Visibility::OwnPieces);
+        assert_eq!(view.piece_at(sq!("e5")), Some((PieceType::Pawn, Color::White)));
+        assert_eq!(view.piece_at(sq!("d5")), None);
+
+        let state = view.to_proto();
+        let seen = Board::from_proto_unchecked(state.clone());
+        assert!(seen.pieces_of_color(Color::Black).is_empty());
+        assert_eq!(seen.pieces_of_color(Color::White).len(), 3);
+        assert!(state.white_kingside_castling);
+        assert!(!state.black_queenside_castling);
+        assert_eq!(state.en_passant_target, None);
+    }
+
+    #[test]
+    fn test_blindfold_and_attacked_views() {
+        let board = Board::from_fen("4k3/8/8/3p4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let blind = board.view(Color::White, Visibility::Blindfold);
+        assert!(Board::from_proto_unchecked(blind.to_proto()).all_pieces().next().is_none());
+        assert!(blind.render().lines().take(8).all(|line| line[3..].chars().all(|c| c == '?' || c == ' ')));
+
+        // The rook on d1 sees up the d-file as far as the pawn on d5.
+        let attacked = board.view(Color::White, Visibility::Attacked);
+        assert_eq!(attacked.piece_at(sq!("d5")).map(|(_, color)| color), Some(Color::Black));
+        assert_eq!(attacked.piece_at(sq!("e8")), None);
+        let lines: Vec<String> = attacked.render().lines().map(str::to_string).collect();
+        assert_eq!(lines[0], "8  ? ? ? ? ? ? ? ?");
+        assert_eq!(lines[3], "5  ? ? ? p ? ? ? ?");
+        assert_eq!(lines[7], "1  . . . R K . ? ?");
+
+        let full = board.view(Color::Black, Visibility::Full);
+        assert_eq!(full.to_proto(), board.to_proto());
+    }
+}
@@ -0,0 +1,107 @@
+// JavaScript bindings, for driving a browser chessboard from the crate compiled
+// to WebAssembly. JsBoard keeps the proto types out of sight: positions go in as
+// FEN, moves as UCI strings, and structured results come back as JSON text in
+// the shapes the serde impls give them.
+
+use crate::board::Board;
+use crate::moves::Move;
+use crate::render;
+use wasm_bindgen::prelude::*;
+
+/// A board for JavaScript callers.
+#[wasm_bindgen]
+pub struct JsBoard {
+    board: Board,
+}
+
+#[wasm_bindgen]
+impl JsBoard {
+    /// The standard starting position.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsBoard {
+        JsBoard { board: Board::starting_position() }
+    }
+
+    /// Parse a position from FEN; the error carries the reason.
+    #[wasm_bindgen(js_name = fromFen)]
+    pub fn from_fen(fen: &str) -> Result<JsBoard, JsError> {
+        Ok(JsBoard { board: Board::from_fen(fen)? })
+    }
+
+    pub fn fen(&self) -> String {
+        self.board.to_fen()
+    }
+
+    /// "white" or "black".
+    #[wasm_bindgen(js_name = sideToMove)]
+    pub fn side_to_move(&self) -> String {
+        self.board.current_player().to_string().to_lowercase()
+    }
+
+    /// Legal moves for the side to move as a JSON array of move objects, e.g.
+    /// `{"from":"e2","to":"e4","promotion":null,"piece":"pawn",...}`.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> String {
+        let moves = self.board.legal_moves(self.board.current_player());
+        serde_json::to_string(&moves).expect("moves serialize to JSON")
+    }
+
+    /// Play a move given in UCI notation, e.g. "e2e4" or "e7e8q", and return
+    /// its SAN.
+    #[wasm_bindgen(js_name = applyMove)]
+    pub fn apply_move(&mut self, uci: &str) -> Result<String, JsError> {
+        let mv = Move::from_uci(uci, &self.board)?;
+        let san = mv.to_san(&self.board);
+        self.board.apply_move(mv)?;
+        Ok(san)
+    }
+
+    /// Take back the last move; false if there is none.
+    pub fn undo(&mut self) -> bool {
+        self.board.undo_move().is_some()
+    }
+
+    /// How the position stands, e.g. "Game in progress" or "Checkmate, White wins".
+    pub fn status(&self) -> String {
+        self.board.game_status().to_string()
+    }
+
+    /// The position as a JSON BoardSnapshot: pieces by square and the FEN fields.
+    pub fn snapshot(&self) -> String {
+        serde_json::to_string(&self.board.snapshot()).expect("snapshots serialize to JSON")
+    }
+
+    /// A text diagram of the board, from Black's side when `flipped`.
+    pub fn render(&self, flipped: bool) -> String {
+        render::text::render(&self.board, flipped)
+    }
+
+    /// An SVG diagram of the board in the default colors.
+    #[cfg(feature = "svg")]
+    #[wasm_bindgen(js_name = renderSvg)]
+    pub fn render_svg(&self) -> String {
+        render::svg::render(&self.board, &render::svg::SvgOptions::default())
+    }
+}
+
+impl Default for JsBoard {
+    fn default() -> Self {
+        JsBoard::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_and_list_moves() {
+        let mut board = JsBoard::new();
+        assert_eq!(board.apply_move("e2e4").ok(), Some("e4".to_string()));
+        assert_eq!(board.side_to_move(), "black");
+        let moves: Vec<Move> = serde_json::from_str(&board.legal_moves()).unwrap();
+        assert_eq!(moves.len(), 20);
+        assert!(board.undo());
+        assert_eq!(board.fen(), crate::fen::STARTING_FEN);
+    }
+}
@@ -0,0 +1,286 @@
+// XBoard/CECP protocol front-end for WinBoard-family GUIs, driving the same engine as UCI.
+// Searches run on the reading thread, so commands sent while the engine thinks are
+// handled once it has moved.
+
+use crate::board::Board;
+use crate::engine::{Engine, SearchLimits, SearchResult};
+use crate::moves::Move;
+use crate::pieces::Color;
+use crate::status::GameStatus;
+use crate::time_manager::TimeInfo;
+use crate::uci::ENGINE_NAME;
+use std::io::{self, BufRead, Write};
+
+/// Time control set by "level", "st", and "sd".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeControl {
+    /// Moves per session, or 0 for the whole game.
+    pub moves_per_session: u32,
+    pub base_ms: u64,
+    pub increment_ms: u64,
+    /// Fixed time per move from "st".
+    pub move_time_ms: Option<u64>,
+    /// Depth limit from "sd".
+    pub depth: Option<u32>,
+}
+
+impl TimeControl {
+    /// Parse the arguments of "level MPS BASE INC", where BASE is minutes or
+    /// "minutes:seconds" and INC is seconds.
+    pub fn parse_level(args: &[&str]) -> Option<Self> {
+        let [mps, base, inc] = args else {
+            return None;
+        };
+        let base_ms = match base.split_once(':') {
+            Some((min, sec)) => min.parse::<u64>().ok()? * 60_000 + sec.parse::<u64>().ok()? * 1000,
+            None => base.parse::<u64>().ok()? * 60_000,
+        };
+        Some(TimeControl {
+            moves_per_session: mps.parse().ok()?,
+            base_ms,
+            increment_ms: (inc.parse::<f64>().ok()? * 1000.0) as u64,
+            ..Default::default()
+        })
+    }
+}
+
+/// Read CECP commands from `input` until "quit" or end of input, writing replies to `output`.
+pub fn run<R: BufRead, W: Write>(input: R, output: W) -> io::Result<()> {
+    let mut session = Session::new(output);
+    for line in input.lines() {
+        if !session.handle(&line?)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+struct Session<W> {
+    out: W,
+    engine: Engine,
+    board: Board,
+    /// Side the engine plays; `None` in force mode.
+    engine_color: Option<Color>,
+    control: TimeControl,
+    /// Engine clock from the last "time" command.
+    clock_ms: Option<u64>,
+    post: bool,
+}
+
+impl<W: Write> Session<W> {
+    fn new(out: W) -> Self {
+        Session {
+            out,
+            engine: Engine::new(),
+            board: Board::starting_position(),
+            engine_color: Some(Color::Black),
+            control: TimeControl::default(),
+            clock_ms: None,
+            post: false,
+        }
+    }
+
+    /// Handle one command line; returns false on "quit".
+    fn handle(&mut self, line: &str) -> io::Result<bool> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return Ok(true);
+        };
+        match command {
+            "protover" => self.send(&format!(
+                "feature myname=\"{}\" ping=1 setboard=1 usermove=1 playother=1 san=0 colors=0 sigint=0 sigterm=0 done=1",
+                ENGINE_NAME
+            ))?,
+            "new" => {
+                self.board = Board::starting_position();
+                self.engine_color = Some(Color::Black);
+                self.control.move_time_ms = None;
+                self.control.depth = None;
+                self.engine.clear_hash();
+            }
+            "force" | "result" => self.engine_color = None,
+            "go" => {
+                self.engine_color = Some(self.board.current_player());
+                self.think()?;
+            }
+            "playother" => self.engine_color = Some(self.board.current_player().opposite()),
+            "usermove" => self.user_move(args.first().copied().unwrap_or(""))?,
+            "setboard" => match Board::from_fen(&args.join(" ")) {
+                Ok(board) => self.board = board,
+                Err(err) => self.send(&format!("tellusererror Illegal position: {}", err))?,
+            },
+            "undo" => {
+                self.board.undo_move();
+            }
+            "remove" => {
+                self.board.undo_move();
+                self.board.undo_move();
+            }
+            "level" => match TimeControl::parse_level(args) {
+                Some(level) => {
+                    self.control = TimeControl { depth: self.control.depth, ..level };
+                }
+                None => self.send(&format!("Error (bad level): {}", line))?,
+            },
+            "st" => self.control.move_time_ms = args.first().and_then(|s| s.parse::<u64>().ok()).map(|s| s * 1000),
+            "sd" => self.control.depth = args.first().and_then(|d| d.parse().ok()),
+            // Clocks are sent in centiseconds.
+            "time" => self.clock_ms = args.first().and_then(|t| t.parse::<u64>().ok()).map(|t| t * 10),
+            "ping" => self.send(&format!("pong {}", args.first().copied().unwrap_or("")))?,
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "quit" => return Ok(false),
+            "xboard" | "accepted" | "rejected" | "otim" | "hard" | "easy" | "random" | "computer" | "name" => {}
+            _ => self.send(&format!("Error (unknown command): {}", command))?,
+        }
+        Ok(true)
+    }
+
+    fn user_move(&mut self, text: &str) -> io::Result<()> {
+        let applied = Move::from_uci(text, &self.board)
+            .ok()
+            .and_then(|mv| self.board.apply_move(mv).ok());
+        if applied.is_none() {
+            return self.send(&format!("Illegal move: {}", text));
+        }
+        if self.game_over()? {
+            return Ok(());
+        }
+        if self.engine_color == Some(self.board.current_player()) {
+            self.think()?;
+        }
+        Ok(())
+    }
+
+    /// Search the current position, play the best move, and announce it.
+    fn think(&mut self) -> io::Result<()> {
+        if self.game_over()? {
+            return Ok(());
+        }
+        let limits = self.limits();
+        let post = self.post;
+        let out = &mut self.out;
+        let result = self.engine.search_with_info(&self.board, limits, |r| {
+            if post {
+                // A failed progress line is not worth abandoning the search over.
+                let _ = writeln!(out, "{}", thinking_line(r)).and_then(|_| out.flush());
+            }
+        });
+        let Some(mv) = result.best_move else {
+            return Ok(());
+        };
+        if self.board.apply_move(mv).is_err() {
+            return Ok(());
+        }
+        self.send(&format!("move {}", mv.to_uci()))?;
+        self.game_over()?;
+        Ok(())
+    }
+
+    fn limits(&self) -> SearchLimits {
+        let mut limits = SearchLimits {
+            depth: self.control.depth,
+            move_time_ms: self.control.move_time_ms,
+            ..Default::default()
+        };
+        if limits.move_time_ms.is_none() && (self.clock_ms.is_some() || self.control.base_ms > 0) {
+            let mps = self.control.moves_per_session;
+            let played = (self.board.fullmove_number().max(1) - 1) as u32;
+            limits.clock = Some(TimeInfo {
+                remaining_ms: self.clock_ms.unwrap_or(self.control.base_ms),
+                increment_ms: self.control.increment_ms,
+                moves_to_go: (mps > 0).then(|| mps - played % mps),
+            });
+        }
+        limits
+    }
+
+    /// Announce the result if the game has ended on the board.
+    fn game_over(&mut self) -> io::Result<bool> {
+        let status = self.board.game_status();
+        let reason = match status {
+            GameStatus::Ongoing => return Ok(false),
+            GameStatus::Checkmate { winner } => format!("{} mates", winner),
+            GameStatus::Stalemate => "Stalemate".to_string(),
+            GameStatus::FiftyMoveRule => "Fifty move rule".to_string(),
+            GameStatus::ThreefoldRepetition => "Draw by repetition".to_string(),
+            GameStatus::VariantWin { winner, reason } => format!("{} wins by {}", winner, reason),
+            GameStatus::TimeForfeit { winner } => format!("{} wins on time", winner),
+            GameStatus::TimeoutDraw => "Time forfeit against insufficient material".to_string(),
+            GameStatus::Resignation { winner } => format!("{} resigns", winner.opposite()),
+            GameStatus::DrawAgreed => "Draw agreed".to_string(),
+            GameStatus::Adjudicated { .. } => "Adjudication".to_string(),
+        };
+        self.send(&format!("{} {{{}}}", status.result().to_pgn(), reason))?;
+        Ok(true)
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.out, "{}", line)?;
+        self.out.flush()
+    }
+}
+
+/// Format a search report as CECP thinking output: "ply score time nodes pv",
+/// with the time in centiseconds.
+pub fn thinking_line(result: &SearchResult) -> String {
+    let pv: Vec<String> = result.pv.iter().map(Move::to_uci).collect();
+    format!(
+        "{} {} {} {} {}",
+        result.depth,
+        result.score,
+        result.time_ms / 10,
+        result.nodes,
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(input: &str) -> Vec<String> {
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_handshake_and_ping() {
+        let lines = session("xboard\nprotover 2\nping 7\nquit\n");
+        assert!(lines[0].starts_with("feature myname=\"rchess"));
+        assert!(lines[0].ends_with("done=1"));
+        assert_eq!(lines[1], "pong 7");
+    }
+
+    #[test]
+    fn test_engine_replies_to_user_move() {
+        let lines = session("new\nsd 1\nusermove e2e4\n");
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("move "));
+    }
+
+    #[test]
+    fn test_force_mode_and_go() {
+        let lines = session("new\nforce\nsd 2\nusermove e2e4\nusermove e7e5\nusermove d1h5\nusermove b8c6\nusermove f1c4\nusermove g8f6\ngo\n");
+        assert_eq!(lines, vec!["move h5f7", "1-0 {White mates}"]);
+    }
+
+    #[test]
+    fn test_illegal_move_and_bad_fen() {
+        let lines = session("force\nusermove e2e5\nsetboard not a fen\n");
+        assert_eq!(lines[0], "Illegal move: e2e5");
+        assert!(lines[1].starts_with("tellusererror Illegal position"));
+    }
+
+    #[test]
+    fn test_post_and_level() {
+        let lines = session("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\npost\nsd 2\ngo\n");
+        assert!(lines[0].starts_with("1 "));
+        assert!(lines.contains(&"move a1a8".to_string()));
+
+        let level = TimeControl::parse_level(&["40", "0:30", "2"]).unwrap();
+        assert_eq!((level.moves_per_session, level.base_ms, level.increment_ms), (40, 30_000, 2_000));
+        assert_eq!(TimeControl::parse_level(&["40", "5"]), None);
+    }
+}
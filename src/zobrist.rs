@@ -0,0 +1,149 @@
+// Zobrist hashing: a 64-bit key per position, built by XOR-ing random keys for
+// each piece on its square, the side to move, castling rights, en passant file,
+// in variants that count them, the checks each side has given, and in drop variants
+// the pieces each side holds in hand.
+// Board keeps the piece part up to date as pieces are indexed and unindexed.
+
+use crate::board::Board;
+use crate::pieces::{Color, PieceType, Square};
+use crate::pocket::POCKET_TYPES;
+use std::sync::OnceLock;
+
+struct Keys {
+    pieces: [[u64; 64]; 12],
+    black_to_move: u64,
+    castling: [u64; 4],
+    en_passant_file: [u64; 8],
+    /// Per color, for one, two, and three or more checks given.
+    checks: [[u64; 3]; 2],
+    /// Per color and pocket piece type, for 1 to 16 pieces in hand.
+    pockets: [[[u64; 16]; 5]; 2],
+}
+
+static KEYS: OnceLock<Keys> = OnceLock::new();
+
+/// Keys come from a fixed-seed generator so hashes are stable across runs.
+fn keys() -> &'static Keys {
+    KEYS.get_or_init(|| {
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let mut next = || {
+            // SplitMix64.
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        let mut keys = Keys {
+            pieces: [[0; 64]; 12],
+            black_to_move: 0,
+            castling: [0; 4],
+            en_passant_file: [0; 8],
+            checks: [[0; 3]; 2],
+            pockets: [[[0; 16]; 5]; 2],
+        };
+        for square_keys in keys.pieces.iter_mut() {
+            square_keys.iter_mut().for_each(|key| *key = next());
+        }
+        keys.black_to_move = next();
+        keys.castling.iter_mut().for_each(|key| *key = next());
+        keys.en_passant_file.iter_mut().for_each(|key| *key = next());
+        keys.checks.iter_mut().flatten().for_each(|key| *key = next());
+        keys.pockets.iter_mut().flatten().flatten().for_each(|key| *key = next());
+        keys
+    })
+}
+
+/// Key for a piece of `piece_type` and `color` standing on `square`.
+pub fn piece_key(piece_type: PieceType, color: Color, square: Square) -> u64 {
+    let piece = (color.to_proto() as usize - 1) * 6 + (piece_type.to_proto() as usize - 1);
+    keys().pieces[piece][(square.rank * 8 + square.file) as usize]
+}
+
+impl Board {
+    /// Zobrist hash of the position. Equal positions, in the sense of
+    /// `position_key`, hash equally.
+    pub fn zobrist_key(&self) -> u64 {
+        let keys = keys();
+        let mut key = self.placement_key();
+        if self.current_player() == Color::Black {
+            key ^= keys.black_to_move;
+        }
+        let rights = self.castling_rights();
+        for (held, castling_key) in [
+            rights.white_kingside,
+            rights.white_queenside,
+            rights.black_kingside,
+            rights.black_queenside,
+        ]
+        .into_iter()
+        .zip(keys.castling)
+        {
+            if held {
+                key ^= castling_key;
+            }
+        }
+        if let Some(target) = self.en_passant_target() {
+            key ^= keys.en_passant_file[target.file as usize];
+        }
+        for (color, color_keys) in [Color::White, Color::Black].into_iter().zip(&keys.checks) {
+            match self.checks_given(color) {
+                0 => {}
+                n => key ^= color_keys[n.min(3) as usize - 1],
+            }
+        }
+        for (color, color_keys) in [Color::White, Color::Black].into_iter().zip(&keys.pockets) {
+            let pocket = self.pocket(color);
+            for (piece_type, type_keys) in POCKET_TYPES.into_iter().zip(color_keys) {
+                match pocket.count(piece_type) {
+                    0 => {}
+                    n => key ^= type_keys[n.min(16) as usize - 1],
+                }
+            }
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Move;
+    use crate::sq;
+
+    #[test]
+    fn test_transpositions_hash_equally() {
+        let mut a = Board::starting_position();
+        let mut b = Board::starting_position();
+        for m in [Move::new(sq!("g1"), sq!("f3")), Move::new(sq!("g8"), sq!("f6")), Move::new(sq!("b1"), sq!("c3"))] {
+            a.apply_move(m).unwrap();
+        }
+        for m in [Move::new(sq!("b1"), sq!("c3")), Move::new(sq!("g8"), sq!("f6")), Move::new(sq!("g1"), sq!("f3"))] {
+            b.apply_move(m).unwrap();
+        }
+        assert_eq!(a.zobrist_key(), b.zobrist_key());
+        assert_ne!(a.zobrist_key(), Board::starting_position().zobrist_key());
+    }
+
+    #[test]
+    fn test_key_matches_fresh_board_and_undo() {
+        let mut board = Board::from_fen("r3k2r/6P1/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let start = board.zobrist_key();
+        board.apply_move(Move::new(sq!("e5"), sq!("d6"))).unwrap();
+        board.apply_move(Move::new(sq!("e8"), sq!("c8"))).unwrap();
+        let fresh = Board::from_fen(&board.to_fen()).unwrap();
+        assert_eq!(board.zobrist_key(), fresh.zobrist_key());
+        board.undo_move();
+        board.undo_move();
+        assert_eq!(board.zobrist_key(), start);
+    }
+
+    #[test]
+    fn test_side_castling_and_en_passant_change_key() {
+        let base = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap().zobrist_key();
+        let black = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 0 1").unwrap().zobrist_key();
+        let no_rights = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap().zobrist_key();
+        assert_ne!(base, black);
+        assert_ne!(base, no_rights);
+    }
+}
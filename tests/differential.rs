@@ -0,0 +1,97 @@
+// Differential check of legal move generation over random games: the generated
+// moves must be exactly the moves apply_move accepts when every from/to pair is
+// tried, and a FEN round trip must not change the position or its moves.
+// A divergence is reported with the FEN of the first position it shows up in.
+
+use rchess::board::Board;
+use rchess::moves::Move;
+use rchess::pieces::{PieceType, Square};
+use std::collections::HashSet;
+
+type MoveKey = (u8, u8, Option<PieceType>);
+
+const PROMOTIONS: [Option<PieceType>; 4] =
+    [Some(PieceType::Queen), Some(PieceType::Rook), Some(PieceType::Bishop), Some(PieceType::Knight)];
+
+/// SplitMix64, seeded per game so a failure can be replayed.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+fn key(mv: &Move) -> MoveKey {
+    (mv.from.index(), mv.to.index(), mv.promotion)
+}
+
+fn generated(board: &Board) -> HashSet<MoveKey> {
+    board.legal_moves(board.current_player()).iter().map(key).collect()
+}
+
+/// Every move apply_move accepts, found by trying all of them.
+fn brute_force(board: &Board) -> HashSet<MoveKey> {
+    let mut scratch = board.clone();
+    let mut accepted = HashSet::new();
+    for from in (0..64).filter_map(Square::from_index) {
+        let Some((piece_type, color)) = board.piece_at_typed(from) else { continue };
+        if color != board.current_player() {
+            continue;
+        }
+        for to in (0..64).filter_map(Square::from_index) {
+            let promotes = piece_type == PieceType::Pawn && (to.rank == 0 || to.rank == 7);
+            let promotions: &[Option<PieceType>] = if promotes { &PROMOTIONS } else { &[None] };
+            for &promotion in promotions {
+                let mv = Move { promotion, ..Move::new(from, to) };
+                if scratch.apply_move(mv).is_ok() {
+                    accepted.insert(key(&mv));
+                    scratch.undo_move();
+                }
+            }
+        }
+    }
+    accepted
+}
+
+fn check_position(board: &Board) -> Result<(), String> {
+    let fen = board.to_fen();
+    let moves = generated(board);
+    let reference = brute_force(board);
+    if moves != reference {
+        let missing: Vec<_> = reference.difference(&moves).collect();
+        let extra: Vec<_> = moves.difference(&reference).collect();
+        return Err(format!("{fen}: movegen misses {missing:?} and adds {extra:?}"));
+    }
+    let reloaded = Board::from_fen(&fen).map_err(|err| format!("{fen}: does not parse back: {err}"))?;
+    if reloaded.to_fen() != fen {
+        return Err(format!("{fen}: reloads as {}", reloaded.to_fen()));
+    }
+    if generated(&reloaded) != moves {
+        return Err(format!("{fen}: has different moves after reloading"));
+    }
+    Ok(())
+}
+
+#[test]
+fn random_games_match_brute_force() {
+    for seed in 0..16 {
+        let mut rng = SplitMix(seed);
+        let mut board = Board::starting_position();
+        for _ in 0..120 {
+            if let Err(divergence) = check_position(&board) {
+                panic!("seed {seed}: {divergence}");
+            }
+            let moves = board.legal_moves(board.current_player());
+            if moves.is_empty() {
+                break;
+            }
+            let mv = moves[(rng.next() % moves.len() as u64) as usize];
+            board.apply_move(mv).unwrap();
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// Perft against the published reference counts for the standard test positions
+// (https://www.chessprogramming.org/Perft_Results).
+
+use rchess::board::Board;
+use rchess::perft::{PERFT_SUITE, verify_movegen};
+
+fn perft(fen: &str, depth: u32) -> u64 {
+    Board::from_fen(fen).unwrap().perft(depth)
+}
+
+#[test]
+fn perft_initial_position() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(perft(fen, 1), 20);
+    assert_eq!(perft(fen, 2), 400);
+    assert_eq!(perft(fen, 3), 8_902);
+}
+
+#[test]
+fn perft_kiwipete() {
+    // Castling, en passant, promotions, and pins all at once.
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    assert_eq!(perft(fen, 1), 48);
+    assert_eq!(perft(fen, 2), 2_039);
+    assert_eq!(perft(fen, 3), 97_862);
+}
+
+#[test]
+fn perft_position_3() {
+    // Rook endgame with en-passant discovered checks along the rank.
+    let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    assert_eq!(perft(fen, 1), 14);
+    assert_eq!(perft(fen, 2), 191);
+    assert_eq!(perft(fen, 3), 2_812);
+    assert_eq!(perft(fen, 4), 43_238);
+}
+
+#[test]
+fn perft_position_4() {
+    let fen = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    assert_eq!(perft(fen, 1), 6);
+    assert_eq!(perft(fen, 2), 264);
+    assert_eq!(perft(fen, 3), 9_467);
+}
+
+#[test]
+fn perft_position_5() {
+    let fen = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    assert_eq!(perft(fen, 1), 44);
+    assert_eq!(perft(fen, 2), 1_486);
+    assert_eq!(perft(fen, 3), 62_379);
+}
+
+#[test]
+fn perft_position_6() {
+    let fen = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+    assert_eq!(perft(fen, 1), 46);
+    assert_eq!(perft(fen, 2), 2_079);
+    assert_eq!(perft(fen, 3), 89_890);
+}
+
+#[test]
+fn suite_passes_to_depth_2() {
+    assert_eq!(verify_movegen(2), Ok(()));
+    assert!(PERFT_SUITE.iter().all(|case| case.nodes.len() >= 4));
+}